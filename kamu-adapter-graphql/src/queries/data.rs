@@ -11,7 +11,10 @@ use crate::scalars::*;
 use crate::utils::*;
 
 use async_graphql::*;
+use chrono::prelude::*;
 use kamu::domain;
+use kamu::infra::utils::record_batch_stream::collect_limited;
+use opendatafabric as odf;
 
 ///////////////////////////////////////////////////////////////////////////////
 
@@ -20,8 +23,17 @@ pub struct DataQueries;
 #[Object]
 impl DataQueries {
     const DEFAULT_QUERY_LIMIT: u64 = 100;
+    const MAX_QUERY_LIMIT: u64 = 10_000;
 
     /// Executes a specified query and returns its result
+    ///
+    /// Use `asOfBlocks` to query datasets as of the specified blocks in their
+    /// metadata chains and `asOfSystemTime` to query all other datasets as of
+    /// the specified point in time
+    ///
+    /// The number of returned records is capped by the server. When the result
+    /// contains more records than returned, `nextCursor` can be passed as `cursor`
+    /// along with the same query to fetch the next portion of records
     async fn query(
         &self,
         ctx: &Context<'_>,
@@ -30,34 +42,72 @@ impl DataQueries {
         data_format: Option<DataBatchFormat>,
         schema_format: Option<DataSchemaFormat>,
         limit: Option<u64>,
+        cursor: Option<String>,
+        as_of_blocks: Option<Vec<Multihash>>,
+        as_of_system_time: Option<DateTime<Utc>>,
     ) -> Result<DataQueryResult> {
         // TODO: Default to JsonSoA format once implemented
         let data_format = data_format.unwrap_or(DataBatchFormat::Json);
         let schema_format = schema_format.unwrap_or(DataSchemaFormat::Parquet);
-        let limit = limit.unwrap_or(Self::DEFAULT_QUERY_LIMIT);
+        let limit = limit
+            .unwrap_or(Self::DEFAULT_QUERY_LIMIT)
+            .min(Self::MAX_QUERY_LIMIT);
+        let skip = match cursor {
+            None => 0,
+            Some(cursor) => cursor
+                .parse::<u64>()
+                .map_err(|_| Error::new(format!("Invalid cursor: {}", cursor)))?,
+        };
 
         let query_svc = from_catalog::<dyn domain::QueryService>(ctx).unwrap();
 
         let df = match query_dialect {
             QueryDialect::DataFusion => {
-                let sql_result = query_svc
-                    .sql_statement(&query, domain::QueryOptions::default())
-                    .await;
+                let as_of_blocks: Vec<odf::Multihash> = as_of_blocks
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(Into::into)
+                    .collect();
+
+                let options = if as_of_blocks.is_empty() && as_of_system_time.is_none() {
+                    Ok(domain::QueryOptions::default())
+                } else {
+                    query_svc
+                        .options_as_of(&as_of_blocks, as_of_system_time)
+                        .await
+                };
+
+                let sql_result = match options {
+                    Ok(options) => query_svc.sql_statement(&query, options).await,
+                    Err(e) => Err(e),
+                };
                 match sql_result {
                     Ok(r) => r,
                     Err(e) => return Ok(e.into()),
                 }
             }
         }
-        .limit(0, Some(limit as usize))?;
+        .limit(skip as usize, Some((limit + 1) as usize))?;
 
         let schema = DataSchema::from_data_frame_schema(df.schema(), schema_format)?;
-        let record_batches = match df.collect().await {
-            Ok(rb) => rb,
+
+        // Results are streamed so that execution stops once the limit is reached
+        let records = match df.execute_stream().await {
+            Ok(stream) => collect_limited(stream, limit).await,
+            Err(e) => Err(e),
+        };
+        let records = match records {
+            Ok(records) => records,
             Err(e) => return Ok(e.into()),
         };
-        let data = DataBatch::from_records(&record_batches, data_format)?;
+        let data = DataBatch::from_records(&records.batches, data_format)?;
+
+        let next_cursor = if records.has_more {
+            Some((skip + records.num_records).to_string())
+        } else {
+            None
+        };
 
-        Ok(DataQueryResult::success(schema, data, limit))
+        Ok(DataQueryResult::success(schema, data, limit, next_cursor))
     }
 }
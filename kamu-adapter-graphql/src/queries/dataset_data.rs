@@ -13,6 +13,7 @@ use crate::utils::*;
 use async_graphql::*;
 use kamu::domain;
 use kamu::domain::GetSummaryOpts;
+use kamu::infra::utils::record_batch_stream::collect_limited;
 use opendatafabric as odf;
 use tracing::debug;
 
@@ -23,6 +24,7 @@ pub struct DatasetData {
 #[Object]
 impl DatasetData {
     const DEFAULT_TAIL_LIMIT: u64 = 20;
+    const MAX_TAIL_LIMIT: u64 = 10_000;
 
     #[graphql(skip)]
     pub fn new(dataset_handle: odf::DatasetHandle) -> Self {
@@ -61,11 +63,13 @@ impl DatasetData {
         // TODO: Default to JsonSoA format once implemented
         let data_format = data_format.unwrap_or(DataBatchFormat::Json);
         let schema_format = schema_format.unwrap_or(DataSchemaFormat::Parquet);
-        let limit = limit.unwrap_or(Self::DEFAULT_TAIL_LIMIT);
+        let limit = limit
+            .unwrap_or(Self::DEFAULT_TAIL_LIMIT)
+            .min(Self::MAX_TAIL_LIMIT);
 
         let query_svc = from_catalog::<dyn domain::QueryService>(ctx).unwrap();
         let df = match query_svc
-            .tail(&self.dataset_handle.as_local_ref(), limit)
+            .tail(&self.dataset_handle.as_local_ref(), None, limit)
             .await
         {
             Ok(r) => r,
@@ -76,12 +80,16 @@ impl DatasetData {
         };
 
         let schema = DataSchema::from_data_frame_schema(df.schema(), schema_format)?;
-        let record_batches = match df.collect().await {
-            Ok(rb) => rb,
+        let records = match df.execute_stream().await {
+            Ok(stream) => collect_limited(stream, limit).await,
+            Err(e) => Err(e),
+        };
+        let records = match records {
+            Ok(records) => records,
             Err(e) => return Ok(e.into()),
         };
-        let data = DataBatch::from_records(&record_batches, data_format)?;
+        let data = DataBatch::from_records(&records.batches, data_format)?;
 
-        Ok(DataQueryResult::success(schema, data, limit))
+        Ok(DataQueryResult::success(schema, data, limit, None))
     }
 }
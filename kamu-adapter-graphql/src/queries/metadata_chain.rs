@@ -56,14 +56,16 @@ impl MetadataChain {
     /// Returns all named metadata block references
     async fn refs(&self, ctx: &Context<'_>) -> Result<Vec<BlockRef>> {
         let dataset = self.get_dataset(ctx).await?;
-        Ok(vec![BlockRef {
-            name: "head".to_owned(),
-            block_hash: dataset
-                .as_metadata_chain()
-                .get_ref(&domain::BlockRef::Head)
-                .await?
-                .into(),
-        }])
+        let chain = dataset.as_metadata_chain();
+
+        let mut refs = Vec::new();
+        for r in chain.as_reference_repo().list().await? {
+            refs.push(BlockRef {
+                name: r.to_string(),
+                block_hash: chain.get_ref(&r).await?.into(),
+            });
+        }
+        Ok(refs)
     }
 
     /// Returns a metadata block corresponding to the specified hash
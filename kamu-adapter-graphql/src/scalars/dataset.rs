@@ -231,6 +231,9 @@ pub struct DataQueryResultSuccess {
     pub schema: DataSchema,
     pub data: DataBatch,
     pub limit: u64,
+    /// Cursor to pass along with the same query to fetch the next portion of
+    /// records, present only when the result was truncated
+    pub next_cursor: Option<String>,
 }
 
 #[derive(SimpleObject)]
@@ -252,11 +255,17 @@ pub enum DataQueryResult {
 }
 
 impl DataQueryResult {
-    pub fn success(schema: DataSchema, data: DataBatch, limit: u64) -> DataQueryResult {
+    pub fn success(
+        schema: DataSchema,
+        data: DataBatch,
+        limit: u64,
+        next_cursor: Option<String>,
+    ) -> DataQueryResult {
         DataQueryResult::Success(DataQueryResultSuccess {
             schema,
             data,
             limit,
+            next_cursor,
         })
     }
 
@@ -279,6 +288,7 @@ impl From<QueryError> for DataQueryResult {
     fn from(e: QueryError) -> Self {
         match e {
             QueryError::DatasetNotFound(e) => DataQueryResult::invalid_sql(e.to_string()),
+            QueryError::BlockNotFound(e) => DataQueryResult::invalid_sql(e.to_string()),
             QueryError::DataFusionError(e) => e.into(),
             QueryError::Internal(e) => DataQueryResult::internal(e.to_string()),
         }
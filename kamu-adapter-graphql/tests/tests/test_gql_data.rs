@@ -190,3 +190,130 @@ async fn test_dataset_tail_empty() {
     let data = serde_json::from_str::<serde_json::Value>(data.as_str().unwrap()).unwrap();
     assert_eq!(data, serde_json::json!([]));
 }
+
+#[test_log::test(tokio::test)]
+#[cfg_attr(not(unix), ignore)] // TODO: DataFusion crashes on windows
+async fn test_data_query_as_of_block() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let cat = create_test_dataset(tempdir.path()).await;
+
+    let local_repo = cat.get_one::<dyn LocalDatasetRepository>().unwrap();
+    let ds = local_repo
+        .get_dataset(&DatasetName::new_unchecked("foo").as_local_ref())
+        .await
+        .unwrap();
+    let first_data_block = ds
+        .as_metadata_chain()
+        .get_ref(&BlockRef::Head)
+        .await
+        .unwrap();
+
+    let tmp_data_path = tempdir.path().join("data");
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("offset", DataType::UInt64, false),
+        Field::new("blah", DataType::Utf8, false),
+    ]));
+    let a: Arc<dyn Array> = Arc::new(UInt64Array::from(vec![4, 5]));
+    let b: Arc<dyn Array> = Arc::new(StringArray::from(vec!["d", "e"]));
+    let record_batch = RecordBatch::try_new(Arc::clone(&schema), vec![a, b]).unwrap();
+    ParquetWriterHelper::from_record_batch(&tmp_data_path, &record_batch).unwrap();
+
+    ds.commit_add_data(
+        None,
+        Some(OffsetInterval { start: 4, end: 5 }),
+        Some(tmp_data_path),
+        None,
+        None,
+        CommitOpts::default(),
+    )
+    .await
+    .unwrap();
+
+    let schema = kamu_adapter_graphql::schema(cat);
+
+    let query = |as_of: String| {
+        format!(
+            r#"{{
+                data {{
+                    query(
+                        query: "SELECT COUNT(*) AS cnt FROM foo",
+                        queryDialect: DATA_FUSION,
+                        dataFormat: JSON
+                        {as_of}
+                    ) {{
+                        ... on DataQueryResultSuccess {{
+                            data {{ content }}
+                        }}
+                    }}
+                }}
+            }}"#
+        )
+    };
+
+    let res = schema.execute(query(String::new())).await;
+    assert!(res.is_ok(), "{:?}", res);
+    let json = serde_json::to_value(&res.data).unwrap();
+    let data = json["data"]["query"]["data"]["content"].as_str().unwrap();
+    let data = serde_json::from_str::<serde_json::Value>(data).unwrap();
+    assert_eq!(data, serde_json::json!([{"cnt": 5}]));
+
+    let res = schema
+        .execute(query(format!(", asOfBlocks: [\"{}\"]", first_data_block)))
+        .await;
+    assert!(res.is_ok(), "{:?}", res);
+    let json = serde_json::to_value(&res.data).unwrap();
+    let data = json["data"]["query"]["data"]["content"].as_str().unwrap();
+    let data = serde_json::from_str::<serde_json::Value>(data).unwrap();
+    assert_eq!(data, serde_json::json!([{"cnt": 3}]));
+}
+
+#[test_log::test(tokio::test)]
+#[cfg_attr(not(unix), ignore)] // TODO: DataFusion crashes on windows
+async fn test_data_query_cursor() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let cat = create_test_dataset(tempdir.path()).await;
+
+    let schema = kamu_adapter_graphql::schema(cat);
+
+    let query = |cursor: &str| {
+        format!(
+            r#"{{
+                data {{
+                    query(
+                        query: "SELECT blah FROM foo ORDER BY offset",
+                        queryDialect: DATA_FUSION,
+                        dataFormat: JSON,
+                        limit: 2
+                        {cursor}
+                    ) {{
+                        ... on DataQueryResultSuccess {{
+                            data {{ content }}
+                            nextCursor
+                        }}
+                    }}
+                }}
+            }}"#
+        )
+    };
+
+    let res = schema.execute(query("")).await;
+    assert!(res.is_ok(), "{:?}", res);
+    let json = serde_json::to_value(&res.data).unwrap();
+    let data = json["data"]["query"]["data"]["content"].as_str().unwrap();
+    let data = serde_json::from_str::<serde_json::Value>(data).unwrap();
+    assert_eq!(data, serde_json::json!([{"blah": "a"}, {"blah": "b"}]));
+    let next_cursor = json["data"]["query"]["nextCursor"].as_str().unwrap();
+
+    let res = schema
+        .execute(query(&format!(", cursor: \"{}\"", next_cursor)))
+        .await;
+    assert!(res.is_ok(), "{:?}", res);
+    let json = serde_json::to_value(&res.data).unwrap();
+    let data = json["data"]["query"]["data"]["content"].as_str().unwrap();
+    let data = serde_json::from_str::<serde_json::Value>(data).unwrap();
+    assert_eq!(data, serde_json::json!([{"blah": "c"}]));
+    assert_eq!(json["data"]["query"]["nextCursor"], serde_json::Value::Null);
+
+    let res = schema.execute(query(", cursor: \"foo\"")).await;
+    assert!(res.is_err(), "{:?}", res);
+}
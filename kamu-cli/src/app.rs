@@ -55,7 +55,7 @@ pub async fn run(
             "Initializing kamu-cli"
         );
 
-        load_config(&workspace_layout, &mut catalog_builder);
+        load_config(&workspace_layout, &mut catalog_builder)?;
 
         (_log_thread, catalog_builder.build())
     };
@@ -129,6 +129,9 @@ fn configure_catalog() -> CatalogBuilder {
     b.add::<ResetServiceImpl>();
     b.bind::<dyn ResetService, ResetServiceImpl>();
 
+    b.add::<GcServiceImpl>();
+    b.bind::<dyn GcService, GcServiceImpl>();
+
     b.add::<ProvenanceServiceImpl>();
     b.bind::<dyn ProvenanceService, ProvenanceServiceImpl>();
 
@@ -145,12 +148,24 @@ fn configure_catalog() -> CatalogBuilder {
 // Config
 /////////////////////////////////////////////////////////////////////////////////////////
 
-fn load_config(workspace_layout: &WorkspaceLayout, catalog: &mut CatalogBuilder) {
+fn load_config(
+    workspace_layout: &WorkspaceLayout,
+    catalog: &mut CatalogBuilder,
+) -> Result<(), CLIError> {
     let config_svc = ConfigService::new(workspace_layout);
     let config = config_svc.load_with_defaults(ConfigScope::Flattened);
 
     info!(config = ?config, "Loaded configuration");
 
+    for engine in config.engine.as_ref().unwrap().engines.as_ref().unwrap() {
+        if EngineProvisionerLocalConfig::RESERVED_ENGINE_IDS.contains(&engine.id.as_str()) {
+            return Err(CLIError::usage_error(format!(
+                "Engine id {} is reserved for a built-in engine and cannot be declared in engine.engines",
+                engine.id
+            )));
+        }
+    }
+
     let network_ns = config.engine.as_ref().unwrap().network_ns.unwrap();
 
     // Registrer JupyterConfig used by some commands
@@ -197,6 +212,16 @@ fn load_config(workspace_layout: &WorkspaceLayout, catalog: &mut CatalogBuilder)
             .flink
             .clone()
             .unwrap(),
+        engines: config
+            .engine
+            .as_ref()
+            .unwrap()
+            .engines
+            .clone()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.into())
+            .collect(),
     });
 
     let ipfs_conf = config.protocol.as_ref().unwrap().ipfs.as_ref().unwrap();
@@ -206,6 +231,8 @@ fn load_config(workspace_layout: &WorkspaceLayout, catalog: &mut CatalogBuilder)
         pre_resolve_dnslink: ipfs_conf.pre_resolve_dnslink.unwrap(),
     });
     catalog.add_value(kamu::infra::utils::ipfs_wrapper::IpfsClient::default());
+
+    Ok(())
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -7,6 +7,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use kamu::domain::DatasetRefLocalAt;
+use kamu::domain::QueryAsOf;
 use opendatafabric::DatasetName;
 use opendatafabric::DatasetRefAny;
 use opendatafabric::DatasetRefLocal;
@@ -30,6 +32,18 @@ pub fn get_command(
             submatches.get_flag("recursive"),
             submatches.get_flag("replace"),
         )),
+        Some(("branch", submatches)) => Box::new(BlockRefCommand::new(
+            catalog.get_one()?,
+            catalog.get_one()?,
+            BlockRefKind::Branch,
+            submatches
+                .get_one::<DatasetRefLocal>("dataset")
+                .unwrap()
+                .clone(),
+            submatches.get_one("name").map(String::as_str),
+            submatches.get_one("block").map(|h: &Multihash| h.clone()),
+            submatches.get_flag("delete"),
+        )),
         Some(("complete", submatches)) => Box::new(CompleteCommand::new(
             if in_workspace(catalog.get_one()?) {
                 Some(catalog.get_one()?)
@@ -91,6 +105,7 @@ pub fn get_command(
         Some(("init", submatches)) => {
             if submatches.get_flag("pull-images") || submatches.get_flag("pull-test-images") {
                 Box::new(PullImagesCommand::new(
+                    catalog.get_one()?,
                     catalog.get_one()?,
                     submatches.get_flag("pull-test-images"),
                     submatches.get_flag("list-only"),
@@ -229,6 +244,7 @@ pub fn get_command(
                     .unwrap()
                     .clone(),
                 add_matches.get_one("url").map(String::as_str).unwrap(),
+                add_matches.get_one::<String>("auth-token").cloned(),
             )),
             Some(("delete", delete_matches)) => Box::new(RepositoryDeleteCommand::new(
                 catalog.get_one()?,
@@ -309,12 +325,32 @@ pub fn get_command(
                 catalog.get_one()?,
                 catalog.get_one()?,
                 catalog.get_one()?,
+                catalog.get_one()?,
                 submatches.get_one("command").map(String::as_str),
+                submatches.get_one("script").map(String::as_str),
                 submatches.get_one("url").map(String::as_str),
                 submatches.get_one("engine").map(String::as_str),
+                submatches
+                    .get_many("as-of")
+                    .unwrap_or_default()
+                    .map(|v: &QueryAsOf| v.clone()),
             )),
             Some(("server", server_matches)) => {
-                if !server_matches.get_flag("livy") {
+                if server_matches.get_flag("flight-sql") {
+                    Box::new(SqlServerFlightSqlCommand::new(
+                        catalog.get_one()?,
+                        catalog.get_one()?,
+                        *server_matches.get_one("address").unwrap(),
+                        *(server_matches.get_one("port").unwrap()),
+                    ))
+                } else if server_matches.get_flag("pg") {
+                    Box::new(SqlServerPgCommand::new(
+                        catalog.get_one()?,
+                        catalog.get_one()?,
+                        *server_matches.get_one("address").unwrap(),
+                        *(server_matches.get_one("port").unwrap()),
+                    ))
+                } else if !server_matches.get_flag("livy") {
                     Box::new(SqlServerCommand::new(
                         catalog.get_one()?,
                         catalog.get_one()?,
@@ -343,6 +379,8 @@ pub fn get_command(
                     catalog.get_one()?,
                     server_matches.get_one("address").map(|a| *a),
                     server_matches.get_one("http-port").map(|p| *p),
+                    server_matches.get_flag("serve-datasets"),
+                    server_matches.get_one::<String>("auth-token").cloned(),
                 )),
                 Some(("gql-query", query_matches)) => Box::new(APIServerGqlQueryCommand::new(
                     catalog.clone(), // TODO: Currently very expensive!
@@ -354,6 +392,21 @@ pub fn get_command(
                 )),
                 _ => return Err(CommandInterpretationFailed.into()),
             },
+            Some(("gc", gc_matches)) => Box::new(SystemGcCommand::new(
+                catalog.get_one()?,
+                catalog.get_one()?,
+                gc_matches
+                    .get_many("dataset")
+                    .unwrap_or_default()
+                    .map(|r: &DatasetRefLocal| r.clone()),
+                gc_matches.get_flag("dry-run"),
+                *gc_matches.get_one("min-age").unwrap(),
+            )),
+            Some(("info", _)) => Box::new(SystemInfoCommand::new(
+                catalog.get_one()?,
+                catalog.get_one()?,
+                catalog.get_one()?,
+            )),
             Some(("ipfs", ipfs_matches)) => match ipfs_matches.subcommand() {
                 Some(("add", add_matches)) => Box::new(SystemIpfsAddCommand::new(
                     catalog.get_one()?,
@@ -366,12 +419,24 @@ pub fn get_command(
             },
             _ => return Err(CommandInterpretationFailed.into()),
         },
-        Some(("tail", submatches)) => Box::new(TailCommand::new(
+        Some(("tag", submatches)) => Box::new(BlockRefCommand::new(
             catalog.get_one()?,
+            catalog.get_one()?,
+            BlockRefKind::Tag,
             submatches
                 .get_one::<DatasetRefLocal>("dataset")
                 .unwrap()
                 .clone(),
+            submatches.get_one("name").map(String::as_str),
+            submatches.get_one("block").map(|h: &Multihash| h.clone()),
+            submatches.get_flag("delete"),
+        )),
+        Some(("tail", submatches)) => Box::new(TailCommand::new(
+            catalog.get_one()?,
+            submatches
+                .get_one::<DatasetRefLocalAt>("dataset")
+                .unwrap()
+                .clone(),
             *(submatches.get_one("num-records").unwrap()),
             catalog.get_one()?,
         )),
@@ -382,7 +447,6 @@ pub fn get_command(
             submatches.get_one("http-port").map(|p| *p),
         )),
         Some(("verify", submatches)) => Box::new(VerifyCommand::new(
-            catalog.get_one()?,
             catalog.get_one()?,
             catalog.get_one()?,
             submatches
@@ -391,6 +455,9 @@ pub fn get_command(
                 .map(|r: &DatasetRefLocal| r.clone()),
             submatches.get_flag("recursive"),
             submatches.get_flag("integrity"),
+            submatches.get_flag("continue-on-error"),
+            submatches.get_flag("allow-unsigned"),
+            submatches.get_one("output-format").map(String::as_str),
         )),
         _ => return Err(CommandInterpretationFailed.into()),
     };
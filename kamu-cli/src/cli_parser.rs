@@ -10,6 +10,7 @@
 use std::net::IpAddr;
 
 use clap::{value_parser, Arg, ArgAction, Command};
+use kamu::domain::{BlockRef, DatasetRefLocalAt, QueryAsOf};
 use opendatafabric::*;
 
 fn tabular_output_params<'a>(app: Command) -> Command {
@@ -147,6 +148,56 @@ pub fn cli() -> Command {
                     To add dataset from a repository see `kamu pull` command.
                     "
                     )),
+                tabular_output_params(
+                    Command::new("branch")
+                        .about("Lists, creates, or deletes dataset branches")
+                        .args([
+                            Arg::new("dataset")
+                                .required(true)
+                                .index(1)
+                                .value_parser(value_parse_dataset_ref_local)
+                                .help("Local dataset reference"),
+                            Arg::new("name")
+                                .index(2)
+                                .value_parser(value_parse_block_ref_name)
+                                .help("Name of the branch to create or delete"),
+                            Arg::new("block")
+                                .long("block")
+                                .value_name("HASH")
+                                .value_parser(value_parse_multihash)
+                                .conflicts_with("delete")
+                                .help("Hash of the block to point at (defaults to the current head)"),
+                            Arg::new("delete")
+                                .short('d')
+                                .long("delete")
+                                .action(ArgAction::SetTrue)
+                                .help("Delete the branch"),
+                        ])
+                        .after_help(indoc::indoc!(
+                            "
+                    Branches are named pointers to metadata blocks that, unlike tags, can be \
+                    moved to a different block by creating the branch again.
+
+                    ### Examples ###
+
+                    List all branches of a dataset:
+
+                        kamu branch my.dataset
+
+                    Create a branch at a specific block (or move an existing one):
+
+                        kamu branch my.dataset experiment --block zW1a...
+
+                    Delete a branch:
+
+                        kamu branch my.dataset experiment --delete
+
+                    Display the data as of a branch:
+
+                        kamu tail my.dataset@refs/branches/experiment
+                    "
+                        )),
+                ),
                 Command::new("complete")
                     .about("Completes a command in the shell")
                     .hide(true)
@@ -826,6 +877,13 @@ pub fn cli() -> Command {
                                 s3://bucket.my-company.example
                                 s3+http://my-minio-server:9000/bucket
                                 s3+https://my-minio-server:9000/bucket
+
+                            For HTTP repositories use following URL formats:
+                                http://my-kamu-node:12312/
+                                https://my-kamu-node.example/
+
+                            HTTP repositories that accept pushes may require a bearer token:
+                                kamu repo add my-node https://my-kamu-node.example/ --auth-token <secret>
                         "
                             ))
                             .args(&[
@@ -838,6 +896,10 @@ pub fn cli() -> Command {
                                     .required(true)
                                     .index(2)
                                     .help("URL of the repository"),
+                                Arg::new("auth-token")
+                                    .long("auth-token")
+                                    .value_name("TOKEN")
+                                    .help("Bearer token to authenticate with the repository"),
                             ]),
                         Command::new("delete")
                             .about("Deletes a reference to repository")
@@ -1021,6 +1083,16 @@ pub fn cli() -> Command {
                                     .action(ArgAction::SetTrue)
                                     .help("Run Livy server instead of JDBC")
                                     .hide(true),
+                                Arg::new("flight-sql")
+                                    .long("flight-sql")
+                                    .action(ArgAction::SetTrue)
+                                    .conflicts_with("livy")
+                                    .help("Run Arrow Flight SQL server instead of JDBC (does not require Docker)"),
+                                Arg::new("pg")
+                                    .long("pg")
+                                    .action(ArgAction::SetTrue)
+                                    .conflicts_with_all(["livy", "flight-sql"])
+                                    .help("Run PostgreSQL wire protocol server instead of JDBC (does not require Docker)"),
                             ]),
                         )
                         .args(&[
@@ -1041,6 +1113,12 @@ pub fn cli() -> Command {
                                 .value_parser(["spark", "datafusion"])
                                 .value_name("ENG")
                                 .help("Engine type to use for this SQL session"),
+                            Arg::new("as-of")
+                                .long("as-of")
+                                .action(ArgAction::Append)
+                                .value_parser(value_parse_query_as_of)
+                                .value_name("HASH|TIME")
+                                .help("Query the data as of the specified block hash or system time (RFC3339), can be repeated for multiple datasets"),
                         ])
                         .after_help(indoc::indoc!(
                             "
@@ -1055,14 +1133,36 @@ pub fn cli() -> Command {
 
                             kamu sql
 
+                        Drop into a lightweight SQL shell that doesn't require Docker \
+                        (type \\? inside the shell to see meta-commands like \\d and \\timing):
+
+                            kamu sql --engine datafusion
+
                         Execute SQL command and return its output in CSV format:
 
                             kamu sql -c 'SELECT * FROM `org.example.data` LIMIT 10' -o csv
 
+                        Execute a script with multiple statements (views and settings persist between them):
+
+                            kamu sql --engine datafusion --script ./queries.sql
+
+                        Query the data as it was at a specific point in time:
+
+                            kamu sql --engine datafusion --as-of 2022-01-01T00:00:00Z -c 'SELECT * FROM \"org.example.data\"'
+
                         Run SQL server to use with external data processing tools:
 
                             kamu sql server --address 0.0.0.0 --port 8080
 
+                        Run Arrow Flight SQL server that uses the embedded DataFusion engine:
+
+                            kamu sql server --flight-sql --port 50050
+
+                        Run PostgreSQL-compatible server and connect to it with `psql`:
+
+                            kamu sql server --pg --port 5432
+                            psql -h localhost -p 5432 kamu
+
                         Connect to a remote SQL server:
 
                             kamu sql --url jdbc:hive2://example.com:10000
@@ -1104,6 +1204,15 @@ pub fn cli() -> Command {
                                     .long("http-port")
                                     .value_parser(value_parser!(u16))
                                     .help("Expose HTTP server on specific port"),
+                                Arg::new("serve-datasets")
+                                    .long("serve-datasets")
+                                    .action(ArgAction::SetTrue)
+                                    .help("Serve workspace datasets as a read-only ODF repository"),
+                                Arg::new("auth-token")
+                                    .long("auth-token")
+                                    .value_name("TOKEN")
+                                    .requires("serve-datasets")
+                                    .help("Allow clients presenting this bearer token to push datasets"),
                             ])
                             .after_help(indoc::indoc!(
                                 "
@@ -1113,6 +1222,17 @@ pub fn cli() -> Command {
 
                                 kamu system api-server --http-port 12312
 
+                            Let others pull datasets directly from your workspace:
+
+                                kamu system api-server --http-port 12312 --serve-datasets
+                                kamu pull http://<host>:12312/my.dataset
+
+                            Also let others push datasets into your workspace using a secret token:
+
+                                kamu system api-server --http-port 12312 --serve-datasets --auth-token <secret>
+                                kamu repo add my-node http://<host>:12312/ --auth-token <secret>
+                                kamu push my.dataset --to my-node/my.dataset
+
                             Execute a single GraphQL query and print result to stdout:
 
                                 kamu system api-server gql-query '{ apiVersion }'
@@ -1122,6 +1242,66 @@ pub fn cli() -> Command {
                                 kamu system api-server gql-schema
                             "
                             )),
+                        Command::new("gc")
+                            .about("Deletes objects that are no longer reachable from any dataset reference")
+                            .args([
+                                Arg::new("dataset")
+                                    .action(ArgAction::Append)
+                                    .index(1)
+                                    .value_parser(value_parse_dataset_ref_local)
+                                    .help("Local dataset reference(s), all datasets if not specified"),
+                                Arg::new("dry-run")
+                                    .long("dry-run")
+                                    .action(ArgAction::SetTrue)
+                                    .help("Only report how much space would be reclaimed"),
+                                Arg::new("min-age")
+                                    .long("min-age")
+                                    .value_name("DUR")
+                                    .default_value("1h")
+                                    .value_parser(value_parse_duration)
+                                    .help("Keep unreachable objects that were written more recently than this (e.g. `30m`, `1h`, `0s`)"),
+                            ])
+                            .after_help(indoc::indoc!(
+                                "
+                            Metadata blocks, data slices, and checkpoints can become unreachable \
+                            after operations like `kamu reset` or a forced pull. This command \
+                            finds such objects by walking all tags and branches of a dataset \
+                            and deletes them.
+
+                            Objects written more recently than `--min-age` are never deleted, \
+                            so that data of a pull or push that is still in progress is not \
+                            mistaken for garbage.
+
+                            ### Examples ###
+
+                            See how much space can be reclaimed:
+
+                                kamu system gc --dry-run
+
+                            Collect garbage of a specific dataset:
+
+                                kamu system gc my.dataset
+                            "
+                            )),
+                        Command::new("info")
+                            .about("Summarizes the environment and the available engines")
+                            .after_help(indoc::indoc!(
+                                "
+                            Lists the built-in engines along with the ones declared in the \
+                            `engine.engines` section of `.kamuconfig`, showing whether their \
+                            images were already pulled.
+
+                            ### Examples ###
+
+                            Declare an in-house engine that implements the ODF engine protocol:
+
+                                kamu config set engine.engines '[{id: my-engine, image: \"example.com/my-engine:0.1.0\", grpcPort: 2884, memoryLimit: 4g}]'
+
+                            Pull images of all configured engines in advance:
+
+                                kamu init --pull-images
+                            "
+                            )),
                         Command::new("ipfs")
                             .about("IPFS helpers")
                             .subcommand_required(true)
@@ -1134,14 +1314,69 @@ pub fn cli() -> Command {
                                     .help("Dataset reference")])]),
                     ]),
                 tabular_output_params(
-                    Command::new("tail")
-                        .about("Displays a sample of most recent records in a dataset")
+                    Command::new("tag")
+                        .about("Lists, creates, or deletes dataset tags")
                         .args([
                             Arg::new("dataset")
                                 .required(true)
                                 .index(1)
                                 .value_parser(value_parse_dataset_ref_local)
                                 .help("Local dataset reference"),
+                            Arg::new("name")
+                                .index(2)
+                                .value_parser(value_parse_block_ref_name)
+                                .help("Name of the tag to create or delete"),
+                            Arg::new("block")
+                                .long("block")
+                                .value_name("HASH")
+                                .value_parser(value_parse_multihash)
+                                .conflicts_with("delete")
+                                .help("Hash of the block to point at (defaults to the current head)"),
+                            Arg::new("delete")
+                                .short('d')
+                                .long("delete")
+                                .action(ArgAction::SetTrue)
+                                .help("Delete the tag"),
+                        ])
+                        .after_help(indoc::indoc!(
+                            "
+                    Tags are named pointers to metadata blocks that, once created, never move. \
+                    They are useful for marking known-good releases of a dataset.
+
+                    ### Examples ###
+
+                    List all tags of a dataset:
+
+                        kamu tag my.dataset
+
+                    Tag the current head of a dataset:
+
+                        kamu tag my.dataset v1
+
+                    Tag a specific block:
+
+                        kamu tag my.dataset v1 --block zW1a...
+
+                    Delete a tag:
+
+                        kamu tag my.dataset v1 --delete
+
+                    Query the data as of a tag:
+
+                        kamu tail my.dataset@v1
+                        kamu sql --engine datafusion --command 'SELECT * FROM \"my.dataset@v1\"'
+                    "
+                        )),
+                ),
+                tabular_output_params(
+                    Command::new("tail")
+                        .about("Displays a sample of most recent records in a dataset")
+                        .args([
+                            Arg::new("dataset")
+                                .required(true)
+                                .index(1)
+                                .value_parser(value_parse_dataset_ref_local_at)
+                                .help("Local dataset reference, optionally followed by `@<tag>` or `@refs/branches/<branch>`"),
                             Arg::new("num-records")
                                 .long("num-records")
                                 .short('n')
@@ -1205,6 +1440,24 @@ pub fn cli() -> Command {
                                 "Check only the hashes of metadata ",
                                 "and data without replaying transformations"
                             )),
+                        Arg::new("continue-on-error")
+                            .long("continue-on-error")
+                            .action(ArgAction::SetTrue)
+                            .help(
+                                "Report all problems found instead of stopping at the first one",
+                            ),
+                        Arg::new("allow-unsigned")
+                            .long("allow-unsigned")
+                            .action(ArgAction::SetTrue)
+                            .help(
+                                "Accept legacy datasets created before metadata blocks were signed",
+                            ),
+                        Arg::new("output-format")
+                            .long("output-format")
+                            .short('o')
+                            .value_name("FMT")
+                            .value_parser(["table", "json"])
+                            .help("Format of the verification report"),
                         Arg::new("dataset")
                             .action(ArgAction::Append)
                             .index(1)
@@ -1255,12 +1508,23 @@ pub fn cli() -> Command {
                     or during the transmission:
 
                         kamu verify --integrity com.example.deriv
+
+                    Check the entire chain reporting all problems found in a machine-readable \
+                    format, e.g. to use as a gate in CI:
+
+                        kamu verify --continue-on-error --output-format json com.example.deriv
                     "
                     )),
             ],
         )
 }
 
+fn value_parse_duration(s: &str) -> Result<chrono::Duration, String> {
+    let dur = duration_string::DurationString::from_string(s.to_owned())
+        .map_err(|_| format!("Duration should be in form: `30s`, `15m`, `1h`"))?;
+    chrono::Duration::from_std(dur.into()).map_err(|e| e.to_string())
+}
+
 fn value_parse_dataset_name(s: &str) -> Result<DatasetName, String> {
     match DatasetName::try_from(s) {
         Ok(v) => Ok(v),
@@ -1290,6 +1554,25 @@ fn value_parse_dataset_ref_local(s: &str) -> Result<DatasetRefLocal, String> {
     }
 }
 
+fn value_parse_dataset_ref_local_at(s: &str) -> Result<DatasetRefLocalAt, String> {
+    match DatasetRefLocalAt::try_from(s) {
+        Ok(v) => Ok(v),
+        Err(_) => Err(format!(
+            "Local reference should be in form: `my.dataset.id`, `my.dataset.id@tag`, \
+            or `my.dataset.id@refs/branches/branch`",
+        )),
+    }
+}
+
+fn value_parse_block_ref_name(s: &str) -> Result<String, String> {
+    match BlockRef::tag(s) {
+        Ok(_) => Ok(s.to_owned()),
+        Err(_) => Err(format!(
+            "Reference name can only contain alphanumerics, dashes, underscores, and dots",
+        )),
+    }
+}
+
 fn value_parse_dataset_ref_remote(s: &str) -> Result<DatasetRefRemote, String> {
     match DatasetRefRemote::try_from(s) {
         Ok(v) => Ok(v),
@@ -1327,6 +1610,19 @@ fn value_parse_multihash(s: &str) -> Result<Multihash, String> {
     }
 }
 
+fn value_parse_query_as_of(s: &str) -> Result<QueryAsOf, String> {
+    if let Ok(hash) = Multihash::try_from(s) {
+        return Ok(QueryAsOf::Block(hash));
+    }
+    match chrono::DateTime::parse_from_rfc3339(s) {
+        Ok(t) => Ok(QueryAsOf::SystemTime(t.into())),
+        Err(_) => Err(format!(
+            "Value should be either a block hash or a system time in RFC3339 format, \
+            e.g. `2022-01-01T00:00:00Z`",
+        )),
+    }
+}
+
 fn validate_log_filter(s: &str) -> Result<String, String> {
     let items: Vec<_> = s.split(',').collect();
     for item in items {
@@ -0,0 +1,262 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::{CLIError, Command};
+use crate::{output::*, records_writers::TableWriter};
+use kamu::domain::*;
+use opendatafabric::*;
+
+use std::sync::Arc;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BlockRefKind {
+    Tag,
+    Branch,
+}
+
+impl BlockRefKind {
+    fn title(&self) -> &'static str {
+        match self {
+            BlockRefKind::Tag => "Tag",
+            BlockRefKind::Branch => "Branch",
+        }
+    }
+
+    fn new_ref(&self, name: &str) -> Result<BlockRef, CLIError> {
+        match self {
+            BlockRefKind::Tag => BlockRef::tag(name),
+            BlockRefKind::Branch => BlockRef::branch(name),
+        }
+        .map_err(|e| CLIError::usage_error_from(e))
+    }
+
+    fn matches(&self, r: &BlockRef) -> bool {
+        match (self, r) {
+            (BlockRefKind::Tag, BlockRef::Tag(_)) => true,
+            (BlockRefKind::Branch, BlockRef::Branch(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Lists, creates, and deletes tags or branches of a dataset
+pub struct BlockRefCommand {
+    local_repo: Arc<dyn LocalDatasetRepository>,
+    output_config: Arc<OutputConfig>,
+    kind: BlockRefKind,
+    dataset_ref: DatasetRefLocal,
+    name: Option<String>,
+    block_hash: Option<Multihash>,
+    delete: bool,
+}
+
+impl BlockRefCommand {
+    pub fn new(
+        local_repo: Arc<dyn LocalDatasetRepository>,
+        output_config: Arc<OutputConfig>,
+        kind: BlockRefKind,
+        dataset_ref: DatasetRefLocal,
+        name: Option<&str>,
+        block_hash: Option<Multihash>,
+        delete: bool,
+    ) -> Self {
+        Self {
+            local_repo,
+            output_config,
+            kind,
+            dataset_ref,
+            name: name.map(|s| s.to_owned()),
+            block_hash,
+            delete,
+        }
+    }
+
+    async fn list(&self, dataset: &dyn Dataset) -> Result<(), CLIError> {
+        let chain = dataset.as_metadata_chain();
+
+        let mut refs = Vec::new();
+        for r in chain
+            .as_reference_repo()
+            .list()
+            .await
+            .map_err(|e| CLIError::critical(e))?
+        {
+            if self.kind.matches(&r) {
+                let hash = chain.get_ref(&r).await?;
+                refs.push((r, hash));
+            }
+        }
+
+        match self.output_config.format {
+            OutputFormat::Table => {
+                use prettytable::*;
+
+                let mut table = Table::new();
+                table.set_format(TableWriter::get_table_format());
+                table.set_titles(row![bc->self.kind.title(), bc->"Block"]);
+
+                for (r, hash) in &refs {
+                    table.add_row(Row::new(vec![
+                        Cell::new(r.name()),
+                        Cell::new(&hash.to_multibase_string()),
+                    ]));
+                }
+
+                // Header doesn't render when there are no data rows in the table
+                if refs.is_empty() {
+                    table.add_row(Row::new(vec![Cell::new(""), Cell::new("")]));
+                }
+
+                table.printstd();
+            }
+            OutputFormat::Csv | OutputFormat::Json | OutputFormat::JsonLD => {
+                use datafusion::arrow::array::StringArray;
+                use datafusion::arrow::datatypes::{DataType, Field, Schema};
+                use datafusion::arrow::record_batch::RecordBatch;
+
+                let schema = Arc::new(Schema::new(vec![
+                    Field::new(self.kind.title(), DataType::Utf8, false),
+                    Field::new("Block", DataType::Utf8, false),
+                ]));
+
+                let records = RecordBatch::try_new(
+                    schema,
+                    vec![
+                        Arc::new(StringArray::from(
+                            refs.iter().map(|(r, _)| r.name()).collect::<Vec<_>>(),
+                        )),
+                        Arc::new(StringArray::from(
+                            refs.iter()
+                                .map(|(_, hash)| hash.to_multibase_string())
+                                .collect::<Vec<_>>(),
+                        )),
+                    ],
+                )
+                .map_err(CLIError::critical)?;
+
+                let mut writer = self
+                    .output_config
+                    .get_records_writer(RecordsFormat::new())?;
+                writer.write_batches(&[records])?;
+                writer.finish()?;
+            }
+            OutputFormat::JsonSoA => {
+                return Err(CLIError::usage_error(format!(
+                    "Listing {}s in {:?} format is not supported",
+                    self.kind.title().to_lowercase(),
+                    self.output_config.format
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn create(&self, dataset: &dyn Dataset, block_ref: &BlockRef) -> Result<(), CLIError> {
+        let chain = dataset.as_metadata_chain();
+
+        let hash = match &self.block_hash {
+            Some(hash) => hash.clone(),
+            None => chain.get_ref(&BlockRef::Head).await?,
+        };
+
+        // Tags are immutable while branches can be moved freely
+        let check_ref_is = match self.kind {
+            BlockRefKind::Tag => Some(None),
+            BlockRefKind::Branch => None,
+        };
+
+        match chain
+            .set_ref(
+                block_ref,
+                &hash,
+                SetRefOpts {
+                    validate_block_present: true,
+                    check_ref_is,
+                },
+            )
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(SetRefError::CASFailed(_)) => Err(CLIError::usage_error(format!(
+                "{} {} already exists",
+                self.kind.title(),
+                block_ref.name()
+            ))),
+            Err(e @ SetRefError::BlockNotFound(_)) => Err(CLIError::usage_error_from(e)),
+            Err(e) => Err(CLIError::critical(e)),
+        }?;
+
+        eprintln!(
+            "{}: {} -> {}",
+            console::style(format!("{} created", self.kind.title()))
+                .green()
+                .bold(),
+            block_ref.name(),
+            hash.short(),
+        );
+
+        Ok(())
+    }
+
+    async fn delete(&self, dataset: &dyn Dataset, block_ref: &BlockRef) -> Result<(), CLIError> {
+        let chain = dataset.as_metadata_chain();
+
+        if chain.try_get_ref(block_ref).await?.is_none() {
+            return Err(CLIError::usage_error(format!(
+                "{} {} does not exist",
+                self.kind.title(),
+                block_ref.name()
+            )));
+        }
+
+        chain
+            .as_reference_repo()
+            .delete(block_ref)
+            .await
+            .map_err(|e| CLIError::critical(e))?;
+
+        eprintln!(
+            "{}: {}",
+            console::style(format!("{} deleted", self.kind.title()))
+                .green()
+                .bold(),
+            block_ref.name(),
+        );
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Command for BlockRefCommand {
+    async fn run(&mut self) -> Result<(), CLIError> {
+        let dataset = self.local_repo.get_dataset(&self.dataset_ref).await?;
+
+        match &self.name {
+            None if self.delete => Err(CLIError::usage_error(format!(
+                "Specify the name of the {} to delete",
+                self.kind.title().to_lowercase()
+            ))),
+            None => self.list(dataset.as_ref()).await,
+            Some(name) => {
+                let block_ref = self.kind.new_ref(name)?;
+                if self.delete {
+                    self.delete(dataset.as_ref(), &block_ref).await
+                } else {
+                    self.create(dataset.as_ref(), &block_ref).await
+                }
+            }
+        }
+    }
+}
@@ -127,7 +127,7 @@ impl Command for ListCommand {
                 ]
             });
 
-        let mut writer = self.output_config.get_records_writer(records_format);
+        let mut writer = self.output_config.get_records_writer(records_format)?;
 
         let schema = Arc::new(Schema::new(if self.detail_level == 0 {
             vec![
@@ -23,6 +23,9 @@ pub use alias_delete_command::*;
 mod alias_list_command;
 pub use alias_list_command::*;
 
+mod block_ref_command;
+pub use block_ref_command::*;
+
 mod complete_command;
 pub use complete_command::*;
 
@@ -92,9 +95,15 @@ pub use set_watermark_command::*;
 mod sql_server_command;
 pub use sql_server_command::*;
 
+mod sql_server_flightsql_command;
+pub use sql_server_flightsql_command::*;
+
 mod sql_server_livy_command;
 pub use sql_server_livy_command::*;
 
+mod sql_server_pg_command;
+pub use sql_server_pg_command::*;
+
 mod sql_shell_command;
 pub use sql_shell_command::*;
 
@@ -110,6 +119,12 @@ pub use system_api_server_gql_schema_command::*;
 mod system_api_server_run_command;
 pub use system_api_server_run_command::*;
 
+mod system_gc_command;
+pub use system_gc_command::*;
+
+mod system_info_command;
+pub use system_info_command::*;
+
 mod system_ipfs_add_command;
 pub use system_ipfs_add_command::*;
 
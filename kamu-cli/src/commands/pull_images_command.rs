@@ -14,9 +14,11 @@ use crate::JupyterConfig;
 use super::{CLIError, Command};
 use container_runtime::ContainerRuntime;
 use kamu::infra::utils::docker_images;
+use kamu::infra::EngineProvisionerLocalConfig;
 
 pub struct PullImagesCommand {
     container_runtime: Arc<ContainerRuntime>,
+    engine_prov_config: Arc<EngineProvisionerLocalConfig>,
     pull_test_deps: bool,
     list_only: bool,
 }
@@ -24,11 +26,13 @@ pub struct PullImagesCommand {
 impl PullImagesCommand {
     pub fn new<'a>(
         container_runtime: Arc<ContainerRuntime>,
+        engine_prov_config: Arc<EngineProvisionerLocalConfig>,
         pull_test_deps: bool,
         list_only: bool,
     ) -> Self {
         Self {
             container_runtime,
+            engine_prov_config,
             pull_test_deps,
             list_only,
         }
@@ -42,20 +46,30 @@ impl Command for PullImagesCommand {
     }
 
     async fn run(&mut self) -> Result<(), CLIError> {
-        let mut images = vec![
-            docker_images::SPARK,
-            docker_images::FLINK,
-            JupyterConfig::IMAGE,
-        ];
+        // Includes the engines declared in the config
+        let mut images: Vec<String> = self
+            .engine_prov_config
+            .engine_specs()
+            .into_iter()
+            .map(|spec| spec.image)
+            .collect();
+
+        images.push(JupyterConfig::IMAGE.to_owned());
 
         if self.pull_test_deps {
-            images.extend(vec![
-                docker_images::HTTPD,
-                docker_images::FTP,
-                docker_images::MINIO,
-            ])
+            images.extend(
+                [
+                    docker_images::HTTPD,
+                    docker_images::FTP,
+                    docker_images::MINIO,
+                ]
+                .map(str::to_owned),
+            )
         }
 
+        let mut seen = std::collections::HashSet::new();
+        images.retain(|img| seen.insert(img.clone()));
+
         if self.list_only {
             for img in images {
                 println!("{}", img);
@@ -64,7 +78,7 @@ impl Command for PullImagesCommand {
             for img in images {
                 eprintln!("{}: {}", console::style("Pulling image").bold(), img);
                 self.container_runtime
-                    .pull_cmd(img)
+                    .pull_cmd(&img)
                     .status()?
                     .exit_ok()
                     .map_err(|e| CLIError::failure(e))?;
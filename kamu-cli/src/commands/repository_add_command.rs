@@ -18,6 +18,7 @@ pub struct RepositoryAddCommand {
     remote_repo_reg: Arc<dyn RemoteRepositoryRegistry>,
     name: RepositoryName,
     url: String,
+    auth_token: Option<String>,
 }
 
 impl RepositoryAddCommand {
@@ -25,6 +26,7 @@ impl RepositoryAddCommand {
         remote_repo_reg: Arc<dyn RemoteRepositoryRegistry>,
         name: RepositoryName,
         url: S,
+        auth_token: Option<String>,
     ) -> Self
     where
         S: Into<String>,
@@ -33,6 +35,7 @@ impl RepositoryAddCommand {
             remote_repo_reg,
             name,
             url: url.into(),
+            auth_token,
         }
     }
 }
@@ -49,6 +52,12 @@ impl Command for RepositoryAddCommand {
             .add_repository(&self.name, url)
             .map_err(CLIError::failure)?;
 
+        if self.auth_token.is_some() {
+            self.remote_repo_reg
+                .set_repository_auth_token(&self.name, self.auth_token.clone())
+                .map_err(CLIError::failure)?;
+        }
+
         eprintln!("{}: {}", console::style("Added").green(), &self.name);
         Ok(())
     }
@@ -12,6 +12,8 @@ use crate::{output::*, records_writers::TableWriter};
 use kamu::domain::*;
 use opendatafabric::*;
 
+use chrono::{DateTime, Utc};
+use chrono_humanize::HumanTime;
 use std::sync::Arc;
 
 pub struct SearchCommand {
@@ -40,10 +42,24 @@ impl SearchCommand {
         }
     }
 
+    fn humanize_relative_date(last_updated: DateTime<Utc>) -> String {
+        format!("{}", HumanTime::from(last_updated - Utc::now()))
+    }
+
+    fn humanize_data_size(size: u64) -> String {
+        use humansize::{format_size, BINARY};
+        format_size(size, BINARY)
+    }
+
+    fn humanize_quantity(num: u64) -> String {
+        use num_format::{Locale, ToFormattedString};
+        num.to_formatted_string(&Locale::en)
+    }
+
     fn print_table(&self, mut search_result: SearchResult) {
         use prettytable::*;
 
-        search_result.datasets.sort();
+        search_result.datasets.sort_by(|a, b| a.name.cmp(&b.name));
 
         let mut table = Table::new();
         table.set_format(TableWriter::get_table_format());
@@ -52,15 +68,24 @@ impl SearchCommand {
             row![bc->"Name", bc->"Kind", bc->"Description", bc->"Updated", bc->"Records", bc->"Size"],
         );
 
-        for name in &search_result.datasets {
-            table.add_row(Row::new(vec![
-                Cell::new(&name.to_string()),
-                Cell::new("-"),
-                Cell::new("-"),
-                Cell::new("-"),
-                Cell::new("-"),
-                Cell::new("-"),
-            ]));
+        for dataset in &search_result.datasets {
+            table.add_row(row![
+                dataset.name.to_string(),
+                c->dataset.kind.map(|k| format!("{:?}", k)).unwrap_or("-".to_owned()),
+                c->"-",
+                c->dataset
+                    .last_updated
+                    .map(Self::humanize_relative_date)
+                    .unwrap_or("-".to_owned()),
+                r->dataset
+                    .num_records
+                    .map(Self::humanize_quantity)
+                    .unwrap_or("-".to_owned()),
+                r->dataset
+                    .estimated_size
+                    .map(Self::humanize_data_size)
+                    .unwrap_or("-".to_owned()),
+            ]);
         }
 
         // Header doesn't render when there are no data rows in the table
@@ -81,13 +106,31 @@ impl SearchCommand {
     fn print_csv(&self, mut search_result: SearchResult) {
         use std::io::Write;
 
-        search_result.datasets.sort();
+        search_result.datasets.sort_by(|a, b| a.name.cmp(&b.name));
 
         let mut out = std::io::stdout();
-        write!(out, "Name\n").unwrap();
-
-        for name in &search_result.datasets {
-            write!(out, "{}\n", name).unwrap();
+        write!(out, "Name,Kind,Updated,Records,Size\n").unwrap();
+
+        for dataset in &search_result.datasets {
+            write!(
+                out,
+                "{},{},{},{},{}\n",
+                dataset.name,
+                dataset.kind.map(|k| format!("{:?}", k)).unwrap_or_default(),
+                dataset
+                    .last_updated
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_default(),
+                dataset
+                    .num_records
+                    .map(|n| n.to_string())
+                    .unwrap_or_default(),
+                dataset
+                    .estimated_size
+                    .map(|n| n.to_string())
+                    .unwrap_or_default(),
+            )
+            .unwrap();
         }
     }
 }
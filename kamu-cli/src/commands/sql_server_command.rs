@@ -53,7 +53,7 @@ impl Command for SqlServerCommand {
     async fn run(&mut self) -> Result<(), CLIError> {
         let sql_shell = SqlShellImpl::new(
             self.container_runtime.clone(),
-            self.engine_prov_config.spark_image.clone(),
+            self.engine_prov_config.spark_engine_image(),
         );
 
         let spinner = if self.output_config.verbosity_level == 0 && !self.output_config.quiet {
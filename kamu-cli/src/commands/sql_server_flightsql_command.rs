@@ -0,0 +1,62 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::{CLIError, Command};
+use crate::explore::FlightSqlServer;
+use crate::output::*;
+use kamu::domain::*;
+
+use console::style as s;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+pub struct SqlServerFlightSqlCommand {
+    query_svc: Arc<dyn QueryService>,
+    output_config: Arc<OutputConfig>,
+    address: IpAddr,
+    port: u16,
+}
+
+impl SqlServerFlightSqlCommand {
+    pub fn new(
+        query_svc: Arc<dyn QueryService>,
+        output_config: Arc<OutputConfig>,
+        address: IpAddr,
+        port: u16,
+    ) -> Self {
+        Self {
+            query_svc,
+            output_config,
+            address,
+            port,
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Command for SqlServerFlightSqlCommand {
+    async fn run(&mut self) -> Result<(), CLIError> {
+        let server = FlightSqlServer::new(self.query_svc.clone(), self.address, self.port).await?;
+
+        tracing::info!("Flight SQL server is listening on: {}", server.local_addr());
+
+        if !self.output_config.quiet {
+            eprintln!(
+                "{}\n  {}",
+                s("Flight SQL server is now running at:").green().bold(),
+                s(format!("grpc://{}", server.local_addr())).bold(),
+            );
+            eprintln!("{}", s("Use Ctrl+C to stop the server").yellow());
+        }
+
+        server.run().await.map_err(|e| CLIError::critical(e))?;
+
+        Ok(())
+    }
+}
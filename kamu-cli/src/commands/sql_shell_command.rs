@@ -9,53 +9,63 @@
 
 use super::common::PullImageProgress;
 use super::{CLIError, Command};
-use crate::explore::SqlShellImpl;
+use crate::explore::{SqlShellDataFusion, SqlShellImpl};
 use crate::output::*;
 use container_runtime::ContainerRuntime;
-use kamu::domain::{QueryOptions, QueryService};
+use kamu::domain::{LocalDatasetRepository, QueryAsOf, QueryError, QueryOptions, QueryService};
 use kamu::infra::*;
 
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
 pub struct SqlShellCommand {
     query_svc: Arc<dyn QueryService>,
+    local_repo: Arc<dyn LocalDatasetRepository>,
     workspace_layout: Arc<WorkspaceLayout>,
     engine_prov_config: Arc<EngineProvisionerLocalConfig>,
     output_config: Arc<OutputConfig>,
     container_runtime: Arc<ContainerRuntime>,
     command: Option<String>,
+    script: Option<PathBuf>,
     url: Option<String>,
     engine: Option<String>,
+    as_of: Vec<QueryAsOf>,
 }
 
 impl SqlShellCommand {
     pub fn new(
         query_svc: Arc<dyn QueryService>,
+        local_repo: Arc<dyn LocalDatasetRepository>,
         workspace_layout: Arc<WorkspaceLayout>,
         engine_prov_config: Arc<EngineProvisionerLocalConfig>,
         output_config: Arc<OutputConfig>,
         container_runtime: Arc<ContainerRuntime>,
         command: Option<&str>,
+        script: Option<&str>,
         url: Option<&str>,
         engine: Option<&str>,
+        as_of: impl Iterator<Item = QueryAsOf>,
     ) -> Self {
         Self {
             query_svc,
+            local_repo,
             workspace_layout,
             engine_prov_config,
             output_config,
             container_runtime,
             command: command.map(|v| v.to_owned()),
+            script: script.map(PathBuf::from),
             url: url.map(|v| v.to_owned()),
             engine: engine.map(|v| v.to_owned()),
+            as_of: as_of.collect(),
         }
     }
 
     fn run_spark_shell(&self) -> Result<(), CLIError> {
         let sql_shell = SqlShellImpl::new(
             self.container_runtime.clone(),
-            self.engine_prov_config.spark_image.clone(),
+            self.engine_prov_config.spark_engine_image(),
         );
 
         let spinner = if self.output_config.verbosity_level == 0 && !self.output_config.quiet {
@@ -99,35 +109,88 @@ impl SqlShellCommand {
         Ok(())
     }
 
-    async fn run_datafusion_command(&self) -> Result<(), CLIError> {
-        let df = self
-            .query_svc
-            .sql_statement(self.command.as_ref().unwrap(), QueryOptions::default())
+    async fn query_options(&self) -> Result<QueryOptions, CLIError> {
+        if self.as_of.is_empty() {
+            return Ok(QueryOptions::default());
+        }
+
+        let mut blocks = Vec::new();
+        let mut system_time = None;
+        for as_of in &self.as_of {
+            match as_of {
+                QueryAsOf::Block(hash) => blocks.push(hash.clone()),
+                QueryAsOf::SystemTime(t) if system_time.is_none() => system_time = Some(*t),
+                QueryAsOf::SystemTime(_) => {
+                    return Err(CLIError::usage_error(
+                        "System time can only be specified once",
+                    ))
+                }
+            }
+        }
+
+        self.query_svc
+            .options_as_of(&blocks, system_time)
             .await
-            .map_err(|e| CLIError::failure(e))?;
+            .map_err(|e| match e {
+                e @ QueryError::BlockNotFound(_) => CLIError::usage_error_from(e),
+                e => CLIError::failure(e),
+            })
+    }
 
-        let records = df.collect().await.map_err(|e| CLIError::failure(e))?;
+    async fn datafusion_shell(&self) -> Result<SqlShellDataFusion, CLIError> {
+        let options = self.query_options().await?;
 
-        let mut writer = self
-            .output_config
-            .get_records_writer(RecordsFormat::default());
-        writer.write_batches(&records)?;
-        writer.finish()?;
+        Ok(SqlShellDataFusion::new(
+            self.query_svc.clone(),
+            self.local_repo.clone(),
+            self.output_config.clone(),
+            self.workspace_layout.run_info_dir.clone(),
+            options,
+        ))
+    }
 
-        Ok(())
+    async fn run_datafusion_shell(&self) -> Result<(), CLIError> {
+        self.datafusion_shell().await?.run().await
+    }
+
+    async fn run_datafusion_script(&self) -> Result<(), CLIError> {
+        let script = match (&self.command, &self.script) {
+            (Some(command), None) => command.clone(),
+            (None, Some(path)) => std::fs::read_to_string(path).map_err(|e| {
+                CLIError::usage_error(format!("Cannot read script {}: {}", path.display(), e))
+            })?,
+            _ => unreachable!(),
+        };
+
+        self.datafusion_shell().await?.run_script(&script).await
     }
 }
 
 #[async_trait::async_trait(?Send)]
 impl Command for SqlShellCommand {
     async fn run(&mut self) -> Result<(), CLIError> {
+        if self.command.is_some() && self.script.is_some() {
+            return Err(CLIError::usage_error(
+                "Command and script cannot be specified at the same time",
+            ));
+        }
+
         match (
             self.engine.as_ref().map(|s| s.as_str()),
-            &self.command,
+            self.command.is_some() || self.script.is_some(),
             &self.url,
         ) {
-            (Some("datafusion"), Some(_), None) => self.run_datafusion_command().await,
-            (Some("datafusion"), _, _) => Err(CLIError::usage_error("DataFusion engine currently doesn't have a shell and only supports single command execution")),
+            (Some("datafusion"), _, Some(_)) => Err(CLIError::usage_error(
+                "DataFusion engine does not support connecting to a remote server",
+            )),
+            (Some("datafusion"), true, None) => self.run_datafusion_script().await,
+            (Some("datafusion"), false, None) => self.run_datafusion_shell().await,
+            (Some("spark") | None, _, _) if self.script.is_some() => Err(CLIError::usage_error(
+                "Executing scripts is currently only supported by DataFusion engine",
+            )),
+            (Some("spark") | None, _, _) if !self.as_of.is_empty() => Err(CLIError::usage_error(
+                "Querying data as of a point in history is only supported by DataFusion engine",
+            )),
             (Some("spark") | None, _, _) => self.run_spark_shell(),
             _ => unreachable!(),
         }
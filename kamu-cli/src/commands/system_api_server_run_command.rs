@@ -19,6 +19,8 @@ pub struct APIServerRunCommand {
     output_config: Arc<OutputConfig>,
     address: Option<IpAddr>,
     port: Option<u16>,
+    serve_datasets: bool,
+    auth_token: Option<String>,
 }
 
 impl APIServerRunCommand {
@@ -27,12 +29,16 @@ impl APIServerRunCommand {
         output_config: Arc<OutputConfig>,
         address: Option<IpAddr>,
         port: Option<u16>,
+        serve_datasets: bool,
+        auth_token: Option<String>,
     ) -> Self {
         Self {
             catalog,
             output_config,
             address,
             port,
+            serve_datasets,
+            auth_token,
         }
     }
 }
@@ -41,8 +47,13 @@ impl APIServerRunCommand {
 impl Command for APIServerRunCommand {
     async fn run(&mut self) -> Result<(), CLIError> {
         // TODO: Cloning catalog is too expensive currently
-        let api_server =
-            crate::explore::APIServer::new(self.catalog.clone(), self.address, self.port);
+        let api_server = crate::explore::APIServer::new(
+            self.catalog.clone(),
+            self.address,
+            self.port,
+            self.serve_datasets,
+            self.auth_token.clone(),
+        );
 
         tracing::info!(
             "API server is listening on: http://{}",
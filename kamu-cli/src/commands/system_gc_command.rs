@@ -0,0 +1,117 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::{CLIError, Command};
+use kamu::domain::*;
+use opendatafabric::*;
+
+use futures::TryStreamExt;
+use std::sync::Arc;
+
+///////////////////////////////////////////////////////////////////////////////
+// Command
+///////////////////////////////////////////////////////////////////////////////
+
+pub struct SystemGcCommand {
+    local_repo: Arc<dyn LocalDatasetRepository>,
+    gc_svc: Arc<dyn GcService>,
+    dataset_refs: Vec<DatasetRefLocal>,
+    dry_run: bool,
+    min_age: chrono::Duration,
+}
+
+impl SystemGcCommand {
+    pub fn new<I>(
+        local_repo: Arc<dyn LocalDatasetRepository>,
+        gc_svc: Arc<dyn GcService>,
+        dataset_refs: I,
+        dry_run: bool,
+        min_age: chrono::Duration,
+    ) -> Self
+    where
+        I: Iterator<Item = DatasetRefLocal>,
+    {
+        Self {
+            local_repo,
+            gc_svc,
+            dataset_refs: dataset_refs.collect(),
+            dry_run,
+            min_age,
+        }
+    }
+
+    fn humanize_data_size(size: u64) -> String {
+        use humansize::{format_size, BINARY};
+        format_size(size, BINARY)
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Command for SystemGcCommand {
+    async fn run(&mut self) -> Result<(), CLIError> {
+        let dataset_handles: Vec<DatasetHandle> = if self.dataset_refs.is_empty() {
+            self.local_repo.get_all_datasets().try_collect().await?
+        } else {
+            let mut handles = Vec::new();
+            for dataset_ref in &self.dataset_refs {
+                handles.push(self.local_repo.resolve_dataset_ref(dataset_ref).await?);
+            }
+            handles
+        };
+
+        let mut total = GcResult::default();
+
+        for hdl in &dataset_handles {
+            let res = self
+                .gc_svc
+                .gc(
+                    &hdl.as_local_ref(),
+                    GcOptions {
+                        dry_run: self.dry_run,
+                        min_age: self.min_age,
+                    },
+                )
+                .await
+                .map_err(|e| CLIError::failure(e))?;
+
+            if res.num_objects() != 0 {
+                eprintln!(
+                    "{}: {} blocks, {} data slices, {} checkpoints ({})",
+                    hdl.name,
+                    res.num_blocks,
+                    res.num_data_slices,
+                    res.num_checkpoints,
+                    Self::humanize_data_size(res.bytes_reclaimed),
+                );
+            }
+
+            total += res;
+        }
+
+        let msg = if self.dry_run {
+            "Would reclaim"
+        } else {
+            "Reclaimed"
+        };
+
+        eprintln!(
+            "{}",
+            console::style(format!(
+                "{} {} in {} unreachable objects",
+                msg,
+                Self::humanize_data_size(total.bytes_reclaimed),
+                total.num_objects()
+            ))
+            .green()
+            .bold()
+        );
+
+        Ok(())
+    }
+}
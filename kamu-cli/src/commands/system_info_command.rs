@@ -0,0 +1,111 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::{CLIError, Command};
+use kamu::infra::*;
+
+use container_runtime::{ContainerRuntime, ContainerRuntimeType, NetworkNamespaceType};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+///////////////////////////////////////////////////////////////////////////////
+// Command
+///////////////////////////////////////////////////////////////////////////////
+
+pub struct SystemInfoCommand {
+    workspace_layout: Arc<WorkspaceLayout>,
+    container_runtime: Arc<ContainerRuntime>,
+    engine_prov_config: Arc<EngineProvisionerLocalConfig>,
+}
+
+impl SystemInfoCommand {
+    pub fn new(
+        workspace_layout: Arc<WorkspaceLayout>,
+        container_runtime: Arc<ContainerRuntime>,
+        engine_prov_config: Arc<EngineProvisionerLocalConfig>,
+    ) -> Self {
+        Self {
+            workspace_layout,
+            container_runtime,
+            engine_prov_config,
+        }
+    }
+
+    fn collect(&self) -> SystemInfo {
+        let mut engines = vec![EngineInfo {
+            id: DataFusionEngine::ENGINE_ID.to_owned(),
+            image: None,
+            grpc_port: None,
+            pulled: None,
+        }];
+
+        engines.extend(
+            self.engine_prov_config
+                .engine_specs()
+                .into_iter()
+                .map(|spec| EngineInfo {
+                    pulled: Some(self.container_runtime.has_image(&spec.image)),
+                    id: spec.id,
+                    image: Some(spec.image),
+                    grpc_port: Some(spec.grpc_port),
+                }),
+        );
+
+        SystemInfo {
+            version: crate::VERSION.to_owned(),
+            workspace_dir: if crate::in_workspace(self.workspace_layout.clone()) {
+                Some(self.workspace_layout.root_dir.clone())
+            } else {
+                None
+            },
+            container_runtime: self.container_runtime.config.runtime,
+            network_ns: self.container_runtime.config.network_ns,
+            engines,
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Command for SystemInfoCommand {
+    fn needs_workspace(&self) -> bool {
+        false
+    }
+
+    async fn run(&mut self) -> Result<(), CLIError> {
+        let info = self.collect();
+        print!("{}", serde_yaml::to_string(&info).map_err(CLIError::critical)?);
+        Ok(())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemInfo {
+    version: String,
+    workspace_dir: Option<PathBuf>,
+    container_runtime: ContainerRuntimeType,
+    network_ns: NetworkNamespaceType,
+    engines: Vec<EngineInfo>,
+}
+
+/// Engines without an image run in-process
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EngineInfo {
+    id: String,
+    image: Option<String>,
+    grpc_port: Option<u16>,
+    pulled: Option<bool>,
+}
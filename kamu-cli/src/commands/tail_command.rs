@@ -11,13 +11,13 @@ use std::sync::Arc;
 
 use super::{CLIError, Command};
 use crate::output::*;
+use crate::records_writers::write_stream;
 
-use kamu::domain::QueryService;
-use opendatafabric::*;
+use kamu::domain::*;
 
 pub struct TailCommand {
     query_svc: Arc<dyn QueryService>,
-    dataset_ref: DatasetRefLocal,
+    dataset_ref: DatasetRefLocalAt,
     num_records: u64,
     output_cfg: Arc<OutputConfig>,
 }
@@ -25,7 +25,7 @@ pub struct TailCommand {
 impl TailCommand {
     pub fn new(
         query_svc: Arc<dyn QueryService>,
-        dataset_ref: DatasetRefLocal,
+        dataset_ref: DatasetRefLocalAt,
         num_records: u64,
         output_cfg: Arc<OutputConfig>,
     ) -> Self {
@@ -43,14 +43,25 @@ impl Command for TailCommand {
     async fn run(&mut self) -> Result<(), CLIError> {
         let df = self
             .query_svc
-            .tail(&self.dataset_ref, self.num_records)
+            .tail(
+                &self.dataset_ref.dataset_ref,
+                self.dataset_ref.block_ref.as_ref(),
+                self.num_records,
+            )
             .await
             .map_err(|e| CLIError::failure(e))?;
 
-        let record_batches = df.collect().await.map_err(|e| CLIError::failure(e))?;
+        let stream = df
+            .execute_stream()
+            .await
+            .map_err(|e| CLIError::failure(e))?;
 
-        let mut writer = self.output_cfg.get_records_writer(RecordsFormat::default());
-        writer.write_batches(&record_batches)?;
+        let mut writer = self
+            .output_cfg
+            .get_records_writer(RecordsFormat::default())?;
+        write_stream(writer.as_mut(), stream)
+            .await
+            .map_err(|e| CLIError::failure(e))?;
         writer.finish()?;
         Ok(())
     }
@@ -17,6 +17,7 @@ use std::{
 
 use kamu::domain::*;
 use opendatafabric::*;
+use serde::Serialize;
 
 use super::{BatchError, CLIError, Command};
 use crate::output::OutputConfig;
@@ -34,36 +35,61 @@ type GenericVerificationResult = Result<
 ///////////////////////////////////////////////////////////////////////////////
 
 pub struct VerifyCommand {
-    local_repo: Arc<dyn LocalDatasetRepository>,
     verification_svc: Arc<dyn VerificationService>,
     output_config: Arc<OutputConfig>,
     refs: Vec<DatasetRefLocal>,
     recursive: bool,
     integrity: bool,
+    continue_on_error: bool,
+    allow_unsigned: bool,
+    output_format: Option<String>,
 }
 
 impl VerifyCommand {
     pub fn new<I>(
-        local_repo: Arc<dyn LocalDatasetRepository>,
         verification_svc: Arc<dyn VerificationService>,
         output_config: Arc<OutputConfig>,
         refs: I,
         recursive: bool,
         integrity: bool,
+        continue_on_error: bool,
+        allow_unsigned: bool,
+        output_format: Option<&str>,
     ) -> Self
     where
         I: Iterator<Item = DatasetRefLocal>,
     {
         Self {
-            local_repo,
             verification_svc,
             output_config,
             refs: refs.collect(),
             recursive,
             integrity,
+            continue_on_error,
+            allow_unsigned,
+            output_format: output_format.map(|s| s.to_owned()),
         }
     }
 
+    fn print_json_report(
+        &self,
+        verification_results: &[(
+            DatasetRefLocal,
+            Result<VerificationResult, VerificationError>,
+        )],
+    ) -> Result<(), CLIError> {
+        let reports: Vec<_> = verification_results
+            .iter()
+            .map(|(dataset_ref, res)| DatasetReport::new(dataset_ref, res))
+            .collect();
+
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&reports).map_err(CLIError::critical)?
+        );
+        Ok(())
+    }
+
     async fn verify_with_progress(
         &self,
         options: VerificationOptions,
@@ -88,70 +114,62 @@ impl VerifyCommand {
         options: VerificationOptions,
         listener: Option<Arc<VerificationMultiProgress>>,
     ) -> GenericVerificationResult {
-        let dataset_handle = self
-            .local_repo
-            .resolve_dataset_ref(self.refs.first().unwrap())
-            .await?;
-
-        let listener = listener.and_then(|l| l.begin_verify(&dataset_handle));
+        let mut requests = self.refs.iter().map(|r| VerificationRequest {
+            dataset_ref: r.clone(),
+            block_range: (None, None),
+        });
 
-        let res = self
+        Ok(self
             .verification_svc
-            .verify(
-                &dataset_handle.as_local_ref(),
-                (None, None),
+            .verify_multi(
+                &mut requests,
                 options,
-                listener,
+                listener.map(|l| l as Arc<dyn VerificationMultiListener>),
             )
-            .await;
-
-        Ok(vec![(dataset_handle.into(), res)])
+            .await)
     }
 }
 
 #[async_trait::async_trait(?Send)]
 impl Command for VerifyCommand {
     async fn run(&mut self) -> Result<(), CLIError> {
-        if self.refs.len() != 1 {
-            return Err(CLIError::usage_error(
-                "Verifying multiple datasets at once is not yet supported",
-            ));
-        }
-
         if self.recursive {
             return Err(CLIError::usage_error(
                 "Verifying datasets recursively is not yet supported",
             ));
         }
 
-        let options = if self.integrity {
-            VerificationOptions {
-                check_integrity: true,
-                replay_transformations: false,
-            }
-        } else {
-            VerificationOptions {
-                check_integrity: true,
-                replay_transformations: true,
-            }
+        let json_output = self.output_format.as_deref() == Some("json");
+
+        let options = VerificationOptions {
+            check_integrity: true,
+            replay_transformations: !self.integrity,
+            continue_on_error: self.continue_on_error,
+            max_concurrency: None,
+            allow_unsigned: self.allow_unsigned,
         };
 
         let verification_results = if self.output_config.is_tty
             && self.output_config.verbosity_level == 0
             && !self.output_config.quiet
+            && !json_output
         {
             self.verify_with_progress(options).await?
         } else {
             self.verify(options, None).await?
         };
 
+        if json_output {
+            self.print_json_report(&verification_results)?;
+        }
+
         let mut valid = 0;
-        let mut errors = 0;
+        let mut failed = 0;
 
         for (_, res) in verification_results.iter() {
             match res {
-                Ok(_) => valid += 1,
-                Err(_) => errors += 1,
+                Ok(VerificationResult::Valid) => valid += 1,
+                Ok(VerificationResult::Invalid(_)) | Err(_) => failed += 1,
             }
         }
 
@@ -163,12 +181,32 @@ impl Command for VerifyCommand {
                     .bold()
             );
         }
-        if errors != 0 {
+        if failed != 0 {
             Err(BatchError::new(
-                format!("Failed to verify {} dataset(s)", errors),
-                verification_results.into_iter().filter_map(|(id, res)| {
-                    res.err().map(|e| (e, format!("Failed to verify {}", id)))
-                }),
+                format!("Failed to verify {} dataset(s)", failed),
+                verification_results.into_iter().flat_map(
+                    |(id, res)| -> Vec<(VerificationError, String)> {
+                        match res {
+                            Ok(VerificationResult::Valid) => Vec::new(),
+                            Ok(VerificationResult::Invalid(report)) => report
+                                .problems
+                                .into_iter()
+                                .map(|p| {
+                                    (
+                                        p.error,
+                                        format!(
+                                            "Problem in {} at block {} ({:?})",
+                                            id,
+                                            p.block_hash.short(),
+                                            p.phase
+                                        ),
+                                    )
+                                })
+                                .collect(),
+                            Err(e) => vec![(e, format!("Failed to verify {}", id))],
+                        }
+                    },
+                ),
             )
             .into())
         } else {
@@ -322,6 +360,21 @@ impl VerificationListener for VerificationProgress {
                     None,
                 ))
             }
+            VerificationResult::Invalid(report) => {
+                let s = self.state.lock().unwrap();
+                self.curr_progress.finish_with_message(
+                    self.spinner_message(
+                        s.num_blocks,
+                        s.num_blocks,
+                        console::style(format!(
+                            "Dataset is invalid ({} problem(s) found)",
+                            report.problems.len()
+                        ))
+                        .red(),
+                        None,
+                    ),
+                )
+            }
         }
     }
 
@@ -334,6 +387,9 @@ impl VerificationListener for VerificationProgress {
             VerificationError::DataNotReproducible(..) => {
                 format!("Validation error (data is not reproducible)")
             }
+            VerificationError::InvalidSignature(..) => {
+                format!("Validation error (invalid block signature)")
+            }
             _ => format!("Error during transformation"),
         };
         self.curr_progress.finish_with_message(self.spinner_message(
@@ -349,6 +405,7 @@ impl VerificationListener for VerificationProgress {
             VerificationPhase::DataIntegrity => "Verifying data integrity",
             VerificationPhase::ReplayTransform => "Replaying transformations",
             VerificationPhase::MetadataIntegrity => "Verifying metadata integrity",
+            VerificationPhase::MetadataSignatures => "Verifying metadata signatures",
         };
         self.curr_progress.set_message(message);
     }
@@ -362,18 +419,22 @@ impl VerificationListener for VerificationProgress {
         num_blocks: usize,
         phase: VerificationPhase,
     ) {
-        self.save_state(block_hash, block_index, num_blocks, phase);
         match phase {
             VerificationPhase::MetadataIntegrity => unreachable!(),
-            VerificationPhase::DataIntegrity => {
+            VerificationPhase::MetadataSignatures => {
+                self.save_state(block_hash, block_index, num_blocks, phase);
                 self.curr_progress.set_message(self.spinner_message(
                     block_index + 1,
                     num_blocks,
-                    "Verifying data integrity",
+                    "Verifying block signature",
                     Some(block_hash),
                 ))
             }
+            // Data blocks are verified concurrently and only end in order,
+            // so the progress is tracked in end_block instead
+            VerificationPhase::DataIntegrity => {}
             VerificationPhase::ReplayTransform => {
+                self.save_state(block_hash, block_index, num_blocks, phase);
                 self.curr_progress.set_message(self.spinner_message(
                     block_index + 1,
                     num_blocks,
@@ -386,11 +447,30 @@ impl VerificationListener for VerificationProgress {
 
     fn end_block(
         &self,
-        _block_hash: &Multihash,
-        _block_index: usize,
-        _num_blocks: usize,
-        _phase: VerificationPhase,
+        block_hash: &Multihash,
+        block_index: usize,
+        num_blocks: usize,
+        phase: VerificationPhase,
     ) {
+        if phase != VerificationPhase::DataIntegrity {
+            return;
+        }
+
+        // Any error reported next belongs to the following block in the chain order
+        {
+            let mut s = self.state.lock().unwrap();
+            s.block_hash = None;
+            s.block_index = block_index + 1;
+            s.num_blocks = num_blocks;
+            s.phase = phase;
+        }
+
+        self.curr_progress.set_message(self.spinner_message(
+            block_index + 1,
+            num_blocks,
+            "Verifying data integrity",
+            Some(block_hash),
+        ));
     }
 
     fn get_transform_listener(self: Arc<Self>) -> Option<Arc<dyn TransformListener>> {
@@ -453,3 +533,81 @@ impl PullImageListener for VerificationProgress {
         ))
     }
 }
+
+///////////////////////////////////////////////////////////////////////////////
+// JSON report
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DatasetReport {
+    dataset: String,
+    valid: bool,
+    /// Error that prevented verification from completing
+    error: Option<String>,
+    problems: Vec<ProblemReport>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProblemReport {
+    block_hash: String,
+    phase: String,
+    kind: String,
+    message: String,
+}
+
+impl DatasetReport {
+    fn new(
+        dataset_ref: &DatasetRefLocal,
+        res: &Result<VerificationResult, VerificationError>,
+    ) -> Self {
+        let (error, problems) = match res {
+            Ok(VerificationResult::Valid) => (None, Vec::new()),
+            Ok(VerificationResult::Invalid(report)) => (
+                None,
+                report.problems.iter().map(ProblemReport::new).collect(),
+            ),
+            Err(e) => (Some(e.to_string()), Vec::new()),
+        };
+
+        Self {
+            dataset: dataset_ref.to_string(),
+            valid: error.is_none() && problems.is_empty(),
+            error,
+            problems,
+        }
+    }
+}
+
+impl ProblemReport {
+    fn new(problem: &VerificationProblem) -> Self {
+        let (kind, message) = match &problem.error {
+            VerificationError::DataDoesNotMatchMetadata(e) => {
+                ("DataDoesNotMatchMetadata", e.to_string())
+            }
+            VerificationError::CheckpointDoesNotMatchMetadata(e) => {
+                ("CheckpointDoesNotMatchMetadata", e.to_string())
+            }
+            VerificationError::DataNotReproducible(e) => (
+                "DataNotReproducible",
+                format!(
+                    "Replaying block {} produced non-equivalent block {}",
+                    e.expected_block_hash, e.actual_block_hash
+                ),
+            ),
+            VerificationError::InvalidSignature(e) => ("InvalidSignature", e.to_string()),
+            VerificationError::InvalidMetadataSequence(e) => {
+                ("InvalidMetadataSequence", e.to_string())
+            }
+            e => ("Other", e.to_string()),
+        };
+
+        Self {
+            block_hash: problem.block_hash.to_string(),
+            phase: format!("{:?}", problem.phase),
+            kind: kind.to_owned(),
+            message,
+        }
+    }
+}
@@ -12,7 +12,7 @@ use std::path::{Path, PathBuf};
 
 use container_runtime::{ContainerRuntimeType, NetworkNamespaceType};
 use kamu::infra::utils::docker_images;
-use kamu::infra::WorkspaceLayout;
+use kamu::infra::{ODFEngineSpec, WorkspaceLayout};
 use opendatafabric::serde::yaml::Manifest;
 
 use dill::*;
@@ -20,6 +20,7 @@ use duration_string::DurationString;
 use merge::Merge;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
+use std::collections::BTreeMap;
 use std::fmt::Write;
 use url::Url;
 
@@ -99,6 +100,8 @@ pub struct EngineConfig {
     /// UNSTABLE: Default engine images
     #[merge(strategy = merge_recursive)]
     pub images: Option<EngineImagesConfig>,
+    /// Additional engines that implement the ODF engine protocol
+    pub engines: Option<Vec<ODFEngineConfig>>,
 }
 
 impl EngineConfig {
@@ -110,6 +113,7 @@ impl EngineConfig {
             start_timeout: None,
             shutdown_timeout: None,
             images: None,
+            engines: None,
         }
     }
 
@@ -131,6 +135,7 @@ impl Default for EngineConfig {
             start_timeout: Some(DurationString::from_string("30s".to_owned()).unwrap()),
             shutdown_timeout: Some(DurationString::from_string("5s".to_owned()).unwrap()),
             images: Some(EngineImagesConfig::default()),
+            engines: Some(Vec::new()),
         }
     }
 }
@@ -171,6 +176,38 @@ impl Default for EngineImagesConfig {
 
 ////////////////////////////////////////////////////////////////////////////////////////
 
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct ODFEngineConfig {
+    /// Identifier that transforms use to refer to the engine
+    pub id: String,
+    /// Container image of the engine
+    pub image: String,
+    /// Port on which the engine adapter serves gRPC requests (defaults to 2884)
+    pub grpc_port: Option<u16>,
+    /// Environment variables passed to the engine container
+    pub env: Option<BTreeMap<String, String>>,
+    /// Maximum amount of memory the engine container can use (e.g. `4g`)
+    pub memory_limit: Option<String>,
+    /// Number of CPUs the engine container can use (e.g. `1.5`)
+    pub cpu_limit: Option<f64>,
+}
+
+impl From<ODFEngineConfig> for ODFEngineSpec {
+    fn from(value: ODFEngineConfig) -> Self {
+        Self {
+            grpc_port: value.grpc_port.unwrap_or(Self::DEFAULT_GRPC_PORT),
+            environment_vars: value.env.unwrap_or_default().into_iter().collect(),
+            memory_limit: value.memory_limit,
+            cpu_limit: value.cpu_limit,
+            ..Self::new(value.id, value.image)
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Merge, Serialize, Deserialize)]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
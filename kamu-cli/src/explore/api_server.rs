@@ -8,6 +8,7 @@
 // by the Apache License, Version 2.0.
 
 use dill::Catalog;
+use kamu::domain::*;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
 pub struct APIServer {
@@ -18,27 +19,39 @@ pub struct APIServer {
 }
 
 impl APIServer {
-    pub fn new(catalog: Catalog, address: Option<IpAddr>, port: Option<u16>) -> Self {
+    pub fn new(
+        catalog: Catalog,
+        address: Option<IpAddr>,
+        port: Option<u16>,
+        serve_datasets: bool,
+        auth_token: Option<String>,
+    ) -> Self {
+        let local_repo = catalog.get_one::<dyn LocalDatasetRepository>().unwrap();
         let gql_schema = kamu_adapter_graphql::schema(catalog);
 
-        let app = axum::Router::new()
+        let mut app = axum::Router::new()
             .route("/", axum::routing::get(root))
             .route(
                 "/graphql",
                 axum::routing::get(graphql_playground).post(graphql_handler),
-            )
-            .layer(
-                tower::ServiceBuilder::new()
-                    .layer(tower_http::trace::TraceLayer::new_for_http())
-                    .layer(
-                        tower_http::cors::CorsLayer::new()
-                            .allow_origin(tower_http::cors::Any)
-                            .allow_methods(vec![http::Method::GET, http::Method::POST])
-                            .allow_headers(tower_http::cors::Any),
-                    )
-                    .layer(axum::extract::Extension(gql_schema)),
             );
 
+        if serve_datasets {
+            app = super::add_dataset_routes(app, local_repo, auth_token);
+        }
+
+        let app = app.layer(
+            tower::ServiceBuilder::new()
+                .layer(tower_http::trace::TraceLayer::new_for_http())
+                .layer(
+                    tower_http::cors::CorsLayer::new()
+                        .allow_origin(tower_http::cors::Any)
+                        .allow_methods(vec![http::Method::GET, http::Method::POST])
+                        .allow_headers(tower_http::cors::Any),
+                )
+                .layer(axum::extract::Extension(gql_schema)),
+        );
+
         let addr = SocketAddr::from((
             address.unwrap_or(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))),
             port.unwrap_or(0),
@@ -0,0 +1,860 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Serves workspace datasets as an ODF repository using the same
+//! `blocks/`, `refs/`, `data/`, `checkpoints/`, `cache/`, and `info/` layout
+//! that is expected by HTTP repositories, plus the smart transfer protocol.
+//!
+//! Repository is read-only unless an auth token is configured, in which case
+//! clients presenting it as a bearer token can also `PUT` and `DELETE` objects.
+//!
+//! Reference updates can be made conditional by passing the hash the reference
+//! is expected to point to in the [EXPECTED_REF_HEADER] header (empty value if the
+//! reference is expected to not exist), in which case `409 Conflict` is returned
+//! on mismatch.
+
+use futures::TryStreamExt;
+use kamu::domain::*;
+use kamu::infra::utils::data_utils;
+use kamu::infra::utils::smart_transfer_protocol::*;
+use opendatafabric::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use subtle::ConstantTimeEq;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Header carrying the expected current value of a reference for compare-and-swap updates
+pub const EXPECTED_REF_HEADER: &str = "x-odf-expected-ref";
+
+/// Pushes of new datasets without any requests for this long are considered
+/// abandoned and their staging is discarded
+const PENDING_PUSH_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+pub fn add_dataset_routes(
+    router: axum::Router,
+    local_repo: Arc<dyn LocalDatasetRepository>,
+    auth_token: Option<String>,
+) -> axum::Router {
+    let writable = auth_token.is_some();
+
+    let state = DatasetServerState {
+        local_repo,
+        auth_token: auth_token.map(Arc::new),
+        pending: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        ref_update: Arc::new(tokio::sync::Mutex::new(())),
+    };
+
+    let mut blocks = axum::routing::get(block_get_handler);
+    let mut data = axum::routing::get(data_get_handler);
+    let mut checkpoints = axum::routing::get(checkpoint_get_handler);
+    let mut refs = axum::routing::get(ref_get_handler);
+    let mut cache = axum::routing::get(cache_get_handler);
+    let mut info = axum::routing::get(info_get_handler);
+
+    if writable {
+        blocks = blocks.put(block_put_handler).delete(block_delete_handler);
+        data = data.put(data_put_handler).delete(data_delete_handler);
+        checkpoints = checkpoints
+            .put(checkpoint_put_handler)
+            .delete(checkpoint_delete_handler);
+        refs = refs.put(ref_put_handler).delete(ref_delete_handler);
+        cache = cache.put(cache_put_handler).delete(cache_delete_handler);
+        info = info.put(info_put_handler).delete(info_delete_handler);
+    }
+
+    router
+        .route("/:dataset_name/blocks/:hash", blocks)
+        .route("/:dataset_name/data/:hash", data)
+        .route("/:dataset_name/checkpoints/:hash", checkpoints)
+        .route("/:dataset_name/refs/*path", refs)
+        .route("/:dataset_name/cache/*name", cache)
+        .route("/:dataset_name/info/*name", info)
+        .route(
+            "/:dataset_name/smart/negotiate",
+            axum::routing::post(smart_negotiate_handler),
+        )
+        .route(
+            "/:dataset_name/smart/bundle",
+            axum::routing::post(smart_bundle_handler),
+        )
+        .layer(axum::extract::Extension(state))
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// State
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone)]
+struct DatasetServerState {
+    local_repo: Arc<dyn LocalDatasetRepository>,
+    auth_token: Option<Arc<String>>,
+    /// Datasets that are being pushed for the first time. They stay in staging
+    /// until the `head` reference is set and are added to the workspace after,
+    /// or discarded after [PENDING_PUSH_TIMEOUT] of inactivity.
+    pending: Arc<tokio::sync::Mutex<HashMap<DatasetName, PendingDataset>>>,
+    /// Serializes reference updates to make compare-and-swap atomic
+    ref_update: Arc<tokio::sync::Mutex<()>>,
+}
+
+struct PendingDataset {
+    builder: Arc<dyn DatasetBuilder>,
+    last_active: Instant,
+}
+
+enum ServedDataset {
+    Existing(Arc<dyn Dataset>),
+    Pending(DatasetName, Arc<dyn DatasetBuilder>),
+}
+
+impl ServedDataset {
+    fn as_dataset(&self) -> &dyn Dataset {
+        match self {
+            Self::Existing(ds) => ds.as_ref(),
+            Self::Pending(_, builder) => builder.as_dataset(),
+        }
+    }
+}
+
+impl DatasetServerState {
+    async fn get_dataset(&self, dataset_name: &str) -> Result<ServedDataset, ApiError> {
+        let dataset_name = DatasetName::try_from(dataset_name).map_err(bad_request)?;
+
+        match self
+            .local_repo
+            .get_dataset(&dataset_name.as_local_ref())
+            .await
+        {
+            Ok(dataset) => Ok(ServedDataset::Existing(dataset)),
+            Err(GetDatasetError::NotFound(e)) => {
+                let mut pending = self.pending.lock().await;
+                Self::evict_abandoned(&mut pending).await;
+
+                match pending.get_mut(&dataset_name) {
+                    Some(p) => {
+                        p.last_active = Instant::now();
+                        Ok(ServedDataset::Pending(dataset_name, p.builder.clone()))
+                    }
+                    None => Err(not_found(e)),
+                }
+            }
+            Err(GetDatasetError::Internal(e)) => Err(internal_error(e)),
+        }
+    }
+
+    /// Same as [Self::get_dataset] but begins creating the dataset if it doesn't exist
+    async fn get_or_create_dataset(&self, dataset_name: &str) -> Result<ServedDataset, ApiError> {
+        let dataset_name = DatasetName::try_from(dataset_name).map_err(bad_request)?;
+
+        match self
+            .local_repo
+            .get_dataset(&dataset_name.as_local_ref())
+            .await
+        {
+            Ok(dataset) => Ok(ServedDataset::Existing(dataset)),
+            Err(GetDatasetError::NotFound(_)) => {
+                let mut pending = self.pending.lock().await;
+                Self::evict_abandoned(&mut pending).await;
+
+                if let Some(p) = pending.get_mut(&dataset_name) {
+                    p.last_active = Instant::now();
+                    return Ok(ServedDataset::Pending(dataset_name, p.builder.clone()));
+                }
+
+                tracing::info!(%dataset_name, "Creating pushed dataset");
+
+                let builder: Arc<dyn DatasetBuilder> =
+                    match self.local_repo.create_dataset(&dataset_name).await {
+                        Ok(b) => Ok(Arc::from(b)),
+                        Err(BeginCreateDatasetError::Internal(e)) => Err(internal_error(e)),
+                    }?;
+
+                pending.insert(
+                    dataset_name.clone(),
+                    PendingDataset {
+                        builder: builder.clone(),
+                        last_active: Instant::now(),
+                    },
+                );
+                Ok(ServedDataset::Pending(dataset_name, builder))
+            }
+            Err(GetDatasetError::Internal(e)) => Err(internal_error(e)),
+        }
+    }
+
+    /// Moves the pending dataset into the workspace once it has a head
+    async fn finish_pending(&self, dataset: ServedDataset) -> Result<(), ApiError> {
+        let (dataset_name, builder) = match dataset {
+            ServedDataset::Existing(_) => return Ok(()),
+            ServedDataset::Pending(name, builder) => (name, builder),
+        };
+
+        let mut pending = self.pending.lock().await;
+
+        match builder.finish().await {
+            Ok(_) => {
+                pending.remove(&dataset_name);
+                Ok(())
+            }
+            Err(CreateDatasetError::NameCollision(e)) => {
+                // Dataset was created by someone else, so this push can never complete
+                pending.remove(&dataset_name);
+                Self::discard(&dataset_name, builder.as_ref()).await;
+                Err((http::StatusCode::CONFLICT, e.to_string()))
+            }
+            Err(e) => Err(internal_error(e.int_err())),
+        }
+    }
+
+    async fn evict_abandoned(pending: &mut HashMap<DatasetName, PendingDataset>) {
+        let abandoned: Vec<_> = pending
+            .iter()
+            .filter(|(_, p)| p.last_active.elapsed() > PENDING_PUSH_TIMEOUT)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        for dataset_name in abandoned {
+            let p = pending.remove(&dataset_name).unwrap();
+            tracing::info!(%dataset_name, "Discarding abandoned push");
+            Self::discard(&dataset_name, p.builder.as_ref()).await;
+        }
+    }
+
+    async fn discard(dataset_name: &DatasetName, builder: &dyn DatasetBuilder) {
+        if let Err(error) = builder.discard().await {
+            tracing::warn!(%dataset_name, ?error, "Failed to discard pushed dataset staging");
+        }
+    }
+
+    fn authorize(&self, headers: &http::HeaderMap) -> Result<(), ApiError> {
+        let expected = match &self.auth_token {
+            Some(t) => t,
+            None => {
+                return Err((
+                    http::StatusCode::METHOD_NOT_ALLOWED,
+                    "Repository is read-only".to_owned(),
+                ))
+            }
+        };
+
+        let token = headers
+            .get(http::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+
+        match token {
+            None => Err((
+                http::StatusCode::UNAUTHORIZED,
+                "Bearer token is required".to_owned(),
+            )),
+            // Constant-time comparison to not leak the token through response timing
+            Some(token) if bool::from(token.as_bytes().ct_eq(expected.as_bytes())) => Ok(()),
+            Some(_) => Err((
+                http::StatusCode::FORBIDDEN,
+                "Invalid bearer token".to_owned(),
+            )),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Errors
+/////////////////////////////////////////////////////////////////////////////////////////
+
+type ApiError = (http::StatusCode, String);
+
+fn not_found(e: impl std::fmt::Display) -> ApiError {
+    (http::StatusCode::NOT_FOUND, e.to_string())
+}
+
+fn bad_request(e: impl std::fmt::Display) -> ApiError {
+    (http::StatusCode::BAD_REQUEST, e.to_string())
+}
+
+fn forbidden(e: impl std::fmt::Display) -> ApiError {
+    (http::StatusCode::FORBIDDEN, e.to_string())
+}
+
+fn access_error(e: AccessError) -> ApiError {
+    match e {
+        AccessError::ReadOnly(_) => (http::StatusCode::METHOD_NOT_ALLOWED, e.to_string()),
+        AccessError::Unauthorized(_) | AccessError::Forbidden(_) => forbidden(e),
+    }
+}
+
+fn internal_error(e: InternalError) -> ApiError {
+    tracing::error!(error = ?e, "Internal error");
+    (
+        http::StatusCode::INTERNAL_SERVER_ERROR,
+        "Internal error".to_owned(),
+    )
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Helpers
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy)]
+enum ObjectKind {
+    Block,
+    Data,
+    Checkpoint,
+}
+
+fn object_repo(dataset: &dyn Dataset, kind: ObjectKind) -> &dyn ObjectRepository {
+    match kind {
+        ObjectKind::Block => dataset.as_metadata_chain().as_object_repo(),
+        ObjectKind::Data => dataset.as_data_repo(),
+        ObjectKind::Checkpoint => dataset.as_checkpoint_repo(),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NamedKind {
+    Cache,
+    Info,
+}
+
+fn named_repo(dataset: &dyn Dataset, kind: NamedKind) -> &dyn NamedObjectRepository {
+    match kind {
+        NamedKind::Cache => dataset.as_cache_repo(),
+        NamedKind::Info => dataset.as_info_repo(),
+    }
+}
+
+/// Guards named object repositories against path traversal
+fn validate_object_name(name: &str) -> Result<&str, ApiError> {
+    let name = name.trim_start_matches('/');
+    if name
+        .split('/')
+        .any(|s| s.is_empty() || s == "." || s == ".." || s.contains('\\'))
+    {
+        Err(bad_request(format!("Invalid object name: {}", name)))
+    } else {
+        Ok(name)
+    }
+}
+
+/// Private objects are reported as missing to not reveal their existence
+fn validate_named_object(kind: NamedKind, name: &str) -> Result<&str, ApiError> {
+    let name = validate_object_name(name)?;
+    match kind {
+        NamedKind::Info if !is_public_info_object(name) => {
+            Err(not_found(format!("Object does not exist: {}", name)))
+        }
+        _ => Ok(name),
+    }
+}
+
+fn octet_stream(body: impl axum::response::IntoResponse) -> impl axum::response::IntoResponse {
+    (
+        [(http::header::CONTENT_TYPE, "application/octet-stream")],
+        body,
+    )
+}
+
+fn body_reader(body: axum::extract::BodyStream) -> Box<dyn tokio::io::AsyncRead + Send + Unpin> {
+    Box::new(tokio_util::io::StreamReader::new(Box::pin(
+        body.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+    )))
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Objects
+/////////////////////////////////////////////////////////////////////////////////////////
+
+async fn get_object(
+    state: &DatasetServerState,
+    dataset_name: &str,
+    hash: &str,
+    kind: ObjectKind,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let dataset = state.get_dataset(dataset_name).await?;
+    let hash = Multihash::from_multibase_str(hash).map_err(bad_request)?;
+
+    let stream = match object_repo(dataset.as_dataset(), kind)
+        .get_stream(&hash)
+        .await
+    {
+        Ok(stream) => Ok(stream),
+        Err(GetError::NotFound(e)) => Err(not_found(e)),
+        Err(GetError::Access(e)) => Err(access_error(e)),
+        Err(GetError::Internal(e)) => Err(internal_error(e)),
+    }?;
+
+    Ok(octet_stream(axum::body::StreamBody::new(
+        tokio_util::io::ReaderStream::new(stream),
+    )))
+}
+
+async fn put_object(
+    state: &DatasetServerState,
+    headers: &http::HeaderMap,
+    dataset_name: &str,
+    hash: &str,
+    kind: ObjectKind,
+    body: axum::extract::BodyStream,
+) -> Result<http::StatusCode, ApiError> {
+    state.authorize(headers)?;
+    let dataset = state.get_or_create_dataset(dataset_name).await?;
+    let hash = Multihash::from_multibase_str(hash).map_err(bad_request)?;
+
+    tracing::debug!(%dataset_name, %hash, ?kind, "Receiving object");
+
+    // Object is staged in a temporary file while its hash is computed, so that
+    // content is known to match its name before it reaches the repository and
+    // large objects are never held in memory
+    let tmp_dir = tempfile::tempdir().map_err(|e| internal_error(e.int_err()))?;
+    let tmp_path = tmp_dir.path().join("object");
+
+    let (actual_hash, size) =
+        data_utils::write_stream_with_physical_hash(&mut body_reader(body), &tmp_path)
+            .await
+            .map_err(|e| internal_error(e.int_err()))?;
+
+    if actual_hash != hash {
+        return Err(bad_request(HashMismatchError {
+            expected: hash,
+            actual: actual_hash,
+        }));
+    }
+
+    let file = tokio::fs::File::open(&tmp_path)
+        .await
+        .map_err(|e| internal_error(e.int_err()))?;
+
+    match object_repo(dataset.as_dataset(), kind)
+        .insert_stream(
+            Box::new(file),
+            InsertOpts {
+                precomputed_hash: Some(&hash),
+                expected_hash: Some(&hash),
+                size_hint: Some(size as usize),
+                ..Default::default()
+            },
+        )
+        .await
+    {
+        Ok(_) => Ok(http::StatusCode::NO_CONTENT),
+        Err(InsertError::HashMismatch(e)) => Err(bad_request(e)),
+        Err(InsertError::Access(e)) => Err(access_error(e)),
+        Err(InsertError::Internal(e)) => Err(internal_error(e)),
+    }
+}
+
+async fn delete_object(
+    state: &DatasetServerState,
+    headers: &http::HeaderMap,
+    dataset_name: &str,
+    hash: &str,
+    kind: ObjectKind,
+) -> Result<http::StatusCode, ApiError> {
+    state.authorize(headers)?;
+    let dataset = state.get_dataset(dataset_name).await?;
+    let hash = Multihash::from_multibase_str(hash).map_err(bad_request)?;
+
+    match object_repo(dataset.as_dataset(), kind).delete(&hash).await {
+        Ok(_) => Ok(http::StatusCode::NO_CONTENT),
+        Err(DeleteError::Access(e)) => Err(access_error(e)),
+        Err(DeleteError::Internal(e)) => Err(internal_error(e)),
+    }
+}
+
+async fn block_get_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, hash)): axum::extract::Path<(String, String)>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    get_object(&state, &dataset_name, &hash, ObjectKind::Block).await
+}
+
+async fn block_put_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, hash)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+    body: axum::extract::BodyStream,
+) -> Result<http::StatusCode, ApiError> {
+    put_object(
+        &state,
+        &headers,
+        &dataset_name,
+        &hash,
+        ObjectKind::Block,
+        body,
+    )
+    .await
+}
+
+async fn block_delete_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, hash)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+) -> Result<http::StatusCode, ApiError> {
+    delete_object(&state, &headers, &dataset_name, &hash, ObjectKind::Block).await
+}
+
+async fn data_get_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, hash)): axum::extract::Path<(String, String)>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    get_object(&state, &dataset_name, &hash, ObjectKind::Data).await
+}
+
+async fn data_put_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, hash)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+    body: axum::extract::BodyStream,
+) -> Result<http::StatusCode, ApiError> {
+    put_object(
+        &state,
+        &headers,
+        &dataset_name,
+        &hash,
+        ObjectKind::Data,
+        body,
+    )
+    .await
+}
+
+async fn data_delete_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, hash)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+) -> Result<http::StatusCode, ApiError> {
+    delete_object(&state, &headers, &dataset_name, &hash, ObjectKind::Data).await
+}
+
+async fn checkpoint_get_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, hash)): axum::extract::Path<(String, String)>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    get_object(&state, &dataset_name, &hash, ObjectKind::Checkpoint).await
+}
+
+async fn checkpoint_put_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, hash)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+    body: axum::extract::BodyStream,
+) -> Result<http::StatusCode, ApiError> {
+    put_object(
+        &state,
+        &headers,
+        &dataset_name,
+        &hash,
+        ObjectKind::Checkpoint,
+        body,
+    )
+    .await
+}
+
+async fn checkpoint_delete_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, hash)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+) -> Result<http::StatusCode, ApiError> {
+    delete_object(
+        &state,
+        &headers,
+        &dataset_name,
+        &hash,
+        ObjectKind::Checkpoint,
+    )
+    .await
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// References
+/////////////////////////////////////////////////////////////////////////////////////////
+
+async fn ref_get_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, path)): axum::extract::Path<(String, String)>,
+) -> Result<String, ApiError> {
+    let dataset = state.get_dataset(&dataset_name).await?;
+    let block_ref = BlockRef::from_path(validate_object_name(&path)?).map_err(bad_request)?;
+
+    match dataset
+        .as_dataset()
+        .as_metadata_chain()
+        .get_ref(&block_ref)
+        .await
+    {
+        Ok(hash) => Ok(hash.to_multibase_string()),
+        Err(GetRefError::NotFound(e)) => Err(not_found(e)),
+        Err(GetRefError::Access(e)) => Err(access_error(e)),
+        Err(GetRefError::Internal(e)) => Err(internal_error(e)),
+    }
+}
+
+async fn ref_put_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, path)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+    body: String,
+) -> Result<http::StatusCode, ApiError> {
+    state.authorize(&headers)?;
+    let dataset = state.get_or_create_dataset(&dataset_name).await?;
+    let block_ref = BlockRef::from_path(validate_object_name(&path)?).map_err(bad_request)?;
+    let hash = Multihash::from_multibase_str(body.trim()).map_err(bad_request)?;
+
+    let expected = match headers.get(EXPECTED_REF_HEADER) {
+        None => None,
+        Some(v) => match v.to_str().map_err(bad_request)?.trim() {
+            "" => Some(None),
+            v => Some(Some(Multihash::from_multibase_str(v).map_err(bad_request)?)),
+        },
+    };
+
+    tracing::debug!(%dataset_name, %block_ref, %hash, ?expected, "Setting reference");
+
+    let _guard = state.ref_update.lock().await;
+
+    // References can only point to blocks that were already pushed
+    match dataset
+        .as_dataset()
+        .as_metadata_chain()
+        .set_ref(
+            &block_ref,
+            &hash,
+            SetRefOpts {
+                validate_block_present: true,
+                check_ref_is: expected.as_ref().map(|h| h.as_ref()),
+            },
+        )
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(SetRefError::BlockNotFound(e)) => Err(bad_request(e)),
+        Err(SetRefError::CASFailed(e)) => Err((http::StatusCode::CONFLICT, e.to_string())),
+        Err(SetRefError::Access(e)) => Err(access_error(e)),
+        Err(SetRefError::Internal(e)) => Err(internal_error(e)),
+    }?;
+
+    if block_ref == BlockRef::Head {
+        state.finish_pending(dataset).await?;
+    }
+
+    Ok(http::StatusCode::NO_CONTENT)
+}
+
+async fn ref_delete_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, path)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+) -> Result<http::StatusCode, ApiError> {
+    state.authorize(&headers)?;
+    let dataset = state.get_dataset(&dataset_name).await?;
+    let block_ref = BlockRef::from_path(validate_object_name(&path)?).map_err(bad_request)?;
+
+    match dataset
+        .as_dataset()
+        .as_metadata_chain()
+        .as_reference_repo()
+        .delete(&block_ref)
+        .await
+    {
+        Ok(_) => Ok(http::StatusCode::NO_CONTENT),
+        Err(DeleteRefError::Access(e)) => Err(access_error(e)),
+        Err(DeleteRefError::Internal(e)) => Err(internal_error(e)),
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Named objects
+/////////////////////////////////////////////////////////////////////////////////////////
+
+async fn get_named_object(
+    state: &DatasetServerState,
+    dataset_name: &str,
+    name: &str,
+    kind: NamedKind,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    use kamu::domain::repos::named_object_repository::GetError;
+
+    let dataset = state.get_dataset(dataset_name).await?;
+    let name = validate_named_object(kind, name)?;
+
+    match named_repo(dataset.as_dataset(), kind).get(name).await {
+        Ok(data) => Ok(octet_stream(data)),
+        Err(GetError::NotFound(e)) => Err(not_found(e)),
+        Err(GetError::Access(e)) => Err(access_error(e)),
+        Err(GetError::Internal(e)) => Err(internal_error(e)),
+    }
+}
+
+async fn put_named_object(
+    state: &DatasetServerState,
+    headers: &http::HeaderMap,
+    dataset_name: &str,
+    name: &str,
+    kind: NamedKind,
+    data: axum::body::Bytes,
+) -> Result<http::StatusCode, ApiError> {
+    use kamu::domain::repos::named_object_repository::SetError;
+
+    state.authorize(headers)?;
+    let dataset = state.get_or_create_dataset(dataset_name).await?;
+
+    // Private objects like the signing key can never be overwritten remotely
+    let name = validate_object_name(name)?;
+    if let NamedKind::Info = kind {
+        if !is_public_info_object(name) {
+            return Err(forbidden(format!("Object cannot be modified: {}", name)));
+        }
+    }
+
+    match named_repo(dataset.as_dataset(), kind)
+        .set(name, &data)
+        .await
+    {
+        Ok(_) => Ok(http::StatusCode::NO_CONTENT),
+        Err(SetError::Access(e)) => Err(access_error(e)),
+        Err(SetError::Internal(e)) => Err(internal_error(e)),
+    }
+}
+
+async fn delete_named_object(
+    state: &DatasetServerState,
+    headers: &http::HeaderMap,
+    dataset_name: &str,
+    name: &str,
+    kind: NamedKind,
+) -> Result<http::StatusCode, ApiError> {
+    use kamu::domain::repos::named_object_repository::DeleteError;
+
+    state.authorize(headers)?;
+    let dataset = state.get_dataset(dataset_name).await?;
+
+    let name = validate_object_name(name)?;
+    if let NamedKind::Info = kind {
+        if !is_public_info_object(name) {
+            return Err(forbidden(format!("Object cannot be modified: {}", name)));
+        }
+    }
+
+    match named_repo(dataset.as_dataset(), kind).delete(name).await {
+        Ok(_) => Ok(http::StatusCode::NO_CONTENT),
+        Err(DeleteError::Access(e)) => Err(access_error(e)),
+        Err(DeleteError::Internal(e)) => Err(internal_error(e)),
+    }
+}
+
+async fn cache_get_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, name)): axum::extract::Path<(String, String)>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    get_named_object(&state, &dataset_name, &name, NamedKind::Cache).await
+}
+
+async fn cache_put_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, name)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+    data: axum::body::Bytes,
+) -> Result<http::StatusCode, ApiError> {
+    put_named_object(
+        &state,
+        &headers,
+        &dataset_name,
+        &name,
+        NamedKind::Cache,
+        data,
+    )
+    .await
+}
+
+async fn cache_delete_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, name)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+) -> Result<http::StatusCode, ApiError> {
+    delete_named_object(&state, &headers, &dataset_name, &name, NamedKind::Cache).await
+}
+
+async fn info_get_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, name)): axum::extract::Path<(String, String)>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    get_named_object(&state, &dataset_name, &name, NamedKind::Info).await
+}
+
+async fn info_put_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, name)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+    data: axum::body::Bytes,
+) -> Result<http::StatusCode, ApiError> {
+    put_named_object(
+        &state,
+        &headers,
+        &dataset_name,
+        &name,
+        NamedKind::Info,
+        data,
+    )
+    .await
+}
+
+async fn info_delete_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path((dataset_name, name)): axum::extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+) -> Result<http::StatusCode, ApiError> {
+    delete_named_object(&state, &headers, &dataset_name, &name, NamedKind::Info).await
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Smart Transfer Protocol
+/////////////////////////////////////////////////////////////////////////////////////////
+
+async fn smart_negotiate_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path(dataset_name): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<NegotiateRequest>,
+) -> Result<axum::Json<NegotiateResponse>, ApiError> {
+    let dataset = match state.get_dataset(&dataset_name).await? {
+        ServedDataset::Existing(ds) => ds,
+        ServedDataset::Pending(..) => return Err(not_found("Dataset is being pushed")),
+    };
+
+    if request.have.len() > NegotiateRequest::MAX_HAVE {
+        return Err(bad_request(format!(
+            "Negotiation request can list at most {} blocks",
+            NegotiateRequest::MAX_HAVE
+        )));
+    }
+
+    let response = SmartTransferProtocolServer::negotiate(dataset.as_ref(), request)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(axum::Json(response))
+}
+
+async fn smart_bundle_handler(
+    state: axum::extract::Extension<DatasetServerState>,
+    axum::extract::Path(dataset_name): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<BundleRequest>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let dataset = match state.get_dataset(&dataset_name).await? {
+        ServedDataset::Existing(ds) => ds,
+        ServedDataset::Pending(..) => return Err(not_found("Dataset is being pushed")),
+    };
+
+    // Errors in the middle of the stream abort the response, which the client
+    // detects by the missing end frame
+    let stream = SmartTransferProtocolServer::bundle(dataset, request)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
+
+    Ok(octet_stream(axum::body::StreamBody::new(stream)))
+}
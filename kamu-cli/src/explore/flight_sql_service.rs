@@ -0,0 +1,646 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use kamu::domain::*;
+
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::sql::server::FlightSqlService;
+use arrow_flight::sql::{
+    ActionClosePreparedStatementRequest, ActionCreatePreparedStatementRequest,
+    ActionCreatePreparedStatementResult, CommandGetCatalogs, CommandGetCrossReference,
+    CommandGetDbSchemas, CommandGetExportedKeys, CommandGetImportedKeys, CommandGetPrimaryKeys,
+    CommandGetSqlInfo, CommandGetTableTypes, CommandGetTables, CommandPreparedStatementQuery,
+    CommandPreparedStatementUpdate, CommandStatementQuery, CommandStatementUpdate, ProstMessageExt,
+    SqlInfo, TicketStatementQuery,
+};
+use arrow_flight::utils::flight_data_from_arrow_batch;
+use arrow_flight::{
+    Action, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo, HandshakeRequest,
+    HandshakeResponse, IpcMessage, SchemaAsIpc, Ticket,
+};
+use datafusion::arrow::array::{ArrayRef, BinaryArray, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::ipc::writer::IpcWriteOptions;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::prelude::SessionContext;
+use futures::{Stream, StreamExt, TryStreamExt};
+use prost::Message;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status, Streaming};
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+const CATALOG_NAME: &str = "kamu";
+const SCHEMA_NAME: &str = "kamu";
+const TABLE_TYPE: &str = "TABLE";
+
+type FlightDataStream = Pin<Box<dyn Stream<Item = Result<FlightData, Status>> + Send + 'static>>;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Serves workspace datasets over the Arrow Flight SQL protocol
+pub struct FlightSqlServer {
+    listener: tokio::net::TcpListener,
+    service: KamuFlightSqlService,
+}
+
+impl FlightSqlServer {
+    pub async fn new(
+        query_svc: Arc<dyn QueryService>,
+        address: IpAddr,
+        port: u16,
+    ) -> Result<Self, std::io::Error> {
+        let listener = tokio::net::TcpListener::bind(SocketAddr::new(address, port)).await?;
+        Ok(Self {
+            listener,
+            service: KamuFlightSqlService::new(query_svc),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.listener.local_addr().unwrap()
+    }
+
+    pub async fn run(self) -> Result<(), tonic::transport::Error> {
+        tonic::transport::Server::builder()
+            .add_service(FlightServiceServer::new(self.service))
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(
+                self.listener,
+            ))
+            .await
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Flight SQL service that executes queries using the embedded DataFusion engine.
+///
+/// Every request runs in a new session, so prepared statements are stateless and their
+/// handle is simply the text of the query.
+pub struct KamuFlightSqlService {
+    query_svc: Arc<dyn QueryService>,
+}
+
+impl KamuFlightSqlService {
+    pub fn new(query_svc: Arc<dyn QueryService>) -> Self {
+        Self { query_svc }
+    }
+
+    async fn session(&self) -> Result<SessionContext, Status> {
+        self.query_svc
+            .create_session(QueryOptions::default())
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+
+    async fn get_query_schema(&self, query: &str) -> Result<Schema, Status> {
+        let df = self.session().await?.sql(query).await.map_err(df_status)?;
+        Ok(Schema::from(df.schema().clone()))
+    }
+
+    async fn execute_query(&self, query: &str) -> Result<FlightDataStream, Status> {
+        let df = self.session().await?.sql(query).await.map_err(df_status)?;
+        let stream = df.execute_stream().await.map_err(df_status)?;
+        Ok(record_batch_stream_to_flight_data(stream))
+    }
+
+    async fn get_tables(&self, query: &CommandGetTables) -> Result<RecordBatch, Status> {
+        let include_catalog = query
+            .catalog
+            .as_ref()
+            .map(|c| c.is_empty() || c == CATALOG_NAME)
+            .unwrap_or(true);
+        let include_schema = query
+            .db_schema_filter_pattern
+            .as_ref()
+            .map(|p| sql_like(p, SCHEMA_NAME))
+            .unwrap_or(true);
+        let include_type =
+            query.table_types.is_empty() || query.table_types.iter().any(|t| t == TABLE_TYPE);
+
+        let mut names = Vec::new();
+        let mut schemas = Vec::new();
+
+        if include_catalog && include_schema && include_type {
+            let schema = self
+                .session()
+                .await?
+                .catalog(CATALOG_NAME)
+                .and_then(|c| c.schema(SCHEMA_NAME))
+                .ok_or_else(|| Status::internal("Default schema is not registered"))?;
+
+            let mut table_names = schema.table_names();
+            table_names.sort();
+
+            for name in table_names {
+                if let Some(pattern) = &query.table_name_filter_pattern {
+                    if !sql_like(pattern, &name) {
+                        continue;
+                    }
+                }
+                if query.include_schema {
+                    // Dataset could've been deleted since the names were listed
+                    let table = match schema.table(&name) {
+                        Some(table) => table,
+                        None => continue,
+                    };
+                    schemas.push(schema_to_ipc_bytes(&table.schema())?);
+                }
+                names.push(name);
+            }
+        }
+
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec![CATALOG_NAME; names.len()])),
+            Arc::new(StringArray::from(vec![SCHEMA_NAME; names.len()])),
+            Arc::new(StringArray::from_iter_values(names.iter())),
+            Arc::new(StringArray::from(vec![TABLE_TYPE; names.len()])),
+        ];
+
+        if query.include_schema {
+            columns.push(Arc::new(BinaryArray::from_iter_values(schemas.iter())));
+        }
+
+        Ok(RecordBatch::try_new(Self::tables_schema(query.include_schema), columns).unwrap())
+    }
+
+    fn get_catalogs() -> RecordBatch {
+        RecordBatch::try_new(
+            Self::catalogs_schema(),
+            vec![Arc::new(StringArray::from(vec![CATALOG_NAME]))],
+        )
+        .unwrap()
+    }
+
+    fn get_db_schemas(query: &CommandGetDbSchemas) -> RecordBatch {
+        let matches = query
+            .catalog
+            .as_ref()
+            .map(|c| c.is_empty() || c == CATALOG_NAME)
+            .unwrap_or(true)
+            && query
+                .db_schema_filter_pattern
+                .as_ref()
+                .map(|p| sql_like(p, SCHEMA_NAME))
+                .unwrap_or(true);
+
+        let rows = if matches { 1 } else { 0 };
+
+        RecordBatch::try_new(
+            Self::db_schemas_schema(),
+            vec![
+                Arc::new(StringArray::from(vec![CATALOG_NAME; rows])),
+                Arc::new(StringArray::from(vec![SCHEMA_NAME; rows])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn get_table_types() -> RecordBatch {
+        RecordBatch::try_new(
+            Self::table_types_schema(),
+            vec![Arc::new(StringArray::from(vec![TABLE_TYPE]))],
+        )
+        .unwrap()
+    }
+
+    fn catalogs_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![Field::new(
+            "catalog_name",
+            DataType::Utf8,
+            false,
+        )]))
+    }
+
+    fn db_schemas_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("catalog_name", DataType::Utf8, true),
+            Field::new("db_schema_name", DataType::Utf8, false),
+        ]))
+    }
+
+    fn tables_schema(include_schema: bool) -> SchemaRef {
+        let mut fields = vec![
+            Field::new("catalog_name", DataType::Utf8, true),
+            Field::new("db_schema_name", DataType::Utf8, true),
+            Field::new("table_name", DataType::Utf8, false),
+            Field::new("table_type", DataType::Utf8, false),
+        ];
+        if include_schema {
+            fields.push(Field::new("table_schema", DataType::Binary, false));
+        }
+        Arc::new(Schema::new(fields))
+    }
+
+    fn table_types_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![Field::new(
+            "table_type",
+            DataType::Utf8,
+            false,
+        )]))
+    }
+
+    /// Creates a flight info with a single endpoint that can be fetched with the specified ticket
+    fn flight_info(
+        schema: &Schema,
+        descriptor: FlightDescriptor,
+        ticket: impl ProstMessageExt,
+    ) -> Result<FlightInfo, Status> {
+        let schema_ipc: IpcMessage = SchemaAsIpc::new(schema, &IpcWriteOptions::default())
+            .try_into()
+            .map_err(|e: datafusion::arrow::error::ArrowError| Status::internal(e.to_string()))?;
+
+        let endpoint = FlightEndpoint {
+            ticket: Some(Ticket {
+                ticket: ticket.as_any().encode_to_vec().into(),
+            }),
+            location: Vec::new(),
+        };
+
+        Ok(FlightInfo::new(
+            schema_ipc,
+            Some(descriptor),
+            vec![endpoint],
+            -1,
+            -1,
+        ))
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[tonic::async_trait]
+impl FlightSqlService for KamuFlightSqlService {
+    type FlightService = KamuFlightSqlService;
+
+    // Authentication is not supported - all clients are treated as anonymous
+    async fn do_handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<
+        Response<Pin<Box<dyn Stream<Item = Result<HandshakeResponse, Status>> + Send>>>,
+        Status,
+    > {
+        let response = HandshakeResponse {
+            protocol_version: 0,
+            payload: Vec::new().into(),
+        };
+        Ok(Response::new(Box::pin(futures::stream::iter(vec![Ok(
+            response,
+        )]))))
+    }
+
+    async fn get_flight_info_statement(
+        &self,
+        query: CommandStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        tracing::debug!(query = %query.query, "Planning statement");
+
+        let schema = self.get_query_schema(&query.query).await?;
+        let ticket = TicketStatementQuery {
+            statement_handle: query.query.into_bytes().into(),
+        };
+
+        Ok(Response::new(Self::flight_info(
+            &schema,
+            request.into_inner(),
+            ticket,
+        )?))
+    }
+
+    async fn get_flight_info_prepared_statement(
+        &self,
+        cmd: CommandPreparedStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let query = handle_to_query(&cmd.prepared_statement_handle)?;
+        let schema = self.get_query_schema(&query).await?;
+
+        Ok(Response::new(Self::flight_info(
+            &schema,
+            request.into_inner(),
+            cmd,
+        )?))
+    }
+
+    async fn get_flight_info_catalogs(
+        &self,
+        query: CommandGetCatalogs,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Ok(Response::new(Self::flight_info(
+            &Self::catalogs_schema(),
+            request.into_inner(),
+            query,
+        )?))
+    }
+
+    async fn get_flight_info_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Ok(Response::new(Self::flight_info(
+            &Self::db_schemas_schema(),
+            request.into_inner(),
+            query,
+        )?))
+    }
+
+    async fn get_flight_info_tables(
+        &self,
+        query: CommandGetTables,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Ok(Response::new(Self::flight_info(
+            &Self::tables_schema(query.include_schema),
+            request.into_inner(),
+            query,
+        )?))
+    }
+
+    async fn get_flight_info_table_types(
+        &self,
+        query: CommandGetTableTypes,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Ok(Response::new(Self::flight_info(
+            &Self::table_types_schema(),
+            request.into_inner(),
+            query,
+        )?))
+    }
+
+    async fn get_flight_info_sql_info(
+        &self,
+        _query: CommandGetSqlInfo,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Err(Status::unimplemented("GetSqlInfo is not supported"))
+    }
+
+    async fn get_flight_info_primary_keys(
+        &self,
+        _query: CommandGetPrimaryKeys,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Err(Status::unimplemented("GetPrimaryKeys is not supported"))
+    }
+
+    async fn get_flight_info_exported_keys(
+        &self,
+        _query: CommandGetExportedKeys,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Err(Status::unimplemented("GetExportedKeys is not supported"))
+    }
+
+    async fn get_flight_info_imported_keys(
+        &self,
+        _query: CommandGetImportedKeys,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Err(Status::unimplemented("GetImportedKeys is not supported"))
+    }
+
+    async fn get_flight_info_cross_reference(
+        &self,
+        _query: CommandGetCrossReference,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Err(Status::unimplemented("GetCrossReference is not supported"))
+    }
+
+    async fn do_get_statement(
+        &self,
+        ticket: TicketStatementQuery,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let query = handle_to_query(&ticket.statement_handle)?;
+        Ok(Response::new(self.execute_query(&query).await?))
+    }
+
+    async fn do_get_prepared_statement(
+        &self,
+        query: CommandPreparedStatementQuery,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let query = handle_to_query(&query.prepared_statement_handle)?;
+        Ok(Response::new(self.execute_query(&query).await?))
+    }
+
+    async fn do_get_catalogs(
+        &self,
+        _query: CommandGetCatalogs,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        Ok(Response::new(record_batch_to_flight_data(
+            Self::get_catalogs(),
+        )))
+    }
+
+    async fn do_get_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        Ok(Response::new(record_batch_to_flight_data(
+            Self::get_db_schemas(&query),
+        )))
+    }
+
+    async fn do_get_tables(
+        &self,
+        query: CommandGetTables,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        Ok(Response::new(record_batch_to_flight_data(
+            self.get_tables(&query).await?,
+        )))
+    }
+
+    async fn do_get_table_types(
+        &self,
+        _query: CommandGetTableTypes,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        Ok(Response::new(record_batch_to_flight_data(
+            Self::get_table_types(),
+        )))
+    }
+
+    async fn do_get_sql_info(
+        &self,
+        _query: CommandGetSqlInfo,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        Err(Status::unimplemented("GetSqlInfo is not supported"))
+    }
+
+    async fn do_get_primary_keys(
+        &self,
+        _query: CommandGetPrimaryKeys,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        Err(Status::unimplemented("GetPrimaryKeys is not supported"))
+    }
+
+    async fn do_get_exported_keys(
+        &self,
+        _query: CommandGetExportedKeys,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        Err(Status::unimplemented("GetExportedKeys is not supported"))
+    }
+
+    async fn do_get_imported_keys(
+        &self,
+        _query: CommandGetImportedKeys,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        Err(Status::unimplemented("GetImportedKeys is not supported"))
+    }
+
+    async fn do_get_cross_reference(
+        &self,
+        _query: CommandGetCrossReference,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        Err(Status::unimplemented("GetCrossReference is not supported"))
+    }
+
+    // Datasets can only be modified through the metadata chain, so all updates are rejected
+    async fn do_put_statement_update(
+        &self,
+        _ticket: CommandStatementUpdate,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<i64, Status> {
+        Err(Status::permission_denied("Datasets are read-only"))
+    }
+
+    async fn do_put_prepared_statement_query(
+        &self,
+        _query: CommandPreparedStatementQuery,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<<Self as FlightService>::DoPutStream>, Status> {
+        Err(Status::unimplemented(
+            "Prepared statement parameters are not supported",
+        ))
+    }
+
+    async fn do_put_prepared_statement_update(
+        &self,
+        _query: CommandPreparedStatementUpdate,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<i64, Status> {
+        Err(Status::permission_denied("Datasets are read-only"))
+    }
+
+    async fn do_action_create_prepared_statement(
+        &self,
+        query: ActionCreatePreparedStatementRequest,
+        _request: Request<Action>,
+    ) -> Result<ActionCreatePreparedStatementResult, Status> {
+        tracing::debug!(query = %query.query, "Preparing statement");
+
+        // Planning the query validates it and lets clients know the result schema upfront
+        let schema = self.get_query_schema(&query.query).await?;
+
+        Ok(ActionCreatePreparedStatementResult {
+            prepared_statement_handle: query.query.into_bytes().into(),
+            dataset_schema: schema_to_ipc_bytes(&schema)?.into(),
+            parameter_schema: Vec::new().into(),
+        })
+    }
+
+    // Statements are stateless, so there is nothing to clean up
+    async fn do_action_close_prepared_statement(
+        &self,
+        _query: ActionClosePreparedStatementRequest,
+        _request: Request<Action>,
+    ) {
+    }
+
+    async fn register_sql_info(&self, _id: i32, _result: &SqlInfo) {}
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+fn df_status(e: DataFusionError) -> Status {
+    match e {
+        DataFusionError::SQL(_) | DataFusionError::Plan(_) | DataFusionError::SchemaError(_) => {
+            Status::invalid_argument(e.to_string())
+        }
+        _ => Status::internal(e.to_string()),
+    }
+}
+
+fn handle_to_query(handle: &[u8]) -> Result<String, Status> {
+    String::from_utf8(handle.to_vec())
+        .map_err(|_| Status::invalid_argument("Invalid statement handle"))
+}
+
+fn schema_to_ipc_bytes(schema: &Schema) -> Result<Vec<u8>, Status> {
+    let IpcMessage(bytes) = SchemaAsIpc::new(schema, &IpcWriteOptions::default())
+        .try_into()
+        .map_err(|e: datafusion::arrow::error::ArrowError| Status::internal(e.to_string()))?;
+    Ok(bytes.into())
+}
+
+/// Matches a value against an SQL `LIKE` pattern, where `%` matches any sequence
+/// of characters and `_` matches any single character
+fn sql_like(pattern: &str, value: &str) -> bool {
+    let mut re = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '%' => re.push_str(".*"),
+            '_' => re.push('.'),
+            _ => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    regex::Regex::new(&re).unwrap().is_match(value)
+}
+
+fn record_batch_to_flight_data(batch: RecordBatch) -> FlightDataStream {
+    let schema = batch.schema();
+    record_batch_stream_to_flight_data(Box::pin(
+        datafusion::physical_plan::stream::RecordBatchStreamAdapter::new(
+            schema,
+            futures::stream::iter(vec![Ok(batch)]),
+        ),
+    ))
+}
+
+/// Encodes record batches as they are produced, so results are streamed to the client
+/// without being collected in memory first
+fn record_batch_stream_to_flight_data(stream: SendableRecordBatchStream) -> FlightDataStream {
+    let options = IpcWriteOptions::default();
+    let schema: FlightData = SchemaAsIpc::new(&stream.schema(), &options).into();
+
+    let batches = stream
+        .map_err(df_status)
+        .map_ok(move |batch| {
+            let (dictionaries, data) = flight_data_from_arrow_batch(&batch, &options);
+            futures::stream::iter(
+                dictionaries
+                    .into_iter()
+                    .chain(std::iter::once(data))
+                    .map(Ok),
+            )
+        })
+        .try_flatten();
+
+    Box::pin(futures::stream::once(async move { Ok(schema) }).chain(batches))
+}
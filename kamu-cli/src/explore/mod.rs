@@ -10,12 +10,28 @@
 mod api_server;
 pub use api_server::*;
 
+mod dataset_handlers;
+pub use dataset_handlers::*;
+
+mod flight_sql_service;
+pub use flight_sql_service::*;
+
 mod livy_server_impl;
 pub use livy_server_impl::*;
 
 mod notebook_server_impl;
 pub use notebook_server_impl::*;
 
+mod pg_catalog;
+mod pg_protocol;
+mod pg_types;
+
+mod pg_server;
+pub use pg_server::*;
+
+mod sql_shell_datafusion;
+pub use sql_shell_datafusion::*;
+
 mod sql_shell_impl;
 pub use sql_shell_impl::*;
 
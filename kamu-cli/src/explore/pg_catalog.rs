@@ -0,0 +1,168 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::pg_types::TYPE_NAMES;
+
+use datafusion::arrow::array::{ArrayRef, BooleanArray, Int32Array, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::catalog::schema::{MemorySchemaProvider, SchemaProvider};
+use datafusion::datasource::MemTable;
+use datafusion::error::DataFusionError;
+use datafusion::prelude::SessionContext;
+use std::sync::Arc;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) const DATABASE_NAME: &str = "kamu";
+const SCHEMA_NAME: &str = "kamu";
+const OWNER_NAME: &str = "kamu";
+
+const OID_NAMESPACE_PG_CATALOG: i32 = 11;
+const OID_NAMESPACE_KAMU: i32 = 2200;
+const OID_NAMESPACE_INFORMATION_SCHEMA: i32 = 13000;
+const OID_DATABASE: i32 = 16384;
+// OIDs of user objects start here
+const OID_FIRST_TABLE: i32 = 16385;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Registers a minimal `pg_catalog` schema that lists the datasets, so that clients
+/// that introspect the database on connect can discover the tables.
+///
+/// Only the most commonly used columns of `pg_namespace`, `pg_class`, `pg_tables`,
+/// `pg_type` and `pg_database` are provided.
+pub(crate) fn register_pg_catalog(ctx: &SessionContext) -> Result<(), DataFusionError> {
+    let catalog = ctx
+        .catalog(DATABASE_NAME)
+        .ok_or_else(|| DataFusionError::Internal("Kamu catalog is not registered".to_owned()))?;
+
+    let mut tables = catalog
+        .schema(SCHEMA_NAME)
+        .map(|s| s.table_names())
+        .unwrap_or_default();
+    tables.sort();
+
+    let schema = MemorySchemaProvider::new();
+    schema.register_table("pg_namespace".to_owned(), pg_namespace())?;
+    schema.register_table("pg_class".to_owned(), pg_class(&tables))?;
+    schema.register_table("pg_tables".to_owned(), pg_tables(&tables))?;
+    schema.register_table("pg_type".to_owned(), pg_type())?;
+    schema.register_table("pg_database".to_owned(), pg_database())?;
+
+    catalog.register_schema("pg_catalog", Arc::new(schema))?;
+    Ok(())
+}
+
+fn mem_table(fields: Vec<Field>, columns: Vec<ArrayRef>) -> Arc<MemTable> {
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+    Arc::new(MemTable::try_new(schema, vec![vec![batch]]).unwrap())
+}
+
+fn pg_namespace() -> Arc<MemTable> {
+    mem_table(
+        vec![
+            Field::new("oid", DataType::Int32, false),
+            Field::new("nspname", DataType::Utf8, false),
+        ],
+        vec![
+            Arc::new(Int32Array::from(vec![
+                OID_NAMESPACE_PG_CATALOG,
+                OID_NAMESPACE_KAMU,
+                OID_NAMESPACE_INFORMATION_SCHEMA,
+            ])),
+            Arc::new(StringArray::from(vec![
+                "pg_catalog",
+                SCHEMA_NAME,
+                "information_schema",
+            ])),
+        ],
+    )
+}
+
+fn pg_class(tables: &[String]) -> Arc<MemTable> {
+    mem_table(
+        vec![
+            Field::new("oid", DataType::Int32, false),
+            Field::new("relname", DataType::Utf8, false),
+            Field::new("relnamespace", DataType::Int32, false),
+            Field::new("relkind", DataType::Utf8, false),
+        ],
+        vec![
+            Arc::new(Int32Array::from_iter_values(
+                (0..tables.len() as i32).map(|i| OID_FIRST_TABLE + i),
+            )),
+            Arc::new(StringArray::from_iter_values(tables.iter())),
+            Arc::new(Int32Array::from(vec![OID_NAMESPACE_KAMU; tables.len()])),
+            Arc::new(StringArray::from(vec!["r"; tables.len()])),
+        ],
+    )
+}
+
+fn pg_tables(tables: &[String]) -> Arc<MemTable> {
+    let no = || Arc::new(BooleanArray::from(vec![false; tables.len()]));
+    mem_table(
+        vec![
+            Field::new("schemaname", DataType::Utf8, false),
+            Field::new("tablename", DataType::Utf8, false),
+            Field::new("tableowner", DataType::Utf8, false),
+            Field::new("tablespace", DataType::Utf8, true),
+            Field::new("hasindexes", DataType::Boolean, false),
+            Field::new("hasrules", DataType::Boolean, false),
+            Field::new("hastriggers", DataType::Boolean, false),
+            Field::new("rowsecurity", DataType::Boolean, false),
+        ],
+        vec![
+            Arc::new(StringArray::from(vec![SCHEMA_NAME; tables.len()])),
+            Arc::new(StringArray::from_iter_values(tables.iter())),
+            Arc::new(StringArray::from(vec![OWNER_NAME; tables.len()])),
+            Arc::new(StringArray::from(vec![None::<&str>; tables.len()])),
+            no(),
+            no(),
+            no(),
+            no(),
+        ],
+    )
+}
+
+fn pg_type() -> Arc<MemTable> {
+    mem_table(
+        vec![
+            Field::new("oid", DataType::Int32, false),
+            Field::new("typname", DataType::Utf8, false),
+            Field::new("typnamespace", DataType::Int32, false),
+        ],
+        vec![
+            Arc::new(Int32Array::from_iter_values(
+                TYPE_NAMES.iter().map(|(oid, _)| *oid as i32),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                TYPE_NAMES.iter().map(|(_, name)| *name),
+            )),
+            Arc::new(Int32Array::from(vec![
+                OID_NAMESPACE_PG_CATALOG;
+                TYPE_NAMES.len()
+            ])),
+        ],
+    )
+}
+
+fn pg_database() -> Arc<MemTable> {
+    mem_table(
+        vec![
+            Field::new("oid", DataType::Int32, false),
+            Field::new("datname", DataType::Utf8, false),
+        ],
+        vec![
+            Arc::new(Int32Array::from(vec![OID_DATABASE])),
+            Arc::new(StringArray::from(vec![DATABASE_NAME])),
+        ],
+    )
+}
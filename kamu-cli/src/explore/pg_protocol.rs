@@ -0,0 +1,410 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Encoding and decoding of PostgreSQL frontend/backend protocol (v3) messages.
+//! See: https://www.postgresql.org/docs/current/protocol-message-formats.html
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+const PROTOCOL_VERSION_3: i32 = 196608;
+const SSL_REQUEST_CODE: i32 = 80877103;
+const GSSENC_REQUEST_CODE: i32 = 80877104;
+const CANCEL_REQUEST_CODE: i32 = 80877102;
+
+// Guards against allocating huge buffers for malformed messages
+const MAX_MESSAGE_LEN: usize = 64 * 1024 * 1024;
+
+pub(crate) const FORMAT_TEXT: i16 = 0;
+pub(crate) const FORMAT_BINARY: i16 = 1;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) enum StartupMessage {
+    SslRequest,
+    GssEncRequest,
+    CancelRequest,
+    Startup { params: HashMap<String, String> },
+}
+
+#[derive(Debug)]
+pub(crate) enum FrontendMessage {
+    Query(String),
+    Parse {
+        name: String,
+        query: String,
+        param_types: Vec<u32>,
+    },
+    Bind {
+        portal: String,
+        statement: String,
+        param_formats: Vec<i16>,
+        params: Vec<Option<Vec<u8>>>,
+        result_formats: Vec<i16>,
+    },
+    Describe {
+        kind: u8,
+        name: String,
+    },
+    Execute {
+        portal: String,
+        max_rows: i32,
+    },
+    Close {
+        kind: u8,
+        name: String,
+    },
+    Sync,
+    Flush,
+    Terminate,
+    Unsupported(u8),
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) async fn read_startup_message<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<StartupMessage, Error> {
+    let len = reader.read_i32().await? as usize;
+    if len < 8 || len > MAX_MESSAGE_LEN {
+        return Err(invalid_data("Invalid startup message length"));
+    }
+
+    let mut body = vec![0; len - 4];
+    reader.read_exact(&mut body).await?;
+
+    let mut body = MessageBody::new(&body);
+    match body.read_i32()? {
+        SSL_REQUEST_CODE => Ok(StartupMessage::SslRequest),
+        GSSENC_REQUEST_CODE => Ok(StartupMessage::GssEncRequest),
+        CANCEL_REQUEST_CODE => Ok(StartupMessage::CancelRequest),
+        PROTOCOL_VERSION_3 => {
+            let mut params = HashMap::new();
+            loop {
+                let key = body.read_cstr()?;
+                if key.is_empty() {
+                    break;
+                }
+                params.insert(key, body.read_cstr()?);
+            }
+            Ok(StartupMessage::Startup { params })
+        }
+        v => Err(invalid_data(format!("Unsupported protocol version {}", v))),
+    }
+}
+
+/// Reads the next message, returning `None` if client closed the connection
+pub(crate) async fn read_message<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<Option<FrontendMessage>, Error> {
+    let tag = match reader.read_u8().await {
+        Ok(tag) => tag,
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let len = reader.read_i32().await? as usize;
+    if len < 4 || len > MAX_MESSAGE_LEN {
+        return Err(invalid_data("Invalid message length"));
+    }
+
+    let mut body = vec![0; len - 4];
+    reader.read_exact(&mut body).await?;
+    let mut body = MessageBody::new(&body);
+
+    let message = match tag {
+        b'Q' => FrontendMessage::Query(body.read_cstr()?),
+        b'P' => {
+            let name = body.read_cstr()?;
+            let query = body.read_cstr()?;
+            let num_types = body.read_i16()?;
+            let param_types = (0..num_types)
+                .map(|_| body.read_i32().map(|t| t as u32))
+                .collect::<Result<_, _>>()?;
+            FrontendMessage::Parse {
+                name,
+                query,
+                param_types,
+            }
+        }
+        b'B' => {
+            let portal = body.read_cstr()?;
+            let statement = body.read_cstr()?;
+            let param_formats = body.read_i16_array()?;
+            let num_params = body.read_i16()?;
+            let params = (0..num_params)
+                .map(|_| match body.read_i32()? {
+                    -1 => Ok(None),
+                    len => body.read_bytes(len as usize).map(|b| Some(b.to_vec())),
+                })
+                .collect::<Result<_, _>>()?;
+            let result_formats = body.read_i16_array()?;
+            FrontendMessage::Bind {
+                portal,
+                statement,
+                param_formats,
+                params,
+                result_formats,
+            }
+        }
+        b'D' => FrontendMessage::Describe {
+            kind: body.read_u8()?,
+            name: body.read_cstr()?,
+        },
+        b'E' => FrontendMessage::Execute {
+            portal: body.read_cstr()?,
+            max_rows: body.read_i32()?,
+        },
+        b'C' => FrontendMessage::Close {
+            kind: body.read_u8()?,
+            name: body.read_cstr()?,
+        },
+        b'S' => FrontendMessage::Sync,
+        b'H' => FrontendMessage::Flush,
+        b'X' => FrontendMessage::Terminate,
+        other => FrontendMessage::Unsupported(other),
+    };
+
+    Ok(Some(message))
+}
+
+fn invalid_data(msg: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+struct MessageBody<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> MessageBody<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.pos + len > self.buf.len() {
+            return Err(invalid_data("Unexpected end of message"));
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Error> {
+        Ok(i16::from_be_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Error> {
+        Ok(i32::from_be_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    fn read_i16_array(&mut self) -> Result<Vec<i16>, Error> {
+        let len = self.read_i16()?;
+        (0..len).map(|_| self.read_i16()).collect()
+    }
+
+    fn read_cstr(&mut self) -> Result<String, Error> {
+        let rest = &self.buf[self.pos..];
+        let end = rest
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| invalid_data("Unterminated string"))?;
+        let s = std::str::from_utf8(&rest[..end])
+            .map_err(|_| invalid_data("String is not valid UTF-8"))?
+            .to_owned();
+        self.pos += end + 1;
+        Ok(s)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub(crate) struct FieldDescription {
+    pub name: String,
+    pub type_oid: u32,
+    pub type_len: i16,
+    pub format: i16,
+}
+
+/// Accumulates backend messages until they are flushed to the client
+#[derive(Default)]
+pub(crate) struct BackendMessages {
+    buf: Vec<u8>,
+}
+
+impl BackendMessages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn begin(&mut self, tag: u8) -> usize {
+        self.buf.push(tag);
+        let start = self.buf.len();
+        self.buf.extend_from_slice(&[0; 4]);
+        start
+    }
+
+    fn end(&mut self, start: usize) {
+        let len = (self.buf.len() - start) as i32;
+        self.buf[start..start + 4].copy_from_slice(&len.to_be_bytes());
+    }
+
+    fn put_i16(&mut self, v: i16) {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn put_i32(&mut self, v: i32) {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn put_cstr(&mut self, s: &str) {
+        self.buf.extend_from_slice(s.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn empty(&mut self, tag: u8) {
+        let start = self.begin(tag);
+        self.end(start);
+    }
+
+    /// Response to SSL and GSS encryption requests that are not supported
+    pub fn encryption_refused(&mut self) {
+        self.buf.push(b'N');
+    }
+
+    pub fn authentication_ok(&mut self) {
+        let start = self.begin(b'R');
+        self.put_i32(0);
+        self.end(start);
+    }
+
+    pub fn parameter_status(&mut self, name: &str, value: &str) {
+        let start = self.begin(b'S');
+        self.put_cstr(name);
+        self.put_cstr(value);
+        self.end(start);
+    }
+
+    pub fn backend_key_data(&mut self, process_id: i32, secret_key: i32) {
+        let start = self.begin(b'K');
+        self.put_i32(process_id);
+        self.put_i32(secret_key);
+        self.end(start);
+    }
+
+    pub fn ready_for_query(&mut self) {
+        let start = self.begin(b'Z');
+        // Transactions are not supported so we are always idle
+        self.buf.push(b'I');
+        self.end(start);
+    }
+
+    pub fn row_description(&mut self, fields: &[FieldDescription]) {
+        let start = self.begin(b'T');
+        self.put_i16(fields.len() as i16);
+        for field in fields {
+            self.put_cstr(&field.name);
+            self.put_i32(0); // Table OID
+            self.put_i16(0); // Column attribute number
+            self.put_i32(field.type_oid as i32);
+            self.put_i16(field.type_len);
+            self.put_i32(-1); // Type modifier
+            self.put_i16(field.format);
+        }
+        self.end(start);
+    }
+
+    pub fn data_row(&mut self, values: &[Option<Vec<u8>>]) {
+        let start = self.begin(b'D');
+        self.put_i16(values.len() as i16);
+        for value in values {
+            match value {
+                None => self.put_i32(-1),
+                Some(v) => {
+                    self.put_i32(v.len() as i32);
+                    self.buf.extend_from_slice(v);
+                }
+            }
+        }
+        self.end(start);
+    }
+
+    pub fn command_complete(&mut self, tag: &str) {
+        let start = self.begin(b'C');
+        self.put_cstr(tag);
+        self.end(start);
+    }
+
+    pub fn error_response(&mut self, code: &str, message: &str) {
+        let start = self.begin(b'E');
+        for (field, value) in [
+            (b'S', "ERROR"),
+            (b'V', "ERROR"),
+            (b'C', code),
+            (b'M', message),
+        ] {
+            self.buf.push(field);
+            self.put_cstr(value);
+        }
+        self.buf.push(0);
+        self.end(start);
+    }
+
+    pub fn parameter_description(&mut self, types: &[u32]) {
+        let start = self.begin(b't');
+        self.put_i16(types.len() as i16);
+        for t in types {
+            self.put_i32(*t as i32);
+        }
+        self.end(start);
+    }
+
+    pub fn empty_query_response(&mut self) {
+        self.empty(b'I');
+    }
+
+    pub fn parse_complete(&mut self) {
+        self.empty(b'1');
+    }
+
+    pub fn bind_complete(&mut self) {
+        self.empty(b'2');
+    }
+
+    pub fn close_complete(&mut self) {
+        self.empty(b'3');
+    }
+
+    pub fn no_data(&mut self) {
+        self.empty(b'n');
+    }
+
+    pub fn portal_suspended(&mut self) {
+        self.empty(b's');
+    }
+
+    pub async fn flush<W: AsyncWrite + Unpin>(&mut self, writer: &mut W) -> Result<(), Error> {
+        writer.write_all(&self.buf).await?;
+        writer.flush().await?;
+        self.buf.clear();
+        Ok(())
+    }
+}
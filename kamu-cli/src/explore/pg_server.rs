@@ -0,0 +1,762 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::pg_catalog::register_pg_catalog;
+use super::pg_protocol::*;
+use super::pg_types::*;
+use kamu::domain::*;
+
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::LogicalPlan;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+use kamu::infra::utils::sql_script::split_sql_statements;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+const SERVER_VERSION: &str = "14.0";
+
+/// Number of rows after which results are flushed to the client, so that
+/// large results are never buffered in full
+const MAX_BUFFERED_ROWS: usize = 1000;
+
+// See: https://www.postgresql.org/docs/current/errcodes-appendix.html
+const SQLSTATE_CONNECTION_FAILURE: &str = "08006";
+const SQLSTATE_PROTOCOL_VIOLATION: &str = "08P01";
+const SQLSTATE_FEATURE_NOT_SUPPORTED: &str = "0A000";
+const SQLSTATE_INVALID_PARAMETER_VALUE: &str = "22023";
+const SQLSTATE_INVALID_SQL_STATEMENT_NAME: &str = "26000";
+const SQLSTATE_INVALID_CURSOR_NAME: &str = "34000";
+const SQLSTATE_SYNTAX_ERROR: &str = "42601";
+const SQLSTATE_SYNTAX_ERROR_OR_ACCESS_RULE_VIOLATION: &str = "42000";
+const SQLSTATE_INSUFFICIENT_PRIVILEGE: &str = "42501";
+const SQLSTATE_DUPLICATE_PREPARED_STATEMENT: &str = "42P05";
+const SQLSTATE_INTERNAL_ERROR: &str = "XX000";
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Serves workspace datasets over the PostgreSQL wire protocol.
+///
+/// Supports simple and extended query protocols without authentication and encryption,
+/// so it's meant for connecting local tools like `psql` and BI dashboards.
+pub struct PgServer {
+    listener: TcpListener,
+    query_svc: Arc<dyn QueryService>,
+}
+
+impl PgServer {
+    pub async fn new(
+        query_svc: Arc<dyn QueryService>,
+        address: IpAddr,
+        port: u16,
+    ) -> Result<Self, std::io::Error> {
+        let listener = TcpListener::bind(SocketAddr::new(address, port)).await?;
+        Ok(Self {
+            listener,
+            query_svc,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.listener.local_addr().unwrap()
+    }
+
+    pub async fn run(self) -> Result<(), std::io::Error> {
+        let mut next_process_id = 1;
+
+        loop {
+            let (socket, peer_addr) = self.listener.accept().await?;
+
+            let connection = PgConnection::new(self.query_svc.clone(), next_process_id);
+            next_process_id += 1;
+
+            tokio::spawn(async move {
+                if let Err(e) = connection.run(socket).await {
+                    tracing::warn!(error = ?e, %peer_addr, "PG connection failed");
+                }
+            });
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// State of a single client connection.
+///
+/// All statements of a connection are executed within one DataFusion session,
+/// so views and settings persist between them.
+struct PgConnection {
+    query_svc: Arc<dyn QueryService>,
+    process_id: i32,
+    session: Option<SessionContext>,
+    statements: HashMap<String, PreparedStatement>,
+    portals: HashMap<String, Portal>,
+    out: BackendMessages,
+}
+
+struct PreparedStatement {
+    query: String,
+    param_types: Vec<u32>,
+}
+
+struct Portal {
+    query: String,
+    result_formats: Vec<i16>,
+    results: Option<QueryResults>,
+}
+
+impl PgConnection {
+    fn new(query_svc: Arc<dyn QueryService>, process_id: i32) -> Self {
+        Self {
+            query_svc,
+            process_id,
+            session: None,
+            statements: HashMap::new(),
+            portals: HashMap::new(),
+            out: BackendMessages::new(),
+        }
+    }
+
+    async fn run(mut self, socket: TcpStream) -> Result<(), std::io::Error> {
+        let (reader, mut writer) = socket.into_split();
+        let mut reader = BufReader::new(reader);
+
+        if !self.startup(&mut reader, &mut writer).await? {
+            return Ok(());
+        }
+
+        // After an error in extended query mode all messages are discarded until `Sync`
+        let mut skip_until_sync = false;
+
+        while let Some(message) = read_message(&mut reader).await? {
+            match message {
+                FrontendMessage::Terminate => break,
+                FrontendMessage::Sync => {
+                    skip_until_sync = false;
+                    self.out.ready_for_query();
+                    self.out.flush(&mut writer).await?;
+                }
+                FrontendMessage::Flush => self.out.flush(&mut writer).await?,
+                FrontendMessage::Query(query) => {
+                    if let Err(e) = self.simple_query(&query, &mut writer).await {
+                        self.out.error_response(e.code, &e.message);
+                    }
+                    self.out.ready_for_query();
+                    self.out.flush(&mut writer).await?;
+                }
+                _ if skip_until_sync => {}
+                message => {
+                    if let Err(e) = self.extended_query(message, &mut writer).await {
+                        self.out.error_response(e.code, &e.message);
+                        skip_until_sync = true;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Negotiates the connection, returning `false` if client does not intend to run queries
+    async fn startup<R, W>(
+        &mut self,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> Result<bool, std::io::Error>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        loop {
+            match read_startup_message(reader).await? {
+                StartupMessage::SslRequest | StartupMessage::GssEncRequest => {
+                    self.out.encryption_refused();
+                    self.out.flush(writer).await?;
+                }
+                // Queries are not cancellable
+                StartupMessage::CancelRequest => return Ok(false),
+                StartupMessage::Startup { params } => {
+                    tracing::info!(
+                        user = ?params.get("user"),
+                        database = ?params.get("database"),
+                        application_name = ?params.get("application_name"),
+                        "PG client connected",
+                    );
+
+                    self.out.authentication_ok();
+                    for (name, value) in [
+                        ("server_version", SERVER_VERSION),
+                        ("server_encoding", "UTF8"),
+                        ("client_encoding", "UTF8"),
+                        ("DateStyle", "ISO, MDY"),
+                        ("TimeZone", "UTC"),
+                        ("integer_datetimes", "on"),
+                        ("standard_conforming_strings", "on"),
+                    ] {
+                        self.out.parameter_status(name, value);
+                    }
+                    self.out.backend_key_data(self.process_id, 0);
+                    self.out.ready_for_query();
+                    self.out.flush(writer).await?;
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    async fn session(&mut self) -> Result<SessionContext, PgError> {
+        if self.session.is_none() {
+            let session = self
+                .query_svc
+                .create_session(QueryOptions::default())
+                .await
+                .map_err(|e| PgError::new(SQLSTATE_INTERNAL_ERROR, e.to_string()))?;
+            register_pg_catalog(&session)?;
+            self.session = Some(session);
+        }
+        Ok(self.session.clone().unwrap())
+    }
+
+    /////////////////////////////////////////////////////////////////////////////////////
+    // Simple query protocol
+    /////////////////////////////////////////////////////////////////////////////////////
+
+    async fn simple_query<W>(&mut self, query: &str, writer: &mut W) -> Result<(), PgError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        tracing::debug!(query, "Executing simple query");
+
+        let statements = split_sql_statements(query);
+        if statements.is_empty() {
+            self.out.empty_query_response();
+            return Ok(());
+        }
+
+        for statement in statements {
+            let mut results = self.execute(&statement.text).await?;
+            if !results.schema.fields().is_empty() {
+                let fields = describe_fields(&results.schema, &[])
+                    .map_err(|e| PgError::new(SQLSTATE_FEATURE_NOT_SUPPORTED, e))?;
+                self.out.row_description(&fields);
+                while !results
+                    .write_rows(&mut self.out, &fields, MAX_BUFFERED_ROWS)
+                    .await?
+                {
+                    self.flush(writer).await?;
+                }
+            }
+            self.out.command_complete(&results.command_tag());
+        }
+
+        Ok(())
+    }
+
+    /////////////////////////////////////////////////////////////////////////////////////
+    // Extended query protocol
+    /////////////////////////////////////////////////////////////////////////////////////
+
+    async fn extended_query<W>(
+        &mut self,
+        message: FrontendMessage,
+        writer: &mut W,
+    ) -> Result<(), PgError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        match message {
+            FrontendMessage::Parse {
+                name,
+                query,
+                param_types,
+            } => self.parse(name, query, param_types),
+            FrontendMessage::Bind {
+                portal,
+                statement,
+                param_formats,
+                params,
+                result_formats,
+            } => self.bind(portal, &statement, &param_formats, &params, result_formats),
+            FrontendMessage::Describe { kind: b'S', name } => self.describe_statement(&name).await,
+            FrontendMessage::Describe { kind: b'P', name } => self.describe_portal(&name).await,
+            FrontendMessage::Execute { portal, max_rows } => {
+                self.execute_portal(&portal, max_rows, writer).await
+            }
+            FrontendMessage::Close { kind, name } => {
+                if kind == b'S' {
+                    self.statements.remove(&name);
+                } else {
+                    self.portals.remove(&name);
+                }
+                self.out.close_complete();
+                Ok(())
+            }
+            FrontendMessage::Unsupported(tag) => Err(PgError::new(
+                SQLSTATE_FEATURE_NOT_SUPPORTED,
+                format!("Unsupported message type '{}'", tag as char),
+            )),
+            message => Err(PgError::new(
+                SQLSTATE_PROTOCOL_VIOLATION,
+                format!("Unexpected message {:?}", message),
+            )),
+        }
+    }
+
+    fn parse(&mut self, name: String, query: String, param_types: Vec<u32>) -> Result<(), PgError> {
+        tracing::debug!(name = %name, query = %query, "Parsing statement");
+
+        if !name.is_empty() && self.statements.contains_key(&name) {
+            return Err(PgError::new(
+                SQLSTATE_DUPLICATE_PREPARED_STATEMENT,
+                format!("Prepared statement \"{}\" already exists", name),
+            ));
+        }
+
+        // Parameters with unspecified types are treated as text and coerced by the engine
+        let num_params = count_params(&query)
+            .map_err(|e| PgError::new(SQLSTATE_PROTOCOL_VIOLATION, e))?
+            .max(param_types.len());
+        let param_types = (0..num_params)
+            .map(|i| match param_types.get(i) {
+                None | Some(0) => OID_TEXT,
+                Some(t) => *t,
+            })
+            .collect();
+
+        self.statements
+            .insert(name, PreparedStatement { query, param_types });
+        self.out.parse_complete();
+        Ok(())
+    }
+
+    fn bind(
+        &mut self,
+        portal: String,
+        statement: &str,
+        param_formats: &[i16],
+        params: &[Option<Vec<u8>>],
+        result_formats: Vec<i16>,
+    ) -> Result<(), PgError> {
+        let statement = self.statements.get(statement).ok_or_else(|| {
+            PgError::new(
+                SQLSTATE_INVALID_SQL_STATEMENT_NAME,
+                format!("Prepared statement \"{}\" does not exist", statement),
+            )
+        })?;
+
+        if params.len() != statement.param_types.len() {
+            return Err(PgError::new(
+                SQLSTATE_PROTOCOL_VIOLATION,
+                format!(
+                    "Statement requires {} parameters but {} were bound",
+                    statement.param_types.len(),
+                    params.len()
+                ),
+            ));
+        }
+
+        let literals = params
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let format = match param_formats.len() {
+                    0 => FORMAT_TEXT,
+                    1 => param_formats[0],
+                    _ => param_formats.get(i).copied().unwrap_or(FORMAT_TEXT),
+                };
+                param_to_literal(value.as_deref(), statement.param_types[i], format)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| PgError::new(SQLSTATE_INVALID_PARAMETER_VALUE, e))?;
+
+        let query = substitute_params(&statement.query, &literals)
+            .map_err(|e| PgError::new(SQLSTATE_PROTOCOL_VIOLATION, e))?;
+
+        self.portals.insert(
+            portal,
+            Portal {
+                query,
+                result_formats,
+                results: None,
+            },
+        );
+        self.out.bind_complete();
+        Ok(())
+    }
+
+    async fn describe_statement(&mut self, name: &str) -> Result<(), PgError> {
+        let statement = self.statements.get(name).ok_or_else(|| {
+            PgError::new(
+                SQLSTATE_INVALID_SQL_STATEMENT_NAME,
+                format!("Prepared statement \"{}\" does not exist", name),
+            )
+        })?;
+
+        // Parameters don't affect the result schema, so we plan the query with nulls instead
+        let param_types = statement.param_types.clone();
+        let query = substitute_params(
+            &statement.query,
+            &vec!["NULL".to_owned(); param_types.len()],
+        )
+        .map_err(|e| PgError::new(SQLSTATE_PROTOCOL_VIOLATION, e))?;
+
+        let schema = self.plan(&query).await?;
+
+        self.out.parameter_description(&param_types);
+        self.write_description(schema.as_deref(), &[])
+    }
+
+    async fn describe_portal(&mut self, name: &str) -> Result<(), PgError> {
+        let portal = self.get_portal(name)?;
+        let result_formats = portal.result_formats.clone();
+        let query = portal.query.clone();
+
+        let schema = match portal.results.as_ref().map(|r| r.schema.clone()) {
+            Some(schema) => Some(schema),
+            None => self.plan(&query).await?,
+        };
+
+        self.write_description(schema.as_deref(), &result_formats)
+    }
+
+    fn write_description(
+        &mut self,
+        schema: Option<&Schema>,
+        formats: &[i16],
+    ) -> Result<(), PgError> {
+        match schema {
+            Some(schema) if !schema.fields().is_empty() => {
+                let fields = describe_fields(schema, formats)
+                    .map_err(|e| PgError::new(SQLSTATE_FEATURE_NOT_SUPPORTED, e))?;
+                self.out.row_description(&fields);
+            }
+            _ => self.out.no_data(),
+        }
+        Ok(())
+    }
+
+    /// Results are streamed from the engine, so a portal suspended after `max_rows`
+    /// continues from where it stopped on the next `Execute`
+    async fn execute_portal<W>(
+        &mut self,
+        name: &str,
+        max_rows: i32,
+        writer: &mut W,
+    ) -> Result<(), PgError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let portal = self.get_portal(name)?;
+
+        if portal.results.is_none() {
+            let query = portal.query.clone();
+            tracing::debug!(query = %query, "Executing portal");
+
+            let results = self.execute(&query).await?;
+            self.portals.get_mut(name).unwrap().results = Some(results);
+        }
+
+        let portal = self.portals.get_mut(name).unwrap();
+        let result_formats = portal.result_formats.clone();
+        let mut results = portal.results.take().unwrap();
+
+        let completed = self
+            .write_portal_rows(&mut results, &result_formats, max_rows, writer)
+            .await?;
+
+        if completed {
+            self.out.command_complete(&results.command_tag());
+        } else {
+            self.out.portal_suspended();
+        }
+
+        self.portals.get_mut(name).unwrap().results = Some(results);
+        Ok(())
+    }
+
+    /// Writes up to `max_rows` rows (or all if zero), returning `true` when all rows were sent
+    async fn write_portal_rows<W>(
+        &mut self,
+        results: &mut QueryResults,
+        result_formats: &[i16],
+        max_rows: i32,
+        writer: &mut W,
+    ) -> Result<bool, PgError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        if results.schema.fields().is_empty() {
+            return Ok(true);
+        }
+
+        let fields = describe_fields(&results.schema, result_formats)
+            .map_err(|e| PgError::new(SQLSTATE_FEATURE_NOT_SUPPORTED, e))?;
+
+        let mut remaining = max_rows.max(0) as usize;
+        loop {
+            let chunk = match remaining {
+                0 => MAX_BUFFERED_ROWS,
+                n => n.min(MAX_BUFFERED_ROWS),
+            };
+            let sent_before = results.num_rows;
+
+            if results.write_rows(&mut self.out, &fields, chunk).await? {
+                return Ok(true);
+            }
+
+            if remaining != 0 {
+                remaining -= results.num_rows - sent_before;
+                if remaining == 0 {
+                    return Ok(false);
+                }
+            }
+
+            self.flush(writer).await?;
+        }
+    }
+
+    async fn flush<W>(&mut self, writer: &mut W) -> Result<(), PgError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.out
+            .flush(writer)
+            .await
+            .map_err(|e| PgError::new(SQLSTATE_CONNECTION_FAILURE, e.to_string()))
+    }
+
+    fn get_portal(&self, name: &str) -> Result<&Portal, PgError> {
+        self.portals.get(name).ok_or_else(|| {
+            PgError::new(
+                SQLSTATE_INVALID_CURSOR_NAME,
+                format!("Portal \"{}\" does not exist", name),
+            )
+        })
+    }
+
+    /////////////////////////////////////////////////////////////////////////////////////
+
+    /// Returns the result schema of the statement without executing it
+    async fn plan(&mut self, statement: &str) -> Result<Option<SchemaRef>, PgError> {
+        if ignored_statement_tag(statement).is_some() {
+            return Ok(None);
+        }
+
+        let plan = self.session().await?.create_logical_plan(statement)?;
+        check_statement_allowed(&plan)?;
+        Ok(Some(Arc::new(Schema::from(plan.schema().as_ref().clone()))))
+    }
+
+    async fn execute(&mut self, statement: &str) -> Result<QueryResults, PgError> {
+        if let Some(tag) = ignored_statement_tag(statement) {
+            return Ok(QueryResults::empty(tag));
+        }
+
+        // Statement is checked before execution, as DDL takes effect when executing the plan
+        let session = self.session().await?;
+        let plan = session.create_logical_plan(statement)?;
+        check_statement_allowed(&plan)?;
+
+        let df = session.execute_logical_plan(plan).await?;
+        let schema = Arc::new(Schema::from(df.schema().clone()));
+        let stream = df.execute_stream().await?;
+
+        Ok(QueryResults {
+            schema,
+            stream: Some(stream),
+            batch: None,
+            row: 0,
+            num_rows: 0,
+            keyword: statement_keyword(statement),
+        })
+    }
+}
+
+/// Only views can be created within the session, as other DDL statements could
+/// affect the host, e.g. `CREATE EXTERNAL TABLE` can read arbitrary files
+fn check_statement_allowed(plan: &LogicalPlan) -> Result<(), PgError> {
+    match plan {
+        LogicalPlan::CreateExternalTable(_)
+        | LogicalPlan::CreateMemoryTable(_)
+        | LogicalPlan::CreateCatalogSchema(_)
+        | LogicalPlan::CreateCatalog(_)
+        | LogicalPlan::DropTable(_) => Err(PgError::new(
+            SQLSTATE_INSUFFICIENT_PRIVILEGE,
+            "Only CREATE VIEW statements are allowed to modify the session",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Results of a statement that are streamed to the client in one or several chunks
+struct QueryResults {
+    schema: SchemaRef,
+    /// Stream of remaining batches, `None` once exhausted
+    stream: Option<SendableRecordBatchStream>,
+    /// Batch that is currently being sent
+    batch: Option<RecordBatch>,
+    row: usize,
+    num_rows: usize,
+    keyword: String,
+}
+
+impl QueryResults {
+    fn empty(keyword: String) -> Self {
+        Self {
+            schema: Arc::new(Schema::empty()),
+            stream: None,
+            batch: None,
+            row: 0,
+            num_rows: 0,
+            keyword,
+        }
+    }
+
+    /// Writes up to `max_rows` rows (or all if zero), returning `true` when all rows were sent
+    async fn write_rows(
+        &mut self,
+        out: &mut BackendMessages,
+        fields: &[FieldDescription],
+        max_rows: usize,
+    ) -> Result<bool, PgError> {
+        let mut sent = 0;
+
+        loop {
+            let exhausted = match &self.batch {
+                Some(batch) => self.row >= batch.num_rows(),
+                None => true,
+            };
+
+            if exhausted {
+                let next = match self.stream.as_mut() {
+                    Some(stream) => stream.try_next().await?,
+                    None => None,
+                };
+
+                match next {
+                    Some(batch) => {
+                        self.batch = Some(batch);
+                        self.row = 0;
+                        continue;
+                    }
+                    None => {
+                        self.stream = None;
+                        self.batch = None;
+                        return Ok(true);
+                    }
+                }
+            }
+
+            if max_rows != 0 && sent == max_rows {
+                return Ok(false);
+            }
+
+            let batch = self.batch.as_ref().unwrap();
+            let values = batch
+                .columns()
+                .iter()
+                .zip(fields)
+                .map(|(column, field)| encode_value(column, self.row, field.format))
+                .collect::<Result<Vec<_>, _>>()?;
+            out.data_row(&values);
+
+            self.row += 1;
+            self.num_rows += 1;
+            sent += 1;
+        }
+    }
+
+    fn command_tag(&self) -> String {
+        if self.schema.fields().is_empty() {
+            self.keyword.clone()
+        } else {
+            format!("SELECT {}", self.num_rows)
+        }
+    }
+}
+
+/// Returns the leading keywords of the statement used in the command completion tag
+fn statement_keyword(statement: &str) -> String {
+    let words: Vec<_> = statement
+        .split_whitespace()
+        .take(2)
+        .map(|w| w.to_uppercase())
+        .collect();
+
+    match words.first().map(String::as_str) {
+        Some("CREATE") | Some("DROP") => words.join(" "),
+        Some(word) => word.to_owned(),
+        None => String::new(),
+    }
+}
+
+/// Session and transaction control statements that DataFusion does not support, but that
+/// drivers commonly issue on connect, are acknowledged without being executed
+fn ignored_statement_tag(statement: &str) -> Option<String> {
+    let keyword = statement_keyword(statement);
+    let tag = match keyword.as_str() {
+        "BEGIN" | "START" => "BEGIN",
+        "COMMIT" | "END" => "COMMIT",
+        "ROLLBACK" | "ABORT" => "ROLLBACK",
+        "DISCARD" => "DISCARD ALL",
+        "DEALLOCATE" => "DEALLOCATE",
+        // Only DataFusion configuration options can be set
+        "SET" if !statement.to_lowercase().contains("datafusion.") => "SET",
+        _ => return None,
+    };
+    Some(tag.to_owned())
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+struct PgError {
+    code: &'static str,
+    message: String,
+}
+
+impl PgError {
+    fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<DataFusionError> for PgError {
+    fn from(e: DataFusionError) -> Self {
+        let code = match &e {
+            DataFusionError::SQL(_) => SQLSTATE_SYNTAX_ERROR,
+            DataFusionError::Plan(_) | DataFusionError::SchemaError(_) => {
+                SQLSTATE_SYNTAX_ERROR_OR_ACCESS_RULE_VIOLATION
+            }
+            DataFusionError::NotImplemented(_) => SQLSTATE_FEATURE_NOT_SUPPORTED,
+            _ => SQLSTATE_INTERNAL_ERROR,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<ArrowError> for PgError {
+    fn from(e: ArrowError) -> Self {
+        Self::new(SQLSTATE_INTERNAL_ERROR, e.to_string())
+    }
+}
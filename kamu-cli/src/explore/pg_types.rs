@@ -0,0 +1,492 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::pg_protocol::{FieldDescription, FORMAT_BINARY, FORMAT_TEXT};
+
+use datafusion::arrow::array::*;
+use datafusion::arrow::datatypes::*;
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::util::display::array_value_to_string;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) const OID_BOOL: u32 = 16;
+pub(crate) const OID_BYTEA: u32 = 17;
+pub(crate) const OID_INT8: u32 = 20;
+pub(crate) const OID_INT2: u32 = 21;
+pub(crate) const OID_INT4: u32 = 23;
+pub(crate) const OID_TEXT: u32 = 25;
+pub(crate) const OID_FLOAT4: u32 = 700;
+pub(crate) const OID_FLOAT8: u32 = 701;
+pub(crate) const OID_VARCHAR: u32 = 1043;
+pub(crate) const OID_DATE: u32 = 1082;
+pub(crate) const OID_TIME: u32 = 1083;
+pub(crate) const OID_TIMESTAMP: u32 = 1114;
+pub(crate) const OID_TIMESTAMPTZ: u32 = 1184;
+pub(crate) const OID_NUMERIC: u32 = 1700;
+
+/// Names of the supported types, as listed in `pg_catalog.pg_type`
+pub(crate) const TYPE_NAMES: &[(u32, &str)] = &[
+    (OID_BOOL, "bool"),
+    (OID_BYTEA, "bytea"),
+    (OID_INT8, "int8"),
+    (OID_INT2, "int2"),
+    (OID_INT4, "int4"),
+    (OID_TEXT, "text"),
+    (OID_FLOAT4, "float4"),
+    (OID_FLOAT8, "float8"),
+    (OID_VARCHAR, "varchar"),
+    (OID_DATE, "date"),
+    (OID_TIME, "time"),
+    (OID_TIMESTAMP, "timestamp"),
+    (OID_TIMESTAMPTZ, "timestamptz"),
+    (OID_NUMERIC, "numeric"),
+];
+
+// Postgres epoch (2000-01-01) relative to the Unix epoch
+const PG_EPOCH_DAYS: i64 = 10957;
+const PG_EPOCH_MICROS: i64 = PG_EPOCH_DAYS * 86_400_000_000;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Maps Arrow type to the closest PG type, falling back to `text` for types
+/// that don't have a counterpart (e.g. structs and lists)
+pub(crate) fn pg_type_of(data_type: &DataType) -> (u32, i16) {
+    match data_type {
+        DataType::Boolean => (OID_BOOL, 1),
+        DataType::Int8 | DataType::Int16 | DataType::UInt8 => (OID_INT2, 2),
+        DataType::Int32 | DataType::UInt16 => (OID_INT4, 4),
+        DataType::Int64 | DataType::UInt32 | DataType::UInt64 => (OID_INT8, 8),
+        DataType::Float16 | DataType::Float32 => (OID_FLOAT4, 4),
+        DataType::Float64 => (OID_FLOAT8, 8),
+        DataType::Binary | DataType::LargeBinary => (OID_BYTEA, -1),
+        DataType::Date32 | DataType::Date64 => (OID_DATE, 4),
+        DataType::Time32(_) | DataType::Time64(_) => (OID_TIME, 8),
+        DataType::Timestamp(_, None) => (OID_TIMESTAMP, 8),
+        DataType::Timestamp(_, Some(_)) => (OID_TIMESTAMPTZ, 8),
+        DataType::Decimal128(_, _) => (OID_NUMERIC, -1),
+        _ => (OID_TEXT, -1),
+    }
+}
+
+/// Describes the result columns using the format codes requested by the client.
+///
+/// Zero codes mean all columns use text format, a single code applies to all columns,
+/// otherwise there is one code per column.
+pub(crate) fn describe_fields(
+    schema: &Schema,
+    formats: &[i16],
+) -> Result<Vec<FieldDescription>, String> {
+    if formats.len() > 1 && formats.len() != schema.fields().len() {
+        return Err(format!(
+            "Expected {} result format codes but got {}",
+            schema.fields().len(),
+            formats.len()
+        ));
+    }
+
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let (type_oid, type_len) = pg_type_of(field.data_type());
+            let format = match formats.len() {
+                0 => FORMAT_TEXT,
+                1 => formats[0],
+                _ => formats[i],
+            };
+            if format == FORMAT_BINARY && matches!(type_oid, OID_NUMERIC | OID_TIME) {
+                return Err(format!(
+                    "Binary format is not supported for column {} of type {}",
+                    field.name(),
+                    field.data_type()
+                ));
+            }
+            Ok(FieldDescription {
+                name: field.name().clone(),
+                type_oid,
+                type_len,
+                format,
+            })
+        })
+        .collect()
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Encodes a single value of the column in the specified format
+pub(crate) fn encode_value(
+    array: &ArrayRef,
+    row: usize,
+    format: i16,
+) -> Result<Option<Vec<u8>>, ArrowError> {
+    if array.is_null(row) {
+        return Ok(None);
+    }
+
+    let value = if format == FORMAT_BINARY {
+        encode_binary(array, row)?
+    } else {
+        encode_text(array, row)?.into_bytes()
+    };
+
+    Ok(Some(value))
+}
+
+fn encode_text(array: &ArrayRef, row: usize) -> Result<String, ArrowError> {
+    match array.data_type() {
+        DataType::Boolean => {
+            let v = as_boolean_array(array).value(row);
+            Ok(if v { "t" } else { "f" }.to_owned())
+        }
+        DataType::Binary => Ok(hex_bytea(as_generic_binary_array::<i32>(array).value(row))),
+        DataType::LargeBinary => Ok(hex_bytea(as_generic_binary_array::<i64>(array).value(row))),
+        // PG uses space instead of `T` as date and time separator
+        DataType::Timestamp(_, _) => Ok(array_value_to_string(array, row)?.replacen('T', " ", 1)),
+        _ => array_value_to_string(array, row),
+    }
+}
+
+fn encode_binary(array: &ArrayRef, row: usize) -> Result<Vec<u8>, ArrowError> {
+    let value = match array.data_type() {
+        DataType::Boolean => vec![as_boolean_array(array).value(row) as u8],
+        DataType::Int8 => (as_primitive_array::<Int8Type>(array).value(row) as i16)
+            .to_be_bytes()
+            .to_vec(),
+        DataType::Int16 => as_primitive_array::<Int16Type>(array)
+            .value(row)
+            .to_be_bytes()
+            .to_vec(),
+        DataType::UInt8 => (as_primitive_array::<UInt8Type>(array).value(row) as i16)
+            .to_be_bytes()
+            .to_vec(),
+        DataType::Int32 => as_primitive_array::<Int32Type>(array)
+            .value(row)
+            .to_be_bytes()
+            .to_vec(),
+        DataType::UInt16 => (as_primitive_array::<UInt16Type>(array).value(row) as i32)
+            .to_be_bytes()
+            .to_vec(),
+        DataType::Int64 => as_primitive_array::<Int64Type>(array)
+            .value(row)
+            .to_be_bytes()
+            .to_vec(),
+        DataType::UInt32 => (as_primitive_array::<UInt32Type>(array).value(row) as i64)
+            .to_be_bytes()
+            .to_vec(),
+        DataType::UInt64 => {
+            let v = as_primitive_array::<UInt64Type>(array).value(row);
+            i64::try_from(v)
+                .map_err(|_| ArrowError::CastError(format!("Value {} overflows int8", v)))?
+                .to_be_bytes()
+                .to_vec()
+        }
+        DataType::Float16 => as_primitive_array::<Float16Type>(array)
+            .value(row)
+            .to_f32()
+            .to_be_bytes()
+            .to_vec(),
+        DataType::Float32 => as_primitive_array::<Float32Type>(array)
+            .value(row)
+            .to_be_bytes()
+            .to_vec(),
+        DataType::Float64 => as_primitive_array::<Float64Type>(array)
+            .value(row)
+            .to_be_bytes()
+            .to_vec(),
+        DataType::Binary => as_generic_binary_array::<i32>(array).value(row).to_vec(),
+        DataType::LargeBinary => as_generic_binary_array::<i64>(array).value(row).to_vec(),
+        DataType::Date32 => {
+            let days = as_primitive_array::<Date32Type>(array).value(row);
+            ((days as i64 - PG_EPOCH_DAYS) as i32)
+                .to_be_bytes()
+                .to_vec()
+        }
+        DataType::Date64 => {
+            let days = as_primitive_array::<Date64Type>(array).value(row) / 86_400_000;
+            ((days - PG_EPOCH_DAYS) as i32).to_be_bytes().to_vec()
+        }
+        DataType::Timestamp(unit, _) => {
+            let micros = timestamp_micros(array, row, unit);
+            (micros - PG_EPOCH_MICROS).to_be_bytes().to_vec()
+        }
+        // Types without a PG counterpart are sent as `text`, which has the same
+        // representation in both formats
+        _ => encode_text(array, row)?.into_bytes(),
+    };
+    Ok(value)
+}
+
+fn timestamp_micros(array: &ArrayRef, row: usize, unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => as_primitive_array::<TimestampSecondType>(array).value(row) * 1_000_000,
+        TimeUnit::Millisecond => {
+            as_primitive_array::<TimestampMillisecondType>(array).value(row) * 1000
+        }
+        TimeUnit::Microsecond => as_primitive_array::<TimestampMicrosecondType>(array).value(row),
+        TimeUnit::Nanosecond => {
+            as_primitive_array::<TimestampNanosecondType>(array).value(row) / 1000
+        }
+    }
+}
+
+fn hex_bytea(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(2 + bytes.len() * 2);
+    s.push_str("\\x");
+    for b in bytes {
+        s.push_str(&format!("{:02x}", b));
+    }
+    s
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Converts a bound parameter into an SQL literal that can be substituted into the query
+pub(crate) fn param_to_literal(
+    value: Option<&[u8]>,
+    type_oid: u32,
+    format: i16,
+) -> Result<String, String> {
+    let value = match value {
+        None => return Ok("NULL".to_owned()),
+        Some(v) => v,
+    };
+
+    if format == FORMAT_BINARY {
+        let invalid = || format!("Invalid binary value for parameter of type {}", type_oid);
+        return match type_oid {
+            OID_BOOL => match value {
+                [0] => Ok("FALSE".to_owned()),
+                [_] => Ok("TRUE".to_owned()),
+                _ => Err(invalid()),
+            },
+            OID_INT2 => Ok(numeric_literal(
+                &i16::from_be_bytes(value.try_into().map_err(|_| invalid())?).to_string(),
+            )),
+            OID_INT4 => Ok(numeric_literal(
+                &i32::from_be_bytes(value.try_into().map_err(|_| invalid())?).to_string(),
+            )),
+            OID_INT8 => Ok(numeric_literal(
+                &i64::from_be_bytes(value.try_into().map_err(|_| invalid())?).to_string(),
+            )),
+            OID_FLOAT4 => {
+                let v = f32::from_be_bytes(value.try_into().map_err(|_| invalid())?);
+                finite_float_literal(v as f64)
+            }
+            OID_FLOAT8 => {
+                let v = f64::from_be_bytes(value.try_into().map_err(|_| invalid())?);
+                finite_float_literal(v)
+            }
+            OID_TEXT | OID_VARCHAR => Ok(quote_literal(
+                std::str::from_utf8(value).map_err(|_| invalid())?,
+            )),
+            _ => Err(format!(
+                "Binary format is not supported for parameters of type {}",
+                type_oid
+            )),
+        };
+    }
+
+    let text =
+        std::str::from_utf8(value).map_err(|_| "Parameter value is not valid UTF-8".to_owned())?;
+
+    match type_oid {
+        OID_BOOL => match text.to_lowercase().as_str() {
+            "t" | "true" | "yes" | "on" | "1" => Ok("TRUE".to_owned()),
+            "f" | "false" | "no" | "off" | "0" => Ok("FALSE".to_owned()),
+            _ => Err(format!("Invalid boolean value: {}", text)),
+        },
+        // Numbers are inserted verbatim so they must strictly follow the SQL grammar
+        OID_INT2 | OID_INT4 | OID_INT8 => {
+            if is_integer(text.trim()) {
+                Ok(numeric_literal(text.trim()))
+            } else {
+                Err(format!("Invalid integer value: {}", text))
+            }
+        }
+        OID_FLOAT4 | OID_FLOAT8 | OID_NUMERIC => {
+            if is_decimal(text.trim()) {
+                Ok(numeric_literal(text.trim()))
+            } else {
+                Err(format!("Invalid numeric value: {}", text))
+            }
+        }
+        _ => Ok(quote_literal(text)),
+    }
+}
+
+fn quote_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Wraps negative numbers in parentheses, so that a minus sign preceding the
+/// placeholder can't turn into a `--` comment
+fn numeric_literal(s: &str) -> String {
+    if s.starts_with('-') {
+        format!("({})", s)
+    } else {
+        s.to_owned()
+    }
+}
+
+fn finite_float_literal(v: f64) -> Result<String, String> {
+    if v.is_finite() {
+        Ok(numeric_literal(&v.to_string()))
+    } else {
+        Err(format!("Non-finite numeric value is not supported: {}", v))
+    }
+}
+
+/// Matches `[+-]?digits`
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Matches `[+-]?(digits[.digits?]|.digits)([eE][+-]?digits)?`
+fn is_decimal(s: &str) -> bool {
+    let s = s.strip_prefix(['+', '-']).unwrap_or(s);
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+
+    let (int_part, frac_part) = match mantissa.split_once('.') {
+        Some((i, f)) => (i, f),
+        None => (mantissa, ""),
+    };
+    let mantissa_valid = (!int_part.is_empty() || !frac_part.is_empty())
+        && int_part.bytes().all(|b| b.is_ascii_digit())
+        && frac_part.bytes().all(|b| b.is_ascii_digit());
+
+    let exponent_valid = match exponent {
+        None => true,
+        Some(e) => {
+            let digits = e.strip_prefix(['+', '-']).unwrap_or(e);
+            !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+        }
+    };
+
+    mantissa_valid && exponent_valid
+}
+
+/// Replaces `$N` placeholders outside of string literals, quoted identifiers
+/// and comments with the provided literals
+pub(crate) fn substitute_params(query: &str, literals: &[String]) -> Result<String, String> {
+    let mut result = String::with_capacity(query.len());
+
+    for part in split_params(query)? {
+        match part {
+            QueryPart::Text(text) => result.push_str(text),
+            QueryPart::Param(index) => {
+                let literal = index
+                    .checked_sub(1)
+                    .and_then(|i| literals.get(i))
+                    .ok_or_else(|| format!("Parameter ${} is not bound", index))?;
+                result.push_str(literal);
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Maximum number of parameters a statement can have, same as in PostgreSQL
+pub(crate) const MAX_PARAMS: usize = u16::MAX as usize;
+
+/// Parses the number of a `$N` placeholder rejecting ones that can't be bound
+fn parse_param_index(num: &str) -> Result<usize, String> {
+    match num.parse::<usize>() {
+        Ok(index) if index <= MAX_PARAMS => Ok(index),
+        _ => Err(format!(
+            "Parameter ${} exceeds the maximum of {} parameters",
+            num, MAX_PARAMS
+        )),
+    }
+}
+
+/// Returns the highest `$N` placeholder number referenced by the query
+pub(crate) fn count_params(query: &str) -> Result<usize, String> {
+    Ok(split_params(query)?
+        .into_iter()
+        .filter_map(|part| match part {
+            QueryPart::Param(index) => Some(index),
+            QueryPart::Text(_) => None,
+        })
+        .max()
+        .unwrap_or(0))
+}
+
+enum QueryPart<'a> {
+    Text(&'a str),
+    Param(usize),
+}
+
+/// Splits the query into text and `$N` placeholders, skipping over quoted strings,
+/// `--` line comments and (nested) `/* */` block comments
+fn split_params(query: &str) -> Result<Vec<QueryPart<'_>>, String> {
+    let bytes = query.as_bytes();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    // All delimiters are ASCII so byte positions always fall on char boundaries
+    while i < bytes.len() {
+        match bytes[i] {
+            q @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != q {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let mut depth = 0;
+                while i < bytes.len() {
+                    if bytes[i] == b'/' && bytes.get(i + 1) == Some(&b'*') {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/') {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            b'$' if bytes.get(i + 1).map_or(false, |b| b.is_ascii_digit()) => {
+                let num_start = i + 1;
+                let mut num_end = num_start;
+                while num_end < bytes.len() && bytes[num_end].is_ascii_digit() {
+                    num_end += 1;
+                }
+
+                parts.push(QueryPart::Text(&query[start..i]));
+                parts.push(QueryPart::Param(parse_param_index(
+                    &query[num_start..num_end],
+                )?));
+                start = num_end;
+                i = num_end;
+            }
+            _ => i += 1,
+        }
+    }
+
+    parts.push(QueryPart::Text(&query[start.min(query.len())..]));
+    Ok(parts)
+}
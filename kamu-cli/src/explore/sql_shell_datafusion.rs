@@ -0,0 +1,338 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::error::*;
+use crate::output::*;
+use crate::records_writers::{write_stream, RecordsWriter};
+use kamu::domain::*;
+
+use datafusion::arrow::array::StringArray;
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::RecordBatchStream;
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+use kamu::infra::utils::sql_script::split_sql_statements;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+const HISTORY_FILE_NAME: &str = "sql_shell_history";
+
+const HELP: &str = indoc::indoc!(
+    r#"
+    Statements can span multiple lines and are executed once terminated with `;`
+
+    Meta-commands:
+      \d [DATASET]  Describe the dataset schema, or list datasets if none specified
+      \dt           List datasets
+      \timing       Toggle display of statement execution time
+      \o [FILE]     Send query results to a file, or back to stdout if none specified
+      \?            Show this help
+      \q            Quit
+    "#
+);
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Interactive SQL shell that executes queries using the embedded DataFusion engine.
+///
+/// All statements run within a single session, so views and settings persist between them.
+pub struct SqlShellDataFusion {
+    query_svc: Arc<dyn QueryService>,
+    local_repo: Arc<dyn LocalDatasetRepository>,
+    output_config: Arc<OutputConfig>,
+    history_path: PathBuf,
+    query_options: QueryOptions,
+    timing: bool,
+    output_path: Option<PathBuf>,
+    buffer: String,
+    session: Option<SessionContext>,
+}
+
+/// What the shell should do after processing a line of input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlShellAction {
+    Continue,
+    Quit,
+}
+
+impl SqlShellDataFusion {
+    pub fn new(
+        query_svc: Arc<dyn QueryService>,
+        local_repo: Arc<dyn LocalDatasetRepository>,
+        output_config: Arc<OutputConfig>,
+        run_info_dir: PathBuf,
+        query_options: QueryOptions,
+    ) -> Self {
+        Self {
+            query_svc,
+            local_repo,
+            output_config,
+            history_path: run_info_dir.join(HISTORY_FILE_NAME),
+            query_options,
+            timing: false,
+            output_path: None,
+            buffer: String::new(),
+            session: None,
+        }
+    }
+
+    /// Executes all statements of the script in order, stopping at the first error
+    pub async fn run_script(&mut self, script: &str) -> Result<(), CLIError> {
+        for (i, statement) in split_sql_statements(script).into_iter().enumerate() {
+            self.execute_statement(&statement.text).await.map_err(|e| {
+                CLIError::failure(SqlScriptError {
+                    index: i + 1,
+                    line: statement.line,
+                    source: e,
+                })
+            })?;
+        }
+        Ok(())
+    }
+
+    pub async fn run(&mut self) -> Result<(), CLIError> {
+        use rustyline::error::ReadlineError;
+
+        let mut editor = rustyline::DefaultEditor::new().map_err(CLIError::critical)?;
+
+        // History is missing on the first run
+        let _ = editor.load_history(&self.history_path);
+
+        eprintln!(
+            "{}",
+            console::style("DataFusion SQL shell. Type \\? for help, \\q to quit.").dim()
+        );
+
+        loop {
+            let prompt = if self.buffer.is_empty() {
+                "kamu> "
+            } else {
+                "  ... "
+            };
+
+            match editor.readline(prompt) {
+                Ok(line) => {
+                    if !line.trim().is_empty() {
+                        let _ = editor.add_history_entry(line.as_str());
+                    }
+                    match self.handle_input(&line).await {
+                        Ok(SqlShellAction::Continue) => {}
+                        Ok(SqlShellAction::Quit) => break,
+                        Err(e) => display_cli_error(&e),
+                    }
+                }
+                // Ctrl+C discards the statement that is being edited
+                Err(ReadlineError::Interrupted) => self.buffer.clear(),
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(CLIError::critical(e)),
+            }
+        }
+
+        if let Err(e) = editor.save_history(&self.history_path) {
+            tracing::warn!(error = ?e, history_path = ?self.history_path, "Failed to save shell history");
+        }
+
+        Ok(())
+    }
+
+    /// Processes a single line of user input, executing a meta-command or
+    /// the statement once its terminating `;` is entered
+    pub async fn handle_input(&mut self, line: &str) -> Result<SqlShellAction, CLIError> {
+        let trimmed = line.trim();
+
+        if self.buffer.is_empty() && trimmed.starts_with('\\') {
+            return self.handle_meta_command(trimmed).await;
+        }
+
+        if trimmed.is_empty() && self.buffer.is_empty() {
+            return Ok(SqlShellAction::Continue);
+        }
+
+        if !self.buffer.is_empty() {
+            self.buffer.push('\n');
+        }
+        self.buffer.push_str(line);
+
+        if !trimmed.ends_with(';') {
+            return Ok(SqlShellAction::Continue);
+        }
+
+        // Input may contain several statements
+        let input = std::mem::take(&mut self.buffer);
+        for statement in split_sql_statements(&input) {
+            self.execute_statement(&statement.text)
+                .await
+                .map_err(CLIError::failure)?;
+        }
+
+        Ok(SqlShellAction::Continue)
+    }
+
+    async fn handle_meta_command(&mut self, command: &str) -> Result<SqlShellAction, CLIError> {
+        let (name, arg) = match command.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, Some(arg.trim()).filter(|a| !a.is_empty())),
+            None => (command, None),
+        };
+
+        match (name, arg) {
+            ("\\q", _) => return Ok(SqlShellAction::Quit),
+            ("\\?", _) => eprint!("{}", HELP),
+            ("\\d", None) | ("\\dt", None) => self.list_datasets().await?,
+            ("\\d", Some(dataset)) => self.describe_dataset(dataset).await?,
+            ("\\timing", None) => {
+                self.timing = !self.timing;
+                eprintln!("Timing is {}", if self.timing { "on" } else { "off" });
+            }
+            ("\\o", None) => self.output_path = None,
+            ("\\o", Some(path)) => {
+                // Truncate the file, subsequent results will be appended to it
+                std::fs::File::create(path).map_err(CLIError::usage_error_from)?;
+                self.output_path = Some(PathBuf::from(path));
+            }
+            _ => {
+                return Err(CLIError::usage_error(format!(
+                    "Invalid command: {}. Type \\? for help.",
+                    command
+                )))
+            }
+        }
+
+        Ok(SqlShellAction::Continue)
+    }
+
+    async fn session(&mut self) -> Result<SessionContext, DataFusionError> {
+        if self.session.is_none() {
+            let session = self
+                .query_svc
+                .create_session(self.query_options.clone())
+                .await
+                .map_err(|e| DataFusionError::External(e.into()))?;
+            self.session = Some(session);
+        }
+        Ok(self.session.clone().unwrap())
+    }
+
+    async fn execute_statement(&mut self, statement: &str) -> Result<(), DataFusionError> {
+        let start = Instant::now();
+
+        let df = self.session().await?.sql(statement).await?;
+        let stream = df.execute_stream().await?;
+        let schema = stream.schema();
+
+        // Statements like `CREATE VIEW` and `SET` don't produce any columns or output,
+        // while queries always print the header, even if they return no rows
+        if !schema.fields().is_empty() {
+            let mut writer = self.records_writer()?;
+            writer.write_batch(&RecordBatch::new_empty(schema))?;
+            write_stream(writer.as_mut(), stream).await?;
+            writer.finish()?;
+        }
+
+        if self.timing {
+            eprintln!("Time: {:.3} ms", start.elapsed().as_secs_f64() * 1000.0);
+        }
+
+        Ok(())
+    }
+
+    async fn list_datasets(&self) -> Result<(), CLIError> {
+        let mut names: Vec<String> = self
+            .local_repo
+            .get_all_datasets()
+            .map_ok(|hdl| hdl.name.to_string())
+            .try_collect()
+            .await?;
+        names.sort();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("Name", DataType::Utf8, false)]));
+        let records =
+            RecordBatch::try_new(schema, vec![Arc::new(StringArray::from(names))]).unwrap();
+
+        Ok(self.write_records(&[records])?)
+    }
+
+    async fn describe_dataset(&mut self, dataset: &str) -> Result<(), CLIError> {
+        let df = self
+            .session()
+            .await
+            .map_err(CLIError::failure)?
+            .sql(&format!("SELECT * FROM \"{}\" LIMIT 0", dataset))
+            .await
+            .map_err(CLIError::failure)?;
+
+        let fields = df.schema().fields();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("Column", DataType::Utf8, false),
+            Field::new("Type", DataType::Utf8, false),
+            Field::new("Nullable", DataType::Utf8, false),
+        ]));
+        let records = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    fields.iter().map(|f| f.name().clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    fields.iter().map(|f| f.data_type().to_string()),
+                )),
+                Arc::new(StringArray::from_iter_values(fields.iter().map(|f| {
+                    if f.is_nullable() {
+                        "YES"
+                    } else {
+                        "NO"
+                    }
+                }))),
+            ],
+        )
+        .unwrap();
+
+        Ok(self.write_records(&[records])?)
+    }
+
+    fn records_writer(&self) -> Result<Box<dyn RecordsWriter>, std::io::Error> {
+        match &self.output_path {
+            None => self
+                .output_config
+                .get_records_writer(RecordsFormat::default()),
+            Some(path) => {
+                let file = std::fs::OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(path)?;
+                self.output_config
+                    .get_records_writer_to(RecordsFormat::default(), Box::new(file))
+            }
+        }
+    }
+
+    fn write_records(&self, records: &[RecordBatch]) -> Result<(), std::io::Error> {
+        let mut writer = self.records_writer()?;
+        writer.write_batches(records)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, thiserror::Error)]
+#[error("Statement {index} at line {line} failed")]
+pub struct SqlScriptError {
+    /// Index (1-based) of the failed statement in the script
+    pub index: usize,
+    pub line: usize,
+    #[source]
+    pub source: DataFusionError,
+}
@@ -23,19 +23,37 @@ pub struct OutputConfig {
 }
 
 impl OutputConfig {
-    pub fn get_records_writer(&self, fmt: RecordsFormat) -> Box<dyn RecordsWriter> {
+    /// Returns a writer that outputs records to stdout in the configured format
+    pub fn get_records_writer(
+        &self,
+        fmt: RecordsFormat,
+    ) -> Result<Box<dyn RecordsWriter>, std::io::Error> {
         match self.format {
-            OutputFormat::Csv => Box::new(
-                CsvWriterBuilder::new()
-                    .has_headers(true)
-                    .build(std::io::stdout()),
-            ),
-            OutputFormat::Json => Box::new(JsonArrayWriter::new(std::io::stdout())),
-            OutputFormat::JsonLD => Box::new(JsonLineDelimitedWriter::new(std::io::stdout())),
-            OutputFormat::JsonSoA => unimplemented!("SoA Json format is not yet implemented"),
-            OutputFormat::Table => Box::new(TableWriter::new(fmt)),
+            // Table is printed to the terminal directly to preserve its styling
+            OutputFormat::Table => Ok(Box::new(TableWriter::new(fmt))),
+            _ => self.get_records_writer_to(fmt, Box::new(std::io::stdout())),
         }
     }
+
+    /// Same as [Self::get_records_writer] but writes into the specified output instead of stdout
+    pub fn get_records_writer_to(
+        &self,
+        fmt: RecordsFormat,
+        out: Box<dyn std::io::Write>,
+    ) -> Result<Box<dyn RecordsWriter>, std::io::Error> {
+        Ok(match self.format {
+            OutputFormat::Csv => Box::new(CsvWriterBuilder::new().has_headers(true).build(out)),
+            OutputFormat::Json => Box::new(JsonArrayWriter::new(out)),
+            OutputFormat::JsonLD => Box::new(JsonLineDelimitedWriter::new(out)),
+            OutputFormat::JsonSoA => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "SoA Json format is not yet implemented",
+                ))
+            }
+            OutputFormat::Table => Box::new(TableWriter::new(fmt).with_output(out)),
+        })
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -12,7 +12,8 @@ use datafusion::arrow::array::ArrayRef;
 use datafusion::arrow::util::display::array_value_to_string;
 use datafusion::arrow::{datatypes::DataType, record_batch::RecordBatch};
 pub use kamu::infra::utils::records_writers::{
-    CsvWriter, CsvWriterBuilder, JsonArrayWriter, JsonLineDelimitedWriter, RecordsWriter,
+    write_stream, CsvWriter, CsvWriterBuilder, JsonArrayWriter, JsonLineDelimitedWriter,
+    RecordsWriter,
 };
 use prettytable::{Cell, Row, Table};
 use std::any::Any;
@@ -238,15 +239,24 @@ impl Default for ColumnFormat {
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
+/// Prints records as a human-readable table.
+///
+/// Column widths depend on all values in the table, so rows are buffered and
+/// printed in chunks of [TableWriter::MAX_BUFFERED_ROWS], each chunk being a
+/// separate table with its own header. This keeps memory usage bounded for
+/// large results at the expense of column alignment between the chunks.
 pub struct TableWriter {
     format: RecordsFormat,
-    header_written: bool,
+    header: Option<Row>,
     rows_written: usize,
     num_columns: usize,
     table: Table,
+    output: Option<Box<dyn std::io::Write>>,
 }
 
 impl TableWriter {
+    pub const MAX_BUFFERED_ROWS: usize = 10_000;
+
     // TODO: prettytable is hard to print out into a generic Writer
     // as it wants tty output to implement term::Terminal trait
     pub fn new(format: RecordsFormat) -> Self {
@@ -255,10 +265,19 @@ impl TableWriter {
 
         Self {
             format,
-            header_written: false,
+            header: None,
             rows_written: 0,
             num_columns: 0,
             table,
+            output: None,
+        }
+    }
+
+    /// Prints the table into the specified output instead of the terminal (without colors)
+    pub fn with_output(self, output: Box<dyn std::io::Write>) -> Self {
+        Self {
+            output: Some(output),
+            ..self
         }
     }
 
@@ -280,21 +299,46 @@ impl TableWriter {
             .padding(1, 1)
             .build()
     }
+
+    /// Prints buffered rows and starts a new table
+    fn flush_table(&mut self) -> Result<(), std::io::Error> {
+        let mut table = Table::new();
+        table.set_format(Self::get_table_format());
+        if let Some(header) = &self.header {
+            table.set_titles(header.clone());
+        }
+        let table = std::mem::replace(&mut self.table, table);
+
+        match &mut self.output {
+            None => {
+                table.printstd();
+            }
+            Some(out) => {
+                table.print(out)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl RecordsWriter for TableWriter {
     fn write_batch(&mut self, records: &RecordBatch) -> Result<(), std::io::Error> {
-        if !self.header_written {
+        if self.header.is_none() {
             let mut header = Vec::new();
             for field in records.schema().fields() {
                 header.push(Cell::new(&field.name()).style_spec("bc"));
             }
-            self.table.set_titles(Row::new(header));
-            self.header_written = true;
+            let header = Row::new(header);
+            self.table.set_titles(header.clone());
+            self.header = Some(header);
             self.num_columns = records.schema().fields().len();
         }
 
         for row in 0..records.num_rows() {
+            if self.table.len() >= Self::MAX_BUFFERED_ROWS {
+                self.flush_table()?;
+            }
+
             let mut cells = Vec::new();
             for col in 0..records.num_columns() {
                 let array = records.column(col);
@@ -320,7 +364,6 @@ impl RecordsWriter for TableWriter {
             }
         }
 
-        self.table.printstd();
-        Ok(())
+        self.flush_table()
     }
 }
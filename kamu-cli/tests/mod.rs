@@ -7,6 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+#![feature(assert_matches)]
 #![feature(exit_status_error)]
 
 mod tests;
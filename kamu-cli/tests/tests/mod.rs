@@ -8,5 +8,8 @@
 // by the Apache License, Version 2.0.
 
 mod test_api_server_command;
+mod test_flight_sql_server;
 mod test_new_dataset_command;
+mod test_pg_server;
 mod test_pull_command;
+mod test_sql_shell_datafusion;
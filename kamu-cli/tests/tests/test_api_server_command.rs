@@ -8,6 +8,7 @@
 // by the Apache License, Version 2.0.
 
 use dill::CatalogBuilder;
+use std::assert_matches::assert_matches;
 use std::path::PathBuf;
 
 #[test_log::test(tokio::test)]
@@ -20,3 +21,380 @@ async fn test_update_graphql_schema() {
 
     std::fs::write(&schema_path, schema).unwrap();
 }
+
+#[test_log::test(tokio::test)]
+async fn test_serve_datasets() {
+    use kamu::domain::*;
+    use kamu::infra::utils::ipfs_wrapper::IpfsClient;
+    use kamu::infra::*;
+    use kamu::testing::MetadataFactory;
+    use opendatafabric::*;
+    use std::sync::Arc;
+
+    let tmp_dir_server = tempfile::tempdir().unwrap();
+    let tmp_dir_client = tempfile::tempdir().unwrap();
+
+    // Serving workspace
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir_server.path()).unwrap());
+    let catalog = CatalogBuilder::new()
+        .add_value(LocalDatasetRepositoryImpl::new(workspace_layout))
+        .bind::<dyn LocalDatasetRepository, LocalDatasetRepositoryImpl>()
+        .build();
+
+    let local_repo = catalog.get_one::<dyn LocalDatasetRepository>().unwrap();
+    let create_result = local_repo
+        .create_dataset_from_snapshot(
+            MetadataFactory::dataset_snapshot()
+                .name("foo")
+                .kind(DatasetKind::Root)
+                .push_event(MetadataFactory::set_polling_source().build())
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    let server = kamu_cli::explore::APIServer::new(catalog, None, None, true, None);
+    let dataset_url = url::Url::parse(&format!("http://{}/foo/", server.local_addr())).unwrap();
+    let _server_hdl = tokio::spawn(server.run());
+
+    // Dataset is readable via the plain HTTP repository layout
+    let remote_dataset = DatasetFactoryImpl::get_http(dataset_url.clone(), None).unwrap();
+    assert_eq!(
+        remote_dataset
+            .as_metadata_chain()
+            .get_ref(&BlockRef::Head)
+            .await
+            .unwrap(),
+        create_result.head
+    );
+    assert!(remote_dataset
+        .get_block_signature(&create_result.head)
+        .await
+        .unwrap()
+        .is_some());
+
+    // Private signing key is never exposed, regardless of how it's spelled
+    for name in ["keypair", "KEYPAIR", "KeyPair", "signatures/../keypair"] {
+        assert_matches!(
+            remote_dataset.as_info_repo().get(name).await,
+            Err(kamu::domain::repos::named_object_repository::GetError::NotFound(_))
+        );
+    }
+
+    // Pulling into another workspace
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir_client.path()).unwrap());
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+    let sync_svc = SyncServiceImpl::new(
+        Arc::new(RemoteRepositoryRegistryImpl::new(workspace_layout)),
+        local_repo.clone(),
+        Arc::new(DatasetFactoryImpl::new()),
+        Arc::new(IpfsClient::default()),
+        IpfsGateway::default(),
+    );
+
+    let res = sync_svc
+        .sync(
+            &DatasetRefRemote::from(dataset_url).as_any_ref(),
+            &DatasetName::new_unchecked("bar").as_any_ref(),
+            SyncOptions::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_matches!(
+        res,
+        SyncResult::Updated {
+            old_head: None,
+            new_head,
+            num_blocks: 2,
+        } if new_head == create_result.head
+    );
+
+    let pulled = local_repo
+        .get_dataset(&DatasetName::new_unchecked("bar").as_local_ref())
+        .await
+        .unwrap();
+    assert!(pulled
+        .get_block_signature(&create_result.head)
+        .await
+        .unwrap()
+        .is_some());
+    assert!(pulled.get_signing_key().await.unwrap().is_none());
+}
+
+#[test_log::test(tokio::test)]
+async fn test_push_to_served_datasets() {
+    use kamu::domain::*;
+    use kamu::infra::utils::ipfs_wrapper::IpfsClient;
+    use kamu::infra::*;
+    use kamu::testing::MetadataFactory;
+    use opendatafabric::*;
+    use std::sync::Arc;
+
+    let tmp_dir_server = tempfile::tempdir().unwrap();
+    let tmp_dir_client = tempfile::tempdir().unwrap();
+
+    // Serving workspace that accepts pushes
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir_server.path()).unwrap());
+    let catalog = CatalogBuilder::new()
+        .add_value(LocalDatasetRepositoryImpl::new(workspace_layout))
+        .bind::<dyn LocalDatasetRepository, LocalDatasetRepositoryImpl>()
+        .build();
+    let server_repo = catalog.get_one::<dyn LocalDatasetRepository>().unwrap();
+
+    let server =
+        kamu_cli::explore::APIServer::new(catalog, None, None, true, Some("secret".to_owned()));
+    let repo_url = url::Url::parse(&format!("http://{}/", server.local_addr())).unwrap();
+    let _server_hdl = tokio::spawn(server.run());
+
+    // Client workspace
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir_client.path()).unwrap());
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+    let remote_repo_reg = Arc::new(RemoteRepositoryRegistryImpl::new(workspace_layout));
+    let sync_svc = SyncServiceImpl::new(
+        remote_repo_reg.clone(),
+        local_repo.clone(),
+        Arc::new(DatasetFactoryImpl::new()),
+        Arc::new(IpfsClient::default()),
+        IpfsGateway::default(),
+    );
+
+    let create_result = local_repo
+        .create_dataset_from_snapshot(
+            MetadataFactory::dataset_snapshot()
+                .name("foo")
+                .kind(DatasetKind::Root)
+                .push_event(MetadataFactory::set_polling_source().build())
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    let anon_repo = RepositoryName::new_unchecked("anon");
+    let authed_repo = RepositoryName::new_unchecked("authed");
+    let bad_repo = RepositoryName::new_unchecked("bad");
+    for repo_name in [&anon_repo, &authed_repo, &bad_repo] {
+        remote_repo_reg
+            .add_repository(repo_name, repo_url.clone())
+            .unwrap();
+    }
+    remote_repo_reg
+        .set_repository_auth_token(&authed_repo, Some("secret".to_owned()))
+        .unwrap();
+    remote_repo_reg
+        .set_repository_auth_token(&bad_repo, Some("wrong".to_owned()))
+        .unwrap();
+
+    let push = |repo_name: &RepositoryName| {
+        let remote_name =
+            RemoteDatasetName::new(repo_name.clone(), None, DatasetName::new_unchecked("foo"));
+        let sync_svc = &sync_svc;
+        async move {
+            sync_svc
+                .sync(
+                    &DatasetName::new_unchecked("foo").as_any_ref(),
+                    &remote_name.into(),
+                    SyncOptions::default(),
+                    None,
+                )
+                .await
+        }
+    };
+
+    // Pushing without a token or with a wrong one is rejected
+    assert_matches!(
+        push(&anon_repo).await,
+        Err(SyncError::Access(AccessError::Unauthorized(_)))
+    );
+    assert_matches!(
+        push(&bad_repo).await,
+        Err(SyncError::Access(AccessError::Forbidden(_)))
+    );
+
+    // Pushing with a valid token creates the dataset on the server
+    assert_matches!(
+        push(&authed_repo).await,
+        Ok(SyncResult::Updated {
+            old_head: None,
+            new_head,
+            num_blocks: 2,
+        }) if new_head == create_result.head
+    );
+
+    let pushed = server_repo
+        .get_dataset(&DatasetName::new_unchecked("foo").as_local_ref())
+        .await
+        .unwrap();
+    assert_eq!(
+        pushed
+            .as_metadata_chain()
+            .get_ref(&BlockRef::Head)
+            .await
+            .unwrap(),
+        create_result.head
+    );
+
+    // Reference updates support compare-and-swap
+    let seed = pushed
+        .as_metadata_chain()
+        .get_block(&create_result.head)
+        .await
+        .unwrap()
+        .prev_block_hash
+        .unwrap();
+    let head_url = repo_url.join("foo/refs/head").unwrap();
+    let set_head = |expected: Option<String>| {
+        let mut req = reqwest::Client::new()
+            .put(head_url.clone())
+            .bearer_auth("secret")
+            .body(seed.to_string());
+        if let Some(expected) = expected {
+            req = req.header(kamu_cli::explore::EXPECTED_REF_HEADER, expected);
+        }
+        async move { req.send().await.unwrap().status() }
+    };
+
+    assert_eq!(
+        set_head(Some(seed.to_string())).await,
+        reqwest::StatusCode::CONFLICT
+    );
+    assert_eq!(
+        set_head(Some(String::new())).await,
+        reqwest::StatusCode::CONFLICT
+    );
+    assert_eq!(
+        set_head(Some(create_result.head.to_string())).await,
+        reqwest::StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        pushed
+            .as_metadata_chain()
+            .get_ref(&BlockRef::Head)
+            .await
+            .unwrap(),
+        seed
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn test_smart_transfer_from_served_datasets() {
+    use kamu::domain::*;
+    use kamu::infra::utils::ipfs_wrapper::IpfsClient;
+    use kamu::infra::*;
+    use kamu::testing::MetadataFactory;
+    use opendatafabric::*;
+    use std::sync::Arc;
+
+    async fn commit_blocks(dataset: &dyn Dataset, num: usize) -> Multihash {
+        let mut head = None;
+        for _ in 0..num {
+            let res = dataset
+                .commit_event(
+                    MetadataEvent::SetWatermark(SetWatermark {
+                        output_watermark: chrono::Utc::now(),
+                    }),
+                    CommitOpts::default(),
+                )
+                .await
+                .unwrap();
+            head = Some(res.new_head);
+        }
+        head.unwrap()
+    }
+
+    let tmp_dir_server = tempfile::tempdir().unwrap();
+    let tmp_dir_client = tempfile::tempdir().unwrap();
+
+    // Serving workspace
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir_server.path()).unwrap());
+    let catalog = CatalogBuilder::new()
+        .add_value(LocalDatasetRepositoryImpl::new(workspace_layout))
+        .bind::<dyn LocalDatasetRepository, LocalDatasetRepositoryImpl>()
+        .build();
+    let server_repo = catalog.get_one::<dyn LocalDatasetRepository>().unwrap();
+
+    let b1 = server_repo
+        .create_dataset_from_snapshot(
+            MetadataFactory::dataset_snapshot()
+                .name("foo")
+                .kind(DatasetKind::Root)
+                .push_event(MetadataFactory::set_polling_source().build())
+                .build(),
+        )
+        .await
+        .unwrap()
+        .head;
+    let served = server_repo
+        .get_dataset(&DatasetName::new_unchecked("foo").as_local_ref())
+        .await
+        .unwrap();
+
+    let server = kamu_cli::explore::APIServer::new(catalog, None, None, true, None);
+    let dataset_url = url::Url::parse(&format!("http://{}/foo/", server.local_addr())).unwrap();
+    let _server_hdl = tokio::spawn(server.run());
+
+    // Client workspace
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir_client.path()).unwrap());
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+    let sync_svc = SyncServiceImpl::new(
+        Arc::new(RemoteRepositoryRegistryImpl::new(workspace_layout)),
+        local_repo.clone(),
+        Arc::new(DatasetFactoryImpl::new()),
+        Arc::new(IpfsClient::default()),
+        IpfsGateway::default(),
+    );
+
+    let src = DatasetRefRemote::from(dataset_url).as_any_ref();
+    let dst = DatasetName::new_unchecked("bar");
+    let pull = |opts: SyncOptions| {
+        let (sync_svc, src, dst) = (&sync_svc, &src, &dst);
+        async move { sync_svc.sync(src, &dst.as_any_ref(), opts, None).await }
+    };
+
+    // Initial pull
+    assert_matches!(
+        pull(SyncOptions::default()).await,
+        Ok(SyncResult::Updated {
+            old_head: None,
+            new_head,
+            num_blocks: 2,
+        }) if new_head == b1
+    );
+    let pulled = local_repo.get_dataset(&dst.as_local_ref()).await.unwrap();
+
+    // Subsequent pull
+    let b3 = commit_blocks(served.as_ref(), 2).await;
+    assert_matches!(
+        pull(SyncOptions::default()).await,
+        Ok(SyncResult::Updated {
+            old_head,
+            new_head,
+            num_blocks: 2,
+        }) if old_head.as_ref() == Some(&b1) && new_head == b3
+    );
+    assert_matches!(pull(SyncOptions::default()).await, Ok(SyncResult::UpToDate));
+
+    // Divergence is classified precisely even when the common ancestor falls
+    // between the sampled destination blocks
+    let src_head = commit_blocks(served.as_ref(), 2).await;
+    let dst_head = commit_blocks(pulled.as_ref(), 10).await;
+    assert_matches!(
+        pull(SyncOptions::default()).await,
+        Err(SyncError::DatasetsDiverged(e))
+        if e.src_head == src_head
+            && e.dst_head == dst_head
+            && e.uncommon_blocks_in_src == 2
+            && e.uncommon_blocks_in_dst == 10
+    );
+
+    // Forced pull abandons local blocks
+    assert_matches!(
+        pull(SyncOptions {
+            force: true,
+            ..SyncOptions::default()
+        })
+        .await,
+        Ok(SyncResult::Updated { new_head, .. }) if new_head == src_head
+    );
+}
@@ -0,0 +1,259 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use arrow_flight::flight_service_client::FlightServiceClient;
+use arrow_flight::sql::{
+    ActionCreatePreparedStatementRequest, ActionCreatePreparedStatementResult, CommandGetTables,
+    CommandPreparedStatementQuery, CommandStatementQuery, ProstAnyExt, ProstMessageExt,
+};
+use arrow_flight::utils::flight_data_to_arrow_batch;
+use arrow_flight::{Action, FlightDescriptor, FlightInfo, IpcMessage};
+use datafusion::arrow::array::{Array, Int32Array, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::pretty::pretty_format_batches;
+use futures::TryStreamExt;
+use kamu::domain::*;
+use kamu::infra::utils::data_utils;
+use kamu::infra::*;
+use kamu::testing::{MetadataFactory, ParquetWriterHelper};
+use kamu_cli::explore::FlightSqlServer;
+use opendatafabric::*;
+use prost::Message;
+use tonic::transport::Channel;
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) async fn create_dataset_with_data(
+    local_repo: &dyn LocalDatasetRepository,
+    workspace_layout: &WorkspaceLayout,
+    name: &str,
+) {
+    let create_result = local_repo
+        .create_dataset_from_snapshot(
+            MetadataFactory::dataset_snapshot()
+                .name(name)
+                .kind(DatasetKind::Root)
+                .push_event(MetadataFactory::set_polling_source().build())
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int32, false),
+        Field::new("b", DataType::Utf8, false),
+    ]));
+    let a: Arc<dyn Array> = Arc::new(Int32Array::from(vec![1, 2, 3]));
+    let b: Arc<dyn Array> = Arc::new(StringArray::from(vec!["x", "y", "z"]));
+    let record_batch = RecordBatch::try_new(schema, vec![a, b]).unwrap();
+
+    let dataset_layout = workspace_layout.dataset_layout(&create_result.dataset_handle.name);
+    let data_path = dataset_layout.data_dir.join("tmp");
+    ParquetWriterHelper::from_record_batch(&data_path, &record_batch).unwrap();
+
+    let slice = DataSlice {
+        logical_hash: data_utils::get_parquet_logical_hash(&data_path).unwrap(),
+        physical_hash: data_utils::get_file_physical_hash(&data_path).unwrap(),
+        interval: OffsetInterval { start: 0, end: 2 },
+        size: std::fs::metadata(&data_path).unwrap().len() as i64,
+    };
+    std::fs::rename(&data_path, dataset_layout.data_slice_path(&slice)).unwrap();
+
+    create_result
+        .dataset
+        .as_metadata_chain()
+        .append(
+            MetadataFactory::metadata_block(AddData {
+                input_checkpoint: None,
+                output_data: slice,
+                output_checkpoint: None,
+                output_watermark: None,
+            })
+            .prev(&create_result.head, create_result.head_sequence_number)
+            .build(),
+            AppendOpts::default(),
+        )
+        .await
+        .unwrap();
+}
+
+async fn fetch(client: &mut FlightServiceClient<Channel>, info: FlightInfo) -> String {
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+    let mut stream = client.do_get(ticket).await.unwrap().into_inner();
+
+    let schema_data = stream.message().await.unwrap().unwrap();
+    let schema = Arc::new(Schema::try_from(&schema_data).unwrap());
+
+    let mut batches = Vec::new();
+    while let Some(data) = stream.try_next().await.unwrap() {
+        batches.push(flight_data_to_arrow_batch(&data, schema.clone(), &HashMap::new()).unwrap());
+    }
+
+    pretty_format_batches(&batches).unwrap().to_string()
+}
+
+async fn get_flight_info(
+    client: &mut FlightServiceClient<Channel>,
+    cmd: impl ProstMessageExt,
+) -> FlightInfo {
+    client
+        .get_flight_info(FlightDescriptor::new_cmd(cmd.as_any().encode_to_vec()))
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+async fn test_flight_sql_server() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir.path()).unwrap());
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+    let query_svc = Arc::new(QueryServiceImpl::new(
+        local_repo.clone(),
+        workspace_layout.clone(),
+    ));
+
+    create_dataset_with_data(local_repo.as_ref(), &workspace_layout, "foo").await;
+
+    let server = FlightSqlServer::new(query_svc, IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+        .await
+        .unwrap();
+    let addr = server.local_addr();
+    let server_handle = tokio::spawn(server.run());
+
+    let mut client = FlightServiceClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+
+    // GetTables
+    let info = get_flight_info(
+        &mut client,
+        CommandGetTables {
+            catalog: None,
+            db_schema_filter_pattern: None,
+            table_name_filter_pattern: Some("f%".to_owned()),
+            table_types: Vec::new(),
+            include_schema: false,
+        },
+    )
+    .await;
+
+    assert_eq!(
+        fetch(&mut client, info).await,
+        indoc::indoc!(
+            "
+            +--------------+----------------+------------+------------+
+            | catalog_name | db_schema_name | table_name | table_type |
+            +--------------+----------------+------------+------------+
+            | kamu         | kamu           | foo        | TABLE      |
+            +--------------+----------------+------------+------------+"
+        )
+    );
+
+    // Statement
+    let info = get_flight_info(
+        &mut client,
+        CommandStatementQuery {
+            query: "SELECT a, b FROM foo ORDER BY a DESC".to_owned(),
+        },
+    )
+    .await;
+
+    // Schema is known before the results are fetched
+    let schema = Schema::try_from(IpcMessage(info.schema.clone())).unwrap();
+    assert_eq!(
+        schema
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect::<Vec<_>>(),
+        vec!["a", "b"]
+    );
+
+    assert_eq!(
+        fetch(&mut client, info).await,
+        indoc::indoc!(
+            "
+            +---+---+
+            | a | b |
+            +---+---+
+            | 3 | z |
+            | 2 | y |
+            | 1 | x |
+            +---+---+"
+        )
+    );
+
+    // Prepared statement
+    let action = Action {
+        r#type: "CreatePreparedStatement".to_owned(),
+        body: ActionCreatePreparedStatementRequest {
+            query: "SELECT count(*) AS cnt FROM foo".to_owned(),
+        }
+        .as_any()
+        .encode_to_vec()
+        .into(),
+    };
+    let result = client
+        .do_action(action)
+        .await
+        .unwrap()
+        .into_inner()
+        .try_next()
+        .await
+        .unwrap()
+        .unwrap();
+    let prepared: ActionCreatePreparedStatementResult = prost_types::Any::decode(&*result.body)
+        .unwrap()
+        .unpack()
+        .unwrap()
+        .unwrap();
+
+    let info = get_flight_info(
+        &mut client,
+        CommandPreparedStatementQuery {
+            prepared_statement_handle: prepared.prepared_statement_handle,
+        },
+    )
+    .await;
+
+    assert_eq!(
+        fetch(&mut client, info).await,
+        indoc::indoc!(
+            "
+            +-----+
+            | cnt |
+            +-----+
+            | 3   |
+            +-----+"
+        )
+    );
+
+    // Invalid queries are reported to the client
+    let status = client
+        .get_flight_info(FlightDescriptor::new_cmd(
+            CommandStatementQuery {
+                query: "SELECT * FROM bar".to_owned(),
+            }
+            .as_any()
+            .encode_to_vec(),
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    server_handle.abort();
+}
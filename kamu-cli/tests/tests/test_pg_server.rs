@@ -0,0 +1,229 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use kamu::infra::*;
+use kamu_cli::explore::PgServer;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::Type;
+use tokio_postgres::{NoTls, SimpleQueryMessage};
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+
+use super::test_flight_sql_server::create_dataset_with_data;
+
+fn simple_rows(messages: Vec<SimpleQueryMessage>) -> Vec<Vec<Option<String>>> {
+    messages
+        .into_iter()
+        .filter_map(|m| match m {
+            SimpleQueryMessage::Row(row) => Some(
+                (0..row.len())
+                    .map(|i| row.get(i).map(|v| v.to_owned()))
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test_log::test(tokio::test)]
+async fn test_pg_server() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir.path()).unwrap());
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+    let query_svc = Arc::new(QueryServiceImpl::new(
+        local_repo.clone(),
+        workspace_layout.clone(),
+    ));
+
+    create_dataset_with_data(local_repo.as_ref(), &workspace_layout, "foo").await;
+
+    let server = PgServer::new(query_svc, IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+        .await
+        .unwrap();
+    let addr = server.local_addr();
+    let server_handle = tokio::spawn(server.run());
+
+    let (client, connection) = tokio_postgres::connect(
+        &format!(
+            "host={} port={} user=kamu dbname=kamu",
+            addr.ip(),
+            addr.port()
+        ),
+        NoTls,
+    )
+    .await
+    .unwrap();
+    let connection_handle = tokio::spawn(connection);
+
+    // Settings that drivers issue on connect are accepted
+    client
+        .simple_query("SET extra_float_digits = 3; SET application_name = 'test'")
+        .await
+        .unwrap();
+
+    // Simple query protocol
+    let rows = simple_rows(
+        client
+            .simple_query("SELECT a, b FROM foo ORDER BY a")
+            .await
+            .unwrap(),
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![Some("1".to_owned()), Some("x".to_owned())],
+            vec![Some("2".to_owned()), Some("y".to_owned())],
+            vec![Some("3".to_owned()), Some("z".to_owned())],
+        ]
+    );
+
+    // Extended query protocol with binary parameters and results
+    let rows = client
+        .query("SELECT a, b FROM foo WHERE b = $1", &[&"y"])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>("a"), 2);
+    assert_eq!(rows[0].get::<_, &str>("b"), "y");
+
+    let statement = client
+        .prepare("SELECT count(*) AS cnt FROM foo")
+        .await
+        .unwrap();
+    assert_eq!(
+        statement.columns()[0].type_(),
+        &tokio_postgres::types::Type::INT8
+    );
+    let row = client.query_one(&statement, &[]).await.unwrap();
+    assert_eq!(row.get::<_, i64>("cnt"), 3);
+
+    // Datasets are listed in catalog stubs
+    let rows = simple_rows(
+        client
+            .simple_query("SELECT tablename FROM pg_catalog.pg_tables")
+            .await
+            .unwrap(),
+    );
+    assert_eq!(rows, vec![vec![Some("foo".to_owned())]]);
+
+    let rows = simple_rows(
+        client
+            .simple_query(
+                "SELECT table_name FROM information_schema.tables WHERE table_schema = 'kamu'",
+            )
+            .await
+            .unwrap(),
+    );
+    assert_eq!(rows, vec![vec![Some("foo".to_owned())]]);
+
+    // Errors don't break the connection
+    let err = client.simple_query("SELECT * FROM bar").await.unwrap_err();
+    assert!(err.as_db_error().is_some());
+
+    let err = client
+        .query("SELECT * FROM bar WHERE x = $1", &[&"y"])
+        .await
+        .unwrap_err();
+    assert!(err.as_db_error().is_some());
+
+    // Out of range placeholders are rejected instead of allocating parameters for them
+    for query in [
+        "SELECT a FROM foo WHERE a = $1000000000",
+        "SELECT a FROM foo WHERE a = $99999999999999999999999",
+    ] {
+        let err = client.prepare(query).await.unwrap_err();
+        assert_eq!(
+            err.as_db_error().unwrap().code(),
+            &SqlState::PROTOCOL_VIOLATION
+        );
+    }
+
+    // Only views can be created within the session
+    let err = client
+        .simple_query("CREATE EXTERNAL TABLE t STORED AS CSV LOCATION '/etc/passwd'")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.as_db_error().unwrap().code(),
+        &SqlState::INSUFFICIENT_PRIVILEGE
+    );
+
+    client
+        .simple_query("CREATE VIEW v AS SELECT a FROM foo WHERE a > 1")
+        .await
+        .unwrap();
+    let rows = simple_rows(client.simple_query("SELECT count(*) FROM v").await.unwrap());
+    assert_eq!(rows, vec![vec![Some("2".to_owned())]]);
+
+    // Placeholders in comments are not parameters
+    let statement = client
+        .prepare_typed(
+            "SELECT a FROM foo WHERE a = $1 -- $2\n /* $3 /* $4 */ */",
+            &[Type::INT4],
+        )
+        .await
+        .unwrap();
+    assert_eq!(statement.params(), &[Type::INT4]);
+    let rows = client.query(&statement, &[&2i32]).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>("a"), 2);
+
+    // Negative numbers can't form a comment with a preceding minus sign
+    let statement = client
+        .prepare_typed("SELECT a FROM foo WHERE a = 0 -$1", &[Type::INT4])
+        .await
+        .unwrap();
+    let rows = client.query(&statement, &[&-2i32]).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>("a"), 2);
+
+    // Non-finite numbers are rejected
+    let statement = client
+        .prepare_typed("SELECT a FROM foo WHERE a < $1", &[Type::FLOAT8])
+        .await
+        .unwrap();
+    for value in [f64::NAN, f64::INFINITY] {
+        let err = client.query(&statement, &[&value]).await.unwrap_err();
+        assert_eq!(
+            err.as_db_error().unwrap().code(),
+            &SqlState::INVALID_PARAMETER_VALUE
+        );
+    }
+
+    // Results are streamed across several executions of a portal
+    let transaction = client.transaction().await.unwrap();
+    let portal = transaction
+        .bind("SELECT a FROM foo ORDER BY a", &[])
+        .await
+        .unwrap();
+    let rows = transaction.query_portal(&portal, 2).await.unwrap();
+    assert_eq!(
+        rows.iter()
+            .map(|r| r.get::<_, i32>("a"))
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    let rows = transaction.query_portal(&portal, 2).await.unwrap();
+    assert_eq!(
+        rows.iter()
+            .map(|r| r.get::<_, i32>("a"))
+            .collect::<Vec<_>>(),
+        vec![3]
+    );
+    drop(portal);
+    transaction.commit().await.unwrap();
+
+    let rows = simple_rows(client.simple_query("SELECT 1 AS x").await.unwrap());
+    assert_eq!(rows, vec![vec![Some("1".to_owned())]]);
+
+    drop(client);
+    connection_handle.await.unwrap().unwrap();
+    server_handle.abort();
+}
@@ -0,0 +1,142 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use kamu::domain::*;
+use kamu::infra::*;
+use kamu::testing::MetadataFactory;
+use kamu_cli::explore::{SqlShellAction, SqlShellDataFusion};
+use kamu_cli::{OutputConfig, OutputFormat};
+use opendatafabric::*;
+
+use std::sync::Arc;
+
+#[test_log::test(tokio::test)]
+async fn test_sql_shell_datafusion() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir.path()).unwrap());
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+    let query_svc = Arc::new(QueryServiceImpl::new(
+        local_repo.clone(),
+        workspace_layout.clone(),
+    ));
+
+    local_repo
+        .create_dataset_from_snapshot(
+            MetadataFactory::dataset_snapshot()
+                .name("foo")
+                .kind(DatasetKind::Root)
+                .push_event(MetadataFactory::set_polling_source().build())
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    let mut shell = SqlShellDataFusion::new(
+        query_svc,
+        local_repo,
+        Arc::new(OutputConfig {
+            format: OutputFormat::Csv,
+            ..OutputConfig::default()
+        }),
+        workspace_layout.run_info_dir.clone(),
+        QueryOptions::default(),
+    );
+
+    let out_path = tmp_dir.path().join("out.csv");
+
+    assert_eq!(
+        shell
+            .handle_input(&format!("\\o {}", out_path.display()))
+            .await
+            .unwrap(),
+        SqlShellAction::Continue
+    );
+    assert_eq!(
+        shell.handle_input("\\dt").await.unwrap(),
+        SqlShellAction::Continue
+    );
+
+    // Statement is executed only once terminated
+    assert_eq!(
+        shell.handle_input("SELECT 1 AS x,").await.unwrap(),
+        SqlShellAction::Continue
+    );
+    assert_eq!(std::fs::read_to_string(&out_path).unwrap(), "Name\nfoo\n");
+    assert_eq!(
+        shell.handle_input("  2 AS y;").await.unwrap(),
+        SqlShellAction::Continue
+    );
+
+    assert_eq!(
+        std::fs::read_to_string(&out_path).unwrap(),
+        "Name\nfoo\nx,y\n1,2\n"
+    );
+
+    assert_eq!(
+        shell.handle_input("\\q").await.unwrap(),
+        SqlShellAction::Quit
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn test_sql_shell_datafusion_script() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir.path()).unwrap());
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+    let query_svc = Arc::new(QueryServiceImpl::new(
+        local_repo.clone(),
+        workspace_layout.clone(),
+    ));
+
+    let mut shell = SqlShellDataFusion::new(
+        query_svc,
+        local_repo,
+        Arc::new(OutputConfig {
+            format: OutputFormat::Csv,
+            ..OutputConfig::default()
+        }),
+        workspace_layout.run_info_dir.clone(),
+        QueryOptions::default(),
+    );
+
+    let out_path = tmp_dir.path().join("out.csv");
+    shell
+        .handle_input(&format!("\\o {}", out_path.display()))
+        .await
+        .unwrap();
+
+    // Views and settings persist between statements
+    shell
+        .run_script(indoc::indoc!(
+            r#"
+            SET datafusion.execution.batch_size = 1;
+            CREATE TEMP VIEW foo AS (SELECT 1 AS x UNION ALL SELECT 2 AS x);
+            SELECT sum(x) AS s FROM foo;
+            "#
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(&out_path).unwrap(), "s\n3\n");
+
+    // Header is written even when query returns no rows
+    shell
+        .handle_input("SELECT x FROM foo WHERE x > 10;")
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(&out_path).unwrap(), "s\n3\nx\n");
+
+    // Errors point to the failed statement
+    let err = shell
+        .run_script("SELECT 1;\n\nSELECT * FROM bar;")
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Statement 2 at line 3 failed");
+}
@@ -0,0 +1,91 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use opendatafabric::{DatasetID, Multihash};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Ed25519 signature of a metadata block produced with the dataset's key.
+///
+/// The signed message is the binary representation of the block hash, which
+/// already commits to the entire content of the block.
+/// Public key is stored alongside the signature as dataset ID only contains its digest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct BlockSignature {
+    /// Hex-encoded Ed25519 public key
+    pub public_key: String,
+    /// Hex-encoded Ed25519 signature
+    pub signature: String,
+}
+
+impl BlockSignature {
+    pub fn sign(keypair: &Keypair, block_hash: &Multihash) -> Self {
+        let signature = keypair.sign(&block_hash.to_bytes());
+        Self {
+            public_key: hex::encode(keypair.public.to_bytes()),
+            signature: hex::encode(signature.to_bytes()),
+        }
+    }
+
+    /// Checks that signature is valid for the specified block and that it was
+    /// produced by the key the dataset ID was derived from
+    pub fn verify(
+        &self,
+        dataset_id: &DatasetID,
+        block_hash: &Multihash,
+    ) -> Result<(), BlockSignatureError> {
+        let invalid = |reason: &str| BlockSignatureError::Invalid {
+            block_hash: block_hash.clone(),
+            reason: reason.to_owned(),
+        };
+
+        let public_key_bytes =
+            hex::decode(&self.public_key).map_err(|_| invalid("Malformed public key"))?;
+        let signature_bytes =
+            hex::decode(&self.signature).map_err(|_| invalid("Malformed signature"))?;
+
+        if DatasetID::from_pub_key_ed25519(&public_key_bytes) != *dataset_id {
+            return Err(BlockSignatureError::WrongKey {
+                block_hash: block_hash.clone(),
+                dataset_id: dataset_id.clone(),
+            });
+        }
+
+        let public_key = PublicKey::from_bytes(&public_key_bytes)
+            .map_err(|_| invalid("Malformed public key"))?;
+        let signature = Signature::try_from(&signature_bytes[..])
+            .map_err(|_| invalid("Malformed signature"))?;
+
+        public_key
+            .verify(&block_hash.to_bytes(), &signature)
+            .map_err(|_| invalid("Signature does not match the block"))
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BlockSignatureError {
+    #[error("Block {block_hash} is not signed")]
+    Missing { block_hash: Multihash },
+    #[error("Block {block_hash} is signed by a key that does not belong to dataset {dataset_id}")]
+    WrongKey {
+        block_hash: Multihash,
+        dataset_id: DatasetID,
+    },
+    #[error("Block {block_hash} has invalid signature: {reason}")]
+    Invalid {
+        block_hash: Multihash,
+        reason: String,
+    },
+}
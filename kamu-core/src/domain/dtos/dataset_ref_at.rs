@@ -0,0 +1,89 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::domain::{BlockRef, InvalidBlockRefError};
+use opendatafabric::DatasetRefLocal;
+
+use thiserror::Error;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Local dataset reference optionally pinned to a named block reference,
+/// written as `<dataset>[@<ref>]`, e.g. `my.dataset@v1` or `my.dataset@refs/branches/exp`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DatasetRefLocalAt {
+    pub dataset_ref: DatasetRefLocal,
+    pub block_ref: Option<BlockRef>,
+}
+
+impl DatasetRefLocalAt {
+    pub fn new(dataset_ref: DatasetRefLocal, block_ref: Option<BlockRef>) -> Self {
+        Self {
+            dataset_ref,
+            block_ref,
+        }
+    }
+}
+
+impl std::str::FromStr for DatasetRefLocalAt {
+    type Err = InvalidDatasetRefAtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (dataset, block_ref) = match s.rsplit_once('@') {
+            Some((dataset, block_ref)) => (dataset, Some(block_ref)),
+            None => (s, None),
+        };
+
+        let dataset_ref = DatasetRefLocal::try_from(dataset)
+            .map_err(|_| InvalidDatasetRefAtError::Dataset(dataset.to_owned()))?;
+
+        let block_ref = match block_ref {
+            None => None,
+            Some(r) => Some(r.parse()?),
+        };
+
+        Ok(Self {
+            dataset_ref,
+            block_ref,
+        })
+    }
+}
+
+impl TryFrom<&str> for DatasetRefLocalAt {
+    type Error = InvalidDatasetRefAtError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl std::fmt::Display for DatasetRefLocalAt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.block_ref {
+            None => write!(f, "{}", self.dataset_ref),
+            Some(r) => write!(f, "{}@{}", self.dataset_ref, r),
+        }
+    }
+}
+
+impl From<DatasetRefLocal> for DatasetRefLocalAt {
+    fn from(dataset_ref: DatasetRefLocal) -> Self {
+        Self::new(dataset_ref, None)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Error, Debug)]
+pub enum InvalidDatasetRefAtError {
+    #[error("Invalid dataset reference: {0}")]
+    Dataset(String),
+    #[error(transparent)]
+    BlockRef(#[from] InvalidBlockRefError),
+}
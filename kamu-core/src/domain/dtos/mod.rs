@@ -12,3 +12,9 @@ pub use dataset_summary::*;
 
 mod dataset_repository_info;
 pub use dataset_repository_info::*;
+
+mod dataset_ref_at;
+pub use dataset_ref_at::*;
+
+mod block_signature;
+pub use block_signature::*;
@@ -0,0 +1,154 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::domain::*;
+use opendatafabric::*;
+
+use thiserror::Error;
+
+///////////////////////////////////////////////////////////////////////////////
+// Service
+///////////////////////////////////////////////////////////////////////////////
+
+/// Removes metadata blocks, data slices, and checkpoints that are no longer
+/// reachable from any of the dataset's references, e.g. after a `reset` or a
+/// forced sync.
+///
+/// Objects of an in-flight commit (e.g. a concurrent pull or push) are written
+/// before the block referencing them, so objects younger than
+/// [GcOptions::min_age] are never deleted.
+#[async_trait::async_trait]
+pub trait GcService: Send + Sync {
+    /// Collects garbage of a dataset in the local workspace
+    async fn gc(
+        &self,
+        dataset_ref: &DatasetRefLocal,
+        options: GcOptions,
+    ) -> Result<GcResult, GcError>;
+
+    /// Collects garbage of an arbitrary dataset, e.g. one stored in S3
+    async fn gc_dataset(
+        &self,
+        dataset: &dyn Dataset,
+        options: GcOptions,
+    ) -> Result<GcResult, GcError>;
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// DTOs
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct GcOptions {
+    /// Only report unreachable objects without deleting them
+    pub dry_run: bool,
+    /// Grace period during which unreachable objects are kept, as they may belong
+    /// to a commit that is still in progress. When non-zero, objects whose age
+    /// cannot be determined are kept too.
+    pub min_age: chrono::Duration,
+}
+
+impl GcOptions {
+    pub const DEFAULT_MIN_AGE_SECS: i64 = 3600;
+}
+
+impl Default for GcOptions {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            min_age: chrono::Duration::seconds(Self::DEFAULT_MIN_AGE_SECS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GcResult {
+    pub num_blocks: usize,
+    pub num_data_slices: usize,
+    pub num_checkpoints: usize,
+    /// Total size of unreachable objects in bytes
+    pub bytes_reclaimed: u64,
+}
+
+impl GcResult {
+    pub fn num_objects(&self) -> usize {
+        self.num_blocks + self.num_data_slices + self.num_checkpoints
+    }
+}
+
+impl std::ops::AddAssign for GcResult {
+    fn add_assign(&mut self, rhs: Self) {
+        self.num_blocks += rhs.num_blocks;
+        self.num_data_slices += rhs.num_data_slices;
+        self.num_checkpoints += rhs.num_checkpoints;
+        self.bytes_reclaimed += rhs.bytes_reclaimed;
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Errors
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Error)]
+pub enum GcError {
+    #[error(transparent)]
+    DatasetNotFound(
+        #[from]
+        #[backtrace]
+        DatasetNotFoundError,
+    ),
+    #[error(transparent)]
+    Access(
+        #[from]
+        #[backtrace]
+        AccessError,
+    ),
+    #[error(transparent)]
+    Internal(
+        #[from]
+        #[backtrace]
+        InternalError,
+    ),
+}
+
+impl From<GetDatasetError> for GcError {
+    fn from(v: GetDatasetError) -> Self {
+        match v {
+            GetDatasetError::NotFound(e) => Self::DatasetNotFound(e),
+            GetDatasetError::Internal(e) => Self::Internal(e),
+        }
+    }
+}
+
+impl From<ListObjectsError> for GcError {
+    fn from(v: ListObjectsError) -> Self {
+        match v {
+            ListObjectsError::Access(e) => Self::Access(e),
+            ListObjectsError::Internal(e) => Self::Internal(e),
+        }
+    }
+}
+
+impl From<DeleteError> for GcError {
+    fn from(v: DeleteError) -> Self {
+        match v {
+            DeleteError::Access(e) => Self::Access(e),
+            DeleteError::Internal(e) => Self::Internal(e),
+        }
+    }
+}
+
+impl From<ListRefsError> for GcError {
+    fn from(v: ListRefsError) -> Self {
+        match v {
+            ListRefsError::Access(e) => Self::Access(e),
+            ListRefsError::Internal(e) => Self::Internal(e),
+        }
+    }
+}
@@ -150,6 +150,20 @@ impl IngestInputNotFound {
     }
 }
 
+#[derive(Error, Debug)]
+#[error("Invalid source: {message}")]
+pub struct InvalidSourceError {
+    pub message: String,
+}
+
+impl InvalidSourceError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum IngestError {
     #[error(transparent)]
@@ -177,6 +191,12 @@ pub enum IngestError {
         ImageNotFoundError,
     ),
     #[error(transparent)]
+    InvalidSource(
+        #[from]
+        #[backtrace]
+        InvalidSourceError,
+    ),
+    #[error(transparent)]
     InputNotFound(
         #[from]
         #[backtrace]
@@ -206,6 +226,12 @@ pub enum IngestError {
         source: BoxedError,
         backtrace: Backtrace,
     },
+    #[error("Decompression error: {format:?} {source}")]
+    DecompressError {
+        format: CompressionFormat,
+        source: BoxedError,
+        backtrace: Backtrace,
+    },
     #[error(transparent)]
     Internal(
         #[from]
@@ -245,4 +271,12 @@ impl IngestError {
             backtrace: Backtrace::capture(),
         }
     }
+
+    pub fn decompress(format: CompressionFormat, e: impl Into<BoxedError>) -> Self {
+        IngestError::DecompressError {
+            format: format,
+            source: e.into(),
+            backtrace: Backtrace::capture(),
+        }
+    }
 }
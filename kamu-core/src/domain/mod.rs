@@ -34,6 +34,9 @@ pub use engine::*;
 mod engine_provisioner;
 pub use engine_provisioner::*;
 
+mod gc_service;
+pub use gc_service::*;
+
 pub mod ingest_service;
 pub use ingest_service::IngestRequest;
 pub use ingest_service::*;
@@ -9,19 +9,23 @@
 
 use crate::domain::*;
 
+use chrono::{DateTime, Utc};
 use datafusion::error::DataFusionError;
 use datafusion::parquet::schema::types::Type;
-use datafusion::prelude::DataFrame;
-use opendatafabric::DatasetRefLocal;
+use datafusion::prelude::{DataFrame, SessionContext};
+use opendatafabric::{DatasetRefLocal, Multihash};
 use thiserror::Error;
 
 #[async_trait::async_trait]
 pub trait QueryService: Send + Sync {
     /// Returns the specified number of the latest records in the dataset
     /// This is equivalent to the SQL query: `SELECT * FROM dataset ORDER BY offset DESC LIMIT N`
+    ///
+    /// When `block_ref` is specified the data is read as of the block that reference points to
     async fn tail(
         &self,
         dataset_ref: &DatasetRefLocal,
+        block_ref: Option<&BlockRef>,
         num_records: u64,
     ) -> Result<DataFrame, QueryError>;
 
@@ -31,7 +35,24 @@ pub trait QueryService: Send + Sync {
         options: QueryOptions,
     ) -> Result<DataFrame, QueryError>;
 
+    /// Creates a session where workspace datasets are available as tables.
+    ///
+    /// Unlike [QueryService::sql_statement] the session retains the effects of
+    /// statements like `CREATE TEMP VIEW` and `SET`, allowing to execute scripts.
+    async fn create_session(&self, options: QueryOptions) -> Result<SessionContext, QueryError>;
+
     async fn get_schema(&self, dataset_ref: &DatasetRefLocal) -> Result<Type, QueryError>;
+
+    /// Builds query options that pin datasets to their historical state.
+    ///
+    /// Every block hash pins the dataset whose metadata chain contains it,
+    /// while system time applies to all other datasets in the workspace.
+    /// Without system time the other datasets are read at their `head`.
+    async fn options_as_of(
+        &self,
+        blocks: &[Multihash],
+        system_time: Option<DateTime<Utc>>,
+    ) -> Result<QueryOptions, QueryError>;
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -47,6 +68,19 @@ pub struct DatasetQueryOptions {
     /// Number of records that output requires (starting from latest entries)
     /// Setting this value allows to limit the number of part files examined.
     pub limit: Option<u64>,
+    /// Reference to the block the data should be read as of (defaults to `head`)
+    pub block_ref: Option<BlockRef>,
+    /// Restricts the data to the state the dataset was in at the specified point in history
+    pub as_of: Option<QueryAsOf>,
+}
+
+/// Point in dataset history to query the data as of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryAsOf {
+    /// Only consider blocks up to and including the specified one
+    Block(Multihash),
+    /// Only consider blocks with system time less than or equal to the specified one
+    SystemTime(DateTime<Utc>),
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -62,6 +96,12 @@ pub enum QueryError {
         DatasetNotFoundError,
     ),
     #[error(transparent)]
+    BlockNotFound(
+        #[from]
+        #[backtrace]
+        BlockNotFoundError,
+    ),
+    #[error(transparent)]
     DataFusionError(
         #[from]
         #[backtrace]
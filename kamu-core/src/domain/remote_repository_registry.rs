@@ -25,6 +25,13 @@ pub trait RemoteRepositoryRegistry: Send + Sync {
 
     fn add_repository(&self, repo_name: &RepositoryName, url: Url) -> Result<(), AddRepoError>;
 
+    /// Sets or clears the token used to authenticate with the repository
+    fn set_repository_auth_token(
+        &self,
+        repo_name: &RepositoryName,
+        auth_token: Option<String>,
+    ) -> Result<(), GetRepoError>;
+
     fn delete_repository(&self, repo_name: &RepositoryName) -> Result<(), DeleteRepoError>;
 }
 
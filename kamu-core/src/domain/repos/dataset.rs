@@ -10,6 +10,7 @@
 use std::path::Path;
 
 use crate::domain::*;
+use opendatafabric::serde::yaml::Manifest;
 use opendatafabric::*;
 
 use async_trait::async_trait;
@@ -25,6 +26,7 @@ pub trait Dataset: Send + Sync {
     fn as_data_repo(&self) -> &dyn ObjectRepository;
     fn as_checkpoint_repo(&self) -> &dyn ObjectRepository;
     fn as_cache_repo(&self) -> &dyn NamedObjectRepository;
+    fn as_info_repo(&self) -> &dyn NamedObjectRepository;
 
     /// Returns a brief summary of the dataset
     async fn get_summary(&self, opts: GetSummaryOpts) -> Result<DatasetSummary, GetSummaryError>;
@@ -104,6 +106,8 @@ pub trait DatasetExt: Dataset {
 
         info!(%new_head, "Committed new block");
 
+        self.sign_block(&new_head).await?;
+
         Ok(CommitResult {
             old_head: prev_block_hash,
             new_head,
@@ -220,10 +224,138 @@ pub trait DatasetExt: Dataset {
 
         self.commit_event(metadata_event, opts).await
     }
+
+    /// Stores the key used to sign blocks of this dataset.
+    ///
+    /// The key is kept in the `info` repository which is never synced to remote repositories.
+    async fn set_signing_key(&self, keypair: &ed25519_dalek::Keypair) -> Result<(), InternalError> {
+        self.as_info_repo()
+            .set(SIGNING_KEY_NAME, hex::encode(keypair.to_bytes()).as_bytes())
+            .await
+            .int_err()
+    }
+
+    /// Returns the key used to sign blocks of this dataset if it is known
+    async fn get_signing_key(&self) -> Result<Option<ed25519_dalek::Keypair>, InternalError> {
+        use crate::domain::repos::named_object_repository::GetError;
+
+        let data = match self.as_info_repo().get(SIGNING_KEY_NAME).await {
+            Ok(data) => data,
+            Err(GetError::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(e.int_err()),
+        };
+
+        let bytes = hex::decode(&data[..]).int_err()?;
+        let keypair = ed25519_dalek::Keypair::from_bytes(&bytes).int_err()?;
+        Ok(Some(keypair))
+    }
+
+    /// Signs the specified block with the dataset key and stores the signature.
+    ///
+    /// Does nothing when the key is not available, e.g. for datasets pulled from elsewhere.
+    async fn sign_block(
+        &self,
+        block_hash: &Multihash,
+    ) -> Result<Option<BlockSignature>, InternalError> {
+        let keypair = match self.get_signing_key().await? {
+            Some(keypair) => keypair,
+            None => return Ok(None),
+        };
+
+        let signature = BlockSignature::sign(&keypair, block_hash);
+        self.set_block_signature(block_hash, &signature).await?;
+        Ok(Some(signature))
+    }
+
+    /// Returns the signature of the specified block if it exists
+    async fn get_block_signature(
+        &self,
+        block_hash: &Multihash,
+    ) -> Result<Option<BlockSignature>, InternalError> {
+        use crate::domain::repos::named_object_repository::GetError;
+
+        let data = match self
+            .as_info_repo()
+            .get(&block_signature_name(block_hash))
+            .await
+        {
+            Ok(data) => data,
+            Err(GetError::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(e.int_err()),
+        };
+
+        let manifest: Manifest<BlockSignature> = serde_yaml::from_slice(&data[..]).int_err()?;
+
+        if manifest.kind != "BlockSignature" {
+            return Err(InvalidObjectKind {
+                expected: "BlockSignature".to_owned(),
+                actual: manifest.kind,
+            }
+            .int_err());
+        }
+
+        Ok(Some(manifest.content))
+    }
+
+    /// Stores the signature of the specified block
+    async fn set_block_signature(
+        &self,
+        block_hash: &Multihash,
+        signature: &BlockSignature,
+    ) -> Result<(), InternalError> {
+        let manifest = Manifest {
+            kind: "BlockSignature".to_owned(),
+            version: 1,
+            content: signature.clone(),
+        };
+
+        let data = serde_yaml::to_string(&manifest).int_err()?.into_bytes();
+
+        self.as_info_repo()
+            .set(&block_signature_name(block_hash), &data)
+            .await
+            .int_err()
+    }
+
+    /// Removes the signature of the specified block if it exists
+    async fn delete_block_signature(&self, block_hash: &Multihash) -> Result<(), InternalError> {
+        self.as_info_repo()
+            .delete(&block_signature_name(block_hash))
+            .await
+            .int_err()
+    }
 }
 
 impl<T> DatasetExt for T where T: Dataset + ?Sized {}
 
+const SIGNING_KEY_NAME: &str = "keypair";
+
+const SUMMARY_NAME: &str = "summary";
+const SIGNATURES_PREFIX: &str = "signatures/";
+
+/// Returns `true` only for info objects that can be shared with other parties:
+/// the summary and block signatures. Everything else, like the private signing
+/// key, must never leave the workspace.
+///
+/// Names are matched exactly in their canonical form so that case or encoding
+/// variations can't be used to reach other files on case-insensitive file systems.
+pub fn is_public_info_object(name: &str) -> bool {
+    if name == SUMMARY_NAME {
+        return true;
+    }
+    match name.strip_prefix(SIGNATURES_PREFIX) {
+        Some(hash) => match Multihash::from_multibase_str(hash) {
+            Ok(hash) => block_signature_name(&hash) == name,
+            Err(_) => false,
+        },
+        None => false,
+    }
+}
+
+fn block_signature_name(block_hash: &Multihash) -> String {
+    format!("{}{}", SIGNATURES_PREFIX, block_hash)
+}
+
 /////////////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Clone, Debug)]
@@ -18,10 +18,13 @@ use url::Url;
 
 #[async_trait]
 pub trait DatasetFactory: Send + Sync {
+    /// Returns a dataset stored at the specified URL, optionally authenticating
+    /// with the bearer token where the protocol supports it
     fn get_dataset(
         &self,
         url: &Url,
         create_if_not_exists: bool,
+        auth_token: Option<&str>,
     ) -> Result<Arc<dyn Dataset>, BuildDatasetError>;
 }
 
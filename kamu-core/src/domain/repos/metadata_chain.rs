@@ -108,31 +108,109 @@ impl<T> MetadataChainExt for T where T: MetadataChain + ?Sized {}
 /////////////////////////////////////////////////////////////////////////////////////////
 
 /// References are named pointers to metadata blocks
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum BlockRef {
     Head,
+    /// Immutable user-defined pointer, e.g. `refs/tags/v1`
+    Tag(String),
+    /// Movable user-defined pointer, e.g. `refs/branches/experiment`
+    Branch(String),
 }
 
 impl BlockRef {
-    pub fn as_str(&self) -> &str {
+    const TAGS_PREFIX: &'static str = "tags/";
+    const BRANCHES_PREFIX: &'static str = "branches/";
+
+    pub fn tag(name: impl Into<String>) -> Result<Self, InvalidBlockRefError> {
+        let name = name.into();
+        Self::validate_name(&name)?;
+        Ok(Self::Tag(name))
+    }
+
+    pub fn branch(name: impl Into<String>) -> Result<Self, InvalidBlockRefError> {
+        let name = name.into();
+        Self::validate_name(&name)?;
+        Ok(Self::Branch(name))
+    }
+
+    /// Returns the short name of the reference as shown to the user
+    pub fn name(&self) -> &str {
         match self {
             BlockRef::Head => "head",
+            BlockRef::Tag(name) | BlockRef::Branch(name) => name,
+        }
+    }
+
+    /// Returns the path of the reference relative to the `refs` directory
+    pub fn path(&self) -> String {
+        match self {
+            BlockRef::Head => "head".to_owned(),
+            BlockRef::Tag(name) => format!("{}{}", Self::TAGS_PREFIX, name),
+            BlockRef::Branch(name) => format!("{}{}", Self::BRANCHES_PREFIX, name),
+        }
+    }
+
+    /// Parses the reference from its path relative to the `refs` directory
+    pub fn from_path(path: &str) -> Result<Self, InvalidBlockRefError> {
+        if path == "head" {
+            Ok(BlockRef::Head)
+        } else if let Some(name) = path.strip_prefix(Self::TAGS_PREFIX) {
+            Self::tag(name)
+        } else if let Some(name) = path.strip_prefix(Self::BRANCHES_PREFIX) {
+            Self::branch(name)
+        } else {
+            Err(InvalidBlockRefError {
+                value: path.to_owned(),
+            })
+        }
+    }
+
+    fn validate_name(name: &str) -> Result<(), InvalidBlockRefError> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            && !name.starts_with('.');
+        if valid {
+            Ok(())
+        } else {
+            Err(InvalidBlockRefError {
+                value: name.to_owned(),
+            })
         }
     }
 }
 
-impl AsRef<str> for BlockRef {
-    fn as_ref(&self) -> &str {
-        self.as_str()
+/// Accepts `head`, `refs/tags/<name>`, `tags/<name>`, `refs/branches/<name>`,
+/// `branches/<name>`, or a bare `<name>` which is treated as a tag
+impl std::str::FromStr for BlockRef {
+    type Err = InvalidBlockRefError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = s.strip_prefix("refs/").unwrap_or(s);
+        if path == "head" || path.contains('/') {
+            Self::from_path(path)
+        } else {
+            Self::tag(path)
+        }
     }
 }
 
 impl std::fmt::Display for BlockRef {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.as_str())
+        match self {
+            BlockRef::Head => write!(f, "head"),
+            _ => write!(f, "refs/{}", self.path()),
+        }
     }
 }
 
+#[derive(Error, Debug)]
+#[error("Invalid block reference: {value}")]
+pub struct InvalidBlockRefError {
+    pub value: String,
+}
+
 /////////////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Clone, Debug)]
@@ -26,6 +26,9 @@ pub trait NamedObjectRepository {
 
     /// Deletes specified reference
     async fn delete(&self, name: &str) -> Result<(), DeleteError>;
+
+    /// Lists names of all stored objects
+    async fn list(&self) -> Result<Vec<String>, ListError>;
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -88,6 +91,24 @@ pub enum DeleteError {
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Error, Debug)]
+pub enum ListError {
+    #[error(transparent)]
+    Access(
+        #[from]
+        #[backtrace]
+        AccessError,
+    ),
+    #[error(transparent)]
+    Internal(
+        #[from]
+        #[backtrace]
+        InternalError,
+    ),
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Error, Debug)]
 #[error("Object does not exist: {name}")]
 pub struct NotFoundError {
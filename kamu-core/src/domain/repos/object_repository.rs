@@ -54,6 +54,9 @@ pub trait ObjectRepository: Send + Sync {
     ) -> Result<InsertResult, InsertError>;
 
     async fn delete(&self, hash: &Multihash) -> Result<(), DeleteError>;
+
+    /// Lists all objects stored in the repository along with their sizes
+    async fn list(&self) -> Result<Vec<ObjectInfo>, ListObjectsError>;
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -66,6 +69,17 @@ pub struct InsertResult {
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ObjectInfo {
+    pub hash: Multihash,
+    /// Size of the object in bytes
+    pub size: u64,
+    /// Time when the object was last written, if the storage tracks it
+    pub last_modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Clone, Default, Debug)]
 pub struct InsertOpts<'a> {
     /// Insert object using provided hash computed elsewhere.
@@ -184,6 +198,24 @@ pub enum DeleteError {
     ),
 }
 
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Error, Debug)]
+pub enum ListObjectsError {
+    #[error(transparent)]
+    Access(
+        #[from]
+        #[backtrace]
+        AccessError,
+    ),
+    #[error(transparent)]
+    Internal(
+        #[from]
+        #[backtrace]
+        InternalError,
+    ),
+}
+
 /////////////////////////////////////////////////////////////////////////////////////////
 // Individual Errors
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -25,6 +25,9 @@ pub trait ReferenceRepository {
 
     /// Deletes specified reference
     async fn delete(&self, r: &BlockRef) -> Result<(), DeleteRefError>;
+
+    /// Lists all existing references
+    async fn list(&self) -> Result<Vec<BlockRef>, ListRefsError>;
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -87,6 +90,24 @@ pub enum DeleteRefError {
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Error, Debug)]
+pub enum ListRefsError {
+    #[error(transparent)]
+    Access(
+        #[from]
+        #[backtrace]
+        AccessError,
+    ),
+    #[error(transparent)]
+    Internal(
+        #[from]
+        #[backtrace]
+        InternalError,
+    ),
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Error, Debug)]
 #[error("Reference does not exist: {block_ref:?}")]
 pub struct RefNotFoundError {
@@ -16,4 +16,6 @@ use url::Url;
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
 pub struct RepositoryAccessInfo {
     pub url: Url,
+    /// Bearer token sent with requests to repositories that require authentication
+    pub auth_token: Option<String>,
 }
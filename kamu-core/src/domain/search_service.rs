@@ -8,8 +8,9 @@
 // by the Apache License, Version 2.0.
 
 use crate::domain::*;
-use opendatafabric::{RemoteDatasetName, RepositoryName};
+use opendatafabric::{DatasetKind, RemoteDatasetName, RepositoryName};
 
+use chrono::{DateTime, Utc};
 use thiserror::Error;
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -32,8 +33,39 @@ pub struct SearchOptions {
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct SearchResult {
+    pub datasets: Vec<SearchResultDataset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResultDataset {
     // TODO: REMORE ID: Should be RemoteDatasetHandle
-    pub datasets: Vec<RemoteDatasetName>,
+    pub name: RemoteDatasetName,
+    /// Details below are only available when repository stores dataset summaries
+    pub kind: Option<DatasetKind>,
+    pub last_updated: Option<DateTime<Utc>>,
+    pub num_records: Option<u64>,
+    pub estimated_size: Option<u64>,
+}
+
+impl SearchResultDataset {
+    pub fn new(name: RemoteDatasetName, summary: Option<DatasetSummary>) -> Self {
+        match summary {
+            None => Self {
+                name,
+                kind: None,
+                last_updated: None,
+                num_records: None,
+                estimated_size: None,
+            },
+            Some(summary) => Self {
+                name,
+                kind: Some(summary.kind),
+                last_updated: summary.last_pulled,
+                num_records: Some(summary.num_records),
+                estimated_size: Some(summary.data_size),
+            },
+        }
+    }
 }
 
 impl SearchResult {
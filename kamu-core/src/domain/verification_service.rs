@@ -34,7 +34,10 @@ pub trait VerificationService: Send + Sync {
         requests: &mut dyn Iterator<Item = VerificationRequest>,
         options: VerificationOptions,
         listener: Option<Arc<dyn VerificationMultiListener>>,
-    ) -> Result<VerificationResult, VerificationError>;
+    ) -> Vec<(
+        DatasetRefLocal,
+        Result<VerificationResult, VerificationError>,
+    )>;
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -52,6 +55,74 @@ pub struct VerificationRequest {
 #[derive(Debug)]
 pub enum VerificationResult {
     Valid,
+    /// Returned only when [VerificationOptions::continue_on_error] is set
+    Invalid(VerificationReport),
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Problems found during verification in the order they were discovered
+#[derive(Debug, Default)]
+pub struct VerificationReport {
+    pub problems: Vec<VerificationProblem>,
+}
+
+impl VerificationReport {
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Records the problem when continuing on errors, otherwise returns it as an error
+    pub fn record(
+        &mut self,
+        block_hash: &Multihash,
+        phase: VerificationPhase,
+        error: VerificationError,
+        options: &VerificationOptions,
+    ) -> Result<(), VerificationError> {
+        if !options.continue_on_error {
+            return Err(error);
+        }
+        self.problems.push(VerificationProblem {
+            block_hash: block_hash.clone(),
+            phase,
+            error,
+        });
+        Ok(())
+    }
+
+    pub fn merge(&mut self, result: VerificationResult) {
+        if let VerificationResult::Invalid(other) = result {
+            self.problems.extend(other.problems);
+        }
+    }
+
+    pub fn problems_for_block<'a>(
+        &'a self,
+        block_hash: &'a Multihash,
+        phase: VerificationPhase,
+    ) -> impl Iterator<Item = &'a VerificationProblem> + 'a {
+        self.problems
+            .iter()
+            .filter(move |p| p.block_hash == *block_hash && p.phase == phase)
+    }
+}
+
+impl From<VerificationReport> for VerificationResult {
+    fn from(report: VerificationReport) -> Self {
+        if report.is_empty() {
+            VerificationResult::Valid
+        } else {
+            VerificationResult::Invalid(report)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VerificationProblem {
+    pub block_hash: Multihash,
+    pub phase: VerificationPhase,
+    pub error: VerificationError,
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -60,6 +131,26 @@ pub enum VerificationResult {
 pub struct VerificationOptions {
     pub check_integrity: bool,
     pub replay_transformations: bool,
+    /// Collect all problems into a report instead of stopping at the first one
+    pub continue_on_error: bool,
+    /// Maximum number of data parts (and datasets) to verify concurrently,
+    /// defaults to the number of available CPUs
+    pub max_concurrency: Option<usize>,
+    /// Skip signature checks for legacy datasets created before block signing
+    /// was introduced, where no block in the chain is signed
+    pub allow_unsigned: bool,
+}
+
+impl VerificationOptions {
+    pub fn concurrency(&self) -> usize {
+        self.max_concurrency
+            .unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(|n| n.get())
+                    .unwrap_or(1)
+            })
+            .max(1)
+    }
 }
 
 impl Default for VerificationOptions {
@@ -67,6 +158,9 @@ impl Default for VerificationOptions {
         Self {
             check_integrity: true,
             replay_transformations: true,
+            continue_on_error: false,
+            max_concurrency: None,
+            allow_unsigned: false,
         }
     }
 }
@@ -80,12 +174,18 @@ pub enum VerificationPhase {
     DataIntegrity,
     ReplayTransform,
     MetadataIntegrity,
+    MetadataSignatures,
 }
 
 // The call pattern is:
 //   begin()
 //     begin_phase(MetadataIntegrity)
 //     end_phase(MetadataIntegrity)
+//     begin_phase(MetadataSignatures)
+//       begin_block()
+//       end_block()
+//       ...
+//     end_phase(MetadataSignatures)
 //     begin_phase(DataIntegrity)
 //       begin_block()
 //       end_block()
@@ -98,6 +198,9 @@ pub enum VerificationPhase {
 //       ...
 //     end_phase(ReplayTransform)
 //   success()
+//
+// Blocks of the DataIntegrity phase are hashed concurrently, so several begin_block()
+// calls may precede the end_block() calls, which always arrive in the block order.
 pub trait VerificationListener {
     fn begin(&self) {}
     fn success(&self, _result: &VerificationResult) {}
@@ -190,6 +293,10 @@ pub enum VerificationError {
         #[backtrace]
         DataNotReproducible,
     ),
+    #[error(transparent)]
+    InvalidSignature(#[from] BlockSignatureError),
+    #[error("Metadata sequence is broken: {0}")]
+    InvalidMetadataSequence(AppendValidationError),
     #[error("Checkpoint doesn't match metadata")]
     CheckpointDoesNotMatchMetadata(
         #[from]
@@ -246,6 +353,7 @@ impl From<IterBlocksError> for VerificationError {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DataVerificationError {
+    NotFound,
     SizeMismatch {
         expected: u64,
         actual: u64,
@@ -269,6 +377,9 @@ pub struct DataDoesNotMatchMetadata {
 impl Display for DataDoesNotMatchMetadata {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.error {
+            DataVerificationError::NotFound => {
+                write!(f, "Data file for block {} is missing", self.block_hash)
+            }
             DataVerificationError::SizeMismatch { expected, actual } => write!(
                 f,
                 "Data size for block {} is expected to be {} but actual {}",
@@ -315,6 +426,7 @@ impl Display for DataNotReproducible {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum CheckpointVerificationError {
+    NotFound,
     SizeMismatch {
         expected: u64,
         actual: u64,
@@ -334,6 +446,13 @@ pub struct CheckpointDoesNotMatchMetadata {
 impl Display for CheckpointDoesNotMatchMetadata {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.error {
+            CheckpointVerificationError::NotFound => {
+                write!(
+                    f,
+                    "Checkpoint file for block {} is missing",
+                    self.block_hash
+                )
+            }
             CheckpointVerificationError::SizeMismatch { expected, actual } => write!(
                 f,
                 "Checkpoint size for block {} is expected to be {} but actual {}",
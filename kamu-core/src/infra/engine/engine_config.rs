@@ -14,3 +14,35 @@ pub struct ODFEngineConfig {
     pub start_timeout: Duration,
     pub shutdown_timeout: Duration,
 }
+
+/// Describes a containerized engine that implements the ODF engine protocol
+#[derive(Debug, Clone, PartialEq)]
+pub struct ODFEngineSpec {
+    /// Identifier that transforms use to refer to the engine
+    pub id: String,
+    /// Container image of the engine
+    pub image: String,
+    /// Port on which the engine adapter serves gRPC requests
+    pub grpc_port: u16,
+    /// Environment variables passed to the engine container
+    pub environment_vars: Vec<(String, String)>,
+    /// Maximum amount of memory the engine container can use (e.g. `4g`)
+    pub memory_limit: Option<String>,
+    /// Number of CPUs the engine container can use
+    pub cpu_limit: Option<f64>,
+}
+
+impl ODFEngineSpec {
+    pub const DEFAULT_GRPC_PORT: u16 = 2884;
+
+    pub fn new(id: impl Into<String>, image: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            image: image.into(),
+            grpc_port: Self::DEFAULT_GRPC_PORT,
+            environment_vars: Vec::new(),
+            memory_limit: None,
+            cpu_limit: None,
+        }
+    }
+}
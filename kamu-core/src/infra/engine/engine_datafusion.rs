@@ -0,0 +1,861 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::domain::engine::IngestRequest;
+use crate::domain::*;
+use crate::infra::ingest::*;
+use crate::infra::utils::datafusion_hacks::ListingTableOfFiles;
+
+use ::serde::{Deserialize, Serialize};
+use chrono::{DateTime, TimeZone, Utc};
+use datafusion::arrow::array::{ArrayRef, Int64Array, TimestampMillisecondArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use datafusion::parquet::arrow::ArrowWriter;
+use datafusion::prelude::*;
+use opendatafabric::serde::yaml::*;
+use opendatafabric::*;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::info;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// In-process engine based on DataFusion.
+///
+/// Handles ingestion of simple formats without provisioning any containers.
+/// Use [DataFusionEngine::supports_ingest] to check whether a source can be
+/// handled natively before falling back to the container-based engine.
+///
+/// Also acts as a transform engine for derivative datasets under the
+/// `datafusion` engine id. Transforms are stateless - queries only see the
+/// new slices of their inputs, so windowed aggregations and joins across
+/// slices are not supported.
+pub struct DataFusionEngine;
+
+impl DataFusionEngine {
+    pub const ENGINE_ID: &'static str = "datafusion";
+
+    const CATALOG: &'static str = "kamu";
+    const SCHEMA: &'static str = "kamu";
+
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Returns `true` when the source can be ingested without the Spark engine
+    pub fn supports_ingest(source: &SetPollingSource) -> bool {
+        let read_supported = match &source.read {
+            ReadStep::Csv(csv) => Self::supports_csv(csv),
+            ReadStep::JsonLines(json) => Self::supports_json_lines(json),
+            ReadStep::Parquet(parquet) => Self::supports_schema(&parquet.schema),
+            ReadStep::GeoJson(_) | ReadStep::EsriShapefile(_) => false,
+        };
+
+        let preprocess_supported = match &source.preprocess {
+            None => true,
+            Some(Transform::Sql(sql)) => sql.engine == Self::ENGINE_ID,
+        };
+
+        read_supported && preprocess_supported
+    }
+
+    fn supports_schema(ddl: &Option<Vec<String>>) -> bool {
+        match ddl {
+            None => true,
+            Some(ddl) => parse_ddl_schema(ddl).is_ok(),
+        }
+    }
+
+    fn supports_encoding(encoding: &Option<String>) -> bool {
+        match encoding {
+            None => true,
+            Some(enc) => enc.eq_ignore_ascii_case("utf8") || enc.eq_ignore_ascii_case("utf-8"),
+        }
+    }
+
+    fn supports_csv(csv: &ReadStepCsv) -> bool {
+        // Positional columns are named differently in Spark and DataFusion,
+        // so without a header or a schema we cannot produce identical output
+        let has_names = csv.schema.is_some() || csv.header == Some(true);
+
+        has_names
+            && Self::supports_schema(&csv.schema)
+            && Self::supports_encoding(&csv.encoding)
+            && csv.separator.as_ref().map(|s| s.len() == 1).unwrap_or(true)
+            && csv.quote.as_ref().map(|q| q == "\"").unwrap_or(true)
+            && csv.escape.is_none()
+            && csv.comment.is_none()
+            && csv
+                .null_value
+                .as_ref()
+                .map(|v| v.is_empty())
+                .unwrap_or(true)
+            && csv.empty_value.is_none()
+            && csv.nan_value.is_none()
+            && csv.positive_inf.is_none()
+            && csv.negative_inf.is_none()
+            && csv.date_format.is_none()
+            && csv.timestamp_format.is_none()
+            && csv.multi_line != Some(true)
+    }
+
+    fn supports_json_lines(json: &ReadStepJsonLines) -> bool {
+        Self::supports_schema(&json.schema)
+            && Self::supports_encoding(&json.encoding)
+            && json.date_format.is_none()
+            && json.timestamp_format.is_none()
+            && json.multi_line != Some(true)
+    }
+
+    async fn register_input(
+        &self,
+        ctx: &SessionContext,
+        request: &IngestRequest,
+    ) -> Result<(), EngineError> {
+        let path = request.ingest_path.to_string_lossy();
+
+        match &request.source.read {
+            ReadStep::Csv(csv) => {
+                let schema = csv
+                    .schema
+                    .as_ref()
+                    .map(|s| parse_ddl_schema(s))
+                    .transpose()?;
+                let delimiter = csv
+                    .separator
+                    .as_ref()
+                    .map(|s| s.as_bytes()[0])
+                    .unwrap_or(b',');
+
+                let mut options = CsvReadOptions::new()
+                    .has_header(csv.header.unwrap_or(false))
+                    .delimiter(delimiter)
+                    .file_extension("");
+
+                if let Some(schema) = &schema {
+                    options = options.schema(schema);
+                }
+
+                ctx.register_csv("input", &path, options)
+                    .await
+                    .map_err(into_engine_error)?;
+            }
+            ReadStep::JsonLines(json) => {
+                let schema = json
+                    .schema
+                    .as_ref()
+                    .map(|s| parse_ddl_schema(s))
+                    .transpose()?;
+
+                let options = NdJsonReadOptions {
+                    schema: schema.map(Arc::new),
+                    file_extension: "",
+                    ..NdJsonReadOptions::default()
+                };
+
+                ctx.register_json("input", &path, options)
+                    .await
+                    .map_err(into_engine_error)?;
+            }
+            ReadStep::Parquet(_) => {
+                let options = ParquetReadOptions {
+                    file_extension: "",
+                    ..ParquetReadOptions::default()
+                };
+
+                ctx.register_parquet("input", &path, options)
+                    .await
+                    .map_err(into_engine_error)?;
+            }
+            ReadStep::GeoJson(_) | ReadStep::EsriShapefile(_) => {
+                return Err(EngineError::contract_error(
+                    "Read step is not supported by the DataFusion engine",
+                    Vec::new(),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Registers all intermediate steps as views and returns the output query
+    async fn prepare_preprocess(
+        &self,
+        ctx: &SessionContext,
+        transform: &Transform,
+    ) -> Result<String, EngineError> {
+        let Transform::Sql(sql) = transform;
+
+        let mut steps = match (&sql.query, &sql.queries) {
+            (Some(query), None) => vec![SqlQueryStep {
+                alias: None,
+                query: query.clone(),
+            }],
+            (None, Some(queries)) if !queries.is_empty() => queries.clone(),
+            _ => {
+                return Err(EngineError::invalid_query(
+                    "Transform should specify either a query or a non-empty list of queries",
+                    Vec::new(),
+                ))
+            }
+        };
+
+        let output = steps.pop().unwrap();
+        if output.alias.is_some() {
+            return Err(EngineError::invalid_query(
+                "Last query step should not have an alias",
+                Vec::new(),
+            ));
+        }
+
+        for step in steps {
+            let alias = step.alias.ok_or_else(|| {
+                EngineError::invalid_query(
+                    "All query steps except the last one should have an alias",
+                    Vec::new(),
+                )
+            })?;
+
+            ctx.sql(&format!("CREATE VIEW \"{}\" AS {}", alias, step.query))
+                .await
+                .map_err(into_engine_error)?;
+        }
+
+        Ok(output.query)
+    }
+
+    /// Registers every input under its dataset name as a view that only
+    /// exposes the records of the input slice
+    async fn register_transform_inputs(
+        &self,
+        ctx: &SessionContext,
+        inputs: &[ExecuteQueryInput],
+    ) -> Result<(), EngineError> {
+        for (i, input) in inputs.iter().enumerate() {
+            // Empty slices still need a schema for the query to be planned
+            let files = if input.data_paths.is_empty() {
+                vec![input.schema_file.to_string_lossy().into()]
+            } else {
+                input
+                    .data_paths
+                    .iter()
+                    .map(|p| p.to_string_lossy().into())
+                    .collect()
+            };
+
+            let table = ListingTableOfFiles::try_new(&ctx.state(), files)
+                .await
+                .map_err(into_engine_error)?;
+
+            let table_name = format!("__input_{}", i);
+            ctx.register_table(table_name.as_str(), Arc::new(table))
+                .map_err(into_engine_error)?;
+
+            let offset_column = input.vocab.offset_column.as_deref().unwrap_or("offset");
+            let filter = match &input.data_interval {
+                Some(interval) if !input.data_paths.is_empty() => format!(
+                    "\"{}\" >= {} AND \"{}\" <= {}",
+                    offset_column, interval.start, offset_column, interval.end
+                ),
+                _ => "FALSE".to_owned(),
+            };
+
+            let view_name = format!("__input_view_{}", i);
+            ctx.sql(&format!(
+                "CREATE VIEW {} AS SELECT * FROM {} WHERE {}",
+                view_name, table_name, filter
+            ))
+            .await
+            .map_err(into_engine_error)?;
+
+            // Dataset names can contain dots, which would be interpreted as
+            // a schema qualifier if registered through the context
+            let view = ctx
+                .deregister_table(view_name.as_str())
+                .map_err(into_engine_error)?
+                .unwrap();
+            ctx.catalog(Self::CATALOG)
+                .unwrap()
+                .schema(Self::SCHEMA)
+                .unwrap()
+                .register_table(input.dataset_name.to_string(), view)
+                .map_err(into_engine_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes system columns that queries like `SELECT * FROM input` carry
+    /// over from the inputs, as they will be re-assigned for the output
+    fn drop_system_columns(
+        batches: Vec<RecordBatch>,
+        vocab: &DatasetVocabulary,
+    ) -> Result<Vec<RecordBatch>, EngineError> {
+        let offset_column = vocab.offset_column.as_deref().unwrap_or("offset");
+        let system_time_column = vocab.system_time_column.as_deref().unwrap_or("system_time");
+
+        batches
+            .into_iter()
+            .map(|batch| {
+                let schema = batch.schema();
+                let indices: Vec<_> = schema
+                    .fields()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| f.name() != offset_column && f.name() != system_time_column)
+                    .map(|(i, _)| i)
+                    .collect();
+
+                if indices.len() == schema.fields().len() {
+                    Ok(batch)
+                } else {
+                    batch
+                        .project(&indices)
+                        .map_err(|e| EngineError::internal(e, Vec::new()))
+                }
+            })
+            .collect()
+    }
+
+    /// Determines the watermark of every input, falling back to the one
+    /// stored in the previous checkpoint when the slice carries none
+    fn get_input_watermarks(
+        request: &ExecuteQueryRequest,
+    ) -> Result<Vec<InputWatermark>, EngineError> {
+        let prev_checkpoint: Option<TransformCheckpoint> = match &request.prev_checkpoint_path {
+            None => None,
+            Some(path) => {
+                let file = std::fs::File::open(path)?;
+                let manifest: Manifest<TransformCheckpoint> = serde_yaml::from_reader(file)
+                    .map_err(|e| EngineError::internal(e, Vec::new()))?;
+                if manifest.kind != TransformCheckpoint::KIND {
+                    return Err(EngineError::contract_error(
+                        "Previous checkpoint was not produced by the DataFusion engine",
+                        Vec::new(),
+                    ));
+                }
+                Some(manifest.content)
+            }
+        };
+
+        Ok(request
+            .inputs
+            .iter()
+            .filter_map(|input| {
+                let event_time = input
+                    .explicit_watermarks
+                    .last()
+                    .map(|wm| wm.event_time)
+                    .or_else(|| {
+                        prev_checkpoint.as_ref().and_then(|cp| {
+                            cp.input_watermarks
+                                .iter()
+                                .find(|wm| wm.dataset_id == input.dataset_id)
+                                .map(|wm| wm.event_time)
+                        })
+                    })?;
+
+                Some(InputWatermark {
+                    dataset_id: input.dataset_id.clone(),
+                    event_time,
+                })
+            })
+            .collect())
+    }
+
+    fn write_checkpoint(
+        path: &Path,
+        input_watermarks: Vec<InputWatermark>,
+    ) -> Result<(), EngineError> {
+        let manifest = Manifest {
+            kind: TransformCheckpoint::KIND.to_owned(),
+            version: 1,
+            content: TransformCheckpoint { input_watermarks },
+        };
+        let file = std::fs::File::create(path)?;
+        serde_yaml::to_writer(file, &manifest).map_err(|e| EngineError::internal(e, Vec::new()))?;
+        Ok(())
+    }
+
+    /// Casts columns to the types declared in the read step schema
+    fn coerce_to_schema(
+        batches: Vec<RecordBatch>,
+        ddl: &Option<Vec<String>>,
+    ) -> Result<Vec<RecordBatch>, EngineError> {
+        let target = match ddl {
+            None => return Ok(batches),
+            Some(ddl) => parse_ddl_schema(ddl)?,
+        };
+
+        batches
+            .into_iter()
+            .map(|batch| {
+                let schema = batch.schema();
+                let mut fields = Vec::new();
+                let mut columns = Vec::new();
+
+                for (i, field) in schema.fields().iter().enumerate() {
+                    match target.field_with_name(field.name()) {
+                        Ok(target_field) if target_field.data_type() != field.data_type() => {
+                            let col =
+                                cast(batch.column(i), target_field.data_type()).map_err(|e| {
+                                    EngineError::invalid_query(
+                                        format!(
+                                            "Failed to coerce column {} to {}: {}",
+                                            field.name(),
+                                            target_field.data_type(),
+                                            e
+                                        ),
+                                        Vec::new(),
+                                    )
+                                })?;
+                            fields.push(Field::new(
+                                field.name(),
+                                target_field.data_type().clone(),
+                                true,
+                            ));
+                            columns.push(col);
+                        }
+                        _ => {
+                            fields.push(field.clone());
+                            columns.push(batch.column(i).clone());
+                        }
+                    }
+                }
+
+                RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+                    .map_err(|e| EngineError::internal(e, Vec::new()))
+            })
+            .collect()
+    }
+
+    /// Casts all columns to strings, mimicking readers that don't infer types
+    fn coerce_to_strings(batches: Vec<RecordBatch>) -> Result<Vec<RecordBatch>, EngineError> {
+        batches
+            .into_iter()
+            .map(|batch| {
+                let schema = batch.schema();
+                let mut fields = Vec::new();
+                let mut columns = Vec::new();
+
+                for (i, field) in schema.fields().iter().enumerate() {
+                    match field.data_type() {
+                        DataType::List(_) | DataType::Struct(_) | DataType::Map(_, _) => {
+                            fields.push(field.clone());
+                            columns.push(batch.column(i).clone());
+                        }
+                        _ => {
+                            fields.push(Field::new(field.name(), DataType::Utf8, true));
+                            columns.push(
+                                cast(batch.column(i), &DataType::Utf8)
+                                    .map_err(|e| EngineError::internal(e, Vec::new()))?,
+                            );
+                        }
+                    }
+                }
+
+                RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+                    .map_err(|e| EngineError::internal(e, Vec::new()))
+            })
+            .collect()
+    }
+
+    /// Prepends the offset, system time, and event time columns to the data
+    /// and computes the output watermark.
+    pub(crate) fn add_system_columns(
+        batches: Vec<RecordBatch>,
+        vocab: &DatasetVocabulary,
+        offset: i64,
+        system_time: DateTime<Utc>,
+        event_time: DateTime<Utc>,
+    ) -> Result<(Vec<RecordBatch>, Option<DateTime<Utc>>), EngineError> {
+        let offset_column = vocab.offset_column.as_deref().unwrap_or("offset");
+        let system_time_column = vocab.system_time_column.as_deref().unwrap_or("system_time");
+        let event_time_column = vocab.event_time_column.as_deref().unwrap_or("event_time");
+
+        let timestamp_type = DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".to_owned()));
+
+        let mut next_offset = offset;
+        let mut watermark: Option<i64> = None;
+        let mut result = Vec::new();
+
+        for batch in batches {
+            let num_rows = batch.num_rows();
+            let schema = batch.schema();
+
+            for reserved in [offset_column, system_time_column] {
+                if schema.field_with_name(reserved).is_ok() {
+                    return Err(EngineError::invalid_query(
+                        format!("Data contains a reserved column: {}", reserved),
+                        Vec::new(),
+                    ));
+                }
+            }
+
+            let offsets: ArrayRef = Arc::new(Int64Array::from_iter_values(
+                next_offset..next_offset + num_rows as i64,
+            ));
+            next_offset += num_rows as i64;
+
+            let system_times: ArrayRef = Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    std::iter::repeat(system_time.timestamp_millis()).take(num_rows),
+                )
+                .with_timezone("UTC".to_owned()),
+            );
+
+            let event_times: ArrayRef = match schema.index_of(event_time_column) {
+                Ok(i) => cast(batch.column(i), &timestamp_type).map_err(|e| {
+                    EngineError::invalid_query(
+                        format!(
+                            "Failed to interpret column {} as event time: {}",
+                            event_time_column, e
+                        ),
+                        Vec::new(),
+                    )
+                })?,
+                Err(_) => Arc::new(
+                    TimestampMillisecondArray::from_iter_values(
+                        std::iter::repeat(event_time.timestamp_millis()).take(num_rows),
+                    )
+                    .with_timezone("UTC".to_owned()),
+                ),
+            };
+
+            let batch_watermark = event_times
+                .as_any()
+                .downcast_ref::<TimestampMillisecondArray>()
+                .unwrap()
+                .iter()
+                .flatten()
+                .max();
+            watermark = watermark.max(batch_watermark);
+
+            let mut fields = vec![
+                Field::new(offset_column, DataType::Int64, false),
+                Field::new(system_time_column, timestamp_type.clone(), false),
+                Field::new(event_time_column, timestamp_type.clone(), true),
+            ];
+            let mut columns = vec![offsets, system_times, event_times];
+
+            for (i, field) in schema.fields().iter().enumerate() {
+                if field.name() != event_time_column {
+                    fields.push(field.clone());
+                    columns.push(batch.column(i).clone());
+                }
+            }
+
+            result.push(
+                RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+                    .map_err(|e| EngineError::internal(e, Vec::new()))?,
+            );
+        }
+
+        Ok((
+            result,
+            watermark.map(|w| Utc.timestamp_millis_opt(w).unwrap()),
+        ))
+    }
+
+    pub(crate) fn write_parquet(
+        path: &Path,
+        schema: SchemaRef,
+        batches: &[RecordBatch],
+    ) -> Result<(), EngineError> {
+        let file = std::fs::File::create(path)?;
+        let mut writer = ArrowWriter::try_new(file, schema, None)
+            .map_err(|e| EngineError::internal(e, Vec::new()))?;
+
+        for batch in batches {
+            writer
+                .write(batch)
+                .map_err(|e| EngineError::internal(e, Vec::new()))?;
+        }
+
+        writer
+            .close()
+            .map_err(|e| EngineError::internal(e, Vec::new()))?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl IngestEngine for DataFusionEngine {
+    async fn ingest(
+        &self,
+        request: IngestRequest,
+    ) -> Result<ExecuteQueryResponseSuccess, EngineError> {
+        info!(request = ?request, "Ingesting with DataFusion engine");
+
+        let ctx = SessionContext::new();
+
+        self.register_input(&ctx, &request).await?;
+
+        let query = match &request.source.preprocess {
+            None => "SELECT * FROM input".to_owned(),
+            Some(transform) => self.prepare_preprocess(&ctx, transform).await?,
+        };
+
+        let batches: Vec<_> = ctx
+            .sql(&query)
+            .await
+            .map_err(into_engine_error)?
+            .collect()
+            .await
+            .map_err(into_engine_error)?
+            .into_iter()
+            .filter(|b| b.num_rows() != 0)
+            .collect();
+
+        let batches = match &request.source.read {
+            ReadStep::Parquet(parquet) => Self::coerce_to_schema(batches, &parquet.schema)?,
+            ReadStep::Csv(csv) if csv.schema.is_none() && csv.infer_schema != Some(true) => {
+                Self::coerce_to_strings(batches)?
+            }
+            ReadStep::JsonLines(json)
+                if json.schema.is_none() && json.primitives_as_string == Some(true) =>
+            {
+                Self::coerce_to_strings(batches)?
+            }
+            _ => batches,
+        };
+
+        let merger = get_data_merger(&request.source.merge, &request.dataset_vocab);
+        let prev = if merger.needs_prev_data() {
+            read_prev_data(&request.data_dir).await?
+        } else {
+            None
+        };
+        let batches = merger.merge(prev, batches).await?;
+
+        let num_records: usize = batches.iter().map(|b| b.num_rows()).sum();
+        if num_records == 0 {
+            info!("Input produced no records");
+            return Ok(ExecuteQueryResponseSuccess {
+                data_interval: None,
+                output_watermark: None,
+            });
+        }
+
+        let (batches, output_watermark) = Self::add_system_columns(
+            batches,
+            &request.dataset_vocab,
+            request.offset,
+            request.system_time,
+            request.event_time.unwrap_or(request.system_time),
+        )?;
+
+        Self::write_parquet(
+            &request.out_data_path,
+            batches.first().unwrap().schema(),
+            &batches,
+        )?;
+
+        info!(num_records, ?output_watermark, "Wrote output data");
+
+        Ok(ExecuteQueryResponseSuccess {
+            data_interval: Some(OffsetInterval {
+                start: request.offset,
+                end: request.offset + num_records as i64 - 1,
+            }),
+            output_watermark,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Engine for DataFusionEngine {
+    async fn transform(
+        &self,
+        request: ExecuteQueryRequest,
+    ) -> Result<ExecuteQueryResponseSuccess, EngineError> {
+        info!(request = ?request, "Transforming with DataFusion engine");
+
+        let cfg = SessionConfig::new().with_default_catalog_and_schema(Self::CATALOG, Self::SCHEMA);
+        let ctx = SessionContext::with_config(cfg);
+
+        self.register_transform_inputs(&ctx, &request.inputs)
+            .await?;
+
+        let query = self.prepare_preprocess(&ctx, &request.transform).await?;
+
+        let batches: Vec<_> = ctx
+            .sql(&query)
+            .await
+            .map_err(into_engine_error)?
+            .collect()
+            .await
+            .map_err(into_engine_error)?
+            .into_iter()
+            .filter(|b| b.num_rows() != 0)
+            .collect();
+
+        let batches = Self::drop_system_columns(batches, &request.vocab)?;
+
+        let event_time_column = request
+            .vocab
+            .event_time_column
+            .as_deref()
+            .unwrap_or("event_time");
+
+        if let Some(batch) = batches.first() {
+            if batch.schema().field_with_name(event_time_column).is_err() {
+                return Err(EngineError::invalid_query(
+                    format!(
+                        "Query result does not contain the event time column: {}",
+                        event_time_column
+                    ),
+                    Vec::new(),
+                ));
+            }
+        }
+
+        // Output watermark can only advance once all inputs have one
+        let input_watermarks = Self::get_input_watermarks(&request)?;
+        let output_watermark = if input_watermarks.len() == request.inputs.len() {
+            input_watermarks.iter().map(|wm| wm.event_time).min()
+        } else {
+            None
+        };
+
+        if !input_watermarks.is_empty() {
+            Self::write_checkpoint(&request.new_checkpoint_path, input_watermarks)?;
+        }
+
+        let num_records: usize = batches.iter().map(|b| b.num_rows()).sum();
+        if num_records == 0 {
+            info!(?output_watermark, "Query produced no records");
+            return Ok(ExecuteQueryResponseSuccess {
+                data_interval: None,
+                output_watermark,
+            });
+        }
+
+        let (batches, _) = Self::add_system_columns(
+            batches,
+            &request.vocab,
+            request.offset,
+            request.system_time,
+            request.system_time,
+        )?;
+
+        Self::write_parquet(
+            &request.out_data_path,
+            batches.first().unwrap().schema(),
+            &batches,
+        )?;
+
+        info!(num_records, ?output_watermark, "Wrote output data");
+
+        Ok(ExecuteQueryResponseSuccess {
+            data_interval: Some(OffsetInterval {
+                start: request.offset,
+                end: request.offset + num_records as i64 - 1,
+            }),
+            output_watermark,
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// State carried between transform runs of the DataFusion engine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+struct TransformCheckpoint {
+    input_watermarks: Vec<InputWatermark>,
+}
+
+impl TransformCheckpoint {
+    const KIND: &'static str = "DataFusionTransformCheckpoint";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+struct InputWatermark {
+    dataset_id: DatasetID,
+    #[serde(with = "datetime_rfc3339")]
+    event_time: DateTime<Utc>,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+pub(crate) fn into_engine_error(e: DataFusionError) -> EngineError {
+    match e {
+        DataFusionError::Plan(_) | DataFusionError::SQL(_) | DataFusionError::SchemaError(_) => {
+            EngineError::invalid_query(e.to_string(), Vec::new())
+        }
+        _ => EngineError::internal(e, Vec::new()),
+    }
+}
+
+/// Parses a list of DDL-formatted fields (e.g. `name STRING`) into Arrow schema
+pub(crate) fn parse_ddl_schema(ddl: &[String]) -> Result<Schema, EngineError> {
+    let fields = ddl
+        .iter()
+        .map(|f| parse_ddl_field(f))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Schema::new(fields))
+}
+
+fn parse_ddl_field(field: &str) -> Result<Field, EngineError> {
+    let field = field.trim();
+
+    let (name, rest) = if let Some(quoted) = field.strip_prefix('`') {
+        match quoted.split_once('`') {
+            Some((name, rest)) => (name, rest),
+            None => return Err(invalid_ddl(field)),
+        }
+    } else {
+        match field.split_once(char::is_whitespace) {
+            Some((name, rest)) => (name, rest),
+            None => return Err(invalid_ddl(field)),
+        }
+    };
+
+    let typ = rest.trim().to_uppercase();
+    let data_type = match typ.as_str() {
+        "STRING" | "VARCHAR" | "TEXT" => DataType::Utf8,
+        "BOOLEAN" | "BOOL" => DataType::Boolean,
+        "TINYINT" | "BYTE" => DataType::Int8,
+        "SMALLINT" | "SHORT" => DataType::Int16,
+        "INT" | "INTEGER" => DataType::Int32,
+        "BIGINT" | "LONG" => DataType::Int64,
+        "FLOAT" | "REAL" => DataType::Float32,
+        "DOUBLE" => DataType::Float64,
+        "DATE" => DataType::Date32,
+        "TIMESTAMP" => DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".to_owned())),
+        _ => {
+            if let Some(args) = typ
+                .strip_prefix("DECIMAL(")
+                .and_then(|s| s.strip_suffix(')'))
+            {
+                let (precision, scale) = args.split_once(',').unwrap_or((args, "0"));
+                match (precision.trim().parse(), scale.trim().parse()) {
+                    (Ok(p), Ok(s)) => DataType::Decimal128(p, s),
+                    _ => return Err(invalid_ddl(field)),
+                }
+            } else {
+                return Err(invalid_ddl(field));
+            }
+        }
+    };
+
+    Ok(Field::new(name, data_type, true))
+}
+
+fn invalid_ddl(field: &str) -> EngineError {
+    EngineError::invalid_query(
+        format!("Unsupported schema field definition: {}", field),
+        Vec::new(),
+    )
+}
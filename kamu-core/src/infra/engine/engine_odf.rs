@@ -23,14 +23,14 @@ use opendatafabric as odf;
 use rand::Rng;
 use tracing::{info, info_span, warn};
 
-use super::ODFEngineConfig;
+use super::{ODFEngineConfig, ODFEngineSpec};
 use crate::domain::*;
 use crate::infra::WorkspaceLayout;
 
 pub struct ODFEngine {
     container_runtime: ContainerRuntime,
     engine_config: ODFEngineConfig,
-    image: String,
+    spec: ODFEngineSpec,
     workspace_layout: Arc<WorkspaceLayout>,
 }
 
@@ -40,13 +40,13 @@ impl ODFEngine {
     pub fn new(
         container_runtime: ContainerRuntime,
         engine_config: ODFEngineConfig,
-        image: &str,
+        spec: ODFEngineSpec,
         workspace_layout: Arc<WorkspaceLayout>,
     ) -> Self {
         Self {
             container_runtime,
             engine_config,
-            image: image.to_owned(),
+            spec,
             workspace_layout,
         }
     }
@@ -59,7 +59,7 @@ impl ODFEngine {
         let engine_container = EngineContainer::new(
             self.container_runtime.clone(),
             self.engine_config.clone(),
-            &self.image,
+            &self.spec,
             &run_info,
             vec![(
                 self.workspace_layout.datasets_dir.clone(),
@@ -72,7 +72,7 @@ impl ODFEngine {
         let span = info_span!(
             "Performing engine operation",
             id = engine_container.container_name.as_str(),
-            image = self.image.as_str(),
+            image = self.spec.image.as_str(),
             operation = "execute_query",
             request = ?request,
         );
@@ -259,12 +259,10 @@ struct EngineContainer {
 }
 
 impl EngineContainer {
-    const ADAPTER_PORT: u16 = 2884;
-
     pub fn new(
         container_runtime: ContainerRuntime,
         config: ODFEngineConfig,
-        image: &str,
+        spec: &ODFEngineSpec,
         run_info: &RunInfo,
         volume_map: Vec<(PathBuf, PathBuf)>,
     ) -> Result<Self, EngineError> {
@@ -274,15 +272,18 @@ impl EngineContainer {
         let container_name = format!("kamu-engine-{}", &run_info.run_id);
 
         let mut cmd = container_runtime.run_cmd(RunArgs {
-            image: image.to_owned(),
+            image: spec.image.clone(),
             container_name: Some(container_name.clone()),
             volume_map: volume_map,
             user: Some("root".to_owned()),
-            expose_ports: vec![Self::ADAPTER_PORT],
+            expose_ports: vec![spec.grpc_port],
+            environment_vars: spec.environment_vars.clone(),
+            memory_limit: spec.memory_limit.clone(),
+            cpu_limit: spec.cpu_limit.map(|c| c.to_string()),
             ..RunArgs::default()
         });
 
-        info!(command = ?cmd, image = image, id = container_name.as_str(), "Starting engine");
+        info!(command = ?cmd, image = spec.image.as_str(), id = container_name.as_str(), "Starting engine");
 
         let engine_process = KillOnDrop::new(
             cmd.stdout(std::process::Stdio::from(stdout_file)) // Stdio::inherit()
@@ -292,7 +293,7 @@ impl EngineContainer {
         );
 
         let adapter_host_port = container_runtime
-            .wait_for_host_port(&container_name, Self::ADAPTER_PORT, config.start_timeout)
+            .wait_for_host_port(&container_name, spec.grpc_port, config.start_timeout)
             .map_err(|e| EngineError::internal(e, run_info.log_files()))?;
 
         container_runtime
@@ -17,7 +17,7 @@ use super::engine_spark::*;
 use container_runtime::NetworkNamespaceType;
 use container_runtime::{ContainerRuntime, NullPullImageListener};
 use dill::*;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::process::Stdio;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -30,8 +30,10 @@ use tracing::{error, info};
 pub struct EngineProvisionerLocal {
     config: EngineProvisionerLocalConfig,
     spark_ingest_engine: Arc<dyn IngestEngine>,
-    spark_engine: Arc<dyn Engine>,
-    flink_engine: Arc<dyn Engine>,
+    spark_ingest_image: String,
+    /// Containerized engines by id along with their images
+    odf_engines: BTreeMap<String, (Arc<dyn Engine>, String)>,
+    datafusion_engine: Arc<dyn Engine>,
     container_runtime: ContainerRuntime,
     state: Mutex<State>,
     notify: tokio::sync::Notify,
@@ -54,24 +56,31 @@ impl EngineProvisionerLocal {
             shutdown_timeout: config.shutdown_timeout,
         };
 
+        // Ingest uses the same image as the `spark` transform engine, which can be overridden
+        let spark_ingest_image = config.spark_engine_image();
+
         Self {
             spark_ingest_engine: Arc::new(SparkEngine::new(
                 container_runtime.clone(),
-                &config.spark_image,
-                workspace_layout.clone(),
-            )),
-            spark_engine: Arc::new(ODFEngine::new(
-                container_runtime.clone(),
-                engine_config.clone(),
-                &config.spark_image,
-                workspace_layout.clone(),
-            )),
-            flink_engine: Arc::new(ODFEngine::new(
-                container_runtime.clone(),
-                engine_config.clone(),
-                &config.flink_image,
+                &spark_ingest_image,
                 workspace_layout.clone(),
             )),
+            spark_ingest_image,
+            odf_engines: config
+                .engine_specs()
+                .into_iter()
+                .map(|spec| {
+                    let image = spec.image.clone();
+                    let engine: Arc<dyn Engine> = Arc::new(ODFEngine::new(
+                        container_runtime.clone(),
+                        engine_config.clone(),
+                        spec.clone(),
+                        workspace_layout.clone(),
+                    ));
+                    (spec.id, (engine, image))
+                })
+                .collect(),
+            datafusion_engine: Arc::new(DataFusionEngine::new()),
             container_runtime: container_runtime,
             state: Mutex::new(State {
                 outstanding_handles: 0,
@@ -193,7 +202,7 @@ impl EngineProvisioner for EngineProvisionerLocal {
         maybe_listener: Option<Arc<dyn EngineProvisioningListener>>,
     ) -> Result<IngestEngineHandle, EngineProvisioningError> {
         let listener = maybe_listener.unwrap_or_else(|| Arc::new(NullEngineProvisioningListener));
-        self.ensure_image(&self.config.spark_image, listener.clone())
+        self.ensure_image(&self.spark_ingest_image, listener.clone())
             .await?;
 
         listener.begin("spark-ingest");
@@ -214,18 +223,17 @@ impl EngineProvisioner for EngineProvisionerLocal {
         let listener = maybe_listener.unwrap_or_else(|| Arc::new(NullEngineProvisioningListener));
 
         let (engine, image) = match engine_id {
-            "spark" => Ok((
-                self.spark_engine.clone() as Arc<dyn Engine>,
-                &self.config.spark_image,
-            )),
-            "flink" => Ok((
-                self.flink_engine.clone() as Arc<dyn Engine>,
-                &self.config.flink_image,
-            )),
-            _ => Err(EngineProvisioningError::image_not_found(engine_id)),
+            // Runs in-process and does not need an image
+            DataFusionEngine::ENGINE_ID => Ok((self.datafusion_engine.clone(), None)),
+            _ => match self.odf_engines.get(engine_id) {
+                Some((engine, image)) => Ok((engine.clone(), Some(image))),
+                None => Err(EngineProvisioningError::image_not_found(engine_id)),
+            },
         }?;
 
-        self.ensure_image(image, listener.clone()).await?;
+        if let Some(image) = image {
+            self.ensure_image(image, listener.clone()).await?;
+        }
 
         listener.begin(engine_id);
         self.wait_for_max_concurrency().await;
@@ -271,6 +279,41 @@ pub struct EngineProvisionerLocalConfig {
     // TODO: Remove in favor of explicit images in ODF protocol
     pub spark_image: String,
     pub flink_image: String,
+
+    /// Additional engines that implement the ODF engine protocol.
+    /// Engines with `spark` or `flink` ids replace the built-in ones.
+    pub engines: Vec<ODFEngineSpec>,
+}
+
+impl EngineProvisionerLocalConfig {
+    /// Ids of the in-process engines that cannot be replaced by configured ones
+    pub const RESERVED_ENGINE_IDS: &'static [&'static str] = &[DataFusionEngine::ENGINE_ID];
+
+    /// Returns the image of the `spark` engine, which may be replaced in `engines`
+    pub fn spark_engine_image(&self) -> String {
+        self.engines
+            .iter()
+            .rev()
+            .find(|e| e.id == "spark")
+            .map_or_else(|| self.spark_image.clone(), |e| e.image.clone())
+    }
+
+    /// Returns specs of all containerized engines, built-in ones first
+    pub fn engine_specs(&self) -> Vec<ODFEngineSpec> {
+        let mut specs = vec![
+            ODFEngineSpec::new("spark", &self.spark_image),
+            ODFEngineSpec::new("flink", &self.flink_image),
+        ];
+
+        for engine in &self.engines {
+            match specs.iter_mut().find(|s| s.id == engine.id) {
+                Some(spec) => *spec = engine.clone(),
+                None => specs.push(engine.clone()),
+            }
+        }
+
+        specs
+    }
 }
 
 // This is for tests only
@@ -282,6 +325,7 @@ impl Default for EngineProvisionerLocalConfig {
             shutdown_timeout: Duration::from_secs(5),
             spark_image: docker_images::SPARK.to_owned(),
             flink_image: docker_images::FLINK.to_owned(),
+            engines: Vec::new(),
         }
     }
 }
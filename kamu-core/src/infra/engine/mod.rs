@@ -13,5 +13,8 @@ pub use engine_config::*;
 mod engine_provisioner_local;
 pub use engine_provisioner_local::*;
 
+mod engine_datafusion;
+pub use engine_datafusion::*;
+
 mod engine_odf;
 mod engine_spark;
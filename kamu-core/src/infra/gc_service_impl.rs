@@ -0,0 +1,179 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::domain::*;
+use opendatafabric::*;
+
+use dill::*;
+use futures::TryStreamExt;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{debug, info, info_span};
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct GcServiceImpl {
+    local_repo: Arc<dyn LocalDatasetRepository>,
+}
+
+#[component(pub)]
+impl GcServiceImpl {
+    pub fn new(local_repo: Arc<dyn LocalDatasetRepository>) -> Self {
+        Self { local_repo }
+    }
+
+    /// Walks all references of the dataset and collects hashes of all objects they depend on
+    async fn collect_reachable(&self, dataset: &dyn Dataset) -> Result<Reachable, GcError> {
+        let chain = dataset.as_metadata_chain();
+        let mut reachable = Reachable::default();
+
+        for block_ref in chain.as_reference_repo().list().await? {
+            let mut blocks = chain.iter_blocks_ref(&block_ref);
+
+            while let Some((hash, block)) = blocks.try_next().await.int_err()? {
+                // Chains are linear, so the rest of it was already visited via another reference
+                if !reachable.blocks.insert(hash) {
+                    break;
+                }
+
+                let (input_checkpoint, output_data, output_checkpoint) = match block.event {
+                    MetadataEvent::AddData(e) => {
+                        (e.input_checkpoint, Some(e.output_data), e.output_checkpoint)
+                    }
+                    MetadataEvent::ExecuteQuery(e) => {
+                        (e.input_checkpoint, e.output_data, e.output_checkpoint)
+                    }
+                    _ => continue,
+                };
+
+                if let Some(slice) = output_data {
+                    reachable.data.insert(slice.physical_hash);
+                }
+                if let Some(checkpoint) = output_checkpoint {
+                    reachable.checkpoints.insert(checkpoint.physical_hash);
+                }
+                if let Some(hash) = input_checkpoint {
+                    reachable.checkpoints.insert(hash);
+                }
+            }
+        }
+
+        Ok(reachable)
+    }
+
+    /// Deletes all objects of the repository that are not in the reachable set,
+    /// returning the number of objects and their total size
+    async fn sweep(
+        repo: &dyn ObjectRepository,
+        reachable: &HashSet<Multihash>,
+        options: &GcOptions,
+    ) -> Result<(Vec<Multihash>, u64), GcError> {
+        let mut deleted = Vec::new();
+        let mut bytes = 0;
+
+        let cutoff = chrono::Utc::now() - options.min_age;
+
+        for obj in repo.list().await? {
+            if reachable.contains(&obj.hash) {
+                continue;
+            }
+
+            // Object may belong to a commit that is still in progress
+            let is_recent = match obj.last_modified {
+                Some(t) => t > cutoff,
+                None => options.min_age > chrono::Duration::zero(),
+            };
+            if is_recent {
+                debug!(hash = %obj.hash, last_modified = ?obj.last_modified, "Keeping recent unreachable object");
+                continue;
+            }
+
+            info!(hash = %obj.hash, size = obj.size, dry_run = options.dry_run, "Deleting unreachable object");
+
+            if !options.dry_run {
+                repo.delete(&obj.hash).await?;
+            }
+
+            bytes += obj.size;
+            deleted.push(obj.hash);
+        }
+
+        Ok((deleted, bytes))
+    }
+
+    async fn sweep_signatures(
+        dataset: &dyn Dataset,
+        deleted_blocks: &[Multihash],
+    ) -> Result<(), GcError> {
+        for hash in deleted_blocks {
+            dataset.delete_block_signature(hash).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl GcService for GcServiceImpl {
+    async fn gc(
+        &self,
+        dataset_ref: &DatasetRefLocal,
+        options: GcOptions,
+    ) -> Result<GcResult, GcError> {
+        let dataset = self.local_repo.get_dataset(dataset_ref).await?;
+        self.gc_dataset(dataset.as_ref(), options).await
+    }
+
+    async fn gc_dataset(
+        &self,
+        dataset: &dyn Dataset,
+        options: GcOptions,
+    ) -> Result<GcResult, GcError> {
+        let span = info_span!("Collecting garbage", dry_run = options.dry_run);
+        let _span_guard = span.enter();
+
+        let reachable = self.collect_reachable(dataset).await?;
+
+        let (deleted_blocks, blocks_bytes) = Self::sweep(
+            dataset.as_metadata_chain().as_object_repo(),
+            &reachable.blocks,
+            &options,
+        )
+        .await?;
+
+        let (deleted_data, data_bytes) =
+            Self::sweep(dataset.as_data_repo(), &reachable.data, &options).await?;
+
+        let (deleted_checkpoints, checkpoints_bytes) = Self::sweep(
+            dataset.as_checkpoint_repo(),
+            &reachable.checkpoints,
+            &options,
+        )
+        .await?;
+
+        if !options.dry_run {
+            Self::sweep_signatures(dataset, &deleted_blocks).await?;
+        }
+
+        Ok(GcResult {
+            num_blocks: deleted_blocks.len(),
+            num_data_slices: deleted_data.len(),
+            num_checkpoints: deleted_checkpoints.len(),
+            bytes_reclaimed: blocks_bytes + data_bytes + checkpoints_bytes,
+        })
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Default)]
+struct Reachable {
+    blocks: HashSet<Multihash>,
+    data: HashSet<Multihash>,
+    checkpoints: HashSet<Multihash>,
+}
@@ -9,7 +9,7 @@
 
 use super::*;
 use crate::domain::*;
-use crate::infra::WorkspaceLayout;
+use crate::infra::{ObjectRepositoryS3, WorkspaceLayout};
 use opendatafabric::serde::yaml::*;
 use opendatafabric::*;
 
@@ -17,12 +17,14 @@ use ::serde::{Deserialize, Serialize};
 use ::serde_with::skip_serializing_none;
 use chrono::{DateTime, SubsecRound, TimeZone, Utc};
 use container_runtime::*;
+use rusoto_core::{Region, RusotoError};
+use rusoto_s3::S3Client;
 use std::borrow::Cow;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{debug, error, info, info_span};
+use tracing::{debug, error, info, info_span, warn};
 use url::Url;
 
 pub struct FetchService {
@@ -100,6 +102,13 @@ impl FetchService {
                         target,
                         listener.as_ref(),
                     ),
+                    "s3" | "s3+http" | "s3+https" => Self::fetch_s3(
+                        &url,
+                        furl.event_time.as_ref(),
+                        old_checkpoint,
+                        target,
+                        listener.as_ref(),
+                    ),
                     "ftp" => Self::fetch_ftp(
                         &url,
                         furl.event_time.as_ref(),
@@ -107,7 +116,11 @@ impl FetchService {
                         target,
                         listener.as_ref(),
                     ),
-                    scheme => unimplemented!("Unsupported scheme: {}", scheme),
+                    scheme => Err(InvalidSourceError::new(format!(
+                        "Unsupported URL scheme: {}",
+                        scheme
+                    ))
+                    .into()),
                 }
             }
             FetchStep::FilesGlob(fglob) => {
@@ -191,7 +204,6 @@ impl FetchService {
         target_path: &Path,
         listener: Arc<dyn FetchProgressListener>,
     ) -> Result<ExecutionResult<FetchCheckpoint>, IngestError> {
-        use rand::Rng;
         use std::process::Stdio;
 
         if !container_runtime.has_image(&fetch.image) {
@@ -226,11 +238,7 @@ impl FetchService {
             fetched_bytes: 0,
         });
 
-        let run_id: String = rand::thread_rng()
-            .sample_iter(&rand::distributions::Alphanumeric)
-            .take(10)
-            .map(char::from)
-            .collect();
+        let run_id = new_run_id();
 
         let out_dir = workspace_layout
             .run_info_dir
@@ -384,108 +392,162 @@ impl FetchService {
         target: &Path,
         listener: &dyn FetchProgressListener,
     ) -> Result<ExecutionResult<FetchCheckpoint>, IngestError> {
-        match &fglob.order {
-            None => (),
-            Some(SourceOrdering::ByName) => (),
-            Some(ord) => panic!(
-                "Files glob source can only be ordered by name, found: {:?}",
-                ord
-            ),
+        if let Some((url, key_pattern)) = Self::split_s3_glob(&fglob.path)? {
+            return Self::fetch_s3_glob(
+                fglob,
+                &url,
+                &key_pattern,
+                old_checkpoint,
+                target,
+                listener,
+            );
         }
 
-        let event_time_source = match &fglob.event_time {
-            None => None,
-            Some(EventTimeSource::FromPath(fp)) => Some(fp),
-            Some(src) => panic!(
-                "Files glob source only supports deriving event time from file path, found: {:?}",
-                src
-            ),
-        };
+        if let Some((dir_url, name_pattern)) = Self::split_http_glob(&fglob.path)? {
+            return Self::fetch_http_glob(
+                fglob,
+                &dir_url,
+                &name_pattern,
+                old_checkpoint,
+                target,
+                listener,
+            );
+        }
 
-        let last_filename = match old_checkpoint {
-            Some(FetchCheckpoint {
-                last_filename: Some(ref name),
-                ..
-            }) => Some(name.clone()),
-            _ => None,
-        };
+        Self::validate_glob_order(fglob)?;
 
         let matched_paths = glob::glob(&fglob.path)
             .int_err()?
             .collect::<Result<Vec<_>, _>>()
             .int_err()?;
 
-        let mut matched_files: Vec<(String, PathBuf)> = matched_paths
-            .into_iter()
-            .filter(|p| p.is_file())
-            .map(|p| (p.file_name().unwrap().to_str().unwrap().to_owned(), p))
-            .filter(|(name, _)| {
-                if let Some(ref lfn) = last_filename {
-                    name > lfn
-                } else {
-                    true
-                }
-            })
-            .collect();
-
-        matched_files.sort_by(|a, b| b.0.cmp(&a.0));
+        let mut matched_files = Vec::new();
+        for path in matched_paths.into_iter().filter(|p| p.is_file()) {
+            matched_files.push(GlobMatch {
+                name: path.file_name().unwrap().to_string_lossy().into_owned(),
+                last_modified: Some(Self::file_mod_time(&path)?),
+                location: path.to_string_lossy().into_owned(),
+                event_time: None,
+            });
+        }
 
-        info!(pattern = fglob.path.as_str(), last_filename = ?last_filename, matches = ?matched_files, "Matched the glob pattern");
+        info!(pattern = fglob.path.as_str(), matches = ?matched_files, "Matched the glob pattern");
 
-        if matched_files.is_empty() {
-            return if let Some(cp) = old_checkpoint {
-                Ok(ExecutionResult {
-                    was_up_to_date: true,
-                    checkpoint: cp,
-                })
-            } else {
-                Err(IngestError::not_found(&fglob.path, None))
+        let (next, has_more) =
+            match Self::select_next_glob_match(fglob, matched_files, old_checkpoint.as_ref())? {
+                Some(next) => next,
+                None => return Self::glob_exhausted(fglob, old_checkpoint),
             };
+
+        let exec_res = Self::fetch_file(Path::new(&next.location), None, None, target, listener)?;
+
+        Ok(ExecutionResult {
+            was_up_to_date: exec_res.was_up_to_date,
+            checkpoint: FetchCheckpoint {
+                last_fetched: exec_res.checkpoint.last_fetched,
+                source_event_time: next.event_time,
+                last_filename: Some(next.name),
+                has_more,
+                etag: None,
+                last_modified: None,
+            },
+        })
+    }
+
+    fn validate_glob_order(fglob: &FetchStepFilesGlob) -> Result<(), IngestError> {
+        if fglob.order == Some(SourceOrdering::ByEventTime) && fglob.event_time.is_none() {
+            return Err(InvalidSourceError::new(
+                "Files glob source ordered by event time must specify the event time source",
+            )
+            .into());
         }
+        Ok(())
+    }
 
-        let (first_filename, first_path) = matched_files.pop().unwrap();
-        let res = Self::fetch_file(
-            &first_path,
-            fglob.event_time.as_ref(),
-            None,
-            target,
-            listener,
-        );
+    /// Picks the next file to fetch among the matched ones, returning it along with
+    /// the flag indicating whether more files are left to fetch after it
+    fn select_next_glob_match(
+        fglob: &FetchStepFilesGlob,
+        mut matched_files: Vec<GlobMatch>,
+        old_checkpoint: Option<&FetchCheckpoint>,
+    ) -> Result<Option<(GlobMatch, bool)>, IngestError> {
+        let order = fglob.order.clone().unwrap_or(SourceOrdering::ByName);
+        let last_filename = old_checkpoint.and_then(|cp| cp.last_filename.clone());
+
+        // Files are sorted in the reverse order, so that the next file to fetch is last
+        match order {
+            SourceOrdering::ByName => {
+                matched_files.retain(|f| match &last_filename {
+                    Some(lfn) => f.name > *lfn,
+                    None => true,
+                });
+                matched_files.sort_by(|a, b| b.name.cmp(&a.name));
+            }
+            SourceOrdering::ByEventTime => {
+                // Event time of every file is only needed for ordering, otherwise
+                // it's extracted just for the file that is being fetched
+                for f in matched_files.iter_mut() {
+                    f.event_time = Self::glob_event_time(f, fglob.event_time.as_ref())?;
+                }
 
-        let event_time = match event_time_source {
-            None => None,
-            Some(src) => Some(Self::extract_event_time(&first_filename, &src)?),
-        };
+                let last_event_time = old_checkpoint.and_then(|cp| cp.source_event_time);
 
-        match res {
-            Ok(exec_res) => Ok(ExecutionResult {
-                was_up_to_date: exec_res.was_up_to_date,
-                checkpoint: FetchCheckpoint {
-                    last_fetched: exec_res.checkpoint.last_fetched,
-                    source_event_time: event_time,
-                    last_filename: Some(first_filename),
-                    has_more: !matched_files.is_empty(),
-                    etag: None,
-                    last_modified: None,
-                },
-            }),
-            Err(e) => Err(e),
+                // File name breaks the ties between files with the same event time
+                if let Some(lfn) = &last_filename {
+                    matched_files.retain(|f| (f.event_time, &f.name) > (last_event_time, lfn));
+                }
+                matched_files.sort_by(|a, b| (b.event_time, &b.name).cmp(&(a.event_time, &a.name)));
+            }
+        }
+
+        debug!(?order, last_filename = ?last_filename, remaining = ?matched_files, "Ordered glob matches");
+
+        match matched_files.pop() {
+            None => Ok(None),
+            Some(mut next) => {
+                if order == SourceOrdering::ByName {
+                    next.event_time = Self::glob_event_time(&next, fglob.event_time.as_ref())?;
+                }
+                Ok(Some((next, !matched_files.is_empty())))
+            }
         }
     }
 
-    // TODO: Validate event_time_source
-    // TODO: Support event time from ctime/modtime
-    fn fetch_file(
-        path: &Path,
-        _event_time_source: Option<&EventTimeSource>,
+    fn glob_exhausted(
+        fglob: &FetchStepFilesGlob,
         old_checkpoint: Option<FetchCheckpoint>,
-        target_path: &Path,
-        listener: &dyn FetchProgressListener,
     ) -> Result<ExecutionResult<FetchCheckpoint>, IngestError> {
-        use fs_extra::file::*;
+        if let Some(cp) = old_checkpoint {
+            Ok(ExecutionResult {
+                was_up_to_date: true,
+                checkpoint: cp,
+            })
+        } else {
+            Err(IngestError::not_found(&fglob.path, None))
+        }
+    }
 
-        info!(path = ?path, "Ingesting file");
+    fn glob_event_time(
+        file: &GlobMatch,
+        event_time_source: Option<&EventTimeSource>,
+    ) -> Result<Option<DateTime<Utc>>, IngestError> {
+        match event_time_source {
+            None => Ok(None),
+            Some(EventTimeSource::FromPath(src)) => {
+                Ok(Some(Self::extract_event_time(&file.name, src)?))
+            }
+            Some(EventTimeSource::FromMetadata) => match file.last_modified {
+                Some(t) => Ok(Some(t)),
+                None => Err(EventTimeSourceError::failed_extract(format!(
+                    "Modification time of {} is unknown",
+                    file.location
+                ))
+                .into()),
+            },
+        }
+    }
 
+    fn file_mod_time(path: &Path) -> Result<DateTime<Utc>, IngestError> {
         let meta = std::fs::metadata(path).map_err(|e| match e.kind() {
             std::io::ErrorKind::NotFound => {
                 IngestError::not_found(path.as_os_str().to_string_lossy(), Some(e.into()))
@@ -493,11 +555,22 @@ impl FetchService {
             _ => e.int_err().into(),
         })?;
 
-        let mod_time: DateTime<Utc> = meta
-            .modified()
-            .map(|t| -> DateTime<Utc> { t.into() })
-            .expect("File modification time is not available on this platform")
-            .round_subsecs(3);
+        let mod_time: DateTime<Utc> = meta.modified().int_err()?.into();
+        Ok(mod_time.round_subsecs(3))
+    }
+
+    fn fetch_file(
+        path: &Path,
+        event_time_source: Option<&EventTimeSource>,
+        old_checkpoint: Option<FetchCheckpoint>,
+        target_path: &Path,
+        listener: &dyn FetchProgressListener,
+    ) -> Result<ExecutionResult<FetchCheckpoint>, IngestError> {
+        use fs_extra::file::*;
+
+        info!(path = ?path, "Ingesting file");
+
+        let mod_time = Self::file_mod_time(path)?;
 
         if let Some(cp) = old_checkpoint {
             if cp.last_modified == Some(mod_time) {
@@ -508,11 +581,18 @@ impl FetchService {
             }
         }
 
+        let source_event_time = match event_time_source {
+            None | Some(EventTimeSource::FromMetadata) => mod_time,
+            Some(EventTimeSource::FromPath(src)) => {
+                Self::extract_event_time(&path.to_string_lossy(), src)?
+            }
+        };
+
         let new_checkpoint = FetchCheckpoint {
             last_fetched: Utc::now(),
             last_modified: Some(mod_time),
             etag: None,
-            source_event_time: Some(mod_time),
+            source_event_time: Some(source_event_time),
             last_filename: None,
             has_more: false,
         };
@@ -542,11 +622,19 @@ impl FetchService {
     fn fetch_http(
         url: &Url,
         headers: &Vec<RequestHeader>,
-        _event_time_source: Option<&EventTimeSource>,
+        event_time_source: Option<&EventTimeSource>,
         old_checkpoint: Option<FetchCheckpoint>,
         target_path: &Path,
         listener: &dyn FetchProgressListener,
     ) -> Result<ExecutionResult<FetchCheckpoint>, IngestError> {
+        // Fail early if event time cannot be extracted
+        let path_event_time = match event_time_source {
+            Some(EventTimeSource::FromPath(src)) => {
+                Some(Self::extract_event_time(url.path(), src)?)
+            }
+            _ => None,
+        };
+
         let target_path_tmp = target_path.with_extension("tmp");
 
         let mut h = curl::easy::Easy::new();
@@ -572,19 +660,23 @@ impl FetchService {
         }
         h.http_headers(header_list)?;
 
-        let mut last_modified: Option<DateTime<Utc>> = None;
+        let mut last_modified_header: Option<String> = None;
         let mut etag: Option<String> = None;
         {
             let mut target_file = std::fs::File::create(&target_path_tmp).int_err()?;
+            let mut write_error: Option<std::io::Error> = None;
 
             let mut transfer = h.transfer();
 
             transfer.header_function(|header| {
-                let s = std::str::from_utf8(header).unwrap();
-                if let Some((name, val)) = Self::split_header(s) {
+                // Headers that are not valid UTF-8 are of no interest to us
+                if let Some((name, val)) = std::str::from_utf8(header)
+                    .ok()
+                    .and_then(Self::split_header)
+                {
                     match &name.to_lowercase()[..] {
                         "last-modified" => {
-                            last_modified = Some(Self::parse_http_date_time(val));
+                            last_modified_header = Some(val.to_owned());
                         }
                         "etag" => {
                             etag = Some(val.to_owned());
@@ -595,9 +687,13 @@ impl FetchService {
                 true
             })?;
 
-            transfer.write_function(|data| {
-                let written = target_file.write(data).unwrap();
-                Ok(written)
+            transfer.write_function(|data| match target_file.write_all(data) {
+                Ok(_) => Ok(data.len()),
+                Err(e) => {
+                    // Returning a short count aborts the transfer
+                    write_error = Some(e);
+                    Ok(0)
+                }
             })?;
 
             transfer.progress_function(|f_total, f_downloaded, _, _| {
@@ -612,56 +708,571 @@ impl FetchService {
                 true
             })?;
 
-            transfer.perform().map_err(|e| {
-                std::fs::remove_file(&target_path_tmp).unwrap();
-                match e.code() {
-                    curl_sys::CURLE_COULDNT_RESOLVE_HOST => {
+            let res = transfer.perform();
+            drop(transfer);
+
+            if let Err(e) = res {
+                let _ = std::fs::remove_file(&target_path_tmp);
+                return Err(match (e.code(), write_error) {
+                    (_, Some(write_error)) => write_error.int_err().into(),
+                    (curl_sys::CURLE_COULDNT_RESOLVE_HOST, _) => {
                         IngestError::unreachable(url.as_str(), Some(e.into()))
                     }
-                    curl_sys::CURLE_COULDNT_CONNECT => {
+                    (curl_sys::CURLE_COULDNT_CONNECT, _) => {
                         IngestError::unreachable(url.as_str(), Some(e.into()))
                     }
                     _ => e.int_err().into(),
-                }
-            })?;
+                });
+            }
         }
 
-        match h.response_code()? {
+        let response_code = h.response_code()?;
+        if response_code != 200 {
+            std::fs::remove_file(&target_path_tmp).int_err()?;
+        }
+
+        match response_code {
             200 => {
-                std::fs::rename(target_path_tmp, target_path).unwrap();
+                // Unparseable date is not fatal for caching, but is when it's the event time
+                let last_modified = match last_modified_header
+                    .as_deref()
+                    .map(Self::parse_http_date_time)
+                {
+                    None => None,
+                    Some(Ok(dt)) => Some(dt),
+                    Some(Err(error)) => {
+                        warn!(?last_modified_header, %error, "Malformed Last-Modified header");
+                        None
+                    }
+                };
+
+                let source_event_time = match event_time_source {
+                    None => last_modified,
+                    Some(EventTimeSource::FromMetadata) => match last_modified {
+                        Some(t) => Some(t),
+                        None => {
+                            std::fs::remove_file(&target_path_tmp).int_err()?;
+                            return Err(EventTimeSourceError::failed_extract(format!(
+                                "Response from {} has no valid Last-Modified header",
+                                url
+                            ))
+                            .into());
+                        }
+                    },
+                    Some(EventTimeSource::FromPath(_)) => path_event_time,
+                };
+
+                std::fs::rename(target_path_tmp, target_path).int_err()?;
                 Ok(ExecutionResult {
                     was_up_to_date: false,
                     checkpoint: FetchCheckpoint {
                         last_fetched: Utc::now(),
                         last_modified: last_modified,
                         etag: etag,
-                        source_event_time: last_modified,
+                        source_event_time,
                         last_filename: None,
                         has_more: false,
                     },
                 })
             }
-            304 => {
-                std::fs::remove_file(&target_path_tmp).unwrap();
-                Ok(ExecutionResult {
+            304 => match old_checkpoint {
+                Some(cp) => Ok(ExecutionResult {
                     was_up_to_date: true,
-                    checkpoint: old_checkpoint.unwrap(),
-                })
+                    checkpoint: cp,
+                }),
+                None => Err(IngestError::unreachable(
+                    url.as_str(),
+                    Some(HttpStatusError::new(304).into()),
+                )),
+            },
+            404 => Err(IngestError::not_found(url.as_str(), None)),
+            code => Err(IngestError::unreachable(
+                url.as_str(),
+                Some(HttpStatusError::new(code).into()),
+            )),
+        }
+    }
+
+    fn fetch_http_glob(
+        fglob: &FetchStepFilesGlob,
+        dir_url: &Url,
+        name_pattern: &str,
+        old_checkpoint: Option<FetchCheckpoint>,
+        target_path: &Path,
+        listener: &dyn FetchProgressListener,
+    ) -> Result<ExecutionResult<FetchCheckpoint>, IngestError> {
+        Self::validate_glob_order(fglob)?;
+
+        let pattern = glob::Pattern::new(name_pattern).map_err(|e| {
+            InvalidSourceError::new(format!("Invalid glob pattern {}: {}", name_pattern, e))
+        })?;
+
+        // Index is only needed temporarily, so we download it next to the target
+        let index_path = target_path.with_extension("index");
+        Self::fetch_http(
+            dir_url,
+            &Vec::new(),
+            None,
+            None,
+            &index_path,
+            &NullFetchProgressListener,
+        )?;
+        let index = std::fs::read_to_string(&index_path);
+        std::fs::remove_file(&index_path).int_err()?;
+        let index = index.int_err()?;
+
+        let mut matched_files = Vec::new();
+        for (name, file_url) in Self::parse_http_index(dir_url, &index) {
+            if !pattern.matches(&name) {
+                continue;
             }
-            404 => {
-                std::fs::remove_file(&target_path_tmp).unwrap();
-                Err(IngestError::not_found(url.as_str(), None))
+
+            // Listings rarely contain reliable modification times, so we only
+            // request them when they are needed
+            let last_modified = match fglob.event_time {
+                Some(EventTimeSource::FromMetadata) => Self::http_last_modified(&file_url)?,
+                _ => None,
+            };
+
+            matched_files.push(GlobMatch {
+                name,
+                location: file_url.to_string(),
+                last_modified,
+                event_time: None,
+            });
+        }
+
+        info!(pattern = fglob.path.as_str(), matches = ?matched_files, "Matched the glob pattern");
+
+        let (next, has_more) =
+            match Self::select_next_glob_match(fglob, matched_files, old_checkpoint.as_ref())? {
+                Some(next) => next,
+                None => return Self::glob_exhausted(fglob, old_checkpoint),
+            };
+
+        Self::fetch_http(
+            &Url::parse(&next.location).int_err()?,
+            &Vec::new(),
+            None,
+            None,
+            target_path,
+            listener,
+        )?;
+
+        Ok(ExecutionResult {
+            was_up_to_date: false,
+            checkpoint: FetchCheckpoint {
+                last_fetched: Utc::now(),
+                source_event_time: next.event_time,
+                last_filename: Some(next.name),
+                has_more,
+                etag: None,
+                last_modified: None,
+            },
+        })
+    }
+
+    /// Splits glob path like `https://example.com/data/*.csv` into the URL of the
+    /// directory and the pattern of the file names. Returns `None` for paths that
+    /// are not HTTP URLs.
+    fn split_http_glob(path: &str) -> Result<Option<(Url, String)>, IngestError> {
+        if !path.starts_with("http://") && !path.starts_with("https://") {
+            return Ok(None);
+        }
+
+        let invalid = || InvalidSourceError::new(format!("Invalid HTTP glob path: {}", path));
+
+        // Wildcards are only supported in the file name
+        let (dir, name_pattern) = path.rsplit_once('/').unwrap();
+        if name_pattern.is_empty() || dir.contains(['*', '?', '[']) {
+            return Err(invalid().into());
+        }
+
+        let dir_url = Url::parse(&format!("{}/", dir)).map_err(|_| invalid())?;
+        Ok(Some((dir_url, name_pattern.to_owned())))
+    }
+
+    /// Extracts the links to files located directly in the directory from the
+    /// HTML index page, as served by most web servers
+    fn parse_http_index(dir_url: &Url, index: &str) -> Vec<(String, Url)> {
+        let re_href = regex::Regex::new(r#"(?i)href\s*=\s*["']([^"']+)["']"#).unwrap();
+
+        let mut files = std::collections::BTreeMap::new();
+
+        for capture in re_href.captures_iter(index) {
+            let file_url = match dir_url.join(&capture[1]) {
+                Ok(url) => url,
+                Err(_) => continue,
+            };
+
+            // Skip links to parent and nested directories, sorting parameters etc.
+            if let Some(name) = file_url.as_str().strip_prefix(dir_url.as_str()) {
+                if !name.is_empty() && !name.contains(['/', '?', '#']) {
+                    files.insert(name.to_owned(), file_url.clone());
+                }
             }
-            code => {
-                std::fs::remove_file(&target_path_tmp).unwrap();
-                Err(IngestError::unreachable(
-                    url.as_str(),
-                    Some(HttpStatusError::new(code).into()),
-                ))
+        }
+
+        files.into_iter().collect()
+    }
+
+    fn http_last_modified(url: &Url) -> Result<Option<DateTime<Utc>>, IngestError> {
+        let mut h = curl::easy::Easy::new();
+        h.url(url.as_str())?;
+        h.nobody(true)?;
+        h.connect_timeout(Duration::from_secs(30))?;
+        h.follow_location(true)?;
+
+        let mut last_modified = None;
+        {
+            let mut transfer = h.transfer();
+
+            transfer.header_function(|header| {
+                if let Some((name, val)) = std::str::from_utf8(header)
+                    .ok()
+                    .and_then(Self::split_header)
+                {
+                    if name.eq_ignore_ascii_case("last-modified") {
+                        last_modified = Self::parse_http_date_time(val).ok();
+                    }
+                }
+                true
+            })?;
+
+            transfer
+                .perform()
+                .map_err(|e| IngestError::unreachable(url.as_str(), Some(e.into())))?;
+        }
+
+        match h.response_code()? {
+            200 => Ok(last_modified),
+            404 => Err(IngestError::not_found(url.as_str(), None)),
+            code => Err(IngestError::unreachable(
+                url.as_str(),
+                Some(HttpStatusError::new(code).into()),
+            )),
+        }
+    }
+
+    fn fetch_s3(
+        url: &Url,
+        event_time_source: Option<&EventTimeSource>,
+        old_checkpoint: Option<FetchCheckpoint>,
+        target_path: &Path,
+        listener: &dyn FetchProgressListener,
+    ) -> Result<ExecutionResult<FetchCheckpoint>, IngestError> {
+        let (client, bucket, key) = Self::s3_client(url);
+
+        if key.is_empty() || key.ends_with('/') {
+            return Err(InvalidSourceError::new(format!(
+                "URL {} does not point to an object, use files glob source to fetch objects under a prefix",
+                url
+            ))
+            .into());
+        }
+
+        // Fail early if event time cannot be extracted
+        let path_event_time = match event_time_source {
+            Some(EventTimeSource::FromPath(src)) => Some(Self::extract_event_time(&key, src)?),
+            _ => None,
+        };
+
+        let meta = match Self::fetch_s3_object(
+            &client,
+            &bucket,
+            &key,
+            old_checkpoint.as_ref(),
+            target_path,
+            listener,
+        )? {
+            Some(meta) => meta,
+            None => {
+                return match old_checkpoint {
+                    Some(cp) => Ok(ExecutionResult {
+                        was_up_to_date: true,
+                        checkpoint: cp,
+                    }),
+                    None => Err(IngestError::unreachable(
+                        url.as_str(),
+                        Some(HttpStatusError::new(304).into()),
+                    )),
+                }
+            }
+        };
+
+        let source_event_time = match event_time_source {
+            None => meta.last_modified,
+            Some(EventTimeSource::FromMetadata) => match meta.last_modified {
+                Some(t) => Some(t),
+                None => {
+                    std::fs::remove_file(target_path).int_err()?;
+                    return Err(EventTimeSourceError::failed_extract(format!(
+                        "Object {} has no valid Last-Modified header",
+                        url
+                    ))
+                    .into());
+                }
+            },
+            Some(EventTimeSource::FromPath(_)) => path_event_time,
+        };
+
+        Ok(ExecutionResult {
+            was_up_to_date: false,
+            checkpoint: FetchCheckpoint {
+                last_fetched: Utc::now(),
+                last_modified: meta.last_modified,
+                etag: meta.etag,
+                source_event_time,
+                last_filename: None,
+                has_more: false,
+            },
+        })
+    }
+
+    fn fetch_s3_glob(
+        fglob: &FetchStepFilesGlob,
+        url: &Url,
+        key_pattern: &str,
+        old_checkpoint: Option<FetchCheckpoint>,
+        target_path: &Path,
+        listener: &dyn FetchProgressListener,
+    ) -> Result<ExecutionResult<FetchCheckpoint>, IngestError> {
+        Self::validate_glob_order(fglob)?;
+
+        let pattern = glob::Pattern::new(key_pattern).map_err(|e| {
+            InvalidSourceError::new(format!("Invalid glob pattern {}: {}", key_pattern, e))
+        })?;
+
+        // Wildcards should not cross the "directory" boundaries, same as with local files
+        let match_options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+
+        // Only keys under the literal part of the pattern need to be listed
+        let prefix = match key_pattern.find(['*', '?', '[']) {
+            Some(i) => &key_pattern[..i],
+            None => key_pattern,
+        };
+
+        let (client, bucket, _) = Self::s3_client(url);
+
+        let matched_files: Vec<_> = Self::list_s3_objects(&client, &bucket, prefix, &fglob.path)?
+            .into_iter()
+            .filter(|(key, _)| pattern.matches_with(key, match_options))
+            .map(|(key, last_modified)| GlobMatch {
+                name: key.rsplit('/').next().unwrap().to_owned(),
+                location: key,
+                last_modified: Some(last_modified),
+                event_time: None,
+            })
+            .collect();
+
+        info!(pattern = fglob.path.as_str(), matches = ?matched_files, "Matched the glob pattern");
+
+        let (next, has_more) =
+            match Self::select_next_glob_match(fglob, matched_files, old_checkpoint.as_ref())? {
+                Some(next) => next,
+                None => return Self::glob_exhausted(fglob, old_checkpoint),
+            };
+
+        Self::fetch_s3_object(
+            &client,
+            &bucket,
+            &next.location,
+            None,
+            target_path,
+            listener,
+        )?;
+
+        Ok(ExecutionResult {
+            was_up_to_date: false,
+            checkpoint: FetchCheckpoint {
+                last_fetched: Utc::now(),
+                source_event_time: next.event_time,
+                last_filename: Some(next.name),
+                has_more,
+                etag: None,
+                last_modified: None,
+            },
+        })
+    }
+
+    /// Splits glob path like `s3://bucket/prefix/*.csv` into the URL of the bucket
+    /// and the pattern of the keys. Returns `None` for paths that are not S3 URLs.
+    fn split_s3_glob(path: &str) -> Result<Option<(Url, String)>, IngestError> {
+        let (scheme, rest) = match path.split_once("://") {
+            Some((scheme @ ("s3" | "s3+http" | "s3+https"), rest)) => (scheme, rest),
+            _ => return Ok(None),
+        };
+
+        // Custom endpoints specify the bucket in the path
+        let num_base_segments = if scheme == "s3" { 1 } else { 2 };
+
+        let mut segments = rest.splitn(num_base_segments + 1, '/');
+        let base: Vec<_> = segments.by_ref().take(num_base_segments).collect();
+        let key_pattern = segments.next().unwrap_or_default();
+
+        let url = Url::parse(&format!("{}://{}/", scheme, base.join("/")));
+
+        match url {
+            Ok(url) if base.len() == num_base_segments && !key_pattern.is_empty() => {
+                Ok(Some((url, key_pattern.to_owned())))
             }
+            _ => Err(InvalidSourceError::new(format!("Invalid S3 glob path: {}", path)).into()),
         }
     }
 
+    fn s3_client(url: &Url) -> (S3Client, String, String) {
+        let (endpoint, bucket, key) = ObjectRepositoryS3::<(), 0>::split_url(url);
+
+        let region = match endpoint {
+            None => Region::default(),
+            Some(endpoint) => Region::Custom {
+                name: "custom".to_owned(),
+                endpoint: endpoint,
+            },
+        };
+
+        (S3Client::new(region), bucket, key)
+    }
+
+    fn list_s3_objects(
+        client: &S3Client,
+        bucket: &str,
+        prefix: &str,
+        location: &str,
+    ) -> Result<Vec<(String, DateTime<Utc>)>, IngestError> {
+        use rusoto_s3::{ListObjectsV2Error, ListObjectsV2Request, S3};
+
+        debug!(%bucket, %prefix, "Listing objects");
+
+        let rt = tokio::runtime::Handle::current();
+
+        let mut objects = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let resp = match rt.block_on(client.list_objects_v2(ListObjectsV2Request {
+                bucket: bucket.to_owned(),
+                prefix: Some(prefix.to_owned()).filter(|p| !p.is_empty()),
+                continuation_token: continuation_token.take(),
+                ..ListObjectsV2Request::default()
+            })) {
+                Ok(resp) => resp,
+                Err(RusotoError::Service(ListObjectsV2Error::NoSuchBucket(_))) => {
+                    return Err(IngestError::not_found(location, None))
+                }
+                Err(e @ (RusotoError::HttpDispatch(_) | RusotoError::Credentials(_))) => {
+                    return Err(IngestError::unreachable(location, Some(e.into())))
+                }
+                Err(e) => return Err(e.int_err().into()),
+            };
+
+            for obj in resp.contents.unwrap_or_default() {
+                if let (Some(key), Some(last_modified)) = (obj.key, obj.last_modified) {
+                    let last_modified = DateTime::parse_from_rfc3339(&last_modified).int_err()?;
+                    objects.push((key, last_modified.into()));
+                }
+            }
+
+            if resp.is_truncated == Some(true) && resp.next_continuation_token.is_some() {
+                continuation_token = resp.next_continuation_token;
+            } else {
+                break;
+            }
+        }
+
+        Ok(objects)
+    }
+
+    /// Downloads the object into the target file. When checkpoint is provided the
+    /// request is conditional and `None` is returned if the object was not modified.
+    fn fetch_s3_object(
+        client: &S3Client,
+        bucket: &str,
+        key: &str,
+        old_checkpoint: Option<&FetchCheckpoint>,
+        target_path: &Path,
+        listener: &dyn FetchProgressListener,
+    ) -> Result<Option<S3ObjectMeta>, IngestError> {
+        use futures::TryStreamExt;
+        use rusoto_s3::{GetObjectError, GetObjectRequest, S3};
+
+        let location = format!("s3://{}/{}", bucket, key);
+        info!(%location, "Fetching object");
+
+        let rt = tokio::runtime::Handle::current();
+
+        let resp = match rt.block_on(
+            client.get_object(GetObjectRequest {
+                bucket: bucket.to_owned(),
+                key: key.to_owned(),
+                if_none_match: old_checkpoint.and_then(|cp| cp.etag.clone()),
+                if_modified_since: old_checkpoint
+                    .filter(|cp| cp.etag.is_none())
+                    .and_then(|cp| cp.last_modified)
+                    .map(|t| t.to_rfc2822()),
+                ..GetObjectRequest::default()
+            }),
+        ) {
+            Ok(resp) => resp,
+            Err(RusotoError::Unknown(resp)) if resp.status == 304 => return Ok(None),
+            Err(RusotoError::Service(GetObjectError::NoSuchKey(_))) => {
+                return Err(IngestError::not_found(location, None))
+            }
+            // Missing bucket is reported with a bare status code
+            Err(RusotoError::Unknown(resp)) if resp.status == 404 => {
+                return Err(IngestError::not_found(location, None))
+            }
+            Err(e @ (RusotoError::HttpDispatch(_) | RusotoError::Credentials(_))) => {
+                return Err(IngestError::unreachable(location, Some(e.into())))
+            }
+            Err(e) => return Err(e.int_err().into()),
+        };
+
+        let meta = S3ObjectMeta {
+            etag: resp.e_tag,
+            last_modified: resp
+                .last_modified
+                .as_deref()
+                .and_then(|val| Self::parse_http_date_time(val).ok()),
+        };
+
+        let total_bytes = resp.content_length.unwrap_or(0).max(0) as u64;
+        let target_path_tmp = target_path.with_extension("tmp");
+
+        let res = rt.block_on(async {
+            let mut target_file = std::fs::File::create(&target_path_tmp).int_err()?;
+            let mut fetched_bytes = 0;
+
+            if let Some(mut body) = resp.body {
+                while let Some(chunk) = body
+                    .try_next()
+                    .await
+                    .map_err(|e| IngestError::unreachable(&location, Some(e.into())))?
+                {
+                    target_file.write_all(&chunk).int_err()?;
+                    fetched_bytes += chunk.len() as u64;
+                    listener.on_progress(&FetchProgress {
+                        total_bytes: std::cmp::max(total_bytes, fetched_bytes),
+                        fetched_bytes,
+                    });
+                }
+            }
+
+            Ok::<_, IngestError>(())
+        });
+
+        if let Err(e) = res {
+            let _ = std::fs::remove_file(&target_path_tmp);
+            return Err(e);
+        }
+
+        std::fs::rename(target_path_tmp, target_path).int_err()?;
+        Ok(Some(meta))
+    }
+
     // TODO: not implementing caching as some FTP servers throw errors at us
     // when we request filetime :(
     fn fetch_ftp(
@@ -738,10 +1349,8 @@ impl FetchService {
         }
     }
 
-    fn parse_http_date_time(val: &str) -> DateTime<Utc> {
-        DateTime::parse_from_rfc2822(val)
-            .unwrap_or_else(|e| panic!("Failed to parse Last-Modified header {}: {}", val, e))
-            .into()
+    fn parse_http_date_time(val: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+        DateTime::parse_from_rfc2822(val).map(Into::into)
     }
 
     fn extract_event_time(
@@ -783,6 +1392,21 @@ impl FetchService {
     }
 }
 
+struct S3ObjectMeta {
+    etag: Option<String>,
+    last_modified: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+struct GlobMatch {
+    /// File name used for ordering and checkpointing
+    name: String,
+    /// Local file path, object key, or URL
+    location: String,
+    last_modified: Option<DateTime<Utc>>,
+    event_time: Option<DateTime<Utc>>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
@@ -117,8 +117,8 @@ impl IngestTask {
             vocab: vocab.unwrap_or_default(),
             listener,
             checkpointing_executor: CheckpointingExecutor::new(),
-            fetch_service: FetchService::new(container_runtime, workspace_layout),
-            prep_service: PrepService::new(),
+            fetch_service: FetchService::new(container_runtime, workspace_layout.clone()),
+            prep_service: PrepService::new(workspace_layout),
             read_service: ReadService::new(engine_provisioner),
         })
     }
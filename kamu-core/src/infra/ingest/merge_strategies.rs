@@ -0,0 +1,647 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::domain::*;
+use crate::infra::engine::into_engine_error;
+use crate::infra::utils::datafusion_hacks::ListingTableOfFiles;
+
+use datafusion::arrow::array::{new_null_array, ArrayRef, StringArray};
+use datafusion::arrow::compute::{cast, concat_batches};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::prelude::SessionContext;
+use opendatafabric::*;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::Arc;
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Merges newly read data with the history of the dataset.
+///
+/// Implementations receive a table over the previous data (with system
+/// columns) and the new data (without system columns) and return records that
+/// should be appended to the dataset, also without system columns. Previous
+/// data is queried with DataFusion so only the columns a strategy needs are
+/// read and it never has to fit into memory.
+#[async_trait::async_trait]
+pub trait DataMerger: Send + Sync {
+    async fn merge(
+        &self,
+        prev: Option<Arc<dyn TableProvider>>,
+        new: Vec<RecordBatch>,
+    ) -> Result<Vec<RecordBatch>, EngineError>;
+
+    /// Whether merging requires reading previous data of the dataset
+    fn needs_prev_data(&self) -> bool {
+        true
+    }
+}
+
+/// Instantiates merger corresponding to the specified strategy
+pub fn get_data_merger(strategy: &MergeStrategy, vocab: &DatasetVocabulary) -> Box<dyn DataMerger> {
+    match strategy {
+        MergeStrategy::Append => Box::new(AppendMerger),
+        MergeStrategy::Ledger(ledger) => {
+            Box::new(LedgerMerger::new(ledger.primary_key.clone(), vocab))
+        }
+        MergeStrategy::Snapshot(snapshot) => Box::new(SnapshotMerger::new(snapshot, vocab)),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Append
+///////////////////////////////////////////////////////////////////////////////
+
+/// Appends all new records as they are
+pub struct AppendMerger;
+
+#[async_trait::async_trait]
+impl DataMerger for AppendMerger {
+    async fn merge(
+        &self,
+        _prev: Option<Arc<dyn TableProvider>>,
+        new: Vec<RecordBatch>,
+    ) -> Result<Vec<RecordBatch>, EngineError> {
+        Ok(new)
+    }
+
+    fn needs_prev_data(&self) -> bool {
+        false
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Ledger
+///////////////////////////////////////////////////////////////////////////////
+
+/// Appends only records with primary keys that were not seen before.
+///
+/// Records with a null primary key component never match previous records.
+pub struct LedgerMerger {
+    primary_key: Vec<String>,
+    system_columns: Vec<String>,
+}
+
+impl LedgerMerger {
+    pub fn new(primary_key: Vec<String>, vocab: &DatasetVocabulary) -> Self {
+        Self {
+            primary_key,
+            system_columns: system_columns(vocab),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl DataMerger for LedgerMerger {
+    async fn merge(
+        &self,
+        prev: Option<Arc<dyn TableProvider>>,
+        new: Vec<RecordBatch>,
+    ) -> Result<Vec<RecordBatch>, EngineError> {
+        let new = match concat(new)? {
+            None => return Ok(Vec::new()),
+            Some(new) => new,
+        };
+
+        let prev_schema = prev.as_ref().map(|p| p.schema());
+        let out_schema = union_schema(
+            &new.schema(),
+            prev_schema.as_ref(),
+            &self.system_columns,
+            None,
+        );
+
+        let prev = match prev {
+            None => return non_empty(vec![align_to_schema(&new, &out_schema)?]),
+            Some(prev) => prev,
+        };
+
+        check_data_columns(&new.schema(), &self.primary_key)?;
+        check_prev_columns(&prev.schema(), self.primary_key.iter())?;
+
+        let ctx = merge_session(prev, &new)?;
+
+        // Anti-join that only reads key columns of the previous data
+        let sql = format!(
+            "SELECT {NEW}.* FROM {NEW} \
+             LEFT JOIN (SELECT {keys}, TRUE AS __present FROM {PREV}) AS __prev_keys \
+             ON {on} \
+             WHERE __prev_keys.__present IS NULL",
+            keys = column_list(&self.primary_key),
+            on = join_condition(&self.primary_key, NEW, "__prev_keys"),
+        );
+
+        let batches = query(&ctx, &sql).await?;
+
+        non_empty(
+            batches
+                .iter()
+                .map(|b| align_to_schema(b, &out_schema))
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Snapshot
+///////////////////////////////////////////////////////////////////////////////
+
+/// Converts periodic state snapshots into a stream of observations.
+///
+/// Every record in the output carries an observation column that is set to
+/// (unless overridden in the strategy):
+/// - `I` - when a primary key appears for the first time
+/// - `U` - when any of the compared columns has changed since previous snapshot
+/// - `D` - when a primary key disappears from the snapshot
+pub struct SnapshotMerger {
+    primary_key: Vec<String>,
+    compare_columns: Option<Vec<String>>,
+    observation_column: String,
+    obsv_added: String,
+    obsv_changed: String,
+    obsv_removed: String,
+    offset_column: String,
+    system_columns: Vec<String>,
+}
+
+impl SnapshotMerger {
+    pub const DEFAULT_OBSERVATION_COLUMN: &'static str = "observed";
+    pub const OBSERVATION_INSERT: &'static str = "I";
+    pub const OBSERVATION_UPDATE: &'static str = "U";
+    pub const OBSERVATION_DELETE: &'static str = "D";
+
+    pub fn new(strategy: &MergeStrategySnapshot, vocab: &DatasetVocabulary) -> Self {
+        Self {
+            primary_key: strategy.primary_key.clone(),
+            compare_columns: strategy.compare_columns.clone(),
+            observation_column: strategy
+                .observation_column
+                .clone()
+                .unwrap_or_else(|| Self::DEFAULT_OBSERVATION_COLUMN.to_owned()),
+            obsv_added: strategy
+                .obsv_added
+                .clone()
+                .unwrap_or_else(|| Self::OBSERVATION_INSERT.to_owned()),
+            obsv_changed: strategy
+                .obsv_changed
+                .clone()
+                .unwrap_or_else(|| Self::OBSERVATION_UPDATE.to_owned()),
+            obsv_removed: strategy
+                .obsv_removed
+                .clone()
+                .unwrap_or_else(|| Self::OBSERVATION_DELETE.to_owned()),
+            offset_column: vocab
+                .offset_column
+                .clone()
+                .unwrap_or_else(|| "offset".to_owned()),
+            system_columns: system_columns(vocab),
+        }
+    }
+
+    /// Data columns of the previous data, excluding system and observation columns
+    fn data_columns(&self, prev: &SchemaRef) -> Vec<String> {
+        prev.fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .filter(|c| !self.system_columns.contains(c) && *c != self.observation_column)
+            .collect()
+    }
+
+    fn compare_columns(&self, new: &SchemaRef, prev_data_columns: &[String]) -> Vec<String> {
+        match &self.compare_columns {
+            Some(cols) => cols.clone(),
+            None => new
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .chain(prev_data_columns.iter().cloned())
+                .filter(|c| {
+                    !self.primary_key.contains(c)
+                        && !self.system_columns.contains(c)
+                        && *c != self.observation_column
+                })
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Query for the latest observation of every primary key in the previous
+    /// data, skipping keys that were deleted
+    fn latest_state_query(&self, prev: &SchemaRef, data_columns: &[String]) -> String {
+        let observation = if prev.index_of(&self.observation_column).is_ok() {
+            format!("CAST({} AS VARCHAR)", quote(&self.observation_column))
+        } else {
+            "CAST(NULL AS VARCHAR)".to_owned()
+        };
+
+        format!(
+            "SELECT * FROM (\
+                SELECT {columns}, {observation} AS __observation, \
+                ROW_NUMBER() OVER (PARTITION BY {keys} ORDER BY {offset} DESC) AS __rank \
+                FROM {PREV}\
+             ) AS __ranked \
+             WHERE __rank = 1 AND (__observation IS NULL OR __observation <> {removed})",
+            columns = column_list(data_columns),
+            keys = column_list(&self.primary_key),
+            offset = quote(&self.offset_column),
+            removed = literal(&self.obsv_removed),
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl DataMerger for SnapshotMerger {
+    async fn merge(
+        &self,
+        prev: Option<Arc<dyn TableProvider>>,
+        new: Vec<RecordBatch>,
+    ) -> Result<Vec<RecordBatch>, EngineError> {
+        let new = concat(new)?;
+
+        if let Some(new) = &new {
+            if new.schema().index_of(&self.observation_column).is_ok() {
+                return Err(EngineError::invalid_query(
+                    format!(
+                        "Data already contains the observation column {}",
+                        self.observation_column
+                    ),
+                    Vec::new(),
+                ));
+            }
+        }
+
+        let (new, prev) = match (new, prev) {
+            (None, None) => return Ok(Vec::new()),
+            (Some(new), None) => {
+                // Everything is an insertion
+                let out_schema = self.output_schema(&new.schema(), None);
+                let observations = vec![self.obsv_added.as_str(); new.num_rows()];
+                return non_empty(vec![with_observation(
+                    &align_to_schema(&new, &self.without_observation(&out_schema))?,
+                    &out_schema,
+                    observations,
+                )?]);
+            }
+            (None, Some(prev)) => {
+                // Previous data defines the schema of retractions
+                let prev_schema = prev.schema();
+                let empty = RecordBatch::new_empty(Arc::new(Schema::new(
+                    self.data_columns(&prev_schema)
+                        .iter()
+                        .map(|c| prev_schema.field_with_name(c).unwrap().clone())
+                        .collect(),
+                )));
+                (empty, prev)
+            }
+            (Some(new), Some(prev)) => (new, prev),
+        };
+
+        let prev_schema = prev.schema();
+        check_data_columns(&new.schema(), &self.primary_key)?;
+        check_prev_columns(
+            &prev_schema,
+            self.primary_key.iter().chain([&self.offset_column]),
+        )?;
+
+        let new_schema = new.schema();
+        let data_columns = self.data_columns(&prev_schema);
+        let compare_columns = self.compare_columns(&new_schema, &data_columns);
+        let out_schema = self.output_schema(&new_schema, Some(&prev_schema));
+
+        let ctx = merge_session(prev, &new)?;
+        ctx.sql(&format!(
+            "CREATE VIEW __state AS {}",
+            self.latest_state_query(&prev_schema, &data_columns)
+        ))
+        .await
+        .map_err(into_engine_error)?;
+
+        // Values are compared by their string representation, same as when
+        // the compared column changed its type between snapshots
+        let changed = if compare_columns.is_empty() {
+            "FALSE".to_owned()
+        } else {
+            compare_columns
+                .iter()
+                .map(|c| {
+                    let new_value = if new_schema.index_of(c).is_ok() {
+                        format!("{NEW}.{}", quote(c))
+                    } else {
+                        "NULL".to_owned()
+                    };
+                    let prev_value = if data_columns.contains(c) {
+                        format!("__state.{}", quote(c))
+                    } else {
+                        "NULL".to_owned()
+                    };
+                    format!(
+                        "CAST({} AS VARCHAR) IS DISTINCT FROM CAST({} AS VARCHAR)",
+                        new_value, prev_value
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" OR ")
+        };
+
+        let upserts = query(
+            &ctx,
+            &format!(
+                "SELECT {NEW}.*, \
+                 CASE WHEN __state.__rank IS NULL THEN {added} ELSE {changed_obsv} END AS {obsv} \
+                 FROM {NEW} LEFT JOIN __state ON {on} \
+                 WHERE __state.__rank IS NULL OR {changed}",
+                added = literal(&self.obsv_added),
+                changed_obsv = literal(&self.obsv_changed),
+                obsv = quote(&self.observation_column),
+                on = join_condition(&self.primary_key, NEW, "__state"),
+            ),
+        )
+        .await?;
+
+        let deletions = query(
+            &ctx,
+            &format!(
+                "SELECT {columns}, {removed} AS {obsv} \
+                 FROM __state \
+                 LEFT JOIN (SELECT {keys}, TRUE AS __present FROM {NEW}) AS __new_keys \
+                 ON {on} \
+                 WHERE __new_keys.__present IS NULL",
+                columns = data_columns
+                    .iter()
+                    .map(|c| format!("__state.{}", quote(c)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                removed = literal(&self.obsv_removed),
+                obsv = quote(&self.observation_column),
+                keys = column_list(&self.primary_key),
+                on = join_condition(&self.primary_key, "__state", "__new_keys"),
+            ),
+        )
+        .await?;
+
+        non_empty(
+            upserts
+                .iter()
+                .chain(deletions.iter())
+                .map(|b| align_to_schema(b, &out_schema))
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    }
+}
+
+impl SnapshotMerger {
+    /// Data columns of new and previous data followed by the observation column
+    fn output_schema(&self, new: &SchemaRef, prev: Option<&SchemaRef>) -> SchemaRef {
+        let schema = union_schema(
+            new,
+            prev,
+            &self.system_columns,
+            Some(&self.observation_column),
+        );
+        let mut fields = schema.fields().clone();
+        fields.push(Field::new(&self.observation_column, DataType::Utf8, false));
+        Arc::new(Schema::new(fields))
+    }
+
+    fn without_observation(&self, schema: &SchemaRef) -> SchemaRef {
+        Arc::new(Schema::new(
+            schema
+                .fields()
+                .iter()
+                .filter(|f| *f.name() != self.observation_column)
+                .cloned()
+                .collect(),
+        ))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Helpers
+///////////////////////////////////////////////////////////////////////////////
+
+const NEW: &str = "__new";
+const PREV: &str = "__prev";
+
+/// Returns a table over all data files of the dataset in the specified directory
+pub async fn read_prev_data(
+    data_dir: &Path,
+) -> Result<Option<Arc<dyn TableProvider>>, EngineError> {
+    if !data_dir.exists() {
+        return Ok(None);
+    }
+
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(data_dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+
+    if paths.is_empty() {
+        return Ok(None);
+    }
+
+    paths.sort();
+
+    let ctx = SessionContext::new();
+    let table = ListingTableOfFiles::try_new_with_merged_schema(
+        &ctx.state(),
+        paths.iter().map(|p| p.to_string_lossy().into()).collect(),
+    )
+    .await
+    .map_err(into_engine_error)?;
+
+    Ok(Some(Arc::new(table)))
+}
+
+/// Creates a session where previous data is registered as [PREV] and new data as [NEW]
+fn merge_session(
+    prev: Arc<dyn TableProvider>,
+    new: &RecordBatch,
+) -> Result<SessionContext, EngineError> {
+    let ctx = SessionContext::new();
+
+    ctx.register_table(PREV, prev).map_err(into_engine_error)?;
+
+    let new_table =
+        MemTable::try_new(new.schema(), vec![vec![new.clone()]]).map_err(into_engine_error)?;
+    ctx.register_table(NEW, Arc::new(new_table))
+        .map_err(into_engine_error)?;
+
+    Ok(ctx)
+}
+
+async fn query(ctx: &SessionContext, query: &str) -> Result<Vec<RecordBatch>, EngineError> {
+    ctx.sql(query)
+        .await
+        .map_err(into_engine_error)?
+        .collect()
+        .await
+        .map_err(into_engine_error)
+}
+
+fn check_data_columns(schema: &SchemaRef, primary_key: &[String]) -> Result<(), EngineError> {
+    for name in primary_key {
+        if schema.index_of(name).is_err() {
+            return Err(EngineError::invalid_query(
+                format!("Data is missing primary key column {}", name),
+                Vec::new(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn check_prev_columns<'a>(
+    schema: &SchemaRef,
+    columns: impl Iterator<Item = &'a String>,
+) -> Result<(), EngineError> {
+    for name in columns {
+        if schema.index_of(name).is_err() {
+            return Err(EngineError::contract_error(
+                &format!("Previous data is missing column {}", name),
+                Vec::new(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn column_list(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|c| quote(c))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn join_condition(columns: &[String], left: &str, right: &str) -> String {
+    columns
+        .iter()
+        .map(|c| format!("{left}.{col} = {right}.{col}", col = quote(c)))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+fn non_empty(batches: Vec<RecordBatch>) -> Result<Vec<RecordBatch>, EngineError> {
+    Ok(batches.into_iter().filter(|b| b.num_rows() != 0).collect())
+}
+
+fn with_observation(
+    batch: &RecordBatch,
+    schema: &SchemaRef,
+    observations: Vec<&str>,
+) -> Result<RecordBatch, EngineError> {
+    let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+    columns.push(Arc::new(StringArray::from(observations)));
+    RecordBatch::try_new(schema.clone(), columns).map_err(internal)
+}
+
+fn system_columns(vocab: &DatasetVocabulary) -> Vec<String> {
+    vec![
+        vocab
+            .offset_column
+            .clone()
+            .unwrap_or_else(|| "offset".to_owned()),
+        vocab
+            .system_time_column
+            .clone()
+            .unwrap_or_else(|| "system_time".to_owned()),
+        vocab
+            .event_time_column
+            .clone()
+            .unwrap_or_else(|| "event_time".to_owned()),
+    ]
+}
+
+fn internal(e: impl std::error::Error + Send + Sync + 'static) -> EngineError {
+    EngineError::internal(e, Vec::new())
+}
+
+/// Concatenates batches that may have different (evolving) schemas
+fn concat(batches: Vec<RecordBatch>) -> Result<Option<RecordBatch>, EngineError> {
+    if batches.is_empty() {
+        return Ok(None);
+    }
+
+    let mut fields: Vec<Field> = Vec::new();
+    for batch in &batches {
+        for field in batch.schema().fields() {
+            if !fields.iter().any(|f| f.name() == field.name()) {
+                fields.push(Field::new(field.name(), field.data_type().clone(), true));
+            }
+        }
+    }
+    let schema = Arc::new(Schema::new(fields));
+
+    let aligned = batches
+        .iter()
+        .map(|b| align_to_schema(b, &schema))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Some(concat_batches(&schema, &aligned).map_err(internal)?))
+}
+
+/// Schema of new data extended with data columns that only exist in previous data
+fn union_schema(
+    new: &SchemaRef,
+    prev: Option<&SchemaRef>,
+    system_columns: &[String],
+    observation_column: Option<&String>,
+) -> SchemaRef {
+    // Rows coming from different sides may lack some columns, so all fields are nullable
+    let mut fields: Vec<Field> = new
+        .fields()
+        .iter()
+        .map(|f| Field::new(f.name(), f.data_type().clone(), true))
+        .collect();
+
+    if let Some(prev) = prev {
+        for field in prev.fields() {
+            if system_columns.contains(field.name())
+                || Some(field.name()) == observation_column
+                || new.field_with_name(field.name()).is_ok()
+            {
+                continue;
+            }
+            fields.push(Field::new(field.name(), field.data_type().clone(), true));
+        }
+    }
+
+    Arc::new(Schema::new(fields))
+}
+
+/// Projects batch onto specified schema, filling missing columns with nulls
+fn align_to_schema(batch: &RecordBatch, schema: &SchemaRef) -> Result<RecordBatch, EngineError> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match batch.schema().index_of(field.name()) {
+            Ok(i) if batch.column(i).data_type() == field.data_type() => {
+                Ok(batch.column(i).clone())
+            }
+            Ok(i) => cast(batch.column(i), field.data_type()).map_err(internal),
+            Err(_) => Ok(new_null_array(field.data_type(), batch.num_rows())),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    RecordBatch::try_new(schema.clone(), columns).map_err(internal)
+}
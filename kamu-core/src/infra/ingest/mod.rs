@@ -16,6 +16,9 @@ pub use ingest_task::*;
 mod fetch_service;
 pub use fetch_service::*;
 
+mod merge_strategies;
+pub use merge_strategies::*;
+
 mod prep_service;
 pub use prep_service::*;
 
@@ -9,6 +9,7 @@
 
 use super::*;
 use crate::domain::*;
+use crate::infra::WorkspaceLayout;
 use opendatafabric::serde::yaml::*;
 use opendatafabric::*;
 
@@ -18,18 +19,21 @@ use chrono::{DateTime, Utc};
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::Error as IOError;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::Arc;
-use thiserror::Error;
 
 const BUFFER_SIZE: usize = 8096;
 
-pub struct PrepService {}
+pub struct PrepService {
+    workspace_layout: Arc<WorkspaceLayout>,
+}
 
 impl PrepService {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(workspace_layout: Arc<WorkspaceLayout>) -> Self {
+        Self {
+            workspace_layout: workspace_layout,
+        }
     }
 
     pub fn prepare(
@@ -44,14 +48,8 @@ impl PrepService {
 
         for step in prep_steps.iter() {
             stream = match step {
-                PrepStep::Pipe(ref p) => Box::new(
-                    PipeStream::new(&p.command, stream)
-                        .map_err(|e| IngestError::pipe(p.command.clone(), e))?,
-                ),
-                PrepStep::Decompress(ref dc) => match dc.format {
-                    CompressionFormat::Zip => Box::new(DecompressZipStream::new(stream).int_err()?),
-                    CompressionFormat::Gzip => Box::new(DecompressGzipStream::new(stream)),
-                },
+                PrepStep::Pipe(ref p) => Box::new(self.pipe(p, stream)?),
+                PrepStep::Decompress(ref dc) => Self::decompress(dc, stream)?,
             };
         }
 
@@ -67,6 +65,73 @@ impl PrepService {
             },
         })
     }
+
+    fn pipe(&self, pipe: &PrepStepPipe, input: Box<dyn Stream>) -> Result<PipeStream, IngestError> {
+        if pipe.command.is_empty() {
+            return Err(InvalidSourceError::new("Pipe command cannot be empty").into());
+        }
+
+        std::fs::create_dir_all(&self.workspace_layout.run_info_dir).int_err()?;
+        let stderr_path = self
+            .workspace_layout
+            .run_info_dir
+            .join(format!("prep-{}.err.txt", new_run_id()));
+        let stderr_file = File::create(&stderr_path).int_err()?;
+
+        // Commands inherit the environment of the current process
+        let process = Command::new(&pipe.command[0])
+            .args(&pipe.command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::from(stderr_file))
+            .spawn()
+            .map_err(|e| IngestError::pipe(pipe.command.clone(), e))?;
+
+        Ok(PipeStream::new(
+            pipe.command.clone(),
+            process,
+            stderr_path,
+            input,
+        ))
+    }
+
+    fn decompress(
+        dc: &PrepStepDecompress,
+        input: Box<dyn Stream>,
+    ) -> Result<Box<dyn Stream>, IngestError> {
+        let sub_path = match dc.sub_path {
+            None => None,
+            Some(ref sub_path) => Some(glob::Pattern::new(sub_path).map_err(|e| {
+                InvalidSourceError::new(format!("Invalid sub_path glob {}: {}", sub_path, e))
+            })?),
+        };
+
+        if sub_path.is_some() && !Self::is_archive(dc.format) {
+            return Err(InvalidSourceError::new(format!(
+                "Format {:?} is not a multi-file archive and does not support sub_path",
+                dc.format
+            ))
+            .into());
+        }
+
+        let stream: Box<dyn Stream> = match dc.format {
+            CompressionFormat::Zip => Box::new(ExtractStream::zip(input, sub_path)),
+            CompressionFormat::Gzip => Box::new(DecoderStream::new(
+                dc.format,
+                flate2::read::MultiGzDecoder::new(input),
+                |d| d.into_inner(),
+            )),
+        };
+
+        Ok(stream)
+    }
+
+    fn is_archive(format: CompressionFormat) -> bool {
+        match format {
+            CompressionFormat::Zip => true,
+            CompressionFormat::Gzip => false,
+        }
+    }
 }
 
 #[skip_serializing_none]
@@ -121,51 +186,62 @@ struct PipeStream {
 }
 
 impl PipeStream {
-    fn new(cmd: &Vec<String>, mut input: Box<dyn Stream>) -> Result<Self, IOError> {
-        let mut process = Command::new(cmd.get(0).unwrap())
-            .args(&cmd[1..])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()?;
-
+    fn new(
+        cmd: Vec<String>,
+        mut process: std::process::Child,
+        stderr_path: PathBuf,
+        mut input: Box<dyn Stream>,
+    ) -> Self {
         let stdout = process.stdout.take().unwrap();
         let mut stdin = process.stdin.take().unwrap();
-        let cmd = cmd.clone();
+
         let ingress = std::thread::Builder::new()
             .name("pipe_stream".to_owned())
             .spawn(move || {
                 let mut buf = [0; BUFFER_SIZE];
 
                 loop {
-                    let read = input.read(&mut buf).unwrap();
+                    let read = match input.read(&mut buf) {
+                        Ok(read) => read,
+                        Err(e) => {
+                            drop(stdin);
+                            input.join()?;
+                            return Err(e.int_err().into());
+                        }
+                    };
                     if read == 0 {
                         break;
                     }
-                    stdin.write_all(&buf[..read]).unwrap();
+                    match stdin.write_all(&buf[..read]) {
+                        Ok(_) => (),
+                        // Command exited without reading all of its input
+                        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => break,
+                        Err(e) => return Err(e.int_err().into()),
+                    }
                 }
 
                 drop(stdin);
                 input.join()?;
 
-                let status = process.wait().unwrap();
+                let status = process.wait().int_err()?;
 
                 if !status.success() {
                     Err(IngestError::pipe(
                         cmd,
-                        BadStatusCode {
-                            code: status.code().unwrap(),
-                        },
+                        ProcessError::new(status.code(), vec![stderr_path]),
                     ))
                 } else {
+                    // Log is only kept to diagnose failures
+                    std::fs::remove_file(&stderr_path).int_err()?;
                     Ok(())
                 }
             })
             .unwrap();
 
-        Ok(Self {
+        Self {
             ingress: ingress,
             stdout: stdout,
-        })
+        }
     }
 }
 
@@ -185,18 +261,66 @@ impl Stream for PipeStream {
     }
 
     fn join(self: Box<Self>) -> Result<(), IngestError> {
-        self.ingress.join().unwrap()
+        let this = *self;
+        // Unblock the command in case reading side stopped early
+        drop(this.stdout);
+        this.ingress.join().unwrap()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Decoder Streams
+///////////////////////////////////////////////////////////////////////////////
+
+/// Wraps a streaming decoder of a single-stream compression format
+struct DecoderStream<D: Read + Send> {
+    format: CompressionFormat,
+    decoder: D,
+    into_inner: fn(D) -> Box<dyn Stream>,
+    error: Option<String>,
+}
+
+impl<D: Read + Send> DecoderStream<D> {
+    fn new(format: CompressionFormat, decoder: D, into_inner: fn(D) -> Box<dyn Stream>) -> Self {
+        Self {
+            format: format,
+            decoder: decoder,
+            into_inner: into_inner,
+            error: None,
+        }
     }
 }
 
-#[derive(Debug, Error)]
-#[error("Command exited with code {code}")]
-struct BadStatusCode {
-    code: i32,
+impl<D: Read + Send> Read for DecoderStream<D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, IOError> {
+        self.decoder.read(buf).map_err(|e| {
+            self.error = Some(e.to_string());
+            e
+        })
+    }
+}
+
+impl<D: Read + Send> Stream for DecoderStream<D> {
+    fn as_read(&mut self) -> &mut dyn std::io::Read {
+        self
+    }
+
+    fn as_seekable_read(&mut self) -> Option<&mut dyn ReadAndSeek> {
+        None
+    }
+
+    fn join(self: Box<Self>) -> Result<(), IngestError> {
+        let this = *self;
+        (this.into_inner)(this.decoder).join()?;
+        match this.error {
+            None => Ok(()),
+            Some(e) => Err(IngestError::decompress(this.format, e)),
+        }
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
-// DecompressZipStream
+// Archive Streams
 ///////////////////////////////////////////////////////////////////////////////
 
 struct ReaderHelper<'a>(&'a mut dyn Stream);
@@ -207,116 +331,191 @@ impl<'a> Read for ReaderHelper<'a> {
     }
 }
 
-struct DecompressZipStream {
-    ingress: std::thread::JoinHandle<Result<(), IngestError>>,
-    consumer: ringbuf::Consumer<u8, Arc<ringbuf::HeapRb<u8>>>,
-    done_recvr: std::sync::mpsc::Receiver<usize>,
+/// Decides which entries of a multi-file archive will be extracted.
+///
+/// Without `sub_path` the archive is expected to contain a single file.
+/// Otherwise all files matching the glob are concatenated in archive order.
+struct EntrySelector {
+    format: CompressionFormat,
+    sub_path: Option<glob::Pattern>,
+    selected: Vec<String>,
 }
 
-impl DecompressZipStream {
-    fn new(mut input: Box<dyn Stream>) -> Result<Self, IOError> {
-        let (mut producer, consumer) = ringbuf::HeapRb::<u8>::new(BUFFER_SIZE).split();
-
-        let (tx, rx) = std::sync::mpsc::sync_channel(1);
-
-        // TODO: Threading is a complete overkill here
-        // Only reason for this is the ownership/lifetime issues when creating archive from references
-        // See: https://github.com/mvdnes/zip-rs/issues/111
-        let ingress = std::thread::Builder::new()
-            .name("decompress_zip_stream".to_owned())
-            .spawn(move || {
-                if let Some(seekable) = input.as_seekable_read() {
-                    let mut archive = zip::read::ZipArchive::new(seekable).int_err()?;
-                    let mut file = archive.by_index(0).int_err()?;
-
-                    loop {
-                        let read = producer.read_from(&mut file, None).unwrap();
-                        tx.send(read).unwrap();
-                        if read == 0 {
-                            break;
-                        }
-                    }
-                } else {
-                    let mut read_helper = ReaderHelper(input.as_mut());
-                    let mut file = zip::read::read_zipfile_from_stream(&mut read_helper)
-                        .unwrap()
-                        .unwrap();
-
-                    loop {
-                        let read = producer.read_from(&mut file, None).unwrap();
-                        tx.send(read).unwrap();
-                        if read == 0 {
-                            break;
-                        }
-                    }
-                }
-
-                input.join()
-            })
-            .unwrap();
-
-        Ok(Self {
-            ingress: ingress,
-            consumer: consumer,
-            done_recvr: rx,
-        })
+impl EntrySelector {
+    fn new(format: CompressionFormat, sub_path: Option<glob::Pattern>) -> Self {
+        Self {
+            format: format,
+            sub_path: sub_path,
+            selected: Vec::new(),
+        }
     }
-}
 
-impl Read for DecompressZipStream {
-    fn read(&mut self, buf: &mut [u8]) -> Result<usize, IOError> {
-        while self.consumer.is_empty() {
-            match self.done_recvr.recv() {
-                Ok(0) => break,
-                Ok(_) => (),
-                Err(_) => break,
+    fn select(&mut self, name: &str) -> Result<bool, IngestError> {
+        match &self.sub_path {
+            Some(pattern) => {
+                let options = glob::MatchOptions {
+                    require_literal_separator: true,
+                    ..Default::default()
+                };
+                if !pattern.matches_with(name, options) {
+                    return Ok(false);
+                }
+            }
+            None => {
+                if let Some(first) = self.selected.first() {
+                    return Err(IngestError::decompress(
+                        self.format,
+                        format!(
+                            "Archive contains multiple files ({}, {}), specify sub_path to \
+                             select which ones to read",
+                            first, name
+                        ),
+                    ));
+                }
             }
         }
+        self.selected.push(name.to_owned());
+        Ok(true)
+    }
 
-        if !self.consumer.is_empty() {
-            self.consumer.read(buf)
-        } else {
-            Ok(0)
+    fn finish(self) -> Result<(), IngestError> {
+        if !self.selected.is_empty() {
+            return Ok(());
         }
+        Err(IngestError::decompress(
+            self.format,
+            match self.sub_path {
+                Some(pattern) => format!("No files in archive match sub_path {}", pattern),
+                None => "Archive contains no files".to_owned(),
+            },
+        ))
     }
 }
 
-impl Stream for DecompressZipStream {
-    fn as_read(&mut self) -> &mut dyn std::io::Read {
-        self
-    }
+/// Sends extracted data to the reading side of [ExtractStream] in chunks
+struct ChunkWriter(std::sync::mpsc::SyncSender<Vec<u8>>);
 
-    fn as_seekable_read(&mut self) -> Option<&mut dyn ReadAndSeek> {
-        None
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, IOError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.0.send(buf.to_vec()).map_err(|_| {
+            IOError::new(
+                std::io::ErrorKind::BrokenPipe,
+                "Extracted data receiver is closed",
+            )
+        })?;
+        Ok(buf.len())
     }
 
-    fn join(self: Box<Self>) -> Result<(), IngestError> {
-        self.ingress.join().unwrap()
+    fn flush(&mut self) -> Result<(), IOError> {
+        Ok(())
     }
 }
 
-///////////////////////////////////////////////////////////////////////////////
-// DecompressGzipStream
-///////////////////////////////////////////////////////////////////////////////
-
-struct DecompressGzipStream {
-    decoder: flate2::read::GzDecoder<Box<dyn Stream>>,
+/// Runs archive extraction in a background thread
+///
+/// Threading is needed due to ownership/lifetime issues when creating archives from references
+/// See: https://github.com/mvdnes/zip-rs/issues/111
+struct ExtractStream {
+    ingress: std::thread::JoinHandle<Result<(), IngestError>>,
+    receiver: std::sync::mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
 }
 
-impl DecompressGzipStream {
-    fn new(input: Box<dyn Stream>) -> Self {
-        let decoder = flate2::read::GzDecoder::new(input);
-        Self { decoder: decoder }
+impl ExtractStream {
+    fn new<F>(thread_name: &str, mut input: Box<dyn Stream>, extract: F) -> Self
+    where
+        F: FnOnce(&mut dyn Stream, &mut ChunkWriter) -> Result<(), IngestError> + Send + 'static,
+    {
+        let (tx, rx) = std::sync::mpsc::sync_channel(4);
+
+        let ingress = std::thread::Builder::new()
+            .name(thread_name.to_owned())
+            .spawn(move || {
+                let mut writer = ChunkWriter(tx);
+                let res = extract(input.as_mut(), &mut writer);
+                drop(writer);
+                input.join()?;
+                res
+            })
+            .unwrap();
+
+        Self {
+            ingress: ingress,
+            receiver: rx,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    fn zip(input: Box<dyn Stream>, sub_path: Option<glob::Pattern>) -> Self {
+        Self::new("decompress_zip_stream", input, move |input, out| {
+            let format = CompressionFormat::Zip;
+            let mut selector = EntrySelector::new(format, sub_path);
+
+            if let Some(seekable) = input.as_seekable_read() {
+                let mut archive = zip::read::ZipArchive::new(seekable)
+                    .map_err(|e| IngestError::decompress(format, e))?;
+
+                let mut selected = Vec::new();
+                for i in 0..archive.len() {
+                    let file = archive
+                        .by_index_raw(i)
+                        .map_err(|e| IngestError::decompress(format, e))?;
+                    if file.is_file() && selector.select(file.name())? {
+                        selected.push(i);
+                    }
+                }
+                selector.finish()?;
+
+                for i in selected {
+                    let mut file = archive
+                        .by_index(i)
+                        .map_err(|e| IngestError::decompress(format, e))?;
+                    std::io::copy(&mut file, out)
+                        .map_err(|e| IngestError::decompress(format, e))?;
+                }
+            } else {
+                let mut read_helper = ReaderHelper(input);
+                while let Some(mut file) = zip::read::read_zipfile_from_stream(&mut read_helper)
+                    .map_err(|e| IngestError::decompress(format, e))?
+                {
+                    if file.is_file() && selector.select(file.name())? {
+                        std::io::copy(&mut file, out)
+                            .map_err(|e| IngestError::decompress(format, e))?;
+                    }
+                }
+                selector.finish()?;
+            }
+
+            Ok(())
+        })
     }
 }
 
-impl Read for DecompressGzipStream {
+impl Read for ExtractStream {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, IOError> {
-        self.decoder.read(buf)
+        while self.pos == self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let read = std::cmp::min(buf.len(), self.chunk.len() - self.pos);
+        buf[..read].copy_from_slice(&self.chunk[self.pos..self.pos + read]);
+        self.pos += read;
+        Ok(read)
     }
 }
 
-impl Stream for DecompressGzipStream {
+impl Stream for ExtractStream {
     fn as_read(&mut self) -> &mut dyn std::io::Read {
         self
     }
@@ -326,7 +525,10 @@ impl Stream for DecompressGzipStream {
     }
 
     fn join(self: Box<Self>) -> Result<(), IngestError> {
-        self.decoder.into_inner().join()
+        let this = *self;
+        // Unblock the extraction thread in case reading side stopped early
+        drop(this.receiver);
+        this.ingress.join().unwrap()
     }
 }
 
@@ -346,11 +548,18 @@ impl FileSink {
                 let mut buf = [0; BUFFER_SIZE];
 
                 loop {
-                    let read = input.read(&mut buf).unwrap();
+                    let read = match input.read(&mut buf) {
+                        Ok(read) => read,
+                        Err(e) => {
+                            // Upstream stream is likely to report a more specific error
+                            input.join()?;
+                            return Err(e.int_err().into());
+                        }
+                    };
                     if read == 0 {
                         break;
                     }
-                    file.write_all(&buf[..read]).unwrap();
+                    file.write_all(&buf[..read]).int_err()?;
                 }
 
                 input.join()
@@ -364,3 +573,17 @@ impl FileSink {
         self.ingress.join().unwrap()
     }
 }
+
+///////////////////////////////////////////////////////////////////////////////
+// Helpers
+///////////////////////////////////////////////////////////////////////////////
+
+pub(crate) fn new_run_id() -> String {
+    use rand::Rng;
+
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(10)
+        .map(char::from)
+        .collect()
+}
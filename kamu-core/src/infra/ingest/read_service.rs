@@ -19,17 +19,21 @@ use ::serde_with::skip_serializing_none;
 use chrono::{DateTime, Utc};
 use std::path::Path;
 use std::sync::Arc;
+use tracing::info;
 
 pub struct ReadService {
     engine_provisioner: Arc<dyn EngineProvisioner>,
+    native_engine: DataFusionEngine,
 }
 
 impl ReadService {
     pub fn new(engine_provisioner: Arc<dyn EngineProvisioner>) -> Self {
-        Self { engine_provisioner }
+        Self {
+            engine_provisioner,
+            native_engine: DataFusionEngine::new(),
+        }
     }
 
-    // TODO: Don't use engine for anything but preprocessing
     pub async fn read<'a, 'b>(
         &'a self,
         dataset_handle: &'b DatasetHandle,
@@ -66,11 +70,6 @@ impl ReadService {
             });
         }
 
-        let engine = self
-            .engine_provisioner
-            .provision_ingest_engine(listener.get_engine_provisioning_listener())
-            .await?;
-
         // Clean up previous state leftovers
         if out_data_path.exists() {
             std::fs::remove_file(&out_data_path).int_err()?;
@@ -94,7 +93,18 @@ impl ReadService {
             new_checkpoint_path: out_checkpoint_path.to_owned(),
         };
 
-        let mut response = engine.ingest(request).await?;
+        // Formats not supported natively (e.g. GeoJson, Shapefile) still go through the Spark engine
+        let mut response = if DataFusionEngine::supports_ingest(source) {
+            info!("Reading data using the native engine");
+            self.native_engine.ingest(request).await?
+        } else {
+            let engine = self
+                .engine_provisioner
+                .provision_ingest_engine(listener.get_engine_provisioning_listener())
+                .await?;
+
+            engine.ingest(request).await?
+        };
 
         if let Some(data_interval) = &mut response.data_interval {
             if data_interval.end < data_interval.start || data_interval.start != offset {
@@ -43,6 +43,9 @@ pub use remote_repository_registry_impl::*;
 // Services
 ///////////////////////////////////////////////////////////////////////////////
 
+mod gc_service_impl;
+pub use gc_service_impl::*;
+
 mod ingest_service_impl;
 pub use ingest_service_impl::*;
 
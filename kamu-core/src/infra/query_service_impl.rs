@@ -7,6 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use chrono::{DateTime, Utc};
 use datafusion::{
     catalog::{catalog::CatalogProvider, schema::SchemaProvider},
     datasource::TableProvider,
@@ -24,8 +25,9 @@ use dill::*;
 use futures::stream::TryStreamExt;
 use opendatafabric::*;
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
 use tracing::info_span;
 
@@ -56,8 +58,10 @@ impl QueryService for QueryServiceImpl {
     async fn tail(
         &self,
         dataset_ref: &DatasetRefLocal,
+        block_ref: Option<&BlockRef>,
         num_records: u64,
     ) -> Result<DataFrame, QueryError> {
+        let block_ref = block_ref.unwrap_or(&BlockRef::Head);
         let dataset_handle = self.local_repo.resolve_dataset_ref(dataset_ref).await?;
         let dataset = self
             .local_repo
@@ -66,7 +70,7 @@ impl QueryService for QueryServiceImpl {
 
         let vocab: DatasetVocabulary = dataset
             .as_metadata_chain()
-            .iter_blocks()
+            .iter_blocks_ref(block_ref)
             .filter_map_ok(|(_, b)| b.event.into_variant::<SetVocab>())
             .try_first()
             .await
@@ -79,7 +83,9 @@ impl QueryService for QueryServiceImpl {
         // See:
         // - https://github.com/apache/arrow-datafusion/issues/959
         // - https://github.com/apache/arrow-rs/issues/393
-        let schema = self.get_schema(&dataset_handle.as_local_ref()).await?;
+        let schema = self
+            .get_schema_impl(&dataset_handle, dataset.as_ref(), block_ref)
+            .await?;
         let fields: Vec<String> = match schema {
             Type::GroupType { fields, .. } => fields
                 .iter()
@@ -121,6 +127,8 @@ impl QueryService for QueryServiceImpl {
                 datasets: vec![DatasetQueryOptions {
                     dataset_ref: dataset_handle.as_local_ref(),
                     limit: Some(num_records),
+                    block_ref: Some(block_ref.clone()),
+                    as_of: None,
                 }],
             },
         )
@@ -135,6 +143,11 @@ impl QueryService for QueryServiceImpl {
         let span = info_span!("Executing SQL query", statement);
         let _span_guard = span.enter();
 
+        let ctx = self.create_session(options).await?;
+        Ok(ctx.sql(statement).await?)
+    }
+
+    async fn create_session(&self, options: QueryOptions) -> Result<SessionContext, QueryError> {
         let cfg = SessionConfig::new()
             .with_information_schema(true)
             .with_default_catalog_and_schema("kamu", "kamu");
@@ -150,7 +163,7 @@ impl QueryService for QueryServiceImpl {
             )))),
         );
 
-        Ok(ctx.sql(statement).await?)
+        Ok(ctx)
     }
 
     async fn get_schema(&self, dataset_ref: &DatasetRefLocal) -> Result<Type, QueryError> {
@@ -160,12 +173,71 @@ impl QueryService for QueryServiceImpl {
             .get_dataset(&dataset_handle.as_local_ref())
             .await?;
 
+        self.get_schema_impl(&dataset_handle, dataset.as_ref(), &BlockRef::Head)
+            .await
+    }
+
+    async fn options_as_of(
+        &self,
+        blocks: &[Multihash],
+        system_time: Option<DateTime<Utc>>,
+    ) -> Result<QueryOptions, QueryError> {
+        let mut unresolved: Vec<&Multihash> = blocks.iter().collect();
+        let mut datasets = Vec::new();
+
+        let mut dataset_handles = self.local_repo.get_all_datasets();
+        while let Some(hdl) = dataset_handles.try_next().await? {
+            let dataset = self.local_repo.get_dataset(&hdl.as_local_ref()).await?;
+            let chain = dataset.as_metadata_chain();
+
+            let mut as_of = None;
+            for hash in &unresolved {
+                if chain.try_get_block(hash).await?.is_some() {
+                    as_of = Some(QueryAsOf::Block((*hash).clone()));
+                    break;
+                }
+            }
+
+            if let Some(QueryAsOf::Block(hash)) = &as_of {
+                unresolved.retain(|h| *h != hash);
+            } else if let Some(system_time) = system_time {
+                as_of = Some(QueryAsOf::SystemTime(system_time));
+            }
+
+            // Datasets that are not pinned are still registered so that they
+            // remain visible in the session and are read at their head
+            datasets.push(DatasetQueryOptions {
+                dataset_ref: hdl.as_local_ref(),
+                limit: None,
+                block_ref: None,
+                as_of,
+            });
+        }
+
+        if let Some(hash) = unresolved.first() {
+            return Err(BlockNotFoundError {
+                hash: (*hash).clone(),
+            }
+            .into());
+        }
+
+        Ok(QueryOptions { datasets })
+    }
+}
+
+impl QueryServiceImpl {
+    async fn get_schema_impl(
+        &self,
+        dataset_handle: &DatasetHandle,
+        dataset: &dyn Dataset,
+        block_ref: &BlockRef,
+    ) -> Result<Type, QueryError> {
         // TODO: This service shouldn't know the specifics of dataset layouts
         let dataset_layout = self.workspace_layout.dataset_layout(&dataset_handle.name);
 
         let last_data_file = dataset
             .as_metadata_chain()
-            .iter_blocks()
+            .iter_blocks_ref(block_ref)
             .filter_data_stream_blocks()
             .filter_map_ok(|(_, b)| b.event.output_data)
             .map_ok(|slice| dataset_layout.data_slice_path(&slice))
@@ -186,11 +258,16 @@ impl QueryService for QueryServiceImpl {
 
 struct KamuCatalog {
     schema: Arc<KamuSchema>,
+    /// Schemas registered within the session in addition to the datasets schema
+    extra_schemas: RwLock<HashMap<String, Arc<dyn SchemaProvider>>>,
 }
 
 impl KamuCatalog {
     fn new(schema: Arc<KamuSchema>) -> Self {
-        Self { schema }
+        Self {
+            schema,
+            extra_schemas: RwLock::new(HashMap::new()),
+        }
     }
 }
 
@@ -200,15 +277,35 @@ impl CatalogProvider for KamuCatalog {
     }
 
     fn schema_names(&self) -> Vec<String> {
-        vec!["kamu".to_owned()]
+        let mut names = vec!["kamu".to_owned()];
+        names.extend(self.extra_schemas.read().unwrap().keys().cloned());
+        names
     }
 
     fn schema(&self, name: &str) -> Option<Arc<dyn datafusion::catalog::schema::SchemaProvider>> {
         if name == "kamu" {
             Some(self.schema.clone())
         } else {
-            None
+            self.extra_schemas.read().unwrap().get(name).cloned()
+        }
+    }
+
+    fn register_schema(
+        &self,
+        name: &str,
+        schema: Arc<dyn SchemaProvider>,
+    ) -> datafusion::error::Result<Option<Arc<dyn SchemaProvider>>> {
+        if name == "kamu" {
+            return Err(datafusion::error::DataFusionError::Execution(format!(
+                "Schema {} is reserved for datasets",
+                name
+            )));
         }
+        Ok(self
+            .extra_schemas
+            .write()
+            .unwrap()
+            .insert(name.to_owned(), schema))
     }
 }
 
@@ -222,6 +319,9 @@ struct KamuSchema {
     workspace_layout: Arc<WorkspaceLayout>,
     options: QueryOptions,
     ctx: SessionState,
+    /// Tables registered within the session (e.g. via `CREATE VIEW`) that take
+    /// precedence over datasets with the same name
+    session_tables: Arc<RwLock<HashMap<String, Arc<dyn TableProvider>>>>,
 }
 
 impl KamuSchema {
@@ -236,12 +336,22 @@ impl KamuSchema {
             workspace_layout,
             options,
             ctx,
+            session_tables: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    fn session_table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
+        self.session_tables.read().unwrap().get(name).cloned()
+    }
+
     async fn has_data(&self, dataset_handle: &DatasetHandle) -> Result<bool, InternalError> {
-        let limit = self.options_for(dataset_handle).and_then(|o| o.limit);
-        let files = self.collect_data_files(dataset_handle, limit).await?;
+        let options = self.options_for(dataset_handle);
+        let limit = options.and_then(|o| o.limit);
+        let as_of = options.and_then(|o| o.as_of.as_ref());
+        let block_ref = self.block_ref_for(dataset_handle, None);
+        let files = self
+            .collect_data_files(dataset_handle, &block_ref, as_of, limit)
+            .await?;
 
         if files.is_empty() {
             return Ok(false);
@@ -262,6 +372,8 @@ impl KamuSchema {
     async fn collect_data_files(
         &self,
         dataset_handle: &DatasetHandle,
+        block_ref: &BlockRef,
+        as_of: Option<&QueryAsOf>,
         limit: Option<u64>,
     ) -> Result<Vec<PathBuf>, InternalError> {
         let dataset_layout = self.workspace_layout.dataset_layout(&dataset_handle.name);
@@ -271,12 +383,38 @@ impl KamuSchema {
             .get_dataset(&dataset_handle.as_local_ref())
             .await
         {
+            let chain = dataset.as_metadata_chain();
+
+            // Resolve the block from which the data should be read, only
+            // considering the history of the requested reference
+            let head = match as_of {
+                Some(QueryAsOf::Block(hash)) => chain
+                    .iter_blocks_ref(block_ref)
+                    .filter_ok(|(h, _)| h == hash)
+                    .map_ok(|(h, _)| h)
+                    .try_first()
+                    .await
+                    .int_err()?,
+                Some(QueryAsOf::SystemTime(system_time)) => chain
+                    .iter_blocks_ref(block_ref)
+                    .filter_ok(|(_, b)| b.system_time <= *system_time)
+                    .map_ok(|(h, _)| h)
+                    .try_first()
+                    .await
+                    .int_err()?,
+                None => chain.try_get_ref(block_ref).await?,
+            };
+
+            let head = match head {
+                Some(head) => head,
+                None => return Ok(Vec::new()),
+            };
+
             let mut files = Vec::new();
             let mut num_records = 0;
 
-            let mut slices = dataset
-                .as_metadata_chain()
-                .iter_blocks()
+            let mut slices = chain
+                .iter_blocks_interval(&head, None, false)
                 .filter_data_stream_blocks()
                 .filter_map_ok(|(_, b)| b.event.output_data);
 
@@ -308,6 +446,20 @@ impl KamuSchema {
         None
     }
 
+    /// Reference specified in the table name takes precedence over the query options
+    fn block_ref_for(
+        &self,
+        dataset_handle: &DatasetHandle,
+        table_block_ref: Option<BlockRef>,
+    ) -> BlockRef {
+        table_block_ref
+            .or_else(|| {
+                self.options_for(dataset_handle)
+                    .and_then(|o| o.block_ref.clone())
+            })
+            .unwrap_or(BlockRef::Head)
+    }
+
     async fn table_names_impl(&self) -> Vec<String> {
         if self.options.datasets.is_empty() {
             let mut res = Vec::new();
@@ -329,34 +481,77 @@ impl KamuSchema {
         }
     }
 
+    /// Table names have the form `<dataset>[@<ref>]`
+    fn parse_table_name(name: &str) -> Option<(DatasetRefLocal, Option<BlockRef>)> {
+        let (dataset_name, block_ref) = match name.rsplit_once('@') {
+            Some((dataset_name, block_ref)) => (dataset_name, Some(block_ref)),
+            None => (name, None),
+        };
+
+        let dataset_name = DatasetName::try_from(dataset_name).ok()?;
+        let block_ref = match block_ref {
+            None => None,
+            Some(r) => Some(r.parse::<BlockRef>().ok()?),
+        };
+
+        Some((dataset_name.into(), block_ref))
+    }
+
     async fn table_exist_impl(&self, name: &str) -> bool {
-        let dataset_name = match DatasetName::try_from(name) {
-            Ok(name) => name,
-            Err(_) => return false,
+        let (dataset_ref_local, block_ref) = match Self::parse_table_name(name) {
+            Some(v) => v,
+            None => return false,
         };
 
-        self.local_repo
-            .try_resolve_dataset_ref(&dataset_name.into())
+        // Resolution errors are treated as absence of the table, same as in
+        // `table_impl`
+        let dataset_handle = match self
+            .local_repo
+            .try_resolve_dataset_ref(&dataset_ref_local)
             .await
-            .unwrap()
-            .is_some()
+        {
+            Ok(Some(hdl)) => hdl,
+            Ok(None) | Err(_) => return false,
+        };
+
+        match block_ref {
+            None => true,
+            Some(block_ref) => {
+                let dataset = match self
+                    .local_repo
+                    .get_dataset(&dataset_handle.as_local_ref())
+                    .await
+                {
+                    Ok(dataset) => dataset,
+                    Err(_) => return false,
+                };
+
+                matches!(
+                    dataset.as_metadata_chain().try_get_ref(&block_ref).await,
+                    Ok(Some(_))
+                )
+            }
+        }
     }
 
     async fn table_impl(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
-        let dataset_name = DatasetName::try_from(name);
-        if let Err(_) = dataset_name {
-            return None;
-        }
-        let dataset_ref_local = &dataset_name.unwrap().into();
+        let (dataset_ref_local, table_block_ref) = Self::parse_table_name(name)?;
 
-        match self.local_repo.resolve_dataset_ref(dataset_ref_local).await {
+        match self
+            .local_repo
+            .resolve_dataset_ref(&dataset_ref_local)
+            .await
+        {
             Err(_) => None,
             Ok(dataset_handle) => {
-                let limit = self.options_for(&dataset_handle).and_then(|o| o.limit);
+                let options = self.options_for(&dataset_handle);
+                let limit = options.and_then(|o| o.limit);
+                let as_of = options.and_then(|o| o.as_of.as_ref());
+                let block_ref = self.block_ref_for(&dataset_handle, table_block_ref);
                 let files = self
-                    .collect_data_files(&dataset_handle, limit)
+                    .collect_data_files(&dataset_handle, &block_ref, as_of, limit)
                     .await
-                    .unwrap();
+                    .ok()?;
 
                 if files.is_empty() {
                     None
@@ -387,15 +582,27 @@ impl SchemaProvider for KamuSchema {
     fn table_names(&self) -> Vec<String> {
         let this = self.clone();
 
-        std::thread::spawn(move || {
+        let mut names = std::thread::spawn(move || {
             let runtime = tokio::runtime::Runtime::new().unwrap();
             runtime.block_on(this.table_names_impl())
         })
         .join()
-        .unwrap()
+        .unwrap();
+
+        for name in self.session_tables.read().unwrap().keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+
+        names
     }
 
     fn table_exist(&self, name: &str) -> bool {
+        if self.session_tables.read().unwrap().contains_key(name) {
+            return true;
+        }
+
         let this = self.clone();
         let name = name.to_owned();
 
@@ -408,6 +615,10 @@ impl SchemaProvider for KamuSchema {
     }
 
     fn table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
+        if let Some(table) = self.session_table(name) {
+            return Some(table);
+        }
+
         let this = self.clone();
         let name = name.to_owned();
 
@@ -421,4 +632,19 @@ impl SchemaProvider for KamuSchema {
         .join()
         .unwrap()
     }
+
+    fn register_table(
+        &self,
+        name: String,
+        table: Arc<dyn TableProvider>,
+    ) -> datafusion::error::Result<Option<Arc<dyn TableProvider>>> {
+        Ok(self.session_tables.write().unwrap().insert(name, table))
+    }
+
+    fn deregister_table(
+        &self,
+        name: &str,
+    ) -> datafusion::error::Result<Option<Arc<dyn TableProvider>>> {
+        Ok(self.session_tables.write().unwrap().remove(name))
+    }
 }
@@ -31,6 +31,24 @@ impl RemoteRepositoryRegistryImpl {
     pub fn new(workspace_layout: Arc<WorkspaceLayout>) -> Self {
         Self { workspace_layout }
     }
+
+    fn write_repository(
+        &self,
+        repo_name: &RepositoryName,
+        info: RepositoryAccessInfo,
+    ) -> Result<(), InternalError> {
+        let file_path = self.workspace_layout.repos_dir.join(repo_name);
+
+        let manifest = Manifest {
+            kind: "Repository".to_owned(),
+            version: 1,
+            content: info,
+        };
+
+        let file = std::fs::File::create(&file_path).int_err()?;
+        serde_yaml::to_writer(file, &manifest).int_err()?;
+        Ok(())
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////
@@ -82,14 +100,24 @@ impl RemoteRepositoryRegistry for RemoteRepositoryRegistryImpl {
             url.set_path(&format!("{}/", url.path()));
         }
 
-        let manifest = Manifest {
-            kind: "Repository".to_owned(),
-            version: 1,
-            content: RepositoryAccessInfo { url: url },
-        };
+        self.write_repository(
+            repo_name,
+            RepositoryAccessInfo {
+                url,
+                auth_token: None,
+            },
+        )?;
+        Ok(())
+    }
 
-        let file = std::fs::File::create(&file_path).int_err()?;
-        serde_yaml::to_writer(file, &manifest).int_err()?;
+    fn set_repository_auth_token(
+        &self,
+        repo_name: &RepositoryName,
+        auth_token: Option<String>,
+    ) -> Result<(), GetRepoError> {
+        let mut info = self.get_repository(repo_name)?;
+        info.auth_token = auth_token;
+        self.write_repository(repo_name, info)?;
         Ok(())
     }
 
@@ -133,6 +161,17 @@ impl RemoteRepositoryRegistry for RemoteRepositoryRegistryNull {
         Err("null registry".int_err().into())
     }
 
+    fn set_repository_auth_token(
+        &self,
+        repo_name: &RepositoryName,
+        _auth_token: Option<String>,
+    ) -> Result<(), GetRepoError> {
+        Err(RepositoryNotFoundError {
+            repo_name: repo_name.clone(),
+        }
+        .into())
+    }
+
     fn delete_repository(&self, repo_name: &RepositoryName) -> Result<(), DeleteRepoError> {
         Err(RepositoryNotFoundError {
             repo_name: repo_name.clone(),
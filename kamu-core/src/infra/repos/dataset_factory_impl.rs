@@ -53,18 +53,43 @@ impl DatasetFactoryImpl {
         )
     }
 
-    pub fn get_http(base_url: Url) -> Result<impl Dataset, InternalError> {
-        let client = reqwest::Client::new();
+    pub fn get_http(
+        base_url: Url,
+        auth_token: Option<&str>,
+    ) -> Result<impl Dataset, InternalError> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Some(auth_token) = auth_token {
+            let mut value =
+                reqwest::header::HeaderValue::from_str(&format!("Bearer {}", auth_token))
+                    .int_err()?;
+            value.set_sensitive(true);
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .int_err()?;
+
         Ok(DatasetImpl::new(
             MetadataChainImpl::new(
-                ObjectRepositoryHttp::new(client.clone(), base_url.join("blocks/").unwrap()),
+                ObjectRepositoryHttp::<sha3::Sha3_256, 0x16>::new(
+                    client.clone(),
+                    base_url.join("blocks/").unwrap(),
+                ),
                 ReferenceRepositoryImpl::new(NamedObjectRepositoryHttp::new(
                     client.clone(),
                     base_url.join("refs/").unwrap(),
                 )),
             ),
-            ObjectRepositoryHttp::new(client.clone(), base_url.join("data/").unwrap()),
-            ObjectRepositoryHttp::new(client.clone(), base_url.join("checkpoints/").unwrap()),
+            ObjectRepositoryHttp::<sha3::Sha3_256, 0x16>::new(
+                client.clone(),
+                base_url.join("data/").unwrap(),
+            ),
+            ObjectRepositoryHttp::<sha3::Sha3_256, 0x16>::new(
+                client.clone(),
+                base_url.join("checkpoints/").unwrap(),
+            ),
             NamedObjectRepositoryHttp::new(client.clone(), base_url.join("cache/").unwrap()),
             NamedObjectRepositoryHttp::new(client.clone(), base_url.join("info/").unwrap()),
         ))
@@ -135,6 +160,7 @@ impl DatasetFactory for DatasetFactoryImpl {
         &self,
         url: &Url,
         create_if_not_exists: bool,
+        auth_token: Option<&str>,
     ) -> Result<Arc<dyn Dataset>, BuildDatasetError> {
         match url.scheme() {
             "file" => {
@@ -153,7 +179,7 @@ impl DatasetFactory for DatasetFactoryImpl {
                 Ok(Arc::new(ds) as Arc<dyn Dataset>)
             }
             "http" | "https" => {
-                let ds = Self::get_http(url.clone())?;
+                let ds = Self::get_http(url.clone(), auth_token)?;
                 Ok(Arc::new(ds) as Arc<dyn Dataset>)
             }
             "s3" | "s3+http" | "s3+https" => {
@@ -277,4 +277,8 @@ where
     fn as_cache_repo(&self) -> &dyn NamedObjectRepository {
         &self.cache_repo
     }
+
+    fn as_info_repo(&self) -> &dyn NamedObjectRepository {
+        &self.info_repo
+    }
 }
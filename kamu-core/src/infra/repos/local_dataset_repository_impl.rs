@@ -455,9 +455,9 @@ impl LocalDatasetRepository for LocalDatasetRepositoryImpl {
         let chain = builder.as_dataset().as_metadata_chain();
 
         // We are generating a key pair and deriving a dataset ID from it.
-        // The key pair is discarded for now, but in future can be used for
-        // proof of control over dataset and metadata signing.
-        let (_keypair, dataset_id) = DatasetID::from_new_keypair_ed25519();
+        // The key is persisted alongside the dataset and used to sign every new block.
+        let (keypair, dataset_id) = DatasetID::from_new_keypair_ed25519();
+        builder.as_dataset().set_signing_key(&keypair).await?;
 
         let mut sequence_number = 0;
 
@@ -476,6 +476,7 @@ impl LocalDatasetRepository for LocalDatasetRepositoryImpl {
             )
             .await
             .int_err()?;
+        builder.as_dataset().sign_block(&head).await?;
 
         for event in snapshot.metadata {
             sequence_number += 1;
@@ -491,6 +492,7 @@ impl LocalDatasetRepository for LocalDatasetRepositoryImpl {
                 )
                 .await
                 .int_err()?;
+            builder.as_dataset().sign_block(&head).await?;
         }
 
         let hdl = builder.finish().await?;
@@ -692,4 +694,7 @@ where
     fn as_cache_repo(&self) -> &dyn NamedObjectRepository {
         self.dataset.as_cache_repo()
     }
+    fn as_info_repo(&self) -> &dyn NamedObjectRepository {
+        self.dataset.as_info_repo()
+    }
 }
@@ -7,12 +7,14 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use crate::domain::repos::named_object_repository::{DeleteError, GetError, SetError};
+use super::object_repository_http::map_write_error;
+use crate::domain::repos::named_object_repository::{DeleteError, GetError, ListError, SetError};
 use crate::domain::*;
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use reqwest::Client;
+use tracing::debug;
 use url::Url;
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -60,11 +62,53 @@ impl NamedObjectRepository for NamedObjectRepositoryHttp {
         Ok(data)
     }
 
-    async fn set(&self, _name: &str, _data: &[u8]) -> Result<(), SetError> {
-        Err(AccessError::ReadOnly(None).into())
+    async fn set(&self, name: &str, data: &[u8]) -> Result<(), SetError> {
+        let url = self.base_url.join(name).int_err()?;
+
+        debug!(%url, "Setting object");
+
+        let response = self
+            .client
+            .put(url)
+            .body(Vec::from(data))
+            .send()
+            .await
+            .int_err()?;
+
+        match response.error_for_status() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(match map_write_error(e) {
+                Ok(e) => SetError::Access(e),
+                Err(e) => SetError::Internal(e),
+            }),
+        }
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), DeleteError> {
+        let url = self.base_url.join(name).int_err()?;
+
+        debug!(%url, "Deleting object");
+
+        let response = self.client.delete(url).send().await.int_err()?;
+
+        match response.error_for_status() {
+            Ok(_) => Ok(()),
+            Err(e) if e.status() == Some(reqwest::StatusCode::NOT_FOUND) => Ok(()),
+            Err(e) => Err(match map_write_error(e) {
+                Ok(e) => DeleteError::Access(e),
+                Err(e) => DeleteError::Internal(e),
+            }),
+        }
     }
 
-    async fn delete(&self, _name: &str) -> Result<(), DeleteError> {
-        Err(AccessError::ReadOnly(None).into())
+    // TODO: Plain HTTP repositories have no way to enumerate objects, so we can only
+    // discover the well-known `head` reference
+    async fn list(&self) -> Result<Vec<String>, ListError> {
+        match self.get("head").await {
+            Ok(_) => Ok(vec!["head".to_owned()]),
+            Err(GetError::NotFound(_)) => Ok(Vec::new()),
+            Err(GetError::Access(e)) => Err(e.into()),
+            Err(GetError::Internal(e)) => Err(e.into()),
+        }
     }
 }
@@ -7,7 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use crate::domain::repos::named_object_repository::{DeleteError, GetError, SetError};
+use crate::domain::repos::named_object_repository::{DeleteError, GetError, ListError, SetError};
 use crate::domain::*;
 
 use async_trait::async_trait;
@@ -60,6 +60,14 @@ impl NamedObjectRepository for NamedObjectRepositoryInMemory {
         objects_by_name.remove(name);
         Ok(())
     }
+
+    /// Lists names of all stored objects
+    async fn list(&self) -> Result<Vec<String>, ListError> {
+        let objects_by_name = self.objects_by_name.lock().unwrap();
+        let mut names: Vec<_> = objects_by_name.keys().cloned().collect();
+        names.sort();
+        Ok(names)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
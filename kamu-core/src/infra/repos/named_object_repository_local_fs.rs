@@ -7,7 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use crate::domain::repos::named_object_repository::{DeleteError, GetError, SetError};
+use crate::domain::repos::named_object_repository::{DeleteError, GetError, ListError, SetError};
 use crate::domain::*;
 
 use async_trait::async_trait;
@@ -71,8 +71,14 @@ impl NamedObjectRepository for NamedObjectRepositoryLocalFS {
         let staging_path = self.get_staging_path();
         tokio::fs::write(&staging_path, data).await.int_err()?;
 
+        // Names can be hierarchical (e.g. `tags/v1`)
+        let target_path = self.root.join(name);
+        if let Some(parent) = target_path.parent() {
+            tokio::fs::create_dir_all(parent).await.int_err()?;
+        }
+
         // Atomic move/replace
-        std::fs::rename(&staging_path, &self.root.join(name)).int_err()?;
+        std::fs::rename(&staging_path, &target_path).int_err()?;
         Ok(())
     }
 
@@ -83,4 +89,32 @@ impl NamedObjectRepository for NamedObjectRepositoryLocalFS {
             Err(e) => Err(e.int_err().into()),
         }
     }
+
+    async fn list(&self) -> Result<Vec<String>, ListError> {
+        let mut names = Vec::new();
+        if !self.root.exists() {
+            return Ok(names);
+        }
+
+        for entry in walkdir::WalkDir::new(&self.root).min_depth(1) {
+            let entry = entry.int_err()?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let file_name = entry.file_name().to_string_lossy();
+            if file_name.starts_with(".pending-") {
+                continue;
+            }
+            let rel_path = entry.path().strip_prefix(&self.root).int_err()?;
+            let name = rel_path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            names.push(name);
+        }
+
+        names.sort();
+        Ok(names)
+    }
 }
@@ -7,7 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use crate::domain::repos::named_object_repository::{DeleteError, GetError, SetError};
+use crate::domain::repos::named_object_repository::{DeleteError, GetError, ListError, SetError};
 use crate::domain::*;
 
 use async_trait::async_trait;
@@ -179,4 +179,49 @@ impl NamedObjectRepository for NamedObjectRepositoryS3 {
 
         Ok(())
     }
+
+    async fn list(&self) -> Result<Vec<String>, ListError> {
+        debug!(key_prefix = ?self.key_prefix, "Listing objects");
+
+        let mut names = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let resp = match self
+                .client
+                .list_objects_v2(ListObjectsV2Request {
+                    bucket: self.bucket.clone(),
+                    prefix: Some(self.key_prefix.clone()),
+                    continuation_token: continuation_token.take(),
+                    ..ListObjectsV2Request::default()
+                })
+                .await
+            {
+                Ok(resp) => Ok(resp),
+                Err(e @ RusotoError::Credentials(_)) => {
+                    Err(ListError::Access(AccessError::Unauthorized(e.into())))
+                }
+                Err(e) => Err(e.int_err().into()),
+            }?;
+
+            for obj in resp.contents.unwrap_or_default() {
+                if let Some(name) = obj
+                    .key
+                    .as_ref()
+                    .and_then(|k| k.strip_prefix(&self.key_prefix))
+                {
+                    names.push(name.to_owned());
+                }
+            }
+
+            if resp.is_truncated == Some(true) && resp.next_continuation_token.is_some() {
+                continuation_token = resp.next_continuation_token;
+            } else {
+                break;
+            }
+        }
+
+        names.sort();
+        Ok(names)
+    }
 }
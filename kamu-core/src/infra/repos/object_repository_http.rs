@@ -10,11 +10,12 @@
 use std::path::Path;
 
 use crate::domain::*;
-use opendatafabric::Multihash;
+use opendatafabric::{Multicodec, Multihash};
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use reqwest::Client;
+use std::marker::PhantomData;
 use tokio::io::AsyncRead;
 use tracing::debug;
 use url::Url;
@@ -25,14 +26,20 @@ type AsyncReadObj = dyn AsyncRead + Send + Unpin;
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
-pub struct ObjectRepositoryHttp {
+// TODO: Pass a single type that configures digest algo, multicodec, and hash base
+pub struct ObjectRepositoryHttp<D, const C: u32> {
     client: Client,
     base_url: Url,
+    _phantom: PhantomData<D>,
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
-impl ObjectRepositoryHttp {
+impl<D, const C: u32> ObjectRepositoryHttp<D, C>
+where
+    D: Send + Sync,
+    D: digest::Digest,
+{
     pub fn new(client: Client, base_url: Url) -> Self {
         assert!(
             !base_url.cannot_be_a_base()
@@ -40,14 +47,58 @@ impl ObjectRepositoryHttp {
             "Invalid base url: {}",
             base_url
         );
-        Self { client, base_url }
+        Self {
+            client,
+            base_url,
+            _phantom: PhantomData,
+        }
+    }
+
+    async fn put(
+        &self,
+        hash: &Multihash,
+        body: reqwest::Body,
+        size: Option<usize>,
+    ) -> Result<(), InsertError> {
+        let url = self.base_url.join(&hash.to_multibase_string()).int_err()?;
+
+        debug!(%url, ?size, "Inserting object");
+
+        let mut request = self.client.put(url).body(body);
+        if let Some(size) = size {
+            request = request.header(reqwest::header::CONTENT_LENGTH, size);
+        }
+
+        let response = request.send().await.int_err()?;
+
+        match response.error_for_status() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(match map_write_error(e) {
+                Ok(e) => InsertError::Access(e),
+                Err(e) => InsertError::Internal(e),
+            }),
+        }
+    }
+}
+
+/// Maps HTTP errors of write requests into access errors where applicable
+pub(crate) fn map_write_error(e: reqwest::Error) -> Result<AccessError, InternalError> {
+    match e.status() {
+        Some(reqwest::StatusCode::UNAUTHORIZED) => Ok(AccessError::Unauthorized(e.into())),
+        Some(reqwest::StatusCode::FORBIDDEN) => Ok(AccessError::Forbidden(e.into())),
+        Some(reqwest::StatusCode::METHOD_NOT_ALLOWED) => Ok(AccessError::ReadOnly(Some(e.into()))),
+        _ => Err(e.int_err()),
     }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
 #[async_trait]
-impl ObjectRepository for ObjectRepositoryHttp {
+impl<D, const C: u32> ObjectRepository for ObjectRepositoryHttp<D, C>
+where
+    D: Send + Sync,
+    D: digest::Digest,
+{
     async fn contains(&self, hash: &Multihash) -> Result<bool, ContainsError> {
         let url = self.base_url.join(&hash.to_multibase_string()).int_err()?;
 
@@ -133,29 +184,108 @@ impl ObjectRepository for ObjectRepositoryHttp {
 
     async fn insert_bytes<'a>(
         &'a self,
-        _data: &'a [u8],
-        _options: InsertOpts<'a>,
+        data: &'a [u8],
+        options: InsertOpts<'a>,
     ) -> Result<InsertResult, InsertError> {
-        Err(AccessError::ReadOnly(None).into())
+        let hash = if let Some(hash) = options.precomputed_hash {
+            hash.clone()
+        } else {
+            Multihash::from_digest::<D>(Multicodec::try_from(C).unwrap(), data)
+        };
+
+        if let Some(expected_hash) = options.expected_hash {
+            if *expected_hash != hash {
+                return Err(InsertError::HashMismatch(HashMismatchError {
+                    expected: expected_hash.clone(),
+                    actual: hash,
+                }));
+            }
+        }
+
+        // TODO: PERF: Avoid copying data into a buffer
+        self.put(&hash, Vec::from(data).into(), Some(data.len()))
+            .await?;
+
+        Ok(InsertResult {
+            hash,
+            already_existed: false,
+        })
     }
 
     async fn insert_stream<'a>(
         &'a self,
-        _src: Box<AsyncReadObj>,
-        _options: InsertOpts<'a>,
+        mut src: Box<AsyncReadObj>,
+        options: InsertOpts<'a>,
     ) -> Result<InsertResult, InsertError> {
-        Err(AccessError::ReadOnly(None).into())
+        // Hash has to be known before the upload starts, so without it we buffer the data
+        let hash = if let Some(hash) = options.precomputed_hash {
+            hash.clone()
+        } else {
+            use tokio::io::AsyncReadExt;
+            let mut data = Vec::new();
+            src.read_to_end(&mut data).await.int_err()?;
+            return self.insert_bytes(&data, options).await;
+        };
+
+        if self.contains(&hash).await? {
+            return Ok(InsertResult {
+                hash,
+                already_existed: true,
+            });
+        }
+
+        // Server verifies the hash of uploaded object against its name
+        let stream = tokio_util::io::ReaderStream::new(src);
+        self.put(&hash, reqwest::Body::wrap_stream(stream), options.size_hint)
+            .await?;
+
+        Ok(InsertResult {
+            hash,
+            already_existed: false,
+        })
     }
 
     async fn insert_file_move<'a>(
         &'a self,
-        _src: &Path,
-        _options: InsertOpts<'a>,
+        src: &Path,
+        options: InsertOpts<'a>,
     ) -> Result<InsertResult, InsertError> {
-        Err(AccessError::ReadOnly(None).into())
+        let file = tokio::fs::File::open(src).await.int_err()?;
+        let size = file.metadata().await.int_err()?.len() as usize;
+
+        let res = self
+            .insert_stream(
+                Box::new(file),
+                InsertOpts {
+                    size_hint: Some(size),
+                    ..options
+                },
+            )
+            .await?;
+
+        tokio::fs::remove_file(src).await.int_err()?;
+        Ok(res)
+    }
+
+    async fn delete(&self, hash: &Multihash) -> Result<(), DeleteError> {
+        let url = self.base_url.join(&hash.to_multibase_string()).int_err()?;
+
+        debug!(%url, "Deleting object");
+
+        let response = self.client.delete(url).send().await.int_err()?;
+
+        match response.error_for_status() {
+            Ok(_) => Ok(()),
+            Err(e) if e.status() == Some(reqwest::StatusCode::NOT_FOUND) => Ok(()),
+            Err(e) => Err(match map_write_error(e) {
+                Ok(e) => DeleteError::Access(e),
+                Err(e) => DeleteError::Internal(e),
+            }),
+        }
     }
 
-    async fn delete(&self, _hash: &Multihash) -> Result<(), DeleteError> {
-        Err(AccessError::ReadOnly(None).into())
+    // TODO: Plain HTTP repositories have no way to enumerate objects
+    async fn list(&self) -> Result<Vec<ObjectInfo>, ListObjectsError> {
+        Err(InternalError::new("Listing objects is not supported by HTTP repositories").into())
     }
 }
@@ -109,6 +109,18 @@ impl ObjectRepository for ObjectRepositoryInMemory {
         blocks_by_hash.remove(hash);
         Ok(())
     }
+
+    async fn list(&self) -> Result<Vec<ObjectInfo>, ListObjectsError> {
+        let blocks_by_hash = self.blocks_by_hash.lock().unwrap();
+        Ok(blocks_by_hash
+            .iter()
+            .map(|(hash, bytes)| ObjectInfo {
+                hash: hash.clone(),
+                size: bytes.len() as u64,
+                last_modified: None,
+            })
+            .collect())
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
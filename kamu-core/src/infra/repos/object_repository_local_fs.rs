@@ -304,4 +304,41 @@ where
         }
         Ok(())
     }
+
+    async fn list(&self) -> Result<Vec<ObjectInfo>, ListObjectsError> {
+        debug!(root = ?self.root, "Listing objects");
+
+        let mut objects = Vec::new();
+
+        if !self.root.exists() {
+            return Ok(objects);
+        }
+
+        let mut entries = tokio::fs::read_dir(&self.root).await.int_err()?;
+        while let Some(entry) = entries.next_entry().await.int_err()? {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+
+            // Skip staging files
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            let metadata = entry.metadata().await.int_err()?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            match Multihash::from_multibase_str(&file_name) {
+                Ok(hash) => objects.push(ObjectInfo {
+                    hash,
+                    size: metadata.len(),
+                    last_modified: metadata.modified().ok().map(Into::into),
+                }),
+                Err(_) => debug!(%file_name, "Skipping file that is not a valid object"),
+            }
+        }
+
+        Ok(objects)
+    }
 }
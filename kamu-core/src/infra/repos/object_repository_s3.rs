@@ -322,4 +322,59 @@ where
 
         Ok(())
     }
+
+    async fn list(&self) -> Result<Vec<ObjectInfo>, ListObjectsError> {
+        debug!(key_prefix = ?self.key_prefix, "Listing objects");
+
+        let mut objects = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let resp = match self
+                .client
+                .list_objects_v2(ListObjectsV2Request {
+                    bucket: self.bucket.clone(),
+                    prefix: Some(self.key_prefix.clone()),
+                    continuation_token: continuation_token.take(),
+                    ..ListObjectsV2Request::default()
+                })
+                .await
+            {
+                Ok(resp) => Ok(resp),
+                Err(e @ RusotoError::Credentials(_)) => Err(ListObjectsError::Access(
+                    AccessError::Unauthorized(e.into()),
+                )),
+                Err(e) => Err(e.int_err().into()),
+            }?;
+
+            for obj in resp.contents.unwrap_or_default() {
+                let hash = obj
+                    .key
+                    .as_ref()
+                    .and_then(|k| k.strip_prefix(&self.key_prefix))
+                    .and_then(|name| Multihash::from_multibase_str(name).ok());
+
+                // Skip keys that don't represent objects, e.g. from nested prefixes
+                if let Some(hash) = hash {
+                    objects.push(ObjectInfo {
+                        hash,
+                        size: obj.size.unwrap_or(0) as u64,
+                        last_modified: obj
+                            .last_modified
+                            .as_deref()
+                            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                            .map(|t| t.with_timezone(&chrono::Utc)),
+                    });
+                }
+            }
+
+            if resp.is_truncated == Some(true) && resp.next_continuation_token.is_some() {
+                continuation_token = resp.next_continuation_token;
+            } else {
+                break;
+            }
+        }
+
+        Ok(objects)
+    }
 }
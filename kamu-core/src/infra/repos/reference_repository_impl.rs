@@ -7,7 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use crate::domain::repos::named_object_repository::{DeleteError, GetError};
+use crate::domain::repos::named_object_repository::{DeleteError, GetError, ListError};
 use crate::domain::repos::reference_repository::SetRefError;
 use crate::domain::*;
 use opendatafabric::Multihash;
@@ -36,7 +36,7 @@ where
     R: NamedObjectRepository + Send + Sync,
 {
     async fn get(&self, r: &BlockRef) -> Result<Multihash, GetRefError> {
-        let data = match self.repo.get(&r.as_str()).await {
+        let data = match self.repo.get(&r.path()).await {
             Ok(data) => Ok(data),
             Err(GetError::NotFound(_)) => Err(GetRefError::NotFound(RefNotFoundError {
                 block_ref: r.clone(),
@@ -51,7 +51,7 @@ where
 
     async fn set(&self, r: &BlockRef, hash: &Multihash) -> Result<(), SetRefError> {
         let multibase = hash.to_multibase_string();
-        match self.repo.set(&r.as_str(), multibase.as_bytes()).await {
+        match self.repo.set(&r.path(), multibase.as_bytes()).await {
             Ok(()) => Ok(()),
             Err(SetError::Access(e)) => Err(SetRefError::Access(e)),
             Err(SetError::Internal(e)) => Err(SetRefError::Internal(e)),
@@ -59,10 +59,26 @@ where
     }
 
     async fn delete(&self, r: &BlockRef) -> Result<(), DeleteRefError> {
-        match self.repo.delete(r.as_str()).await {
+        match self.repo.delete(&r.path()).await {
             Ok(()) => Ok(()),
             Err(DeleteError::Access(e)) => Err(DeleteRefError::Access(e)),
             Err(DeleteError::Internal(e)) => Err(DeleteRefError::Internal(e)),
         }
     }
+
+    async fn list(&self) -> Result<Vec<BlockRef>, ListRefsError> {
+        let names = match self.repo.list().await {
+            Ok(names) => Ok(names),
+            Err(ListError::Access(e)) => Err(ListRefsError::Access(e)),
+            Err(ListError::Internal(e)) => Err(ListRefsError::Internal(e)),
+        }?;
+
+        // Skip any unrelated objects that might be stored alongside the references
+        let mut refs: Vec<_> = names
+            .iter()
+            .filter_map(|name| BlockRef::from_path(name).ok())
+            .collect();
+        refs.sort();
+        Ok(refs)
+    }
 }
@@ -8,22 +8,36 @@
 // by the Apache License, Version 2.0.
 
 use dill::*;
+use futures::{StreamExt, TryStreamExt};
 use opendatafabric::*;
-use tracing::info;
+use rusoto_core::{Region, RusotoError};
+use rusoto_s3::*;
+use tracing::{info, warn};
 
 use crate::domain::*;
+use crate::infra::ObjectRepositoryS3;
 
 use std::sync::Arc;
 use url::Url;
 
+/// Maximum number of remote dataset summaries read at the same time
+const MAX_CONCURRENT_SUMMARY_READS: usize = 16;
+
 pub struct SearchServiceImpl {
     remote_repo_reg: Arc<dyn RemoteRepositoryRegistry>,
+    dataset_factory: Arc<dyn DatasetFactory>,
 }
 
 #[component(pub)]
 impl SearchServiceImpl {
-    pub fn new(remote_repo_reg: Arc<dyn RemoteRepositoryRegistry>) -> Self {
-        Self { remote_repo_reg }
+    pub fn new(
+        remote_repo_reg: Arc<dyn RemoteRepositoryRegistry>,
+        dataset_factory: Arc<dyn DatasetFactory>,
+    ) -> Self {
+        Self {
+            remote_repo_reg,
+            dataset_factory,
+        }
     }
 
     // TODO: This is crude temporary implementation until ODF specifies registry interface
@@ -31,7 +45,8 @@ impl SearchServiceImpl {
         &self,
         url: &Url,
         query: Option<&str>,
-    ) -> Result<Vec<DatasetNameWithOwner>, SearchError> {
+    ) -> Result<Vec<DatasetName>, SearchError> {
+        let query = query.unwrap_or_default();
         let mut datasets = Vec::new();
 
         match url.scheme() {
@@ -40,25 +55,25 @@ impl SearchServiceImpl {
                     .to_file_path()
                     .map_err(|_| "Invalid path URL")
                     .int_err()?;
-                let query = query.unwrap_or_default();
                 for entry in std::fs::read_dir(&path).int_err()? {
                     if let Some(file_name) = entry.int_err()?.file_name().to_str() {
-                        if query.is_empty() || file_name.contains(query) {
-                            datasets.push(DatasetNameWithOwner::new(
-                                None,
-                                DatasetName::try_from(file_name).unwrap(),
-                            ));
+                        // Skip entries that don't represent datasets
+                        if let Ok(name) = DatasetName::try_from(file_name) {
+                            if query.is_empty() || name.contains(query) {
+                                datasets.push(name);
+                            }
                         }
                     }
                 }
             }
             "s3" | "s3+http" | "s3+https" => {
-                use crate::infra::ObjectRepositoryS3;
-                use rusoto_core::Region;
-                use rusoto_s3::*;
+                let (endpoint, bucket, mut key_prefix) =
+                    ObjectRepositoryS3::<(), 0>::split_url(url);
 
-                // TODO: Support prefix?
-                let (endpoint, bucket, _) = ObjectRepositoryS3::<(), 0>::split_url(url);
+                // Repositories rooted under a sub-path are treated as directories
+                if !key_prefix.is_empty() && !key_prefix.ends_with('/') {
+                    key_prefix.push('/');
+                }
 
                 let region = match endpoint {
                     None => Region::default(),
@@ -69,33 +84,46 @@ impl SearchServiceImpl {
                 };
                 let client = S3Client::new(region);
 
-                let query = query.unwrap_or_default();
-
-                let list_objects_resp = client
-                    .list_objects_v2(ListObjectsV2Request {
-                        bucket,
-                        delimiter: Some("/".to_owned()),
-                        ..ListObjectsV2Request::default()
-                    })
-                    .await
-                    .int_err()?;
-
-                // TODO: Support iteration
-                assert!(
-                    !list_objects_resp.is_truncated.unwrap_or(false),
-                    "Cannot handle truncated response"
-                );
-
-                for prefix in list_objects_resp.common_prefixes.unwrap_or_default() {
-                    let mut prefix = prefix.prefix.unwrap();
-                    while prefix.ends_with('/') {
-                        prefix.pop();
+                let mut continuation_token = None;
+
+                loop {
+                    let resp = match client
+                        .list_objects_v2(ListObjectsV2Request {
+                            bucket: bucket.clone(),
+                            prefix: Some(key_prefix.clone()).filter(|p| !p.is_empty()),
+                            delimiter: Some("/".to_owned()),
+                            continuation_token: continuation_token.take(),
+                            ..ListObjectsV2Request::default()
+                        })
+                        .await
+                    {
+                        Ok(resp) => Ok(resp),
+                        Err(e @ RusotoError::Credentials(_)) => {
+                            Err(SearchError::Access(AccessError::Unauthorized(e.into())))
+                        }
+                        Err(e) => Err(e.int_err().into()),
+                    }?;
+
+                    for prefix in resp.common_prefixes.unwrap_or_default() {
+                        let name = prefix
+                            .prefix
+                            .as_ref()
+                            .and_then(|p| p.strip_prefix(&key_prefix))
+                            .map(|p| p.trim_end_matches('/'))
+                            .and_then(|p| DatasetName::try_from(p).ok());
+
+                        // Skip prefixes that don't represent datasets
+                        if let Some(name) = name {
+                            if query.is_empty() || name.contains(query) {
+                                datasets.push(name);
+                            }
+                        }
                     }
 
-                    let name = DatasetName::try_from(prefix).int_err()?;
-
-                    if query.is_empty() || name.contains(query) {
-                        datasets.push(DatasetNameWithOwner::new(None, name));
+                    if resp.is_truncated == Some(true) && resp.next_continuation_token.is_some() {
+                        continuation_token = resp.next_continuation_token;
+                    } else {
+                        break;
                     }
                 }
             }
@@ -111,6 +139,43 @@ impl SearchServiceImpl {
         Ok(datasets)
     }
 
+    /// Reads the summary that the remote dataset maintains in its `info/` section.
+    /// Summaries are never computed here, as that would require scanning the entire
+    /// remote metadata chain and writing into a repository we may only have read access to.
+    async fn read_summary(
+        &self,
+        dataset_url: &Url,
+        auth_token: Option<&str>,
+    ) -> Result<Option<DatasetSummary>, SearchError> {
+        let dataset = match self
+            .dataset_factory
+            .get_dataset(dataset_url, false, auth_token)
+        {
+            Ok(ds) => Ok(ds),
+            Err(BuildDatasetError::UnsupportedProtocol(e)) => Err(SearchError::from(e)),
+            Err(BuildDatasetError::Internal(e)) => Err(e.into()),
+        }?;
+
+        match dataset
+            .get_summary(GetSummaryOpts {
+                update_if_stale: false,
+            })
+            .await
+        {
+            Ok(summary) => Ok(Some(summary)),
+            Err(GetSummaryError::EmptyDataset) => Ok(None),
+            // Summary is optional, so a single inaccessible dataset should not fail the search
+            Err(GetSummaryError::Access(e)) => {
+                warn!(error = ?e, %dataset_url, "Access denied to remote dataset summary");
+                Ok(None)
+            }
+            Err(GetSummaryError::Internal(e)) => {
+                warn!(error = ?e, %dataset_url, "Failed to read remote dataset summary");
+                Ok(None)
+            }
+        }
+    }
+
     async fn search_in_repo(
         &self,
         query: Option<&str>,
@@ -120,14 +185,32 @@ impl SearchServiceImpl {
 
         info!(repo_id = repo_name.as_str(), repo_url = ?repo.url, query = ?query, "Searching remote repository");
 
-        let datasets = self.search_in_resource(&repo.url, query).await?;
+        let mut repo_url = repo.url.clone();
+        if !repo_url.path().ends_with('/') {
+            repo_url.set_path(&format!("{}/", repo_url.path()));
+        }
+
+        let names = self.search_in_resource(&repo_url, query).await?;
+
+        let datasets = futures::stream::iter(names)
+            .map(|name| {
+                let repo_url = &repo_url;
+                let auth_token = repo.auth_token.as_deref();
+                async move {
+                    let dataset_url = repo_url.join(&format!("{}/", name)).int_err()?;
+                    let summary = self.read_summary(&dataset_url, auth_token).await?;
+
+                    Ok::<_, SearchError>(SearchResultDataset::new(
+                        RemoteDatasetName::new(repo_name.clone(), None, name),
+                        summary,
+                    ))
+                }
+            })
+            .buffered(MAX_CONCURRENT_SUMMARY_READS)
+            .try_collect()
+            .await?;
 
-        Ok(SearchResult {
-            datasets: datasets
-                .into_iter()
-                .map(|name| name.as_remote_name(repo_name))
-                .collect(),
-        })
+        Ok(SearchResult { datasets })
     }
 }
 
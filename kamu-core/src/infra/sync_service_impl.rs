@@ -11,6 +11,7 @@ use crate::domain::sync_service::DatasetNotFoundError;
 use crate::domain::*;
 use crate::infra::utils::ipfs_wrapper::*;
 use crate::infra::utils::simple_transfer_protocol::SimpleTransferProtocol;
+use crate::infra::utils::smart_transfer_protocol::SmartTransferProtocol;
 use opendatafabric::*;
 
 use dill::*;
@@ -66,10 +67,8 @@ impl SyncServiceImpl {
         }
     }
 
-    async fn resolve_remote_dataset_url(
-        &self,
-        remote_ref: &DatasetRefRemote,
-    ) -> Result<Url, SyncError> {
+    /// Returns the dataset URL as configured, without resolving IPFS names
+    fn get_remote_dataset_url(&self, remote_ref: &DatasetRefRemote) -> Result<Url, SyncError> {
         // TODO: REMOTE ID
         let dataset_url = match remote_ref {
             DatasetRefRemote::ID(_) => {
@@ -91,6 +90,30 @@ impl SyncServiceImpl {
             }
         };
 
+        Ok(dataset_url)
+    }
+
+    /// Returns the token for authenticating with the repository the dataset belongs to
+    fn get_remote_auth_token(
+        &self,
+        remote_ref: &DatasetRefRemote,
+    ) -> Result<Option<String>, SyncError> {
+        match remote_ref {
+            DatasetRefRemote::RemoteName(name)
+            | DatasetRefRemote::RemoteHandle(RemoteDatasetHandle { name, .. }) => Ok(self
+                .remote_repo_reg
+                .get_repository(name.repository())?
+                .auth_token),
+            DatasetRefRemote::ID(_) | DatasetRefRemote::Url(_) => Ok(None),
+        }
+    }
+
+    async fn resolve_remote_dataset_url(
+        &self,
+        remote_ref: &DatasetRefRemote,
+    ) -> Result<Url, SyncError> {
+        let dataset_url = self.get_remote_dataset_url(remote_ref)?;
+
         // Resolve IPNS DNSLink names if configured
         let dataset_url = match dataset_url.scheme() {
             "ipns" if self.ipfs_gateway.pre_resolve_dnslink => {
@@ -170,7 +193,9 @@ impl SyncServiceImpl {
         } else {
             let remote_ref = dataset_ref.as_remote_ref().unwrap();
             let url = self.resolve_remote_dataset_url(&remote_ref).await?;
-            self.dataset_factory.get_dataset(&url, false)?
+            let auth_token = self.get_remote_auth_token(&remote_ref)?;
+            self.dataset_factory
+                .get_dataset(&url, false, auth_token.as_deref())?
         };
 
         match dataset.as_metadata_chain().get_ref(&BlockRef::Head).await {
@@ -202,9 +227,12 @@ impl SyncServiceImpl {
         } else {
             let remote_ref = dataset_ref.as_remote_ref().unwrap();
             let url = self.resolve_remote_dataset_url(&remote_ref).await?;
-            let dataset = self
-                .dataset_factory
-                .get_dataset(&url, create_if_not_exists)?;
+            let auth_token = self.get_remote_auth_token(&remote_ref)?;
+            let dataset = self.dataset_factory.get_dataset(
+                &url,
+                create_if_not_exists,
+                auth_token.as_deref(),
+            )?;
 
             if !create_if_not_exists {
                 match dataset.as_metadata_chain().get_ref(&BlockRef::Head).await {
@@ -241,6 +269,39 @@ impl SyncServiceImpl {
             AppendValidation::Full
         };
 
+        // Datasets hosted by kamu over HTTP can be pulled efficiently in a single bundle
+        if let Some(remote_ref) = src.as_remote_ref() {
+            let src_url = self.get_remote_dataset_url(&remote_ref)?;
+            if src_url.scheme() == "http" || src_url.scheme() == "https" {
+                info!("Attempting sync using Smart Transfer Protocol");
+                match SmartTransferProtocol::default()
+                    .sync(
+                        &src_url,
+                        dst_dataset_builder.as_dataset(),
+                        validation,
+                        opts.trust_source.unwrap_or(src_is_local),
+                        opts.force,
+                        listener.clone(),
+                    )
+                    .await
+                {
+                    Ok(Some(result)) => {
+                        info!(?result, "Sync completed");
+                        dst_dataset_builder.finish().await?;
+                        return Ok(result);
+                    }
+                    Ok(None) => {
+                        info!("Source does not support Smart Transfer Protocol");
+                    }
+                    Err(error) => {
+                        info!(?error, "Sync failed");
+                        dst_dataset_builder.discard().await?;
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
         info!("Starting sync using Simple Transfer Protocol");
         match SimpleTransferProtocol
             .sync(
@@ -313,7 +374,9 @@ impl SyncServiceImpl {
                     let dst_http_url = self
                         .resolve_remote_dataset_url(&DatasetRefRemote::from(dst_url))
                         .await?;
-                    let dst_dataset = self.dataset_factory.get_dataset(&dst_http_url, false)?;
+                    let dst_dataset =
+                        self.dataset_factory
+                            .get_dataset(&dst_http_url, false, None)?;
                     match dst_dataset
                         .as_metadata_chain()
                         .get_ref(&BlockRef::Head)
@@ -17,7 +17,7 @@ use dill::*;
 use futures::{StreamExt, TryFutureExt, TryStreamExt};
 use opendatafabric::serde::flatbuffers::FlatbuffersMetadataBlockSerializer;
 use opendatafabric::serde::MetadataBlockSerializer;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::debug;
@@ -263,6 +263,8 @@ impl TransformServiceImpl {
             .await
             .int_err()?;
 
+        dataset.sign_block(&new_block_hash).await?;
+
         info!(output_dataset = %dataset_handle, new_head = %new_block_hash, "Committed new block");
 
         Ok(TransformResult::Updated {
@@ -288,32 +290,55 @@ impl TransformServiceImpl {
             .int_err()?;
         let output_chain = dataset.as_metadata_chain();
 
+        // Sources are collected in reverse chronological order, with the
+        // latest checkpoint remembering how many sources were set after it
         // TODO: limit traversal depth
-        let mut sources: Vec<_> = output_chain
-            .iter_blocks()
-            .try_filter_map(|(_, b)| async move {
-                match b.event {
-                    MetadataEvent::SetTransform(st) => Ok(Some(st)),
+        let mut sources = Vec::new();
+        let mut prev_checkpoint = None;
+
+        {
+            let mut block_stream = output_chain.iter_blocks();
+            while let Some((_, block)) = block_stream.try_next().await.int_err()? {
+                match block.event {
+                    MetadataEvent::SetTransform(st) => sources.push(st),
                     MetadataEvent::SetPollingSource(_) => {
-                        Err("Transform called on non-derivative dataset"
-                            .int_err()
-                            .into())
+                        return Err("Transform called on non-derivative dataset".int_err());
+                    }
+                    MetadataEvent::ExecuteQuery(eq) => {
+                        if prev_checkpoint.is_none() {
+                            prev_checkpoint = eq.output_checkpoint.map(|cp| (cp, sources.len()));
+                        }
                     }
-                    _ => Ok(None),
+                    _ => (),
                 }
+            }
+        }
+
+        let source = sources.first().cloned().ok_or_else(|| {
+            "Expected a derivative dataset but SetTransform block was not found".int_err()
+        })?;
+        debug!(?source, "Transforming using source");
+
+        // Engine state is only valid for the query that produced it, so
+        // checkpoints are reset when the query has changed since
+        // TODO: Checkpoint hash should be contained in metadata explicitly, not inferred
+        let prev_checkpoint = prev_checkpoint
+            .filter(|(_, era)| {
+                sources
+                    .iter()
+                    .take(era + 1)
+                    .all(|s| s.transform == source.transform)
             })
-            .try_collect()
-            .await
-            .int_err()?;
+            .map(|(cp, _)| cp);
 
-        // TODO: source could've changed several times
         if sources.len() > 1 {
-            unimplemented!("Transform evolution is not yet supported");
+            info!(
+                num_sources = sources.len(),
+                resets_checkpoint = prev_checkpoint.is_none(),
+                "Transforming using the latest of evolved sources"
+            );
         }
 
-        let source = sources.pop().unwrap();
-        debug!(?source, "Transforming using source");
-
         if futures::stream::iter(&source.inputs)
             .map(|input| input.id.as_ref().unwrap().as_local_ref())
             .then(|input_ref| async move { self.is_never_pulled(&input_ref).await })
@@ -325,6 +350,7 @@ impl TransformServiceImpl {
         }
 
         // Prepare inputs
+        // Note: Inputs added by the latest source were never processed and will start from their beginning
         let input_slices: Vec<_> = futures::stream::iter(&source.inputs)
             .then(|input| self.get_input_slice(input.id.as_ref().unwrap(), output_chain))
             .try_collect()
@@ -347,15 +373,6 @@ impl TransformServiceImpl {
 
         let vocab = self.get_vocab(&dataset_handle.as_local_ref()).await?;
 
-        // TODO: Checkpoint hash should be contained in metadata explicitly, not inferred
-        let prev_checkpoint = output_chain
-            .iter_blocks()
-            .filter_map_ok(|(_, b)| b.event.into_variant::<ExecuteQuery>())
-            .filter_map_ok(|b| b.output_checkpoint)
-            .try_first()
-            .await
-            .int_err()?;
-
         let data_offset_end = output_chain
             .iter_blocks()
             .filter_map_ok(|(_, b)| b.event.into_variant::<ExecuteQuery>())
@@ -640,7 +657,9 @@ impl TransformServiceImpl {
         };
         let tail = block_range.0;
 
-        let mut source = None;
+        // Sources are collected with their sequence numbers to later find
+        // the transform that was active when each block was produced
+        let mut sources = Vec::new();
         let mut vocab = None;
         let mut blocks = Vec::new();
         let mut finished_range = false;
@@ -652,14 +671,7 @@ impl TransformServiceImpl {
             while let Some((block_hash, block)) = block_stream.try_next().await? {
                 match block.event {
                     MetadataEvent::SetTransform(st) => {
-                        if source.is_none() {
-                            source = Some(st);
-                        } else {
-                            // TODO: Support dataset evolution
-                            unimplemented!(
-                                "Verifying datasets with evolving queries is not yet supported"
-                            );
-                        }
+                        sources.push((block.sequence_number, st));
                     }
                     MetadataEvent::SetVocab(sv) => {
                         if vocab.is_none() {
@@ -696,20 +708,26 @@ impl TransformServiceImpl {
             .into());
         }
 
-        let source = source.ok_or(
-            "Expected a derivative dataset but SetTransform block was not found".int_err(),
-        )?;
+        if sources.is_empty() {
+            return Err(
+                "Expected a derivative dataset but SetTransform block was not found"
+                    .int_err()
+                    .into(),
+            );
+        }
         let dataset_layout = self.workspace_layout.dataset_layout(&dataset_handle.name);
 
-        let dataset_vocabs: BTreeMap<_, _> = futures::stream::iter(&source.inputs)
-            .map(|input| {
-                (
-                    input.id.clone().unwrap(),
-                    input.id.as_ref().unwrap().as_local_ref(),
-                )
-            })
-            .then(|(input_id, input_ref)| async move {
-                self.get_vocab(&input_ref)
+        // Inputs could have been added and removed by evolving sources, so
+        // only resolve those that were actually used by the replayed blocks
+        let input_ids: BTreeSet<_> = blocks
+            .iter()
+            .filter_map(|(_, b)| b.as_typed::<ExecuteQuery>())
+            .flat_map(|b| b.event.input_slices.iter().map(|s| s.dataset_id.clone()))
+            .collect();
+
+        let dataset_vocabs: BTreeMap<_, _> = futures::stream::iter(input_ids)
+            .then(|input_id| async move {
+                self.get_vocab(&input_id.as_local_ref())
                     .map_ok(|vocab| (input_id, vocab))
                     .await
             })
@@ -721,6 +739,15 @@ impl TransformServiceImpl {
         for (block_hash, block) in blocks.into_iter().rev() {
             let block_t = block.as_typed::<ExecuteQuery>().unwrap();
 
+            // Sources are in reverse chronological order
+            let source = sources
+                .iter()
+                .find(|(sequence_number, _)| *sequence_number < block.sequence_number)
+                .map(|(_, st)| st)
+                .ok_or_else(|| {
+                    format!("Block {} precedes all SetTransform blocks", block_hash).int_err()
+                })?;
+
             let inputs = futures::stream::iter(&block_t.event.input_slices)
                 .map(|slice| {
                     (
@@ -870,10 +897,11 @@ impl TransformService for TransformServiceImpl {
         &self,
         dataset_ref: &DatasetRefLocal,
         block_range: (Option<Multihash>, Option<Multihash>),
-        _options: VerificationOptions,
+        options: VerificationOptions,
         maybe_listener: Option<Arc<dyn VerificationListener>>,
     ) -> Result<VerificationResult, VerificationError> {
         let listener = maybe_listener.unwrap_or(Arc::new(NullVerificationListener {}));
+        let mut report = VerificationReport::default();
 
         let dataset_handle = self.local_repo.resolve_dataset_ref(dataset_ref).await?;
 
@@ -980,16 +1008,25 @@ impl TransformService for TransformServiceImpl {
                 info!(block_hash = %expected_block_hash, expected = ?expected_block, actual = ?actual_block, "Block invalid");
 
                 let err = VerificationError::DataNotReproducible(DataNotReproducible {
-                    expected_block_hash,
+                    expected_block_hash: expected_block_hash.clone(),
                     expected_block,
                     actual_block_hash,
                     actual_block,
                 });
-                listener.error(&err);
-                return Err(err);
+
+                if !options.continue_on_error {
+                    listener.error(&err);
+                }
+                report.record(
+                    &expected_block_hash,
+                    VerificationPhase::ReplayTransform,
+                    err,
+                    &options,
+                )?;
+            } else {
+                info!(block_hash = %expected_block_hash, "Block valid");
             }
 
-            info!(block_hash = %expected_block_hash, "Block valid");
             listener.end_block(
                 &expected_block_hash,
                 step_index,
@@ -999,7 +1036,7 @@ impl TransformService for TransformServiceImpl {
         }
 
         listener.end_phase(VerificationPhase::ReplayTransform);
-        Ok(VerificationResult::Valid)
+        Ok(VerificationResult::from(report))
     }
 
     async fn verify_transform_multi(
@@ -55,3 +55,39 @@ pub fn get_file_physical_hash(file_path: &Path) -> Result<Multihash, std::io::Er
 
     Ok(Multihash::new(Multicodec::Sha3_256, &hasher.finalize()))
 }
+
+/// Copies the stream into a file computing its physical hash along the way.
+///
+/// Returns the hash and the number of bytes written.
+pub async fn write_stream_with_physical_hash<R>(
+    src: &mut R,
+    file_path: &Path,
+) -> Result<(Multihash, u64), std::io::Error>
+where
+    R: tokio::io::AsyncRead + Unpin + ?Sized,
+{
+    use digest::Digest;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut file = tokio::fs::File::create(file_path).await?;
+    let mut buffer = [0; 2048];
+    let mut hasher = sha3::Sha3_256::new();
+    let mut size = 0;
+
+    loop {
+        let count = src.read(&mut buffer).await?;
+        if count == 0 {
+            break;
+        }
+        hasher.update(&buffer[..count]);
+        file.write_all(&buffer[..count]).await?;
+        size += count as u64;
+    }
+
+    file.flush().await?;
+
+    Ok((
+        Multihash::new(Multicodec::Sha3_256, &hasher.finalize()),
+        size,
+    ))
+}
@@ -11,7 +11,7 @@ use std::{any::Any, sync::Arc};
 
 use async_trait::async_trait;
 use datafusion::{
-    arrow::datatypes::{Schema, SchemaRef},
+    arrow::datatypes::{Field, Schema, SchemaRef},
     datasource::{
         datasource::TableProviderFilterPushDown, file_format::FileFormat, TableProvider, TableType,
     },
@@ -62,6 +62,42 @@ impl ListingTableOfFiles {
             file_schema,
         })
     }
+
+    /// Same as [ListingTableOfFiles::try_new] but infers schema from all files
+    /// so that columns added or removed over time are all present in the
+    /// table. All fields are made nullable as some files may lack them.
+    pub async fn try_new_with_merged_schema(
+        ctx: &SessionState,
+        files: Vec<String>,
+    ) -> Result<Self> {
+        let format = Arc::new(ParquetFormat::new(ctx.config_options()));
+
+        let store = ctx
+            .runtime_env
+            .object_store(ObjectStoreUrl::local_filesystem())?;
+
+        let mut file_metas = Vec::new();
+        for file in &files {
+            let file_path = object_store::path::Path::parse(file).unwrap();
+            file_metas.push(store.head(&file_path).await?);
+        }
+
+        let merged_schema = format.infer_schema(&store, &file_metas).await?;
+        let file_schema = Arc::new(Schema::new(
+            merged_schema
+                .fields()
+                .iter()
+                .map(|f| Field::new(f.name(), f.data_type().clone(), true))
+                .collect(),
+        ));
+
+        Ok(Self {
+            format,
+            files,
+            table_schema: file_schema.clone(),
+            file_schema,
+        })
+    }
 }
 
 #[async_trait]
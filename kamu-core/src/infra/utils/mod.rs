@@ -13,4 +13,7 @@ pub mod docker_images;
 pub mod records_writers;
 pub mod schema_utils;
 pub mod simple_transfer_protocol;
-pub mod ipfs_wrapper;
\ No newline at end of file
+pub mod ipfs_wrapper;
+pub mod smart_transfer_protocol;
+pub mod sql_script;
+pub mod record_batch_stream;
\ No newline at end of file
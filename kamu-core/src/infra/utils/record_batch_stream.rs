@@ -0,0 +1,78 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::TryStreamExt;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Records collected from the beginning of a stream
+#[derive(Debug)]
+pub struct LimitedRecords {
+    pub batches: Vec<RecordBatch>,
+    pub num_records: u64,
+    /// Whether the stream had more records than the limit
+    pub has_more: bool,
+}
+
+/// Collects up to `limit` records from the stream.
+///
+/// Batches are pulled only until the limit is reached, so the rest of the
+/// result is never computed.
+pub async fn collect_limited(
+    mut stream: SendableRecordBatchStream,
+    limit: u64,
+) -> Result<LimitedRecords, DataFusionError> {
+    let mut batches = Vec::new();
+    let mut num_records = 0;
+
+    while let Some(batch) = stream.try_next().await? {
+        let remaining = limit - num_records;
+        let batch_len = batch.num_rows() as u64;
+
+        if batch_len > remaining {
+            if remaining != 0 {
+                batches.push(batch.slice(0, remaining as usize));
+                num_records += remaining;
+            }
+            return Ok(LimitedRecords {
+                batches,
+                num_records,
+                has_more: true,
+            });
+        }
+
+        num_records += batch_len;
+        batches.push(batch);
+
+        if num_records == limit {
+            // Check for remaining records without buffering them
+            let has_more = loop {
+                match stream.try_next().await? {
+                    Some(b) if b.num_rows() == 0 => continue,
+                    Some(_) => break true,
+                    None => break false,
+                }
+            };
+            return Ok(LimitedRecords {
+                batches,
+                num_records,
+                has_more,
+            });
+        }
+    }
+
+    Ok(LimitedRecords {
+        batches,
+        num_records,
+        has_more: false,
+    })
+}
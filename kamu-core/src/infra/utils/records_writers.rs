@@ -13,6 +13,9 @@ pub use datafusion::arrow::csv::{Writer as CsvWriter, WriterBuilder as CsvWriter
 pub use datafusion::arrow::json::ArrayWriter as JsonArrayWriter;
 pub use datafusion::arrow::json::LineDelimitedWriter as JsonLineDelimitedWriter;
 use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::TryStreamExt;
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
@@ -33,6 +36,23 @@ pub trait RecordsWriter {
     }
 }
 
+/// Writes records as they are produced by the stream, so that the entire result
+/// never has to be held in memory. The next batch is only pulled once the previous
+/// one is written. Returns the number of written records.
+///
+/// Note that writer is not finished, allowing to write several streams into it.
+pub async fn write_stream(
+    writer: &mut dyn RecordsWriter,
+    mut stream: SendableRecordBatchStream,
+) -> Result<u64, DataFusionError> {
+    let mut num_records = 0;
+    while let Some(batch) = stream.try_next().await? {
+        writer.write_batch(&batch)?;
+        num_records += batch.num_rows() as u64;
+    }
+    Ok(num_records)
+}
+
 /////////////////////////////////////////////////////////////////////////////////////////
 // CSV
 /////////////////////////////////////////////////////////////////////////////////////////
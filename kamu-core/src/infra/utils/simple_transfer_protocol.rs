@@ -18,6 +18,12 @@ use tracing::*;
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
+/// Kamu-specific cache files that are transferred along with the dataset
+pub(crate) const KAMU_CACHE_FILES: [&str; 4] =
+    ["fetch.yaml", "prep.yaml", "read.yaml", "commit.yaml"];
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
 /// Implements "Simple Transfer Protocol" as described in ODF spec
 pub struct SimpleTransferProtocol;
 
@@ -140,7 +146,7 @@ impl SimpleTransferProtocol {
         }
     }
 
-    async fn get_dest_head(
+    pub(crate) async fn get_dest_head(
         &self,
         dst_chain: &dyn MetadataChain,
     ) -> Result<Option<Multihash>, SyncError> {
@@ -171,6 +177,88 @@ impl SimpleTransferProtocol {
         }
     }
 
+    /// Appends a block received from the source to the destination chain without updating the head
+    pub(crate) async fn append_block(
+        dst: &dyn Dataset,
+        hash: Multihash,
+        block: MetadataBlock,
+        validation: AppendValidation,
+        trust_source_hashes: bool,
+    ) -> Result<(), SyncError> {
+        debug!(?hash, "Appending block");
+
+        match dst
+            .as_metadata_chain()
+            .append(
+                block,
+                AppendOpts {
+                    validation,
+                    update_ref: None, // We will update head once, after sync is complete
+                    precomputed_hash: if !trust_source_hashes {
+                        None
+                    } else {
+                        Some(&hash)
+                    },
+                    expected_hash: Some(&hash),
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(AppendError::InvalidBlock(AppendValidationError::HashMismatch(e))) => {
+                Err(CorruptedSourceError {
+                    message: concat!(
+                        "Block hash declared by the source didn't match ",
+                        "the computed - this may be an indication of hashing ",
+                        "algorithm mismatch or an attempted tampering",
+                    )
+                    .to_owned(),
+                    source: Some(e.into()),
+                }
+                .into())
+            }
+            Err(AppendError::InvalidBlock(e)) => Err(CorruptedSourceError {
+                message: "Source metadata chain is logically inconsistent".to_owned(),
+                source: Some(e.into()),
+            }
+            .into()),
+            Err(AppendError::RefNotFound(_) | AppendError::RefCASFailed(_)) => unreachable!(),
+            Err(AppendError::Access(e)) => Err(SyncError::Access(e)),
+            Err(AppendError::Internal(e)) => Err(SyncError::Internal(e)),
+        }?;
+
+        Ok(())
+    }
+
+    /// Points destination head to the new block, failing if it was concurrently modified
+    pub(crate) async fn update_head(
+        dst: &dyn Dataset,
+        src_head: &Multihash,
+        dst_head: Option<&Multihash>,
+    ) -> Result<(), SyncError> {
+        match dst
+            .as_metadata_chain()
+            .set_ref(
+                &BlockRef::Head,
+                src_head,
+                SetRefOpts {
+                    validate_block_present: false,
+                    check_ref_is: Some(dst_head),
+                },
+            )
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(SetRefError::CASFailed(e)) => Err(SyncError::UpdatedConcurrently(e.into())),
+            Err(SetRefError::Access(e)) => Err(SyncError::Access(e)),
+            Err(SetRefError::Internal(e)) => Err(SyncError::Internal(e)),
+            Err(SetRefError::BlockNotFound(e)) => Err(SyncError::Internal(e.int_err())),
+        }?;
+
+        Ok(())
+    }
+
     async fn synchronize_blocks<'a>(
         &'a self,
         blocks: Vec<(Multihash, MetadataBlock)>,
@@ -330,48 +418,16 @@ impl SimpleTransferProtocol {
 
         // Commit blocks
         for (hash, block) in blocks.into_iter().rev() {
-            debug!(?hash, "Appending block");
-
-            match dst
-                .as_metadata_chain()
-                .append(
-                    block,
-                    AppendOpts {
-                        validation,
-                        update_ref: None, // We will update head once, after sync is complete
-                        precomputed_hash: if !trust_source_hashes {
-                            None
-                        } else {
-                            Some(&hash)
-                        },
-                        expected_hash: Some(&hash),
-                        ..Default::default()
-                    },
-                )
-                .await
-            {
-                Ok(_) => Ok(()),
-                Err(AppendError::InvalidBlock(AppendValidationError::HashMismatch(e))) => {
-                    Err(CorruptedSourceError {
-                        message: concat!(
-                            "Block hash declared by the source didn't match ",
-                            "the computed - this may be an indication of hashing ",
-                            "algorithm mismatch or an attempted tampering",
-                        )
-                        .to_owned(),
-                        source: Some(e.into()),
-                    }
-                    .into())
-                }
-                Err(AppendError::InvalidBlock(e)) => Err(CorruptedSourceError {
-                    message: "Source metadata chain is logically inconsistent".to_owned(),
-                    source: Some(e.into()),
-                }
-                .into()),
-                Err(AppendError::RefNotFound(_) | AppendError::RefCASFailed(_)) => unreachable!(),
-                Err(AppendError::Access(e)) => Err(SyncError::Access(e)),
-                Err(AppendError::Internal(e)) => Err(SyncError::Internal(e)),
-            }?;
+            let signature = src.get_block_signature(&hash).await?;
+
+            Self::append_block(dst, hash.clone(), block, validation, trust_source_hashes).await?;
+
+            // Signatures are written only after the block was appended to avoid leaving
+            // orphaned signatures behind, but before the head is updated, so the destination
+            // never exposes a block without its signature
+            if let Some(signature) = signature {
+                dst.set_block_signature(&hash, &signature).await?;
+            }
 
             stats.dst.metadata_blocks_writen += 1;
             listener.on_status(SyncStage::CommitBlocks, &stats);
@@ -379,31 +435,13 @@ impl SimpleTransferProtocol {
 
         // Update reference, atomically commiting the sync operation
         // Any failures before this point may result in dangling files but will keep the destination dataset in its original logical state
-        match dst
-            .as_metadata_chain()
-            .set_ref(
-                &BlockRef::Head,
-                &src_head,
-                SetRefOpts {
-                    validate_block_present: false,
-                    check_ref_is: Some(dst_head),
-                },
-            )
-            .await
-        {
-            Ok(()) => Ok(()),
-            Err(SetRefError::CASFailed(e)) => Err(SyncError::UpdatedConcurrently(e.into())),
-            Err(SetRefError::Access(e)) => Err(SyncError::Access(e)),
-            Err(SetRefError::Internal(e)) => Err(SyncError::Internal(e)),
-            Err(SetRefError::BlockNotFound(e)) => Err(SyncError::Internal(e.int_err())),
-        }?;
+        Self::update_head(dst, src_head, dst_head).await?;
 
         // Download kamu-specific cache files (if exist)
         // TODO: This is not part of the ODF spec and should be revisited.
         // See also: IPFS sync procedure.
         // Ticket: https://www.notion.so/Where-to-store-ingest-checkpoints-4d48e8db656042168f94a8ab2793daef
-        let cache_files = ["fetch.yaml", "prep.yaml", "read.yaml", "commit.yaml"];
-        for name in &cache_files {
+        for name in &KAMU_CACHE_FILES {
             use crate::domain::repos::named_object_repository::GetError;
 
             match src.as_cache_repo().get(name).await {
@@ -419,8 +457,22 @@ impl SimpleTransferProtocol {
                 Err(GetError::Internal(e)) => Err(SyncError::Internal(e)),
             }?;
         }
+
+        Self::update_summary(dst).await;
+
         Ok(())
     }
+
+    /// Refreshes the summary stored in the destination, so that repositories can
+    /// describe their datasets (e.g. in search results) without scanning metadata.
+    ///
+    /// Failures are not fatal as the new head is already committed at this point.
+    pub(crate) async fn update_summary(dst: &dyn Dataset) {
+        match dst.get_summary(GetSummaryOpts::default()).await {
+            Ok(_) => {}
+            Err(error) => warn!(?error, "Failed to update destination dataset summary"),
+        }
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,842 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::domain::*;
+use crate::infra::utils::simple_transfer_protocol::{SimpleTransferProtocol, KAMU_CACHE_FILES};
+use opendatafabric::serde::flatbuffers::*;
+use opendatafabric::serde::MetadataBlockDeserializer;
+use opendatafabric::*;
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tracing::*;
+use url::Url;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Protocol messages
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Sent by the client to find the latest block both parties have in common
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NegotiateRequest {
+    /// Sample of destination chain block hashes ordered from head to tail
+    pub have: Vec<Multihash>,
+}
+
+impl NegotiateRequest {
+    /// Maximum number of block hashes in the `have` list, servers reject longer lists
+    pub const MAX_HAVE: usize = 64;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NegotiateResponse {
+    /// Current head of the source
+    pub head: Multihash,
+    /// Latest block of the source chain that is present in the `have` list
+    pub ancestor: Option<Multihash>,
+    /// Number of blocks between the head and the ancestor
+    pub num_blocks: usize,
+    /// Data and checkpoint files referenced by the missing blocks
+    pub objects: Vec<BundleObject>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleObject {
+    pub kind: BundleObjectKind,
+    pub hash: Multihash,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BundleObjectKind {
+    Data,
+    Checkpoint,
+}
+
+/// Requests a bundle of all objects between the head and the ancestor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRequest {
+    pub head: Multihash,
+    pub ancestor: Option<Multihash>,
+    /// Objects the client already has, e.g. from an interrupted transfer
+    pub skip: Vec<Multihash>,
+}
+
+/// Header of a single frame in the bundle stream.
+///
+/// On the wire every frame is encoded as a big-endian `u32` length of the JSON
+/// header followed by the header itself and `size` bytes of the payload.
+/// Data files and checkpoints come first, followed by signatures and blocks
+/// in chronological order, and the stream is terminated by the `end` frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum BundleFrame {
+    Data { hash: Multihash, size: u64 },
+    Checkpoint { hash: Multihash, size: u64 },
+    Signature { hash: Multihash, size: u64 },
+    Block { hash: Multihash, size: u64 },
+    Cache { name: String, size: u64 },
+    End,
+}
+
+impl BundleFrame {
+    const MAX_HEADER_LEN: u32 = 64 * 1024;
+
+    /// Maximum size of payloads that are read into memory, i.e. of all frames
+    /// except data files and checkpoints that are streamed to disk
+    const MAX_PAYLOAD_SIZE: u64 = 16 * 1024 * 1024;
+
+    pub fn encode_header(&self) -> Bytes {
+        let header = serde_json::to_vec(self).unwrap();
+        let mut buf = Vec::with_capacity(4 + header.len());
+        buf.extend_from_slice(&(header.len() as u32).to_be_bytes());
+        buf.extend_from_slice(&header);
+        Bytes::from(buf)
+    }
+
+    pub async fn decode_header<R>(reader: &mut R) -> Result<Self, InternalError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let len = reader.read_u32().await.int_err()?;
+        if len > Self::MAX_HEADER_LEN {
+            return Err(format!("Bundle frame header is too long: {}", len).int_err());
+        }
+
+        let mut buf = vec![0; len as usize];
+        reader.read_exact(&mut buf).await.int_err()?;
+        serde_json::from_slice(&buf).int_err()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Server
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Server side of the "Smart Transfer Protocol"
+pub struct SmartTransferProtocolServer;
+
+impl SmartTransferProtocolServer {
+    pub async fn negotiate(
+        dataset: &dyn Dataset,
+        request: NegotiateRequest,
+    ) -> Result<NegotiateResponse, InternalError> {
+        let chain = dataset.as_metadata_chain();
+        let head = chain.get_ref(&BlockRef::Head).await.int_err()?;
+        let have: HashSet<Multihash> = request.have.into_iter().collect();
+
+        let mut ancestor = None;
+        let mut num_blocks = 0;
+        let mut objects = Vec::new();
+
+        let mut blocks = chain.iter_blocks_interval(&head, None, false);
+        while let Some((hash, block)) = blocks.try_next().await.int_err()? {
+            if have.contains(&hash) {
+                ancestor = Some(hash);
+                break;
+            }
+
+            num_blocks += 1;
+
+            if let Some(block) = block.as_data_stream_block() {
+                if let Some(slice) = block.event.output_data {
+                    objects.push(BundleObject {
+                        kind: BundleObjectKind::Data,
+                        hash: slice.physical_hash.clone(),
+                        size: slice.size as u64,
+                    });
+                }
+                if let Some(checkpoint) = block.event.output_checkpoint {
+                    objects.push(BundleObject {
+                        kind: BundleObjectKind::Checkpoint,
+                        hash: checkpoint.physical_hash.clone(),
+                        size: checkpoint.size as u64,
+                    });
+                }
+            }
+        }
+
+        Ok(NegotiateResponse {
+            head,
+            ancestor,
+            num_blocks,
+            objects,
+        })
+    }
+
+    /// Produces a stream of bundle frames that can be sent as a response body
+    pub fn bundle(
+        dataset: Arc<dyn Dataset>,
+        request: BundleRequest,
+    ) -> impl Stream<Item = Result<Bytes, InternalError>> + Send + 'static {
+        async_stream::try_stream! {
+            let chain = dataset.as_metadata_chain();
+            let skip: HashSet<Multihash> = request.skip.into_iter().collect();
+
+            let mut blocks: Vec<(Multihash, MetadataBlock)> = chain
+                .iter_blocks_interval(&request.head, request.ancestor.as_ref(), false)
+                .try_collect()
+                .await
+                .int_err()?;
+            blocks.reverse();
+
+            for (_, block) in &blocks {
+                let block = match block.as_data_stream_block() {
+                    Some(block) => block,
+                    None => continue,
+                };
+
+                if let Some(slice) = block.event.output_data {
+                    if !skip.contains(&slice.physical_hash) {
+                        yield BundleFrame::Data {
+                            hash: slice.physical_hash.clone(),
+                            size: slice.size as u64,
+                        }
+                        .encode_header();
+
+                        let mut stream = tokio_util::io::ReaderStream::new(
+                            dataset
+                                .as_data_repo()
+                                .get_stream(&slice.physical_hash)
+                                .await
+                                .int_err()?,
+                        );
+                        while let Some(chunk) = stream.next().await {
+                            yield chunk.int_err()?;
+                        }
+                    }
+                }
+
+                if let Some(checkpoint) = block.event.output_checkpoint {
+                    if !skip.contains(&checkpoint.physical_hash) {
+                        yield BundleFrame::Checkpoint {
+                            hash: checkpoint.physical_hash.clone(),
+                            size: checkpoint.size as u64,
+                        }
+                        .encode_header();
+
+                        let mut stream = tokio_util::io::ReaderStream::new(
+                            dataset
+                                .as_checkpoint_repo()
+                                .get_stream(&checkpoint.physical_hash)
+                                .await
+                                .int_err()?,
+                        );
+                        while let Some(chunk) = stream.next().await {
+                            yield chunk.int_err()?;
+                        }
+                    }
+                }
+            }
+
+            for (hash, _) in &blocks {
+                if let Some(signature) = dataset.get_block_signature(hash).await? {
+                    let data = serde_json::to_vec(&signature).int_err()?;
+                    yield BundleFrame::Signature {
+                        hash: hash.clone(),
+                        size: data.len() as u64,
+                    }
+                    .encode_header();
+                    yield Bytes::from(data);
+                }
+
+                let data = chain.as_object_repo().get_bytes(hash).await.int_err()?;
+                yield BundleFrame::Block {
+                    hash: hash.clone(),
+                    size: data.len() as u64,
+                }
+                .encode_header();
+                yield data;
+            }
+
+            for name in &KAMU_CACHE_FILES {
+                use crate::domain::repos::named_object_repository::GetError;
+
+                match dataset.as_cache_repo().get(name).await {
+                    Ok(data) => {
+                        yield BundleFrame::Cache {
+                            name: name.to_string(),
+                            size: data.len() as u64,
+                        }
+                        .encode_header();
+                        yield data;
+                    }
+                    Err(GetError::NotFound(_)) => (),
+                    Err(e) => Err(e.int_err())?,
+                }
+            }
+
+            yield BundleFrame::End.encode_header();
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Client
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Implements "Smart Transfer Protocol" supported by kamu-hosted HTTP repositories.
+///
+/// Instead of fetching objects one by one the client negotiates the common ancestor
+/// with the server and then receives all missing blocks, data files, and checkpoints
+/// in a single bundle stream. Data files and checkpoints are stored as soon as they
+/// arrive, so an interrupted transfer can be resumed without downloading them again.
+#[derive(Default)]
+pub struct SmartTransferProtocol {
+    client: reqwest::Client,
+}
+
+impl SmartTransferProtocol {
+    /// Pulls the dataset from a kamu-hosted HTTP repository.
+    ///
+    /// Returns `None` if the source does not support this protocol,
+    /// in which case caller should fall back to [SimpleTransferProtocol].
+    pub async fn sync<'a>(
+        &'a self,
+        src_url: &'a Url,
+        dst: &'a dyn Dataset,
+        validation: AppendValidation,
+        trust_source_hashes: bool,
+        force: bool,
+        listener: Arc<dyn SyncListener + 'static>,
+    ) -> Result<Option<SyncResult>, SyncError> {
+        let dst_chain = dst.as_metadata_chain();
+        let dst_head = SimpleTransferProtocol.get_dest_head(dst_chain).await?;
+
+        // Destination chain is read in full to precisely classify divergence
+        let dst_blocks: Vec<Multihash> = match &dst_head {
+            None => Vec::new(),
+            Some(dst_head) => dst_chain
+                .iter_blocks_interval(dst_head, None, false)
+                .map_ok(|(h, _)| h)
+                .try_collect()
+                .await
+                .int_err()?,
+        };
+
+        let mut stats = SyncStats::default();
+        stats.dst.metadata_blocks_read = dst_blocks.len();
+        listener.on_status(SyncStage::ReadMetadata, &stats);
+
+        let negotiation = match self
+            .negotiate(
+                src_url,
+                &NegotiateRequest {
+                    have: Self::sample_have(&dst_blocks),
+                },
+            )
+            .await?
+        {
+            Some(n) => n,
+            None => return Ok(None),
+        };
+
+        let src_head = negotiation.head.clone();
+        info!(?src_head, ?dst_head, ancestor = ?negotiation.ancestor, num_blocks = negotiation.num_blocks, "Negotiated sync");
+
+        if Some(&src_head) == dst_head.as_ref() {
+            return Ok(Some(SyncResult::UpToDate));
+        }
+
+        let is_fast_forward = dst_head.is_none() || negotiation.ancestor == dst_head;
+
+        if !is_fast_forward && !force {
+            if let Some(dst_ahead_size) = dst_blocks.iter().position(|h| *h == src_head) {
+                return Err(DestinationAheadError {
+                    src_head,
+                    dst_head: dst_head.unwrap(),
+                    dst_ahead_size,
+                }
+                .into());
+            }
+
+            let negotiation = self
+                .refine_ancestor(src_url, &dst_blocks, negotiation)
+                .await?;
+
+            let uncommon_blocks_in_dst = match &negotiation.ancestor {
+                Some(ancestor) => dst_blocks.iter().position(|h| h == ancestor).unwrap(),
+                None => dst_blocks.len(),
+            };
+            return Err(DatasetsDivergedError {
+                src_head,
+                dst_head: dst_head.unwrap(),
+                uncommon_blocks_in_dst,
+                uncommon_blocks_in_src: negotiation.num_blocks,
+            }
+            .into());
+        }
+
+        // Forced sync fully re-synchronizes the chain, same as the simple protocol does
+        let negotiation = if !is_fast_forward {
+            match self
+                .negotiate(src_url, &NegotiateRequest { have: Vec::new() })
+                .await?
+            {
+                Some(n) if n.head == src_head => n,
+                _ => {
+                    return Err(CorruptedSourceError {
+                        message: "Source head has changed during the sync".to_owned(),
+                        source: None,
+                    }
+                    .into())
+                }
+            }
+        } else {
+            negotiation
+        };
+
+        let num_blocks = negotiation.num_blocks;
+
+        self.transfer_bundle(
+            src_url,
+            negotiation,
+            dst,
+            dst_head.as_ref(),
+            validation,
+            trust_source_hashes,
+            listener,
+            stats,
+        )
+        .await?;
+
+        Ok(Some(SyncResult::Updated {
+            old_head: dst_head,
+            new_head: src_head,
+            num_blocks,
+        }))
+    }
+
+    /// Picks head and exponentially spaced ancestors.
+    ///
+    /// Head is always included, so fast-forwards are detected precisely, but the
+    /// ancestor negotiated from this sample may be older than the actual common
+    /// block when chains have diverged - see [Self::refine_ancestor].
+    fn sample_have(dst_blocks: &[Multihash]) -> Vec<Multihash> {
+        let mut have = Vec::new();
+        let mut i = 0;
+        let mut step = 1;
+        while i < dst_blocks.len() && have.len() < NegotiateRequest::MAX_HAVE {
+            have.push(dst_blocks[i].clone());
+            if have.len() > 2 {
+                step *= 2;
+            }
+            i += step;
+        }
+        have
+    }
+
+    /// Narrows down the common ancestor of diverged chains by repeatedly
+    /// negotiating with denser samples of the interval between the newest
+    /// destination block the source doesn't have and the ancestor found so far
+    async fn refine_ancestor(
+        &self,
+        src_url: &Url,
+        dst_blocks: &[Multihash],
+        mut negotiation: NegotiateResponse,
+    ) -> Result<NegotiateResponse, SyncError> {
+        // Destination head is always sampled and is known to be absent in the source
+        let mut missing = 0;
+
+        loop {
+            let found = match &negotiation.ancestor {
+                Some(ancestor) => dst_blocks.iter().position(|h| h == ancestor).unwrap(),
+                None => dst_blocks.len(),
+            };
+
+            if found - missing <= 1 {
+                return Ok(negotiation);
+            }
+
+            let step = (found - missing - 1 + NegotiateRequest::MAX_HAVE - 2)
+                / (NegotiateRequest::MAX_HAVE - 1);
+            let sampled: Vec<usize> = (missing + 1..found).step_by(step).collect();
+
+            // Previously found ancestor is included so the result can only improve
+            let mut have: Vec<Multihash> = sampled.iter().map(|i| dst_blocks[*i].clone()).collect();
+            have.extend(negotiation.ancestor.iter().cloned());
+
+            let refined = match self.negotiate(src_url, &NegotiateRequest { have }).await? {
+                Some(n) if n.head == negotiation.head => n,
+                _ => {
+                    return Err(CorruptedSourceError {
+                        message: "Source head has changed during the sync".to_owned(),
+                        source: None,
+                    }
+                    .into())
+                }
+            };
+
+            let refined_found = match &refined.ancestor {
+                Some(ancestor) => dst_blocks.iter().position(|h| h == ancestor).unwrap(),
+                None => dst_blocks.len(),
+            };
+
+            missing = sampled
+                .into_iter()
+                .filter(|i| *i < refined_found)
+                .max()
+                .unwrap_or(missing);
+            negotiation = refined;
+        }
+    }
+
+    async fn negotiate(
+        &self,
+        src_url: &Url,
+        request: &NegotiateRequest,
+    ) -> Result<Option<NegotiateResponse>, SyncError> {
+        let url = src_url.join("smart/negotiate").int_err()?;
+
+        debug!(%url, "Negotiating transfer");
+
+        let response = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(request).int_err()?)
+            .send()
+            .await
+            .int_err()?;
+
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND
+            | reqwest::StatusCode::METHOD_NOT_ALLOWED
+            | reqwest::StatusCode::NOT_IMPLEMENTED => {
+                debug!(status = %response.status(), "Source does not support smart transfer");
+                return Ok(None);
+            }
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(AccessError::Unauthorized(
+                    response.error_for_status().unwrap_err().into(),
+                )
+                .into())
+            }
+            reqwest::StatusCode::FORBIDDEN => {
+                return Err(
+                    AccessError::Forbidden(response.error_for_status().unwrap_err().into()).into(),
+                )
+            }
+            _ => (),
+        }
+
+        let body = response
+            .error_for_status()
+            .int_err()?
+            .bytes()
+            .await
+            .int_err()?;
+
+        match serde_json::from_slice(&body) {
+            Ok(negotiation) => Ok(Some(negotiation)),
+            Err(e) => {
+                debug!(error = %e, "Source returned unexpected negotiation response");
+                Ok(None)
+            }
+        }
+    }
+
+    async fn transfer_bundle(
+        &self,
+        src_url: &Url,
+        negotiation: NegotiateResponse,
+        dst: &dyn Dataset,
+        dst_head: Option<&Multihash>,
+        validation: AppendValidation,
+        trust_source_hashes: bool,
+        listener: Arc<dyn SyncListener>,
+        mut stats: SyncStats,
+    ) -> Result<(), SyncError> {
+        // Objects that were transferred by a previous interrupted attempt are skipped
+        let mut skip = Vec::new();
+        for obj in &negotiation.objects {
+            let repo = match obj.kind {
+                BundleObjectKind::Data => dst.as_data_repo(),
+                BundleObjectKind::Checkpoint => dst.as_checkpoint_repo(),
+            };
+            if repo.contains(&obj.hash).await.int_err()? {
+                skip.push(obj.hash.clone());
+                continue;
+            }
+
+            stats.src_estimated.bytes_read += obj.size as usize;
+            stats.dst_estimated.bytes_written += obj.size as usize;
+            match obj.kind {
+                BundleObjectKind::Data => {
+                    stats.src_estimated.data_slices_read += 1;
+                    stats.dst_estimated.data_slices_written += 1;
+                }
+                BundleObjectKind::Checkpoint => {
+                    stats.src_estimated.checkpoints_read += 1;
+                    stats.dst_estimated.checkpoints_written += 1;
+                }
+            }
+        }
+        stats.src_estimated.metadata_blocks_read += negotiation.num_blocks;
+        stats.dst_estimated.metadata_blocks_writen += negotiation.num_blocks;
+
+        info!(?stats, num_skipped = skip.len(), "Requesting bundle");
+        listener.on_status(SyncStage::TransferData, &stats);
+
+        let url = src_url.join("smart/bundle").int_err()?;
+        let request = BundleRequest {
+            head: negotiation.head.clone(),
+            ancestor: negotiation.ancestor.clone(),
+            skip,
+        };
+
+        let response = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(&request).int_err()?)
+            .send()
+            .await
+            .int_err()?
+            .error_for_status()
+            .int_err()?;
+
+        let stream = response
+            .bytes_stream()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
+        let mut reader = tokio_util::io::StreamReader::new(Box::pin(stream));
+
+        let tmp_dir = tempfile::tempdir().int_err()?;
+        let mut blocks = Vec::new();
+        let mut signatures = std::collections::HashMap::new();
+        let mut cache_files = Vec::new();
+
+        loop {
+            let frame = BundleFrame::decode_header(&mut reader)
+                .await
+                .map_err(|e| Self::corrupted("Malformed bundle frame", e))?;
+
+            match frame {
+                BundleFrame::Data { hash, size } => {
+                    debug!(?hash, "Receiving data file");
+                    Self::receive_object(
+                        &mut reader,
+                        dst.as_data_repo(),
+                        &hash,
+                        size,
+                        tmp_dir.path(),
+                        trust_source_hashes,
+                    )
+                    .await?;
+                    stats.src.data_slices_read += 1;
+                    stats.dst.data_slices_written += 1;
+                    stats.src.bytes_read += size as usize;
+                    stats.dst.bytes_written += size as usize;
+                    listener.on_status(SyncStage::TransferData, &stats);
+                }
+                BundleFrame::Checkpoint { hash, size } => {
+                    debug!(?hash, "Receiving checkpoint file");
+                    Self::receive_object(
+                        &mut reader,
+                        dst.as_checkpoint_repo(),
+                        &hash,
+                        size,
+                        tmp_dir.path(),
+                        trust_source_hashes,
+                    )
+                    .await?;
+                    stats.src.checkpoints_read += 1;
+                    stats.dst.checkpoints_written += 1;
+                    stats.src.bytes_read += size as usize;
+                    stats.dst.bytes_written += size as usize;
+                    listener.on_status(SyncStage::TransferData, &stats);
+                }
+                BundleFrame::Signature { hash, size } => {
+                    let data = Self::read_payload(&mut reader, size).await?;
+                    let signature: BlockSignature = serde_json::from_slice(&data)
+                        .map_err(|e| Self::corrupted("Malformed block signature", e))?;
+                    signatures.insert(hash, signature);
+                }
+                BundleFrame::Block { hash, size } => {
+                    let data = Self::read_payload(&mut reader, size).await?;
+                    let block = FlatbuffersMetadataBlockDeserializer
+                        .read_manifest(&data)
+                        .map_err(|e| Self::corrupted("Malformed metadata block", e))?;
+                    blocks.push((hash, block));
+                    stats.src.metadata_blocks_read += 1;
+                }
+                BundleFrame::Cache { name, size } => {
+                    // Name comes from the source and is used as a path in the cache
+                    // directory, so only the well-known file names are accepted
+                    if !Self::is_known_cache_file(&name) {
+                        return Err(CorruptedSourceError {
+                            message: format!("Bundle contains unexpected cache file {:?}", name),
+                            source: None,
+                        }
+                        .into());
+                    }
+                    let data = Self::read_payload(&mut reader, size).await?;
+                    cache_files.push((name, data));
+                }
+                BundleFrame::End => break,
+            }
+        }
+
+        if blocks.len() != negotiation.num_blocks
+            || blocks.last().map(|(h, _)| h) != Some(&negotiation.head)
+        {
+            return Err(CorruptedSourceError {
+                message: format!(
+                    "Bundle contained {} blocks while {} were expected",
+                    blocks.len(),
+                    negotiation.num_blocks
+                ),
+                source: None,
+            }
+            .into());
+        }
+
+        // Commit blocks
+        for (hash, block) in blocks {
+            SimpleTransferProtocol::append_block(
+                dst,
+                hash.clone(),
+                block,
+                validation,
+                trust_source_hashes,
+            )
+            .await?;
+
+            // Signatures are written only after the block was appended to avoid leaving
+            // orphaned signatures behind, but before the head is updated, so the destination
+            // never exposes a block without its signature
+            if let Some(signature) = signatures.remove(&hash) {
+                dst.set_block_signature(&hash, &signature).await?;
+            }
+
+            stats.dst.metadata_blocks_writen += 1;
+            listener.on_status(SyncStage::CommitBlocks, &stats);
+        }
+
+        // Update reference, atomically commiting the sync operation
+        SimpleTransferProtocol::update_head(dst, &negotiation.head, dst_head).await?;
+
+        for (name, data) in cache_files {
+            dst.as_cache_repo().set(&name, &data).await.int_err()?;
+        }
+
+        SimpleTransferProtocol::update_summary(dst).await;
+
+        Ok(())
+    }
+
+    fn is_known_cache_file(name: &str) -> bool {
+        !name.contains('/')
+            && !name.contains('\\')
+            && !name.contains("..")
+            && KAMU_CACHE_FILES.contains(&name)
+    }
+
+    async fn read_payload<R>(reader: &mut R, size: u64) -> Result<Vec<u8>, SyncError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        if size > BundleFrame::MAX_PAYLOAD_SIZE {
+            return Err(CorruptedSourceError {
+                message: format!("Bundle frame payload is too large: {}", size),
+                source: None,
+            }
+            .into());
+        }
+
+        let mut data = vec![0; size as usize];
+        reader
+            .read_exact(&mut data)
+            .await
+            .map_err(|e| Self::corrupted("Bundle ended unexpectedly", e))?;
+        Ok(data)
+    }
+
+    /// Stages the object payload in a temporary file and inserts it into the repository
+    async fn receive_object<R>(
+        reader: &mut R,
+        repo: &dyn ObjectRepository,
+        hash: &Multihash,
+        size: u64,
+        tmp_dir: &std::path::Path,
+        trust_source_hashes: bool,
+    ) -> Result<(), SyncError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let tmp_path = tmp_dir.join(hash.to_multibase_string());
+
+        let mut file = tokio::fs::File::create(&tmp_path).await.int_err()?;
+        let copied = tokio::io::copy(&mut reader.take(size), &mut file)
+            .await
+            .map_err(|e| Self::corrupted("Bundle ended unexpectedly", e))?;
+        file.flush().await.int_err()?;
+
+        if copied != size {
+            return Err(CorruptedSourceError {
+                message: "Bundle ended unexpectedly".to_owned(),
+                source: None,
+            }
+            .into());
+        }
+
+        let file = tokio::fs::File::open(&tmp_path).await.int_err()?;
+
+        match repo
+            .insert_stream(
+                Box::new(file),
+                InsertOpts {
+                    precomputed_hash: if !trust_source_hashes {
+                        None
+                    } else {
+                        Some(hash)
+                    },
+                    expected_hash: Some(hash),
+                    size_hint: Some(size as usize),
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(InsertError::HashMismatch(e)) => Err(CorruptedSourceError {
+                message: concat!(
+                    "File hash declared by the source didn't match ",
+                    "the computed - this may be an indication of hashing ",
+                    "algorithm mismatch or an attempted tampering",
+                )
+                .to_owned(),
+                source: Some(e.into()),
+            }
+            .into()),
+            Err(InsertError::Access(e)) => Err(SyncError::Access(e)),
+            Err(InsertError::Internal(e)) => Err(SyncError::Internal(e)),
+        }?;
+
+        tokio::fs::remove_file(&tmp_path).await.int_err()?;
+        Ok(())
+    }
+
+    fn corrupted(message: &str, e: impl Into<BoxedError>) -> SyncError {
+        CorruptedSourceError {
+            message: message.to_owned(),
+            source: Some(e.into()),
+        }
+        .into()
+    }
+}
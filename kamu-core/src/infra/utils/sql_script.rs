@@ -0,0 +1,109 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Single statement of an SQL script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlScriptStatement {
+    /// Statement text without the terminating `;`
+    pub text: String,
+    /// Line number (1-based) where statement begins in the script
+    pub line: usize,
+}
+
+/// Splits an SQL script into individual statements separated by `;`.
+///
+/// Separators inside string literals, quoted identifiers, and comments are ignored.
+/// Statements that consist only of whitespace and comments are skipped.
+pub fn split_sql_statements(script: &str) -> Vec<SqlScriptStatement> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum State {
+        Normal,
+        Quoted(char),
+        LineComment,
+        BlockComment,
+    }
+
+    let mut statements = Vec::new();
+    let mut state = State::Normal;
+    let mut text = String::new();
+    let mut has_content = false;
+    let mut start_line = 1;
+    let mut line = 1;
+
+    let mut chars = script.chars().peekable();
+    while let Some(c) = chars.next() {
+        match state {
+            State::Normal => match c {
+                ';' => {
+                    if has_content {
+                        statements.push(SqlScriptStatement {
+                            text: std::mem::take(&mut text).trim().to_owned(),
+                            line: start_line,
+                        });
+                    }
+                    text.clear();
+                    has_content = false;
+                    continue;
+                }
+                '-' if chars.peek() == Some(&'-') => state = State::LineComment,
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if has_content {
+                        text.push_str("/*");
+                    }
+                    state = State::BlockComment;
+                    continue;
+                }
+                '\'' | '"' | '`' => state = State::Quoted(c),
+                _ => {}
+            },
+            State::Quoted(q) if c == q => state = State::Normal,
+            State::LineComment if c == '\n' => state = State::Normal,
+            State::BlockComment if c == '*' && chars.peek() == Some(&'/') => {
+                chars.next();
+                if has_content {
+                    text.push_str("*/");
+                }
+                state = State::Normal;
+                continue;
+            }
+            _ => {}
+        }
+
+        if !has_content && state != State::LineComment && state != State::BlockComment {
+            if c.is_whitespace() {
+                if c == '\n' {
+                    line += 1;
+                }
+                continue;
+            }
+            has_content = true;
+            start_line = line;
+        }
+
+        if c == '\n' {
+            line += 1;
+        }
+
+        if has_content {
+            text.push(c);
+        }
+    }
+
+    if has_content {
+        statements.push(SqlScriptStatement {
+            text: text.trim().to_owned(),
+            line: start_line,
+        });
+    }
+
+    statements
+}
@@ -12,9 +12,12 @@ use crate::infra::*;
 use opendatafabric::*;
 
 use dill::*;
-use futures::TryStreamExt;
+use futures::{StreamExt, TryStreamExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tracing::info_span;
+use tokio::sync::Semaphore;
+use tracing::{info, info_span, warn};
 
 pub struct VerificationServiceImpl {
     local_repo: Arc<dyn LocalDatasetRepository>,
@@ -41,8 +44,11 @@ impl VerificationServiceImpl {
         dataset_handle: &'a DatasetHandle,
         dataset_kind: DatasetKind,
         block_range: (Option<Multihash>, Option<Multihash>),
+        options: &VerificationOptions,
+        report: &mut VerificationReport,
         listener: Arc<dyn VerificationListener>,
-    ) -> Result<VerificationResult, VerificationError> {
+        hashing_permits: Arc<Semaphore>,
+    ) -> Result<(), VerificationError> {
         let span = info_span!("Verifying data integrity");
         let _span_guard = span.enter();
 
@@ -72,127 +78,236 @@ impl VerificationServiceImpl {
 
         listener.begin_phase(VerificationPhase::DataIntegrity);
 
-        for (block_index, (block_hash, block)) in plan.into_iter().enumerate() {
-            listener.begin_block(
+        // Blocking tasks cannot be aborted and keep running after their join handles are
+        // dropped, so we raise this flag when we stop consuming results (e.g. on the first
+        // error) to let the tasks that did not get to hashing yet skip their work
+        let cancelled = CancelOnDrop(Arc::new(AtomicBool::new(false)));
+
+        // Parts are hashed concurrently on the blocking thread pool, while results are
+        // consumed in the block order to produce the same report as sequential verification
+        let mut results = futures::stream::iter(plan.into_iter().enumerate())
+            .map(|(block_index, (block_hash, block))| {
+                let listener = listener.clone();
+                let hashing_permits = hashing_permits.clone();
+                let cancelled = cancelled.0.clone();
+                let continue_on_error = options.continue_on_error;
+                let data_path = block
+                    .event
+                    .output_data
+                    .as_ref()
+                    .map(|slice| dataset_layout.data_slice_path(slice));
+                let checkpoint_path =
+                    block.event.output_checkpoint.as_ref().map(|checkpoint| {
+                        dataset_layout.checkpoint_path(&checkpoint.physical_hash)
+                    });
+
+                async move {
+                    let _permit = hashing_permits.acquire_owned().await.int_err()?;
+
+                    listener.begin_block(
+                        &block_hash,
+                        block_index,
+                        num_blocks,
+                        VerificationPhase::DataIntegrity,
+                    );
+
+                    let errors = tokio::task::spawn_blocking(move || {
+                        Self::check_block_data(
+                            block.event,
+                            data_path,
+                            checkpoint_path,
+                            dataset_kind,
+                            continue_on_error,
+                            &cancelled,
+                        )
+                    })
+                    .await
+                    .int_err()??;
+
+                    Ok::<_, InternalError>((block_index, block_hash, errors))
+                }
+            })
+            .buffered(options.concurrency());
+
+        while let Some((block_index, block_hash, (data_error, checkpoint_error))) =
+            results.try_next().await?
+        {
+            if let Some(error) = data_error {
+                report.record(
+                    &block_hash,
+                    VerificationPhase::DataIntegrity,
+                    DataDoesNotMatchMetadata {
+                        block_hash: block_hash.clone(),
+                        error,
+                    }
+                    .into(),
+                    options,
+                )?;
+            }
+
+            if let Some(error) = checkpoint_error {
+                report.record(
+                    &block_hash,
+                    VerificationPhase::DataIntegrity,
+                    CheckpointDoesNotMatchMetadata {
+                        block_hash: block_hash.clone(),
+                        error,
+                    }
+                    .into(),
+                    options,
+                )?;
+            }
+
+            listener.end_block(
                 &block_hash,
                 block_index,
                 num_blocks,
                 VerificationPhase::DataIntegrity,
             );
+        }
 
-            if let Some(output_slice) = &block.event.output_data {
-                let data_path = dataset_layout.data_slice_path(&output_slice);
+        listener.end_phase(VerificationPhase::DataIntegrity);
 
-                // Check size first
-                let size_actual = std::fs::metadata(&data_path).int_err()?.len();
+        Ok(())
+    }
 
-                if size_actual != (output_slice.size as u64) {
-                    return Err(VerificationError::DataDoesNotMatchMetadata(
-                        DataDoesNotMatchMetadata {
-                            block_hash,
-                            error: DataVerificationError::SizeMismatch {
-                                expected: output_slice.size as u64,
-                                actual: size_actual,
-                            },
-                        },
-                    ));
-                }
+    fn check_block_data(
+        event: MetadataEventDataStream,
+        data_path: Option<PathBuf>,
+        checkpoint_path: Option<PathBuf>,
+        dataset_kind: DatasetKind,
+        continue_on_error: bool,
+        cancelled: &AtomicBool,
+    ) -> Result<
+        (
+            Option<DataVerificationError>,
+            Option<CheckpointVerificationError>,
+        ),
+        InternalError,
+    > {
+        // Results of a cancelled verification are discarded, so there is no point in hashing.
+        // Note that hashing of a file that is already in progress runs to completion.
+        if cancelled.load(Ordering::Relaxed) {
+            return Ok((None, None));
+        }
 
-                // Do a fast pass using physical hash
-                let physical_hash_actual =
-                    crate::infra::utils::data_utils::get_file_physical_hash(&data_path)
-                        .int_err()?;
+        let (output_slice, data_path) = match (event.output_data, data_path) {
+            (Some(output_slice), Some(data_path)) => (output_slice, data_path),
+            _ => return Ok((None, None)),
+        };
 
-                if physical_hash_actual != output_slice.physical_hash {
-                    // Root data files are non-reproducible by definition, so
-                    // if physical hashes don't match - we can give up right away.
-                    if dataset_kind == DatasetKind::Root {
-                        return Err(VerificationError::DataDoesNotMatchMetadata(
-                            DataDoesNotMatchMetadata {
-                                block_hash,
-                                error: DataVerificationError::PhysicalHashMismatch {
-                                    expected: output_slice.physical_hash.clone(),
-                                    actual: physical_hash_actual,
-                                },
-                            },
-                        ));
-                    } else {
-                        // Derivative data may be replayed and produce different binary file
-                        // but data must have same logical hash to be valid.
-                        let logical_hash_actual =
-                            crate::infra::utils::data_utils::get_parquet_logical_hash(&data_path)
-                                .int_err()?;
-
-                        if logical_hash_actual != output_slice.logical_hash {
-                            return Err(VerificationError::DataDoesNotMatchMetadata(
-                                DataDoesNotMatchMetadata {
-                                    block_hash,
-                                    error: DataVerificationError::LogicalHashMismatch {
-                                        expected: output_slice.logical_hash.clone(),
-                                        actual: logical_hash_actual,
-                                    },
-                                },
-                            ));
-                        }
-                    }
-                }
+        let data_error = Self::check_data_slice(&data_path, &output_slice, dataset_kind)?;
 
-                if let Some(checkpoint) = block.event.output_checkpoint {
-                    let checkpoint_path = dataset_layout.checkpoint_path(&checkpoint.physical_hash);
+        // Sequential verification would stop at the data error without looking at the checkpoint
+        if (data_error.is_some() && !continue_on_error) || cancelled.load(Ordering::Relaxed) {
+            return Ok((data_error, None));
+        }
 
-                    // Check size
-                    let size_actual = std::fs::metadata(&checkpoint_path).int_err()?.len();
+        let checkpoint_error = match (event.output_checkpoint, checkpoint_path) {
+            (Some(checkpoint), Some(checkpoint_path)) => {
+                Self::check_checkpoint(&checkpoint_path, &checkpoint)?
+            }
+            _ => None,
+        };
 
-                    if size_actual != (checkpoint.size as u64) {
-                        return Err(VerificationError::CheckpointDoesNotMatchMetadata(
-                            CheckpointDoesNotMatchMetadata {
-                                block_hash,
-                                error: CheckpointVerificationError::SizeMismatch {
-                                    expected: checkpoint.size as u64,
-                                    actual: size_actual,
-                                },
-                            },
-                        ));
-                    }
+        Ok((data_error, checkpoint_error))
+    }
 
-                    // Check physical hash
-                    let physical_hash_actual =
-                        crate::infra::utils::data_utils::get_file_physical_hash(&checkpoint_path)
-                            .int_err()?;
-
-                    if physical_hash_actual != checkpoint.physical_hash {
-                        return Err(VerificationError::CheckpointDoesNotMatchMetadata(
-                            CheckpointDoesNotMatchMetadata {
-                                block_hash,
-                                error: CheckpointVerificationError::PhysicalHashMismatch {
-                                    expected: checkpoint.physical_hash,
-                                    actual: physical_hash_actual,
-                                },
-                            },
-                        ));
-                    }
-                }
+    fn check_data_slice(
+        data_path: &Path,
+        output_slice: &DataSlice,
+        dataset_kind: DatasetKind,
+    ) -> Result<Option<DataVerificationError>, InternalError> {
+        // Check size first
+        let size_actual = match std::fs::metadata(data_path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Some(DataVerificationError::NotFound))
             }
+            Err(e) => return Err(e.int_err()),
+        };
 
-            listener.end_block(
-                &block_hash,
-                block_index,
-                num_blocks,
-                VerificationPhase::DataIntegrity,
-            );
+        if size_actual != (output_slice.size as u64) {
+            return Ok(Some(DataVerificationError::SizeMismatch {
+                expected: output_slice.size as u64,
+                actual: size_actual,
+            }));
         }
 
-        listener.end_phase(VerificationPhase::DataIntegrity);
+        // Do a fast pass using physical hash
+        let physical_hash_actual =
+            crate::infra::utils::data_utils::get_file_physical_hash(data_path).int_err()?;
+
+        if physical_hash_actual == output_slice.physical_hash {
+            return Ok(None);
+        }
+
+        // Root data files are non-reproducible by definition, so
+        // if physical hashes don't match - we can give up right away.
+        if dataset_kind == DatasetKind::Root {
+            return Ok(Some(DataVerificationError::PhysicalHashMismatch {
+                expected: output_slice.physical_hash.clone(),
+                actual: physical_hash_actual,
+            }));
+        }
+
+        // Derivative data may be replayed and produce different binary file
+        // but data must have same logical hash to be valid.
+        let logical_hash_actual =
+            crate::infra::utils::data_utils::get_parquet_logical_hash(data_path).int_err()?;
+
+        if logical_hash_actual != output_slice.logical_hash {
+            return Ok(Some(DataVerificationError::LogicalHashMismatch {
+                expected: output_slice.logical_hash.clone(),
+                actual: logical_hash_actual,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    fn check_checkpoint(
+        checkpoint_path: &Path,
+        checkpoint: &Checkpoint,
+    ) -> Result<Option<CheckpointVerificationError>, InternalError> {
+        // Check size
+        let size_actual = match std::fs::metadata(checkpoint_path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Some(CheckpointVerificationError::NotFound))
+            }
+            Err(e) => return Err(e.int_err()),
+        };
+
+        if size_actual != (checkpoint.size as u64) {
+            return Ok(Some(CheckpointVerificationError::SizeMismatch {
+                expected: checkpoint.size as u64,
+                actual: size_actual,
+            }));
+        }
+
+        // Check physical hash
+        let physical_hash_actual =
+            crate::infra::utils::data_utils::get_file_physical_hash(checkpoint_path).int_err()?;
+
+        if physical_hash_actual != checkpoint.physical_hash {
+            return Ok(Some(CheckpointVerificationError::PhysicalHashMismatch {
+                expected: checkpoint.physical_hash.clone(),
+                actual: physical_hash_actual,
+            }));
+        }
 
-        Ok(VerificationResult::Valid)
+        Ok(None)
     }
 
     async fn check_sequence_integrity<'a>(
         &'a self,
         dataset_handle: &'a DatasetHandle,
         block_range: (Option<Multihash>, Option<Multihash>),
+        options: &VerificationOptions,
+        report: &mut VerificationReport,
         listener: Arc<dyn VerificationListener>,
-    ) -> Result<VerificationResult, VerificationError> {
+    ) -> Result<(), VerificationError> {
         let span = info_span!("Verifying metadata integrity");
         let _span_guard = span.enter();
 
@@ -227,7 +342,7 @@ impl VerificationServiceImpl {
         for (block_hash, block) in blocks.into_iter().rev() {
             match in_memory_chain
                 .append(
-                    block,
+                    block.clone(),
                     AppendOpts {
                         precomputed_hash: Some(&block_hash),
                         ..AppendOpts::default()
@@ -236,6 +351,28 @@ impl VerificationServiceImpl {
                 .await
             {
                 Ok(_) => Ok(()),
+                Err(AppendError::InvalidBlock(e)) => {
+                    report.record(
+                        &block_hash,
+                        VerificationPhase::MetadataIntegrity,
+                        VerificationError::InvalidMetadataSequence(e),
+                        options,
+                    )?;
+
+                    // Keep the block in the chain so the following blocks are checked against it
+                    in_memory_chain
+                        .append(
+                            block,
+                            AppendOpts {
+                                validation: AppendValidation::None,
+                                precomputed_hash: Some(&block_hash),
+                                ..AppendOpts::default()
+                            },
+                        )
+                        .await
+                        .int_err()?;
+                    Ok(())
+                }
                 Err(AppendError::RefNotFound(e)) => Err(VerificationError::RefNotFound(e)),
                 Err(e) => Err(VerificationError::Internal(e.int_err())),
             }?;
@@ -243,20 +380,130 @@ impl VerificationServiceImpl {
 
         listener.end_phase(VerificationPhase::MetadataIntegrity);
 
-        Ok(VerificationResult::Valid)
+        Ok(())
     }
-}
 
-#[async_trait::async_trait(?Send)]
-impl VerificationService for VerificationServiceImpl {
-    async fn verify(
+    async fn check_signatures<'a>(
+        &'a self,
+        dataset_handle: &'a DatasetHandle,
+        block_range: (Option<Multihash>, Option<Multihash>),
+        options: &VerificationOptions,
+        report: &mut VerificationReport,
+        listener: Arc<dyn VerificationListener>,
+    ) -> Result<(), VerificationError> {
+        let span = info_span!("Verifying metadata signatures");
+        let _span_guard = span.enter();
+
+        let dataset = self
+            .local_repo
+            .get_dataset(&dataset_handle.as_local_ref())
+            .await?;
+
+        let chain = dataset.as_metadata_chain();
+
+        let head = match block_range.1 {
+            None => chain.get_ref(&BlockRef::Head).await?,
+            Some(hash) => hash,
+        };
+        let tail = block_range.0;
+
+        // Traversing the entire chain as we need the seed block that carries the dataset ID
+        let blocks: Vec<_> = chain
+            .iter_blocks_interval(&head, None, false)
+            .try_collect()
+            .await?;
+
+        let (seed_hash, dataset_id) = match blocks.last() {
+            Some((
+                hash,
+                MetadataBlock {
+                    event: MetadataEvent::Seed(seed),
+                    ..
+                },
+            )) => (hash.clone(), seed.dataset_id.clone()),
+            _ => {
+                return Err("Metadata chain does not start with a seed block"
+                    .int_err()
+                    .into())
+            }
+        };
+
+        listener.begin_phase(VerificationPhase::MetadataSignatures);
+
+        // Datasets created before block signing was introduced have no signatures at all.
+        // They are rejected unless explicitly allowed, and even then a missing seed
+        // signature alone must not disable the check, so we skip it only when no block
+        // in the chain is signed.
+        if options.allow_unsigned && dataset.get_block_signature(&seed_hash).await?.is_none() {
+            let mut any_signed = false;
+            for (block_hash, _) in &blocks {
+                if dataset.get_block_signature(block_hash).await?.is_some() {
+                    any_signed = true;
+                    break;
+                }
+            }
+
+            if !any_signed {
+                warn!(
+                    %dataset_handle,
+                    "Dataset blocks are not signed, skipping signature verification"
+                );
+                listener.end_phase(VerificationPhase::MetadataSignatures);
+                return Ok(());
+            }
+        }
+
+        let plan: Vec<_> = blocks
+            .into_iter()
+            .take_while(|(hash, _)| Some(hash) != tail.as_ref())
+            .collect();
+        let num_blocks = plan.len();
+
+        for (block_index, (block_hash, _)) in plan.into_iter().enumerate() {
+            listener.begin_block(
+                &block_hash,
+                block_index,
+                num_blocks,
+                VerificationPhase::MetadataSignatures,
+            );
+
+            let res = match dataset.get_block_signature(&block_hash).await? {
+                None => Err(BlockSignatureError::Missing {
+                    block_hash: block_hash.clone(),
+                }),
+                Some(signature) => signature.verify(&dataset_id, &block_hash),
+            };
+
+            if let Err(e) = res {
+                report.record(
+                    &block_hash,
+                    VerificationPhase::MetadataSignatures,
+                    e.into(),
+                    options,
+                )?;
+            }
+
+            listener.end_block(
+                &block_hash,
+                block_index,
+                num_blocks,
+                VerificationPhase::MetadataSignatures,
+            );
+        }
+
+        listener.end_phase(VerificationPhase::MetadataSignatures);
+
+        Ok(())
+    }
+
+    async fn verify_impl(
         &self,
-        dataset_ref: &DatasetRefLocal,
+        dataset_handle: &DatasetHandle,
         block_range: (Option<Multihash>, Option<Multihash>),
         options: VerificationOptions,
         maybe_listener: Option<Arc<dyn VerificationListener>>,
+        hashing_permits: Arc<Semaphore>,
     ) -> Result<VerificationResult, VerificationError> {
-        let dataset_handle = self.local_repo.resolve_dataset_ref(dataset_ref).await?;
         let dataset = self
             .local_repo
             .get_dataset(&dataset_handle.as_local_ref())
@@ -274,11 +521,24 @@ impl VerificationService for VerificationServiceImpl {
         let listener = maybe_listener.unwrap_or(Arc::new(NullVerificationListener {}));
         listener.begin();
 
+        let mut report = VerificationReport::default();
+
         let res = try {
             if options.check_integrity {
                 self.check_sequence_integrity(
                     &dataset_handle,
                     block_range.clone(),
+                    &options,
+                    &mut report,
+                    listener.clone(),
+                )
+                .await?;
+
+                self.check_signatures(
+                    &dataset_handle,
+                    block_range.clone(),
+                    &options,
+                    &mut report,
                     listener.clone(),
                 )
                 .await?;
@@ -287,13 +547,17 @@ impl VerificationService for VerificationServiceImpl {
                     &dataset_handle,
                     dataset_kind,
                     block_range.clone(),
+                    &options,
+                    &mut report,
                     listener.clone(),
+                    hashing_permits,
                 )
                 .await?;
             }
 
             if dataset_kind == DatasetKind::Derivative && options.replay_transformations {
-                self.transform_service
+                let transform_result = self
+                    .transform_service
                     .verify_transform(
                         &dataset_handle.as_local_ref(),
                         block_range.clone(),
@@ -301,9 +565,11 @@ impl VerificationService for VerificationServiceImpl {
                         Some(listener.clone()),
                     )
                     .await?;
+
+                report.merge(transform_result);
             }
 
-            VerificationResult::Valid
+            VerificationResult::from(report)
         };
 
         match &res {
@@ -313,13 +579,88 @@ impl VerificationService for VerificationServiceImpl {
 
         res
     }
+}
 
-    async fn verify_multi(
+#[async_trait::async_trait(?Send)]
+impl VerificationService for VerificationServiceImpl {
+    async fn verify(
         &self,
-        _requests: &mut dyn Iterator<Item = VerificationRequest>,
-        _options: VerificationOptions,
-        _listener: Option<Arc<dyn VerificationMultiListener>>,
+        dataset_ref: &DatasetRefLocal,
+        block_range: (Option<Multihash>, Option<Multihash>),
+        options: VerificationOptions,
+        maybe_listener: Option<Arc<dyn VerificationListener>>,
     ) -> Result<VerificationResult, VerificationError> {
-        unimplemented!()
+        let dataset_handle = self.local_repo.resolve_dataset_ref(dataset_ref).await?;
+        let hashing_permits = Arc::new(Semaphore::new(options.concurrency()));
+
+        self.verify_impl(
+            &dataset_handle,
+            block_range,
+            options,
+            maybe_listener,
+            hashing_permits,
+        )
+        .await
+    }
+
+    async fn verify_multi(
+        &self,
+        requests: &mut dyn Iterator<Item = VerificationRequest>,
+        options: VerificationOptions,
+        maybe_multi_listener: Option<Arc<dyn VerificationMultiListener>>,
+    ) -> Vec<(
+        DatasetRefLocal,
+        Result<VerificationResult, VerificationError>,
+    )> {
+        let multi_listener =
+            maybe_multi_listener.unwrap_or_else(|| Arc::new(NullVerificationMultiListener));
+
+        let requests: Vec<_> = requests.collect();
+        info!(?requests, "Verifying multiple datasets");
+
+        // Datasets share the hashing permits so that the total number of parts
+        // being hashed stays within the same bound as for a single dataset
+        let hashing_permits = Arc::new(Semaphore::new(options.concurrency()));
+
+        let results: Vec<_> = futures::stream::iter(requests.iter())
+            .map(|request| {
+                let options = options.clone();
+                let multi_listener = multi_listener.clone();
+                let hashing_permits = hashing_permits.clone();
+
+                async move {
+                    let dataset_handle = self
+                        .local_repo
+                        .resolve_dataset_ref(&request.dataset_ref)
+                        .await?;
+
+                    self.verify_impl(
+                        &dataset_handle,
+                        request.block_range.clone(),
+                        options,
+                        multi_listener.begin_verify(&dataset_handle),
+                        hashing_permits,
+                    )
+                    .await
+                }
+            })
+            .buffered(options.concurrency())
+            .collect()
+            .await;
+
+        requests
+            .into_iter()
+            .map(|r| r.dataset_ref)
+            .zip(results)
+            .collect()
+    }
+}
+
+/// Raises the shared cancellation flag when going out of scope
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
     }
 }
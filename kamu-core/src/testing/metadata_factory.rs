@@ -171,6 +171,19 @@ impl SetPollingSourceBuilder {
         self
     }
 
+    pub fn preprocess(mut self, transform: Transform) -> Self {
+        self.v = SetPollingSource {
+            preprocess: Some(transform),
+            ..self.v
+        };
+        self
+    }
+
+    pub fn merge(mut self, merge: MergeStrategy) -> Self {
+        self.v = SetPollingSource { merge, ..self.v };
+        self
+    }
+
     pub fn build(self) -> SetPollingSource {
         self.v
     }
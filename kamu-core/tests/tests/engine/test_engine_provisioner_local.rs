@@ -0,0 +1,76 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use container_runtime::ContainerRuntime;
+use kamu::domain::*;
+use kamu::infra::*;
+
+use std::sync::Arc;
+
+#[test]
+fn test_engine_specs_include_configured_engines() {
+    let config = EngineProvisionerLocalConfig {
+        engines: vec![
+            ODFEngineSpec {
+                grpc_port: 5000,
+                environment_vars: vec![("JAVA_OPTS".to_owned(), "-Xmx2g".to_owned())],
+                memory_limit: Some("4g".to_owned()),
+                cpu_limit: Some(1.5),
+                ..ODFEngineSpec::new("my-engine", "example.com/my-engine:0.1.0")
+            },
+            ODFEngineSpec::new("spark", "example.com/engine-spark:custom"),
+        ],
+        ..EngineProvisionerLocalConfig::default()
+    };
+
+    let specs = config.engine_specs();
+
+    assert_eq!(
+        specs.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(),
+        ["spark", "flink", "my-engine"]
+    );
+    assert_eq!(specs[0].image, "example.com/engine-spark:custom");
+    assert_eq!(specs[1].image, config.flink_image);
+    assert_eq!(specs[1].grpc_port, ODFEngineSpec::DEFAULT_GRPC_PORT);
+    assert_eq!(specs[2], config.engines[0]);
+
+    // Overridden Spark image is also used for ingest
+    assert_eq!(
+        config.spark_engine_image(),
+        "example.com/engine-spark:custom"
+    );
+    assert_eq!(
+        EngineProvisionerLocalConfig::default().spark_engine_image(),
+        config.spark_image
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn test_provision_engine_by_id() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tempdir.path()).unwrap());
+
+    let engine_provisioner = EngineProvisionerLocal::new(
+        EngineProvisionerLocalConfig::default(),
+        workspace_layout,
+        ContainerRuntime::default(),
+    );
+
+    // In-process engine needs no image
+    let engine = engine_provisioner
+        .provision_engine(DataFusionEngine::ENGINE_ID, None)
+        .await;
+    assert!(engine.is_ok());
+    drop(engine);
+
+    assert!(matches!(
+        engine_provisioner.provision_engine("unknown", None).await,
+        Err(EngineProvisioningError::ImageNotFound(_))
+    ));
+}
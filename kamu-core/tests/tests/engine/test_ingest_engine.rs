@@ -31,7 +31,6 @@ use std::sync::Arc;
 use tempfile::TempDir;
 
 #[test_log::test(tokio::test)]
-#[cfg_attr(feature = "skip_docker_tests", ignore)]
 async fn test_ingest_csv_with_engine() {
     let harness = IngestTestHarness::new();
 
@@ -97,7 +96,6 @@ async fn test_ingest_csv_with_engine() {
 }
 
 #[test_log::test(tokio::test)]
-#[cfg_attr(feature = "skip_docker_tests", ignore)]
 async fn test_ingest_parquet_with_engine() {
     let harness = IngestTestHarness::new();
 
@@ -175,6 +173,150 @@ async fn test_ingest_parquet_with_engine() {
     );
 }
 
+#[test_log::test(tokio::test)]
+async fn test_ingest_json_lines_with_engine() {
+    let harness = IngestTestHarness::new();
+
+    let src_path = harness.temp_dir.path().join("data.json");
+    std::fs::write(
+        &src_path,
+        indoc!(
+            r#"
+            {"city": "A", "population": 1000}
+            {"city": "B", "population": 2000}
+            {"city": "C", "population": 3000}
+            "#
+        ),
+    )
+    .unwrap();
+
+    let dataset_snapshot = MetadataFactory::dataset_snapshot()
+        .name("foo.bar")
+        .kind(DatasetKind::Root)
+        .push_event(
+            MetadataFactory::set_polling_source()
+                .fetch_file(&src_path)
+                .read(ReadStep::JsonLines(ReadStepJsonLines {
+                    schema: Some(
+                        ["city STRING", "population INT"]
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    ),
+                    ..ReadStepJsonLines::default()
+                }))
+                .build(),
+        )
+        .build();
+
+    let dataset_name = dataset_snapshot.name.clone();
+
+    harness
+        .ingest_snapshot(dataset_snapshot, &dataset_name)
+        .await;
+
+    let parquet_reader = harness.read_datafile(&dataset_name);
+
+    assert_eq!(
+        parquet_reader.get_column_names(),
+        ["offset", "system_time", "event_time", "city", "population"]
+    );
+
+    assert_eq!(
+        parquet_reader
+            .get_row_iter()
+            .map(IngestTestHarness::row_mapper)
+            .sorted()
+            .collect::<Vec<_>>(),
+        [
+            (0, "A".to_owned(), 1000),
+            (1, "B".to_owned(), 2000),
+            (2, "C".to_owned(), 3000)
+        ]
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn test_ingest_csv_with_preprocess() {
+    let harness = IngestTestHarness::new();
+
+    let src_path = harness.temp_dir.path().join("data.csv");
+    std::fs::write(
+        &src_path,
+        indoc!(
+            "
+            city,population
+            A,1000
+            B,2000
+            C,3000
+            "
+        ),
+    )
+    .unwrap();
+
+    let dataset_snapshot = MetadataFactory::dataset_snapshot()
+        .name("foo.bar")
+        .kind(DatasetKind::Root)
+        .push_event(
+            MetadataFactory::set_polling_source()
+                .fetch_file(&src_path)
+                .read(ReadStep::Csv(ReadStepCsv {
+                    header: Some(true),
+                    schema: Some(
+                        ["city STRING", "population INT"]
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    ),
+                    ..ReadStepCsv::default()
+                }))
+                .preprocess(Transform::Sql(TransformSql {
+                    engine: "datafusion".to_owned(),
+                    version: None,
+                    query: None,
+                    queries: Some(vec![
+                        SqlQueryStep {
+                            alias: Some("big".to_owned()),
+                            query: "SELECT * FROM input WHERE population > 1000".to_owned(),
+                        },
+                        SqlQueryStep {
+                            alias: None,
+                            query: "SELECT city, population * 10 AS population FROM big".to_owned(),
+                        },
+                    ]),
+                    temporal_tables: None,
+                }))
+                .build(),
+        )
+        .build();
+
+    let dataset_name = dataset_snapshot.name.clone();
+
+    harness
+        .ingest_snapshot(dataset_snapshot, &dataset_name)
+        .await;
+
+    let parquet_reader = harness.read_datafile(&dataset_name);
+
+    assert_eq!(
+        parquet_reader.get_column_names(),
+        ["offset", "system_time", "event_time", "city", "population"]
+    );
+
+    assert_eq!(
+        parquet_reader
+            .get_row_iter()
+            .map(|r| (
+                r.get_long(0).unwrap(),
+                r.get_string(3).unwrap().clone(),
+                r.get_long(4).unwrap()
+            ))
+            .sorted()
+            .collect::<Vec<_>>(),
+        [(0, "B".to_owned(), 20000), (1, "C".to_owned(), 30000)]
+    );
+}
+
 /////////////////////////////////////////////////////////////////////////////////////////
 
 struct IngestTestHarness {
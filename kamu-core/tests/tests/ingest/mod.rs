@@ -8,4 +8,5 @@
 // by the Apache License, Version 2.0.
 
 mod test_fetch;
+mod test_merge_strategies;
 mod test_prep;
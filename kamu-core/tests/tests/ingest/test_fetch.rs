@@ -11,7 +11,7 @@ use std::assert_matches::assert_matches;
 use std::sync::Arc;
 use std::sync::Mutex;
 
-use crate::utils::{FtpServer, HttpServer};
+use crate::utils::{run_minio, FtpServer, HttpServer};
 
 use chrono::prelude::*;
 use chrono::Utc;
@@ -88,6 +88,58 @@ async fn test_fetch_url_file() {
     assert_eq!(res3.was_up_to_date, false);
 }
 
+#[tokio::test]
+async fn test_fetch_url_file_event_time_from_path() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::new(tempdir.path()));
+
+    let src_path = tempdir.path().join("data-2020-10-01.csv");
+    let target_path = tempdir.path().join("fetched.bin");
+    std::fs::write(&src_path, "city,population\nA,1000\n").unwrap();
+
+    let fetch_step = FetchStep::Url(FetchStepUrl {
+        url: Url::from_file_path(&src_path).unwrap().as_str().to_owned(),
+        event_time: Some(EventTimeSource::FromPath(EventTimeSourceFromPath {
+            pattern: r"data-(\d+-\d+-\d+)\.csv".to_owned(),
+            timestamp_format: None,
+        })),
+        cache: None,
+        headers: None,
+    });
+
+    let fetch_svc = FetchService::new(Arc::new(ContainerRuntime::default()), workspace_layout);
+
+    let res = fetch_svc
+        .fetch(&fetch_step, None, &target_path, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        res.checkpoint.source_event_time,
+        Some(Utc.with_ymd_and_hms(2020, 10, 1, 0, 0, 0).unwrap())
+    );
+}
+
+#[tokio::test]
+async fn test_fetch_url_unsupported_scheme() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::new(tempdir.path()));
+    let target_path = tempdir.path().join("fetched.bin");
+
+    let fetch_step = FetchStep::Url(FetchStepUrl {
+        url: "gopher://localhost/data.csv".to_owned(),
+        event_time: None,
+        cache: None,
+        headers: None,
+    });
+
+    let fetch_svc = FetchService::new(Arc::new(ContainerRuntime::default()), workspace_layout);
+
+    assert_matches!(
+        fetch_svc.fetch(&fetch_step, None, &target_path, None).await,
+        Err(IngestError::InvalidSource(_))
+    );
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // URL: http
 ///////////////////////////////////////////////////////////////////////////////
@@ -232,6 +284,44 @@ async fn test_fetch_url_http_ok() {
     assert!(target_path.exists());
 }
 
+#[tokio::test]
+#[cfg_attr(feature = "skip_docker_tests", ignore)]
+async fn test_fetch_url_http_event_time_from_metadata() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::new(tempdir.path()));
+    let server_dir = tempdir.path().join("srv");
+    std::fs::create_dir(&server_dir).unwrap();
+
+    let src_path = server_dir.join("data.csv");
+    let target_path = tempdir.path().join("fetched.bin");
+    std::fs::write(&src_path, "city,population\nA,1000\n").unwrap();
+
+    let mtime = Utc.with_ymd_and_hms(2020, 10, 1, 12, 0, 0).unwrap();
+    filetime::set_file_mtime(
+        &src_path,
+        filetime::FileTime::from_unix_time(mtime.timestamp(), 0),
+    )
+    .unwrap();
+
+    let http_server = HttpServer::new(&server_dir);
+
+    let fetch_step = FetchStep::Url(FetchStepUrl {
+        url: format!("http://localhost:{}/data.csv", http_server.host_port),
+        event_time: Some(EventTimeSource::FromMetadata),
+        cache: None,
+        headers: None,
+    });
+
+    let fetch_svc = FetchService::new(Arc::new(ContainerRuntime::default()), workspace_layout);
+
+    let res = fetch_svc
+        .fetch(&fetch_step, None, &target_path, None)
+        .await
+        .unwrap();
+    assert_eq!(res.checkpoint.last_modified, Some(mtime));
+    assert_eq!(res.checkpoint.source_event_time, Some(mtime));
+}
+
 #[test_log::test(tokio::test)]
 #[cfg_attr(feature = "skip_docker_tests", ignore)]
 async fn test_fetch_url_http_env_interpolation() {
@@ -294,6 +384,14 @@ async fn test_fetch_url_http_env_interpolation() {
     );
 }
 
+fn serve_http(app: axum::Router) -> std::net::SocketAddr {
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], 0));
+    let server = axum::Server::bind(&addr).serve(app.into_make_service());
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    addr
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // URL: ftp
 ///////////////////////////////////////////////////////////////////////////////
@@ -348,6 +446,111 @@ async fn test_fetch_url_ftp_ok() {
     );
 }
 
+///////////////////////////////////////////////////////////////////////////////
+// URL: s3
+///////////////////////////////////////////////////////////////////////////////
+
+#[test_log::test(tokio::test)]
+#[cfg_attr(feature = "skip_docker_tests", ignore)]
+async fn test_fetch_url_s3() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::new(tempdir.path()));
+    let server_dir = tempdir.path().join("srv");
+    let target_path = tempdir.path().join("fetched.bin");
+
+    let minio = run_minio(&server_dir, "test-bucket");
+
+    let fetch_step = FetchStep::Url(FetchStepUrl {
+        url: format!(
+            "s3+http://{}:{}/test-bucket/raw/data.csv",
+            minio.address, minio.host_port
+        ),
+        event_time: None,
+        cache: None,
+        headers: None,
+    });
+
+    let fetch_svc = FetchService::new(Arc::new(ContainerRuntime::default()), workspace_layout);
+
+    // No object to fetch
+    assert_matches!(
+        fetch_svc.fetch(&fetch_step, None, &target_path, None).await,
+        Err(IngestError::NotFound { .. })
+    );
+    assert!(!target_path.exists());
+
+    let content = indoc!(
+        "
+        city,population
+        A,1000
+        B,2000
+        C,3000
+        "
+    );
+    std::fs::create_dir(server_dir.join("test-bucket").join("raw")).unwrap();
+    std::fs::write(server_dir.join("test-bucket/raw/data.csv"), content).unwrap();
+
+    let listener = Arc::new(TestListener::new());
+    let res = fetch_svc
+        .fetch(&fetch_step, None, &target_path, Some(listener.clone()))
+        .await
+        .unwrap();
+
+    assert!(!res.was_up_to_date);
+    assert_eq!(std::fs::read_to_string(&target_path).unwrap(), content);
+    assert!(res.checkpoint.etag.is_some());
+    assert!(res.checkpoint.last_modified.is_some());
+    assert_eq!(
+        res.checkpoint.source_event_time,
+        res.checkpoint.last_modified
+    );
+    assert_eq!(
+        listener.get_last_progress(),
+        Some(FetchProgress {
+            total_bytes: 37,
+            fetched_bytes: 37
+        })
+    );
+
+    // Not modified according to ETag
+    let res_repeat_etag = fetch_svc
+        .fetch(
+            &fetch_step,
+            Some(res.checkpoint.clone()),
+            &target_path,
+            None,
+        )
+        .await
+        .unwrap();
+    assert!(res_repeat_etag.was_up_to_date);
+
+    // Not modified according to Last-Modified
+    let cp_last_mod = FetchCheckpoint {
+        etag: None,
+        ..res.checkpoint.clone()
+    };
+    let res_repeat_last_mod = fetch_svc
+        .fetch(&fetch_step, Some(cp_last_mod), &target_path, None)
+        .await
+        .unwrap();
+    assert!(res_repeat_last_mod.was_up_to_date);
+
+    // Points to a prefix rather than an object
+    let fetch_step = FetchStep::Url(FetchStepUrl {
+        url: format!(
+            "s3+http://{}:{}/test-bucket/raw/",
+            minio.address, minio.host_port
+        ),
+        event_time: None,
+        cache: None,
+        headers: None,
+    });
+    assert_matches!(
+        fetch_svc.fetch(&fetch_step, None, &target_path, None).await,
+        Err(IngestError::InvalidSource(_))
+    );
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // FilesGlob
 ///////////////////////////////////////////////////////////////////////////////
@@ -509,6 +712,329 @@ async fn test_fetch_files_glob() {
     );
 }
 
+#[tokio::test]
+async fn test_fetch_files_glob_ordered_by_event_time() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::new(tempdir.path()));
+    let target_path = tempdir.path().join("fetched.bin");
+
+    let fetch_step = FetchStep::FilesGlob(FetchStepFilesGlob {
+        path: tempdir.path().join("*.csv").to_str().unwrap().to_owned(),
+        event_time: Some(EventTimeSource::FromPath(EventTimeSourceFromPath {
+            pattern: r"-(\d+-\d+-\d+)\.csv".to_owned(),
+            timestamp_format: None,
+        })),
+        cache: None,
+        order: Some(SourceOrdering::ByEventTime),
+    });
+
+    let fetch_svc = FetchService::new(Arc::new(ContainerRuntime::default()), workspace_layout);
+
+    // Order of names is the opposite of the order of event times
+    std::fs::write(tempdir.path().join("b-2020-10-01.csv"), "city\nA\n").unwrap();
+    std::fs::write(tempdir.path().join("a-2020-10-05.csv"), "city\nB\n").unwrap();
+
+    let res = fetch_svc
+        .fetch(&fetch_step, None, &target_path, None)
+        .await
+        .unwrap();
+    assert_eq!(res.was_up_to_date, false);
+    assert_eq!(std::fs::read_to_string(&target_path).unwrap(), "city\nA\n");
+    assert_eq!(
+        res.checkpoint.last_filename,
+        Some("b-2020-10-01.csv".to_owned())
+    );
+    assert_eq!(
+        res.checkpoint.source_event_time,
+        Some(Utc.with_ymd_and_hms(2020, 10, 1, 0, 0, 0).unwrap())
+    );
+    assert!(res.checkpoint.has_more);
+
+    let res = fetch_svc
+        .fetch(&fetch_step, Some(res.checkpoint), &target_path, None)
+        .await
+        .unwrap();
+    assert_eq!(res.was_up_to_date, false);
+    assert_eq!(std::fs::read_to_string(&target_path).unwrap(), "city\nB\n");
+    assert_eq!(
+        res.checkpoint.last_filename,
+        Some("a-2020-10-05.csv".to_owned())
+    );
+    assert!(!res.checkpoint.has_more);
+
+    // Doesn't consider files older than the last fetched, even if their names are "bigger"
+    std::fs::write(tempdir.path().join("c-2020-10-03.csv"), "city\nC\n").unwrap();
+
+    let res = fetch_svc
+        .fetch(&fetch_step, Some(res.checkpoint), &target_path, None)
+        .await
+        .unwrap();
+    assert_eq!(res.was_up_to_date, true);
+    assert_eq!(
+        res.checkpoint.last_filename,
+        Some("a-2020-10-05.csv".to_owned())
+    );
+
+    // Files with the same event time are ordered by name
+    std::fs::write(tempdir.path().join("e-2020-10-07.csv"), "city\nE\n").unwrap();
+    std::fs::write(tempdir.path().join("d-2020-10-07.csv"), "city\nD\n").unwrap();
+
+    let res = fetch_svc
+        .fetch(&fetch_step, Some(res.checkpoint), &target_path, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        res.checkpoint.last_filename,
+        Some("d-2020-10-07.csv".to_owned())
+    );
+
+    let res = fetch_svc
+        .fetch(&fetch_step, Some(res.checkpoint), &target_path, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        res.checkpoint.last_filename,
+        Some("e-2020-10-07.csv".to_owned())
+    );
+    assert!(!res.checkpoint.has_more);
+}
+
+#[tokio::test]
+async fn test_fetch_files_glob_event_time_from_metadata() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::new(tempdir.path()));
+    let target_path = tempdir.path().join("fetched.bin");
+
+    let fetch_step = FetchStep::FilesGlob(FetchStepFilesGlob {
+        path: tempdir.path().join("*.csv").to_str().unwrap().to_owned(),
+        event_time: Some(EventTimeSource::FromMetadata),
+        cache: None,
+        order: Some(SourceOrdering::ByEventTime),
+    });
+
+    let fetch_svc = FetchService::new(Arc::new(ContainerRuntime::default()), workspace_layout);
+
+    let t1 = Utc.with_ymd_and_hms(2020, 10, 1, 0, 0, 0).unwrap();
+    let t2 = Utc.with_ymd_and_hms(2020, 10, 2, 0, 0, 0).unwrap();
+
+    for (name, t) in [("a.csv", t2), ("b.csv", t1)] {
+        let path = tempdir.path().join(name);
+        std::fs::write(&path, "city\nA\n").unwrap();
+        filetime::set_file_mtime(&path, filetime::FileTime::from_unix_time(t.timestamp(), 0))
+            .unwrap();
+    }
+
+    let res = fetch_svc
+        .fetch(&fetch_step, None, &target_path, None)
+        .await
+        .unwrap();
+    assert_eq!(res.checkpoint.last_filename, Some("b.csv".to_owned()));
+    assert_eq!(res.checkpoint.source_event_time, Some(t1));
+
+    let res = fetch_svc
+        .fetch(&fetch_step, Some(res.checkpoint), &target_path, None)
+        .await
+        .unwrap();
+    assert_eq!(res.checkpoint.last_filename, Some("a.csv".to_owned()));
+    assert_eq!(res.checkpoint.source_event_time, Some(t2));
+}
+
+#[tokio::test]
+async fn test_fetch_files_glob_ordered_by_event_time_requires_source() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::new(tempdir.path()));
+    let target_path = tempdir.path().join("fetched.bin");
+
+    std::fs::write(tempdir.path().join("a.csv"), "city\nA\n").unwrap();
+
+    let fetch_step = FetchStep::FilesGlob(FetchStepFilesGlob {
+        path: tempdir.path().join("*.csv").to_str().unwrap().to_owned(),
+        event_time: None,
+        cache: None,
+        order: Some(SourceOrdering::ByEventTime),
+    });
+
+    let fetch_svc = FetchService::new(Arc::new(ContainerRuntime::default()), workspace_layout);
+
+    assert_matches!(
+        fetch_svc.fetch(&fetch_step, None, &target_path, None).await,
+        Err(IngestError::InvalidSource(_))
+    );
+    assert!(!target_path.exists());
+}
+
+#[test_log::test(tokio::test)]
+#[cfg_attr(feature = "skip_docker_tests", ignore)]
+async fn test_fetch_files_glob_s3() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::new(tempdir.path()));
+    let server_dir = tempdir.path().join("srv");
+    let target_path = tempdir.path().join("fetched.bin");
+
+    let minio = run_minio(&server_dir, "test-bucket");
+
+    let fetch_step = FetchStep::FilesGlob(FetchStepFilesGlob {
+        path: format!(
+            "s3+http://{}:{}/test-bucket/raw/data-*.csv",
+            minio.address, minio.host_port
+        ),
+        event_time: Some(EventTimeSource::FromPath(EventTimeSourceFromPath {
+            pattern: r"data-(\d+-\d+-\d+)\.csv".to_owned(),
+            timestamp_format: None,
+        })),
+        cache: None,
+        order: None,
+    });
+
+    let fetch_svc = FetchService::new(Arc::new(ContainerRuntime::default()), workspace_layout);
+
+    // No objects to fetch
+    assert_matches!(
+        fetch_svc.fetch(&fetch_step, None, &target_path, None).await,
+        Err(IngestError::NotFound { .. })
+    );
+
+    let raw_dir = server_dir.join("test-bucket").join("raw");
+    std::fs::create_dir_all(raw_dir.join("nested")).unwrap();
+    std::fs::write(raw_dir.join("data-2020-10-05.csv"), "city\nB\n").unwrap();
+    std::fs::write(raw_dir.join("data-2020-10-01.csv"), "city\nA\n").unwrap();
+    // Wildcards don't match across the prefix separators
+    std::fs::write(
+        raw_dir.join("nested").join("data-2020-01-01.csv"),
+        "city\nX\n",
+    )
+    .unwrap();
+    std::fs::write(raw_dir.join("other.csv"), "city\nY\n").unwrap();
+
+    let res = fetch_svc
+        .fetch(&fetch_step, None, &target_path, None)
+        .await
+        .unwrap();
+    assert!(!res.was_up_to_date);
+    assert_eq!(std::fs::read_to_string(&target_path).unwrap(), "city\nA\n");
+    assert_eq!(
+        res.checkpoint.last_filename,
+        Some("data-2020-10-01.csv".to_owned())
+    );
+    assert_eq!(
+        res.checkpoint.source_event_time,
+        Some(Utc.with_ymd_and_hms(2020, 10, 1, 0, 0, 0).unwrap())
+    );
+    assert!(res.checkpoint.has_more);
+
+    let res = fetch_svc
+        .fetch(&fetch_step, Some(res.checkpoint), &target_path, None)
+        .await
+        .unwrap();
+    assert!(!res.was_up_to_date);
+    assert_eq!(std::fs::read_to_string(&target_path).unwrap(), "city\nB\n");
+    assert_eq!(
+        res.checkpoint.last_filename,
+        Some("data-2020-10-05.csv".to_owned())
+    );
+    assert!(!res.checkpoint.has_more);
+
+    let res = fetch_svc
+        .fetch(&fetch_step, Some(res.checkpoint), &target_path, None)
+        .await
+        .unwrap();
+    assert!(res.was_up_to_date);
+}
+
+#[test_log::test(tokio::test)]
+async fn test_fetch_files_glob_http_directory() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::new(tempdir.path()));
+    let target_path = tempdir.path().join("fetched.bin");
+
+    let addr = serve_http(
+        axum::Router::new()
+            .route(
+                "/data/",
+                axum::routing::get(|| async {
+                    axum::response::Html(indoc!(
+                        r#"
+                        <a href="../">Parent Directory</a>
+                        <a href="?C=M;O=A">Last modified</a>
+                        <a href="nested/">nested/</a>
+                        <a href="data-2020-10-05.csv">data-2020-10-05.csv</a>
+                        <a href="/data/data-2020-10-01.csv">data-2020-10-01.csv</a>
+                        <a href="other.csv">other.csv</a>
+                        "#
+                    ))
+                }),
+            )
+            .route(
+                "/data/:name",
+                axum::routing::get(
+                    |axum::extract::Path(name): axum::extract::Path<String>| async move {
+                        format!("file {}", name)
+                    },
+                ),
+            ),
+    );
+
+    let fetch_step = FetchStep::FilesGlob(FetchStepFilesGlob {
+        path: format!("http://{}/data/data-*.csv", addr),
+        event_time: Some(EventTimeSource::FromPath(EventTimeSourceFromPath {
+            pattern: r"data-(\d+-\d+-\d+)\.csv".to_owned(),
+            timestamp_format: None,
+        })),
+        cache: None,
+        order: None,
+    });
+
+    let fetch_svc = FetchService::new(Arc::new(ContainerRuntime::default()), workspace_layout);
+
+    let res = fetch_svc
+        .fetch(&fetch_step, None, &target_path, None)
+        .await
+        .unwrap();
+    assert!(!res.was_up_to_date);
+    assert_eq!(
+        std::fs::read_to_string(&target_path).unwrap(),
+        "file data-2020-10-01.csv"
+    );
+    assert_eq!(
+        res.checkpoint.last_filename,
+        Some("data-2020-10-01.csv".to_owned())
+    );
+    assert_eq!(
+        res.checkpoint.source_event_time,
+        Some(Utc.with_ymd_and_hms(2020, 10, 1, 0, 0, 0).unwrap())
+    );
+    assert!(res.checkpoint.has_more);
+
+    let res = fetch_svc
+        .fetch(&fetch_step, Some(res.checkpoint), &target_path, None)
+        .await
+        .unwrap();
+    assert!(!res.was_up_to_date);
+    assert_eq!(
+        std::fs::read_to_string(&target_path).unwrap(),
+        "file data-2020-10-05.csv"
+    );
+    assert!(!res.checkpoint.has_more);
+
+    let res = fetch_svc
+        .fetch(&fetch_step, Some(res.checkpoint), &target_path, None)
+        .await
+        .unwrap();
+    assert!(res.was_up_to_date);
+
+    // Wildcards are only supported in file names
+    let fetch_step = FetchStep::FilesGlob(FetchStepFilesGlob {
+        path: format!("http://{}/*/data.csv", addr),
+        event_time: None,
+        cache: None,
+        order: None,
+    });
+    assert_matches!(
+        fetch_svc.fetch(&fetch_step, None, &target_path, None).await,
+        Err(IngestError::InvalidSource(_))
+    );
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // Container
 ///////////////////////////////////////////////////////////////////////////////
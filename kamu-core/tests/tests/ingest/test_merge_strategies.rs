@@ -0,0 +1,329 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use datafusion::arrow::array::{Array, Int32Array, Int64Array, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::parquet::record::RowAccessor;
+use itertools::Itertools;
+use kamu::infra::ingest::*;
+use kamu::testing::*;
+use opendatafabric::*;
+
+use std::path::Path;
+use std::sync::Arc;
+
+fn new_data(cities: Vec<&str>, populations: Vec<i32>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("city", DataType::Utf8, false),
+        Field::new("population", DataType::Int32, false),
+    ]));
+    let cities: Arc<dyn Array> = Arc::new(StringArray::from(cities));
+    let populations: Arc<dyn Array> = Arc::new(Int32Array::from(populations));
+    RecordBatch::try_new(schema, vec![cities, populations]).unwrap()
+}
+
+fn prev_data(
+    offsets: Vec<i64>,
+    cities: Vec<&str>,
+    populations: Vec<i32>,
+    observed: Option<Vec<&str>>,
+) -> RecordBatch {
+    let mut fields = vec![
+        Field::new("offset", DataType::Int64, false),
+        Field::new("city", DataType::Utf8, false),
+        Field::new("population", DataType::Int32, false),
+    ];
+    let mut columns: Vec<Arc<dyn Array>> = vec![
+        Arc::new(Int64Array::from(offsets)),
+        Arc::new(StringArray::from(cities)),
+        Arc::new(Int32Array::from(populations)),
+    ];
+    if let Some(observed) = observed {
+        fields.push(Field::new("observed", DataType::Utf8, false));
+        columns.push(Arc::new(StringArray::from(observed)));
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+}
+
+fn mem_table(batch: RecordBatch) -> Option<Arc<dyn TableProvider>> {
+    Some(Arc::new(
+        MemTable::try_new(batch.schema(), vec![vec![batch]]).unwrap(),
+    ))
+}
+
+fn write_prev_data(data_dir: &Path, batches: Vec<RecordBatch>) {
+    std::fs::create_dir_all(data_dir).unwrap();
+    for (i, batch) in batches.iter().enumerate() {
+        ParquetWriterHelper::from_record_batch(&data_dir.join(format!("part-{}", i)), batch)
+            .unwrap();
+    }
+}
+
+fn read_result(path: &Path, batches: Vec<RecordBatch>) -> ParquetReaderHelper {
+    assert!(!batches.is_empty());
+    let batch = datafusion::arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    ParquetWriterHelper::from_record_batch(path, &batch).unwrap();
+    ParquetReaderHelper::open(path)
+}
+
+#[tokio::test]
+async fn test_merge_ledger() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let data_dir = tempdir.path().join("data");
+
+    write_prev_data(
+        &data_dir,
+        vec![prev_data(
+            vec![0, 1],
+            vec!["A", "B"],
+            vec![1000, 2000],
+            None,
+        )],
+    );
+
+    let merger = get_data_merger(
+        &MergeStrategy::Ledger(MergeStrategyLedger {
+            primary_key: vec!["city".to_owned()],
+        }),
+        &DatasetVocabulary::default(),
+    );
+
+    let res = merger
+        .merge(
+            read_prev_data(&data_dir).await.unwrap(),
+            vec![new_data(vec!["A", "B", "C"], vec![1000, 2500, 3000])],
+        )
+        .await
+        .unwrap();
+
+    let reader = read_result(&tempdir.path().join("out"), res);
+    assert_eq!(reader.get_column_names(), ["city", "population"]);
+    assert_eq!(
+        reader
+            .get_row_iter()
+            .map(|r| (r.get_string(0).unwrap().clone(), r.get_int(1).unwrap()))
+            .collect::<Vec<_>>(),
+        [("C".to_owned(), 3000)]
+    );
+}
+
+#[tokio::test]
+async fn test_merge_ledger_no_changes() {
+    let merger = get_data_merger(
+        &MergeStrategy::Ledger(MergeStrategyLedger {
+            primary_key: vec!["city".to_owned()],
+        }),
+        &DatasetVocabulary::default(),
+    );
+
+    let res = merger
+        .merge(
+            mem_table(prev_data(vec![0], vec!["A"], vec![1000], None)),
+            vec![new_data(vec!["A"], vec![1000])],
+        )
+        .await
+        .unwrap();
+
+    assert!(res.is_empty());
+}
+
+#[tokio::test]
+async fn test_merge_snapshot_first_snapshot() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let merger = get_data_merger(
+        &MergeStrategy::Snapshot(MergeStrategySnapshot {
+            primary_key: vec!["city".to_owned()],
+            compare_columns: None,
+            observation_column: None,
+            obsv_added: None,
+            obsv_changed: None,
+            obsv_removed: None,
+        }),
+        &DatasetVocabulary::default(),
+    );
+
+    let res = merger
+        .merge(None, vec![new_data(vec!["A", "B"], vec![1000, 2000])])
+        .await
+        .unwrap();
+
+    let reader = read_result(&tempdir.path().join("out"), res);
+    assert_eq!(
+        reader.get_column_names(),
+        ["city", "population", "observed"]
+    );
+    assert_eq!(
+        reader
+            .get_row_iter()
+            .map(|r| (
+                r.get_string(0).unwrap().clone(),
+                r.get_int(1).unwrap(),
+                r.get_string(2).unwrap().clone()
+            ))
+            .sorted()
+            .collect::<Vec<_>>(),
+        [
+            ("A".to_owned(), 1000, "I".to_owned()),
+            ("B".to_owned(), 2000, "I".to_owned()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_merge_snapshot_changes() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let data_dir = tempdir.path().join("data");
+
+    write_prev_data(
+        &data_dir,
+        vec![
+            prev_data(
+                vec![0, 1, 2, 3],
+                vec!["A", "B", "C", "D"],
+                vec![1000, 2000, 3000, 4000],
+                Some(vec!["I", "I", "I", "I"]),
+            ),
+            prev_data(vec![4], vec!["D"], vec![4000], Some(vec!["D"])),
+        ],
+    );
+
+    let merger = get_data_merger(
+        &MergeStrategy::Snapshot(MergeStrategySnapshot {
+            primary_key: vec!["city".to_owned()],
+            compare_columns: None,
+            observation_column: None,
+            obsv_added: None,
+            obsv_changed: None,
+            obsv_removed: None,
+        }),
+        &DatasetVocabulary::default(),
+    );
+
+    // A - unchanged, B - updated, C - deleted, D - re-inserted, E - inserted
+    let res = merger
+        .merge(
+            read_prev_data(&data_dir).await.unwrap(),
+            vec![new_data(
+                vec!["A", "B", "D", "E"],
+                vec![1000, 2500, 4000, 5000],
+            )],
+        )
+        .await
+        .unwrap();
+
+    let reader = read_result(&tempdir.path().join("out"), res);
+    assert_eq!(
+        reader.get_column_names(),
+        ["city", "population", "observed"]
+    );
+    assert_eq!(
+        reader
+            .get_row_iter()
+            .map(|r| (
+                r.get_string(0).unwrap().clone(),
+                r.get_int(1).unwrap(),
+                r.get_string(2).unwrap().clone()
+            ))
+            .sorted()
+            .collect::<Vec<_>>(),
+        [
+            ("B".to_owned(), 2500, "U".to_owned()),
+            ("C".to_owned(), 3000, "D".to_owned()),
+            ("D".to_owned(), 4000, "I".to_owned()),
+            ("E".to_owned(), 5000, "I".to_owned()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_merge_snapshot_custom_observations() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let data_dir = tempdir.path().join("data");
+
+    write_prev_data(
+        &data_dir,
+        vec![
+            prev_data(
+                vec![0, 1, 2],
+                vec!["A", "B", "C"],
+                vec![1000, 2000, 3000],
+                Some(vec!["+", "+", "+"]),
+            ),
+            prev_data(vec![3], vec!["C"], vec![3000], Some(vec!["-"])),
+        ],
+    );
+
+    let merger = get_data_merger(
+        &MergeStrategy::Snapshot(MergeStrategySnapshot {
+            primary_key: vec!["city".to_owned()],
+            compare_columns: None,
+            observation_column: None,
+            obsv_added: Some("+".to_owned()),
+            obsv_changed: Some("~".to_owned()),
+            obsv_removed: Some("-".to_owned()),
+        }),
+        &DatasetVocabulary::default(),
+    );
+
+    // A - updated, B - deleted, C - re-inserted after custom deletion marker
+    let res = merger
+        .merge(
+            read_prev_data(&data_dir).await.unwrap(),
+            vec![new_data(vec!["A", "C"], vec![1500, 3000])],
+        )
+        .await
+        .unwrap();
+
+    let reader = read_result(&tempdir.path().join("out"), res);
+    assert_eq!(
+        reader
+            .get_row_iter()
+            .map(|r| (
+                r.get_string(0).unwrap().clone(),
+                r.get_int(1).unwrap(),
+                r.get_string(2).unwrap().clone()
+            ))
+            .sorted()
+            .collect::<Vec<_>>(),
+        [
+            ("A".to_owned(), 1500, "~".to_owned()),
+            ("B".to_owned(), 2000, "-".to_owned()),
+            ("C".to_owned(), 3000, "+".to_owned()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_merge_snapshot_compare_columns() {
+    let merger = get_data_merger(
+        &MergeStrategy::Snapshot(MergeStrategySnapshot {
+            primary_key: vec!["city".to_owned()],
+            compare_columns: Some(vec![]),
+            observation_column: Some("obs".to_owned()),
+            obsv_added: None,
+            obsv_changed: None,
+            obsv_removed: None,
+        }),
+        &DatasetVocabulary::default(),
+    );
+
+    // Population changes are ignored as no columns are compared
+    let res = merger
+        .merge(
+            mem_table(prev_data(vec![0], vec!["A"], vec![1000], None)),
+            vec![new_data(vec!["A"], vec![1500])],
+        )
+        .await
+        .unwrap();
+
+    assert!(res.is_empty());
+}
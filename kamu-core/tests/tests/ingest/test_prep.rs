@@ -8,13 +8,16 @@
 // by the Apache License, Version 2.0.
 
 use indoc::indoc;
-use kamu::domain::IngestError;
+use kamu::domain::{IngestError, ProcessError};
 use kamu::infra::ingest::*;
+use kamu::infra::WorkspaceLayout;
 use opendatafabric::*;
 
 use chrono::Utc;
 use std::assert_matches::assert_matches;
 use std::io::prelude::*;
+use std::path::Path;
+use std::sync::Arc;
 
 #[test]
 fn test_prep_pipe() {
@@ -27,7 +30,7 @@ fn test_prep_pipe() {
         command: ["jq", "-c", ".[]"].iter().map(|s| s.to_string()).collect(),
     })];
 
-    let prep_svc = PrepService::new();
+    let prep_svc = PrepService::new(Arc::new(WorkspaceLayout::new(tempdir.path())));
 
     std::fs::write(
         &src_path,
@@ -62,6 +65,43 @@ fn test_prep_pipe() {
         std::fs::read_to_string(&target_path).unwrap().replace("\r", ""),
         "{\"city\":\"A\",\"population\":100}\n{\"city\":\"B\",\"population\":200}\n{\"city\":\"A\",\"population\":110}\n"
     );
+
+    // Logs of successful commands are not kept
+    let run_info_dir = WorkspaceLayout::new(tempdir.path()).run_info_dir;
+    assert_eq!(std::fs::read_dir(run_info_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn test_prep_pipe_failure_logs_stderr() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let src_path = tempdir.path().join("data.csv");
+    let target_path = tempdir.path().join("prepared.bin");
+
+    std::fs::write(&src_path, "A,1000\n").unwrap();
+
+    let prep_steps = vec![PrepStep::Pipe(PrepStepPipe {
+        command: ["sh", "-c", "cat > /dev/null; echo 'bad input' >&2; exit 3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+    })];
+
+    let prep_svc = PrepService::new(Arc::new(WorkspaceLayout::new(tempdir.path())));
+
+    let res = prep_svc.prepare(&prep_steps, Utc::now(), None, &src_path, &target_path);
+
+    let source = match res {
+        Err(IngestError::PipeError { source, .. }) => source,
+        _ => panic!("Unexpected result: {:?}", res),
+    };
+    let process_error = source.downcast_ref::<ProcessError>().unwrap();
+    assert_eq!(process_error.exit_code, Some(3));
+    assert_eq!(process_error.log_files.len(), 1);
+    assert_eq!(
+        std::fs::read_to_string(&process_error.log_files[0]).unwrap(),
+        "bad input\n"
+    );
 }
 
 #[test]
@@ -96,7 +136,7 @@ fn test_prep_decompress_zip_single_file() {
         zip.finish().unwrap();
     }
 
-    let prep_svc = PrepService::new();
+    let prep_svc = PrepService::new(Arc::new(WorkspaceLayout::new(tempdir.path())));
 
     let res = prep_svc
         .prepare(&prep_steps, Utc::now(), None, &src_path, &target_path)
@@ -121,10 +161,113 @@ fn test_prep_decompress_zip_bad_file() {
 
     std::fs::write(&src_path, "garbage").unwrap();
 
-    let prep_svc = PrepService::new();
+    let prep_svc = PrepService::new(Arc::new(WorkspaceLayout::new(tempdir.path())));
+
+    let res = prep_svc.prepare(&prep_steps, Utc::now(), None, &src_path, &target_path);
+    assert_matches!(
+        res,
+        Err(IngestError::DecompressError {
+            format: CompressionFormat::Zip,
+            ..
+        })
+    );
+}
+
+fn create_zip(path: &Path, files: &[(&str, &str)]) {
+    use zip::write::*;
+    let mut zip = ZipWriter::new(std::fs::File::create(path).unwrap());
+
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, content) in files {
+        zip.start_file(*name, options).unwrap();
+        zip.write(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[test]
+fn test_prep_decompress_zip_multiple_files_sub_path() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let src_path = tempdir.path().join("data.zip");
+    let target_path = tempdir.path().join("prepared.bin");
+
+    create_zip(
+        &src_path,
+        &[
+            ("README.md", "# Readme\n"),
+            ("data/2020.csv", "A,1000\n"),
+            ("data/2021.csv", "B,2000\n"),
+            ("data/extra/2022.csv", "C,3000\n"),
+        ],
+    );
+
+    let prep_svc = PrepService::new(Arc::new(WorkspaceLayout::new(tempdir.path())));
+
+    // No sub_path with multiple files is ambiguous
+    let prep_steps = vec![PrepStep::Decompress(PrepStepDecompress {
+        format: CompressionFormat::Zip,
+        sub_path: None,
+    })];
+
+    let res = prep_svc.prepare(&prep_steps, Utc::now(), None, &src_path, &target_path);
+    assert_matches!(res, Err(IngestError::DecompressError { .. }));
+
+    // Glob selects and concatenates files in archive order
+    let prep_steps = vec![PrepStep::Decompress(PrepStepDecompress {
+        format: CompressionFormat::Zip,
+        sub_path: Some("data/*.csv".to_owned()),
+    })];
+
+    prep_svc
+        .prepare(&prep_steps, Utc::now(), None, &src_path, &target_path)
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&target_path).unwrap(),
+        "A,1000\nB,2000\n"
+    );
+
+    // No matches
+    let prep_steps = vec![PrepStep::Decompress(PrepStepDecompress {
+        format: CompressionFormat::Zip,
+        sub_path: Some("*.json".to_owned()),
+    })];
 
     let res = prep_svc.prepare(&prep_steps, Utc::now(), None, &src_path, &target_path);
-    assert_matches!(res, Err(IngestError::Internal(_)));
+    assert_matches!(res, Err(IngestError::DecompressError { .. }));
+}
+
+#[test]
+fn test_prep_decompress_zip_from_pipe() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let src_path = tempdir.path().join("data.zip");
+    let target_path = tempdir.path().join("prepared.bin");
+
+    create_zip(&src_path, &[("a.csv", "A,1000\n"), ("b.csv", "B,2000\n")]);
+
+    // Piping makes the input non-seekable
+    let prep_steps = vec![
+        PrepStep::Pipe(PrepStepPipe {
+            command: vec!["cat".to_owned()],
+        }),
+        PrepStep::Decompress(PrepStepDecompress {
+            format: CompressionFormat::Zip,
+            sub_path: Some("*.csv".to_owned()),
+        }),
+    ];
+
+    let prep_svc = PrepService::new(Arc::new(WorkspaceLayout::new(tempdir.path())));
+
+    prep_svc
+        .prepare(&prep_steps, Utc::now(), None, &src_path, &target_path)
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&target_path).unwrap(),
+        "A,1000\nB,2000\n"
+    );
 }
 
 #[test]
@@ -159,7 +302,7 @@ fn test_prep_decompress_gzip() {
         gzip.write(content.as_bytes()).unwrap();
     }
 
-    let prep_svc = PrepService::new();
+    let prep_svc = PrepService::new(Arc::new(WorkspaceLayout::new(tempdir.path())));
 
     let res = prep_svc
         .prepare(&prep_steps, Utc::now(), None, &src_path, &target_path)
@@ -169,3 +312,49 @@ fn test_prep_decompress_gzip() {
 
     assert_eq!(std::fs::read_to_string(&target_path).unwrap(), content);
 }
+
+#[test]
+fn test_prep_decompress_gzip_errors() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let src_path = tempdir.path().join("data.gz");
+    let target_path = tempdir.path().join("prepared.bin");
+
+    let data = {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        let mut enc = GzEncoder::new(Vec::new(), Compression::fast());
+        enc.write_all(b"city,population\nA,1000\nB,2000\n").unwrap();
+        enc.finish().unwrap()
+    };
+
+    let prep_svc = PrepService::new(Arc::new(WorkspaceLayout::new(tempdir.path())));
+
+    // Corrupted data
+    std::fs::write(&src_path, &data[..data.len() / 2]).unwrap();
+
+    let prep_steps = vec![PrepStep::Decompress(PrepStepDecompress {
+        format: CompressionFormat::Gzip,
+        sub_path: None,
+    })];
+
+    let res = prep_svc.prepare(&prep_steps, Utc::now(), None, &src_path, &target_path);
+    assert_matches!(
+        res,
+        Err(IngestError::DecompressError {
+            format: CompressionFormat::Gzip,
+            ..
+        })
+    );
+
+    // Sub-path is only valid for archives
+    std::fs::write(&src_path, &data).unwrap();
+
+    let prep_steps = vec![PrepStep::Decompress(PrepStepDecompress {
+        format: CompressionFormat::Gzip,
+        sub_path: Some("*.csv".to_owned()),
+    })];
+
+    let res = prep_svc.prepare(&prep_steps, Utc::now(), None, &src_path, &target_path);
+    assert_matches!(res, Err(IngestError::InvalidSource(_)));
+}
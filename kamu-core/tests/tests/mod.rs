@@ -10,12 +10,15 @@
 mod engine;
 mod ingest;
 mod repos;
+mod test_gc_service_impl;
 mod test_pull_service_impl;
+mod test_record_batch_stream;
 mod test_reset_service_impl;
 mod test_resource_loader_impl;
 mod test_schema_utils;
 mod test_search_service_impl;
 mod test_serde_yaml;
+mod test_sql_script;
 mod test_sync_service_impl;
 mod test_transform_service_impl;
 mod test_verification_service_impl;
@@ -20,7 +20,7 @@ async fn test_read_only() {
     let http_server = HttpFileServer::new(tmp_repo_dir.path());
     let base_url = url::Url::parse(&format!("http://{}/", http_server.local_addr())).unwrap();
     let _srv_handle = tokio::spawn(http_server.run());
-    let repo = ObjectRepositoryHttp::new(reqwest::Client::new(), base_url);
+    let repo = ObjectRepositoryHttp::<sha3::Sha3_256, 0x16>::new(reqwest::Client::new(), base_url);
 
     assert_matches!(
         repo.insert_bytes(b"foo", InsertOpts::default()).await,
@@ -34,7 +34,7 @@ async fn test_bytes() {
     let http_server = HttpFileServer::new(tmp_repo_dir.path());
     let base_url = url::Url::parse(&format!("http://{}/", http_server.local_addr())).unwrap();
     let _srv_handle = tokio::spawn(http_server.run());
-    let repo = ObjectRepositoryHttp::new(reqwest::Client::new(), base_url);
+    let repo = ObjectRepositoryHttp::<sha3::Sha3_256, 0x16>::new(reqwest::Client::new(), base_url);
 
     let hash_foo = Multihash::from_digest_sha3_256(b"foo");
     let hash_bar = Multihash::from_digest_sha3_256(b"bar");
@@ -66,7 +66,7 @@ async fn test_stream() {
     let http_server = HttpFileServer::new(tmp_repo_dir.path());
     let base_url = url::Url::parse(&format!("http://{}/", http_server.local_addr())).unwrap();
     let _srv_handle = tokio::spawn(http_server.run());
-    let repo = ObjectRepositoryHttp::new(reqwest::Client::new(), base_url);
+    let repo = ObjectRepositoryHttp::<sha3::Sha3_256, 0x16>::new(reqwest::Client::new(), base_url);
 
     let hash_foobar = Multihash::from_digest_sha3_256(b"foobar");
 
@@ -47,3 +47,93 @@ async fn test_basics() {
 
     repo.delete(&BlockRef::Head).await.unwrap();
 }
+
+#[tokio::test]
+async fn test_named_refs() {
+    let tmp_repo_dir = tempfile::tempdir().unwrap();
+    let repo = ReferenceRepositoryImpl::new(NamedObjectRepositoryLocalFS::new(tmp_repo_dir.path()));
+
+    assert_eq!(repo.list().await.unwrap(), Vec::<BlockRef>::new());
+
+    let tag = BlockRef::tag("v1").unwrap();
+    let branch = BlockRef::branch("experiment").unwrap();
+
+    repo.set(&BlockRef::Head, &Multihash::from_digest_sha3_256(b"foo"))
+        .await
+        .unwrap();
+    repo.set(&branch, &Multihash::from_digest_sha3_256(b"bar"))
+        .await
+        .unwrap();
+    repo.set(&tag, &Multihash::from_digest_sha3_256(b"baz"))
+        .await
+        .unwrap();
+
+    assert!(tmp_repo_dir.path().join("tags").join("v1").is_file());
+    assert!(tmp_repo_dir
+        .path()
+        .join("branches")
+        .join("experiment")
+        .is_file());
+
+    assert_eq!(
+        repo.get(&tag).await.unwrap(),
+        Multihash::from_digest_sha3_256(b"baz")
+    );
+    assert_eq!(
+        repo.list().await.unwrap(),
+        vec![BlockRef::Head, tag.clone(), branch.clone()]
+    );
+
+    repo.delete(&tag).await.unwrap();
+    assert_matches!(repo.get(&tag).await, Err(GetRefError::NotFound(_)));
+    assert_eq!(repo.list().await.unwrap(), vec![BlockRef::Head, branch]);
+}
+
+#[test]
+fn test_block_ref_parsing() {
+    assert_eq!("head".parse::<BlockRef>().unwrap(), BlockRef::Head);
+    assert_eq!(
+        "v1".parse::<BlockRef>().unwrap(),
+        BlockRef::Tag("v1".to_owned())
+    );
+    assert_eq!(
+        "refs/tags/v1".parse::<BlockRef>().unwrap(),
+        BlockRef::Tag("v1".to_owned())
+    );
+    assert_eq!(
+        "branches/exp".parse::<BlockRef>().unwrap(),
+        BlockRef::Branch("exp".to_owned())
+    );
+    assert_eq!(
+        "refs/branches/exp".parse::<BlockRef>().unwrap(),
+        BlockRef::Branch("exp".to_owned())
+    );
+    assert_matches!("refs/foo/bar".parse::<BlockRef>(), Err(_));
+    assert_matches!("".parse::<BlockRef>(), Err(_));
+    assert_matches!("../head".parse::<BlockRef>(), Err(_));
+
+    assert_eq!(
+        BlockRef::Branch("exp".to_owned()).to_string(),
+        "refs/branches/exp"
+    );
+    assert_eq!(BlockRef::Tag("v1".to_owned()).path(), "tags/v1");
+
+    assert_eq!(
+        DatasetRefLocalAt::try_from("my.dataset").unwrap(),
+        DatasetRefLocalAt::new(DatasetName::new_unchecked("my.dataset").into(), None)
+    );
+    assert_eq!(
+        DatasetRefLocalAt::try_from("my.dataset@refs/branches/exp").unwrap(),
+        DatasetRefLocalAt::new(
+            DatasetName::new_unchecked("my.dataset").into(),
+            Some(BlockRef::Branch("exp".to_owned()))
+        )
+    );
+    assert_eq!(
+        DatasetRefLocalAt::try_from("my.dataset@v1")
+            .unwrap()
+            .to_string(),
+        "my.dataset@refs/tags/v1"
+    );
+    assert_matches!(DatasetRefLocalAt::try_from("my.dataset@"), Err(_));
+}
@@ -0,0 +1,167 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use datafusion::arrow::array::*;
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use futures::TryStreamExt;
+use kamu::domain::*;
+use kamu::infra::*;
+use kamu::testing::*;
+use opendatafabric::*;
+
+use std::path::Path;
+use std::sync::Arc;
+
+async fn add_data(dataset: &dyn Dataset, tmp_dir: &Path, start: i64, values: &[&str]) -> Multihash {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("offset", DataType::Int64, false),
+        Field::new("value", DataType::Utf8, false),
+    ]));
+    let end = start + values.len() as i64 - 1;
+    let offsets: Arc<dyn Array> = Arc::new(Int64Array::from_iter_values(start..=end));
+    let values: Arc<dyn Array> = Arc::new(StringArray::from(values.to_vec()));
+    let record_batch = RecordBatch::try_new(schema, vec![offsets, values]).unwrap();
+
+    let data_path = tmp_dir.join("data");
+    ParquetWriterHelper::from_record_batch(&data_path, &record_batch).unwrap();
+
+    dataset
+        .commit_add_data(
+            None,
+            Some(OffsetInterval { start, end }),
+            Some(data_path),
+            None,
+            None,
+            CommitOpts::default(),
+        )
+        .await
+        .unwrap()
+        .new_head
+}
+
+#[test_log::test(tokio::test)]
+async fn test_gc_after_reset() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tmp_dir.path()).unwrap());
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout));
+    let gc_svc = GcServiceImpl::new(local_repo.clone());
+
+    let dataset_name = DatasetName::new_unchecked("foo");
+    local_repo
+        .create_dataset_from_snapshot(
+            MetadataFactory::dataset_snapshot()
+                .name("foo")
+                .kind(DatasetKind::Root)
+                .push_event(MetadataFactory::set_polling_source().build())
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    let dataset = local_repo
+        .get_dataset(&dataset_name.as_local_ref())
+        .await
+        .unwrap();
+    let dataset = dataset.as_ref();
+    let head_1 = add_data(dataset, tmp_dir.path(), 0, &["a", "b"]).await;
+    let head_2 = add_data(dataset, tmp_dir.path(), 2, &["c"]).await;
+    let head_3 = add_data(dataset, tmp_dir.path(), 3, &["d", "e"]).await;
+
+    // Nothing to collect yet
+    let res = gc_svc
+        .gc(&dataset_name.as_local_ref(), GcOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(res, GcResult::default());
+
+    // Keep the second block alive via a branch
+    let chain = dataset.as_metadata_chain();
+    chain
+        .set_ref(
+            &BlockRef::branch("wip").unwrap(),
+            &head_2,
+            SetRefOpts::default(),
+        )
+        .await
+        .unwrap();
+
+    chain
+        .set_ref(&BlockRef::Head, &head_1, SetRefOpts::default())
+        .await
+        .unwrap();
+
+    // Freshly written objects are protected by the grace period
+    let res = gc_svc
+        .gc(&dataset_name.as_local_ref(), GcOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(res, GcResult::default());
+    assert!(chain.try_get_block(&head_3).await.unwrap().is_some());
+
+    let no_grace = GcOptions {
+        dry_run: false,
+        min_age: chrono::Duration::zero(),
+    };
+
+    // Dry run only reports
+    let res = gc_svc
+        .gc(
+            &dataset_name.as_local_ref(),
+            GcOptions {
+                dry_run: true,
+                ..no_grace.clone()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.num_blocks, 1);
+    assert_eq!(res.num_data_slices, 1);
+    assert_eq!(res.num_checkpoints, 0);
+    assert!(res.bytes_reclaimed > 0);
+    assert!(chain.try_get_block(&head_3).await.unwrap().is_some());
+    assert!(dataset
+        .get_block_signature(&head_3)
+        .await
+        .unwrap()
+        .is_some());
+
+    let res_real = gc_svc
+        .gc(&dataset_name.as_local_ref(), no_grace.clone())
+        .await
+        .unwrap();
+    assert_eq!(res, res_real);
+    assert!(chain.try_get_block(&head_3).await.unwrap().is_none());
+    assert!(chain.try_get_block(&head_2).await.unwrap().is_some());
+    assert_eq!(dataset.get_block_signature(&head_3).await.unwrap(), None);
+
+    // Everything reachable from head and branch is still intact
+    let data_slices: Vec<_> = chain
+        .iter_blocks_ref(&BlockRef::branch("wip").unwrap())
+        .filter_data_stream_blocks()
+        .filter_map_ok(|(_, b)| b.event.output_data)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(data_slices.len(), 2);
+    for slice in data_slices {
+        assert!(dataset
+            .as_data_repo()
+            .contains(&slice.physical_hash)
+            .await
+            .unwrap());
+    }
+
+    // Collecting again is a no-op
+    let res = gc_svc
+        .gc(&dataset_name.as_local_ref(), no_grace)
+        .await
+        .unwrap();
+    assert_eq!(res, GcResult::default());
+}
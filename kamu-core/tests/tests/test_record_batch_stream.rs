@@ -0,0 +1,89 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use datafusion::arrow::array::{Array, Int32Array};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::physical_plan::memory::MemoryStream;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use kamu::infra::utils::record_batch_stream::collect_limited;
+
+use std::sync::Arc;
+
+fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]))
+}
+
+fn batch(values: Vec<i32>) -> RecordBatch {
+    let a: Arc<dyn Array> = Arc::new(Int32Array::from(values));
+    RecordBatch::try_new(schema(), vec![a]).unwrap()
+}
+
+// Produces batches [1, 2, 3], [4, 5], [6]
+fn stream() -> SendableRecordBatchStream {
+    Box::pin(
+        MemoryStream::try_new(
+            vec![batch(vec![1, 2, 3]), batch(vec![4, 5]), batch(vec![6])],
+            schema(),
+            None,
+        )
+        .unwrap(),
+    )
+}
+
+fn values(batches: &[RecordBatch]) -> Vec<i32> {
+    batches
+        .iter()
+        .flat_map(|b| {
+            b.column(0)
+                .as_any()
+                .downcast_ref::<Int32Array>()
+                .unwrap()
+                .values()
+                .to_vec()
+        })
+        .collect()
+}
+
+#[test_log::test(tokio::test)]
+async fn test_collect_limited_slices_last_batch() {
+    let res = collect_limited(stream(), 4).await.unwrap();
+    assert_eq!(values(&res.batches), vec![1, 2, 3, 4]);
+    assert_eq!(res.num_records, 4);
+    assert!(res.has_more);
+}
+
+#[test_log::test(tokio::test)]
+async fn test_collect_limited_on_batch_boundary() {
+    let res = collect_limited(stream(), 5).await.unwrap();
+    assert_eq!(values(&res.batches), vec![1, 2, 3, 4, 5]);
+    assert_eq!(res.num_records, 5);
+    assert!(res.has_more);
+
+    let res = collect_limited(stream(), 6).await.unwrap();
+    assert_eq!(values(&res.batches), vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(res.num_records, 6);
+    assert!(!res.has_more);
+}
+
+#[test_log::test(tokio::test)]
+async fn test_collect_limited_exceeding_limit() {
+    let res = collect_limited(stream(), 100).await.unwrap();
+    assert_eq!(values(&res.batches), vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(res.num_records, 6);
+    assert!(!res.has_more);
+}
+
+#[test_log::test(tokio::test)]
+async fn test_collect_limited_zero() {
+    let res = collect_limited(stream(), 0).await.unwrap();
+    assert!(res.batches.is_empty());
+    assert_eq!(res.num_records, 0);
+    assert!(res.has_more);
+}
@@ -0,0 +1,48 @@
+// Copyright Kamu Data, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use kamu::infra::utils::sql_script::*;
+
+fn stmt(text: &str, line: usize) -> SqlScriptStatement {
+    SqlScriptStatement {
+        text: text.to_owned(),
+        line,
+    }
+}
+
+#[test]
+fn test_split_sql_statements() {
+    let script = indoc::indoc!(
+        r#"
+        -- Prepare; the view
+        CREATE TEMP VIEW foo AS
+        SELECT 'a;b' AS "c;d";
+
+        SET datafusion.execution.batch_size = 10;
+        /* multi-line;
+           comment */
+        SELECT * FROM foo
+        "#
+    );
+
+    assert_eq!(
+        split_sql_statements(script),
+        vec![
+            stmt("CREATE TEMP VIEW foo AS\nSELECT 'a;b' AS \"c;d\"", 2),
+            stmt("SET datafusion.execution.batch_size = 10", 5),
+            stmt("SELECT * FROM foo", 8),
+        ]
+    );
+}
+
+#[test]
+fn test_split_sql_statements_empty() {
+    assert_eq!(split_sql_statements(""), vec![]);
+    assert_eq!(split_sql_statements(" ;\n; -- comment\n"), vec![]);
+}
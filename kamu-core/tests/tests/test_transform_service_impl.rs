@@ -438,3 +438,203 @@ async fn test_get_verification_plan_one_to_one() {
     );
     assert_eq!(plan[2].operation.request, deriv_req_t6.request);
 }
+
+async fn append_execute_query_block(
+    local_repo: &dyn LocalDatasetRepository,
+    dataset_handle: &DatasetHandle,
+    operation: &TransformOperation,
+    num_records: i64,
+) -> Multihash {
+    let ds = local_repo
+        .get_dataset(&dataset_handle.as_local_ref())
+        .await
+        .unwrap();
+    let chain = ds.as_metadata_chain();
+    let prev_head = chain.get_ref(&BlockRef::Head).await.unwrap();
+    let prev_block = chain.get_block(&prev_head).await.unwrap();
+
+    let offset = operation.request.offset;
+
+    append_block(
+        local_repo,
+        dataset_handle,
+        MetadataFactory::metadata_block(ExecuteQuery {
+            input_slices: operation.input_slices.clone(),
+            input_checkpoint: operation.input_checkpoint.clone(),
+            output_data: Some(DataSlice {
+                logical_hash: Multihash::from_digest_sha3_256(b"foo"),
+                physical_hash: Multihash::from_digest_sha3_256(b"bar"),
+                interval: OffsetInterval {
+                    start: offset,
+                    end: offset + num_records - 1,
+                },
+                size: 10,
+            }),
+            output_checkpoint: Some(Checkpoint {
+                physical_hash: Multihash::from_digest_sha3_256(&offset.to_be_bytes()),
+                size: 10,
+            }),
+            output_watermark: None,
+        })
+        .system_time(operation.request.system_time)
+        .prev(&prev_head, prev_block.sequence_number)
+        .build(),
+    )
+    .await
+}
+
+#[test_log::test(tokio::test)]
+async fn test_get_next_operation_with_evolved_transform() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tempdir.path()).unwrap());
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+    let transform_svc = TransformServiceImpl::new(
+        local_repo.clone(),
+        Arc::new(EngineProvisionerNull),
+        workspace_layout.clone(),
+    );
+
+    let foo = new_root(local_repo.as_ref(), "foo").await;
+    let baz = new_root(local_repo.as_ref(), "baz").await;
+
+    let (bar, bar_source) = new_deriv(local_repo.as_ref(), "bar", &[foo.name.clone()]).await;
+
+    let t1 = Utc.with_ymd_and_hms(2020, 1, 1, 12, 0, 0).unwrap();
+    let t2 = Utc.with_ymd_and_hms(2020, 1, 2, 12, 0, 0).unwrap();
+    let t3 = Utc.with_ymd_and_hms(2020, 1, 3, 12, 0, 0).unwrap();
+
+    // Era 1: Transforming foo
+    append_data_block(local_repo.as_ref(), &foo.name, 10).await;
+
+    let op_1 = transform_svc
+        .get_next_operation(&bar, t1)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(op_1.request.prev_checkpoint_path, None);
+    append_execute_query_block(local_repo.as_ref(), &bar, &op_1, 10).await;
+
+    // Era 2: Same query with baz added as an input
+    let bar_dataset = local_repo.get_dataset(&bar.as_local_ref()).await.unwrap();
+    let head = bar_dataset
+        .as_metadata_chain()
+        .get_ref(&BlockRef::Head)
+        .await
+        .unwrap();
+    let head_block = bar_dataset
+        .as_metadata_chain()
+        .get_block(&head)
+        .await
+        .unwrap();
+    let source_2 = SetTransform {
+        inputs: vec![
+            TransformInput {
+                id: Some(foo.id.clone()),
+                name: foo.name.clone(),
+            },
+            TransformInput {
+                id: Some(baz.id.clone()),
+                name: baz.name.clone(),
+            },
+        ],
+        transform: bar_source.transform.clone(),
+    };
+    append_block(
+        local_repo.as_ref(),
+        &bar,
+        MetadataFactory::metadata_block(source_2.clone())
+            .prev(&head, head_block.sequence_number)
+            .build(),
+    )
+    .await;
+
+    append_data_block(local_repo.as_ref(), &foo.name, 3).await;
+    append_data_block(local_repo.as_ref(), &baz.name, 5).await;
+
+    let op_2 = transform_svc
+        .get_next_operation(&bar, t2)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Checkpoint is kept as the query did not change and new input starts from its beginning
+    assert_eq!(op_2.request.transform, bar_source.transform);
+    assert!(op_2.request.prev_checkpoint_path.is_some());
+    assert_eq!(op_2.request.offset, 10);
+    assert_eq!(
+        op_2.request
+            .inputs
+            .iter()
+            .map(|i| (i.dataset_id.clone(), i.data_interval.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (foo.id.clone(), Some(OffsetInterval { start: 10, end: 12 })),
+            (baz.id.clone(), Some(OffsetInterval { start: 0, end: 4 })),
+        ]
+    );
+    append_execute_query_block(local_repo.as_ref(), &bar, &op_2, 8).await;
+
+    // Era 3: New query with foo removed
+    let head = bar_dataset
+        .as_metadata_chain()
+        .get_ref(&BlockRef::Head)
+        .await
+        .unwrap();
+    let head_block = bar_dataset
+        .as_metadata_chain()
+        .get_block(&head)
+        .await
+        .unwrap();
+    let source_3 = SetTransform {
+        inputs: vec![TransformInput {
+            id: Some(baz.id.clone()),
+            name: baz.name.clone(),
+        }],
+        transform: MetadataFactory::transform()
+            .query("SELECT * FROM baz WHERE value > 0")
+            .build(),
+    };
+    append_block(
+        local_repo.as_ref(),
+        &bar,
+        MetadataFactory::metadata_block(source_3.clone())
+            .prev(&head, head_block.sequence_number)
+            .build(),
+    )
+    .await;
+
+    append_data_block(local_repo.as_ref(), &foo.name, 1).await;
+    append_data_block(local_repo.as_ref(), &baz.name, 2).await;
+
+    let op_3 = transform_svc
+        .get_next_operation(&bar, t3)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Checkpoint is reset as the query has changed
+    assert_eq!(op_3.request.transform, source_3.transform);
+    assert_eq!(op_3.request.prev_checkpoint_path, None);
+    assert_eq!(op_3.input_checkpoint, None);
+    assert_eq!(op_3.request.offset, 18);
+    assert_eq!(
+        op_3.request
+            .inputs
+            .iter()
+            .map(|i| (i.dataset_id.clone(), i.data_interval.clone()))
+            .collect::<Vec<_>>(),
+        vec![(baz.id.clone(), Some(OffsetInterval { start: 5, end: 6 }))]
+    );
+    append_execute_query_block(local_repo.as_ref(), &bar, &op_3, 2).await;
+
+    // Every era is replayed with the transform that was active at the time
+    let plan = transform_svc
+        .get_verification_plan(&bar, (None, None))
+        .await
+        .unwrap();
+
+    assert_eq!(plan.len(), 3);
+    assert_eq!(plan[0].operation.request, op_1.request);
+    assert_eq!(plan[1].operation.request, op_2.request);
+    assert_eq!(plan[2].operation.request, op_3.request);
+}
@@ -66,7 +66,10 @@ async fn test_verify_data_consistency() {
                 (None, None),
                 VerificationOptions {
                     check_integrity: true,
-                    replay_transformations: false
+                    replay_transformations: false,
+                    continue_on_error: false,
+                    max_concurrency: None,
+                    allow_unsigned: false,
                 },
                 None,
             )
@@ -116,6 +119,7 @@ async fn test_verify_data_consistency() {
         )
         .await
         .unwrap();
+    dataset.sign_block(&head).await.unwrap();
     std::fs::rename(
         data_path,
         dataset_layout
@@ -131,7 +135,10 @@ async fn test_verify_data_consistency() {
                 (None, None),
                 VerificationOptions {
                     check_integrity: true,
-                    replay_transformations: false
+                    replay_transformations: false,
+                    continue_on_error: false,
+                    max_concurrency: None,
+                    allow_unsigned: false,
                 },
                 None,
             )
@@ -156,7 +163,7 @@ async fn test_verify_data_consistency() {
         verification_svc.verify(
             &dataset_name.as_local_ref(),
             (None, None),
-            VerificationOptions {check_integrity: true, replay_transformations: false},
+            VerificationOptions {check_integrity: true, replay_transformations: false, continue_on_error: false, max_concurrency: None, allow_unsigned: false},
             None,
         ).await,
         Err(VerificationError::DataDoesNotMatchMetadata(
@@ -167,3 +174,730 @@ async fn test_verify_data_consistency() {
         )) if block_hash == head && expected == data_logical_hash,
     );
 }
+
+#[tokio::test]
+async fn test_verify_block_signatures() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let dataset_name = DatasetName::new_unchecked("foo");
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tempdir.path()).unwrap());
+
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+
+    let verification_svc = Arc::new(VerificationServiceImpl::new(
+        local_repo.clone(),
+        Arc::new(TestTransformService::new(Arc::new(Mutex::new(Vec::new())))),
+        workspace_layout.clone(),
+    ));
+
+    local_repo
+        .create_dataset_from_snapshot(
+            MetadataFactory::dataset_snapshot()
+                .name(&dataset_name)
+                .kind(DatasetKind::Root)
+                .push_event(MetadataFactory::set_polling_source().build())
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    let dataset = local_repo
+        .get_dataset(&dataset_name.as_local_ref())
+        .await
+        .unwrap();
+
+    let commit_result = dataset
+        .commit_event(
+            MetadataEvent::SetWatermark(SetWatermark {
+                output_watermark: chrono::Utc::now(),
+            }),
+            CommitOpts::default(),
+        )
+        .await
+        .unwrap();
+
+    let options = VerificationOptions {
+        check_integrity: true,
+        replay_transformations: false,
+        continue_on_error: false,
+        max_concurrency: None,
+        allow_unsigned: false,
+    };
+
+    // All blocks are signed with the dataset key
+    assert!(dataset
+        .get_block_signature(&commit_result.new_head)
+        .await
+        .unwrap()
+        .is_some());
+
+    assert_matches!(
+        verification_svc
+            .verify(
+                &dataset_name.as_local_ref(),
+                (None, None),
+                options.clone(),
+                None
+            )
+            .await,
+        Ok(VerificationResult::Valid)
+    );
+
+    // Block signed by a foreign key
+    let (foreign_keypair, _) = DatasetID::from_new_keypair_ed25519();
+    dataset
+        .set_block_signature(
+            &commit_result.new_head,
+            &BlockSignature::sign(&foreign_keypair, &commit_result.new_head),
+        )
+        .await
+        .unwrap();
+
+    assert_matches!(
+        verification_svc
+            .verify(
+                &dataset_name.as_local_ref(),
+                (None, None),
+                options.clone(),
+                None
+            )
+            .await,
+        Err(VerificationError::InvalidSignature(
+            BlockSignatureError::WrongKey { block_hash, .. }
+        )) if block_hash == commit_result.new_head
+    );
+
+    // Block with signature removed
+    dataset
+        .as_info_repo()
+        .delete(&format!("signatures/{}", commit_result.new_head))
+        .await
+        .unwrap();
+
+    assert_matches!(
+        verification_svc
+            .verify(&dataset_name.as_local_ref(), (None, None), options, None)
+            .await,
+        Err(VerificationError::InvalidSignature(
+            BlockSignatureError::Missing { block_hash }
+        )) if block_hash == commit_result.new_head
+    );
+}
+
+#[tokio::test]
+async fn test_verify_missing_seed_signature() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let dataset_name = DatasetName::new_unchecked("foo");
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tempdir.path()).unwrap());
+
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+
+    let verification_svc = Arc::new(VerificationServiceImpl::new(
+        local_repo.clone(),
+        Arc::new(TestTransformService::new(Arc::new(Mutex::new(Vec::new())))),
+        workspace_layout.clone(),
+    ));
+
+    local_repo
+        .create_dataset_from_snapshot(
+            MetadataFactory::dataset_snapshot()
+                .name(&dataset_name)
+                .kind(DatasetKind::Root)
+                .push_event(MetadataFactory::set_polling_source().build())
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    let dataset = local_repo
+        .get_dataset(&dataset_name.as_local_ref())
+        .await
+        .unwrap();
+
+    use futures::TryStreamExt;
+    let blocks: Vec<_> = dataset
+        .as_metadata_chain()
+        .iter_blocks()
+        .try_collect()
+        .await
+        .unwrap();
+    let (seed_hash, _) = blocks.last().unwrap();
+
+    // Removing the seed signature must not disable verification of other signed blocks
+    dataset
+        .as_info_repo()
+        .delete(&format!("signatures/{}", seed_hash))
+        .await
+        .unwrap();
+
+    assert_matches!(
+        verification_svc
+            .verify(
+                &dataset_name.as_local_ref(),
+                (None, None),
+                VerificationOptions {
+                    check_integrity: true,
+                    replay_transformations: false,
+                    continue_on_error: false,
+                    max_concurrency: None,
+                    allow_unsigned: false,
+                },
+                None
+            )
+            .await,
+        Err(VerificationError::InvalidSignature(
+            BlockSignatureError::Missing { block_hash }
+        )) if block_hash == *seed_hash
+    );
+}
+
+#[tokio::test]
+async fn test_verify_unsigned_legacy_chain() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let dataset_name = DatasetName::new_unchecked("foo");
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tempdir.path()).unwrap());
+
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+
+    let verification_svc = Arc::new(VerificationServiceImpl::new(
+        local_repo.clone(),
+        Arc::new(TestTransformService::new(Arc::new(Mutex::new(Vec::new())))),
+        workspace_layout.clone(),
+    ));
+
+    local_repo
+        .create_dataset_from_snapshot(
+            MetadataFactory::dataset_snapshot()
+                .name(&dataset_name)
+                .kind(DatasetKind::Root)
+                .push_event(MetadataFactory::set_polling_source().build())
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    let dataset = local_repo
+        .get_dataset(&dataset_name.as_local_ref())
+        .await
+        .unwrap();
+
+    use futures::TryStreamExt;
+    let blocks: Vec<_> = dataset
+        .as_metadata_chain()
+        .iter_blocks()
+        .try_collect()
+        .await
+        .unwrap();
+
+    // Simulate a dataset created before blocks were signed
+    for (block_hash, _) in &blocks {
+        dataset
+            .as_info_repo()
+            .delete(&format!("signatures/{}", block_hash))
+            .await
+            .unwrap();
+    }
+
+    let options = VerificationOptions {
+        check_integrity: true,
+        replay_transformations: false,
+        continue_on_error: false,
+        max_concurrency: None,
+        allow_unsigned: false,
+    };
+
+    assert_matches!(
+        verification_svc
+            .verify(&dataset_name.as_local_ref(), (None, None), options.clone(), None)
+            .await,
+        Err(VerificationError::InvalidSignature(
+            BlockSignatureError::Missing { .. }
+        ))
+    );
+
+    assert_matches!(
+        verification_svc
+            .verify(
+                &dataset_name.as_local_ref(),
+                (None, None),
+                VerificationOptions {
+                    allow_unsigned: true,
+                    ..options
+                },
+                None
+            )
+            .await,
+        Ok(VerificationResult::Valid)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_continue_on_error() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let dataset_name = DatasetName::new_unchecked("foo");
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tempdir.path()).unwrap());
+
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+
+    let verification_svc = Arc::new(VerificationServiceImpl::new(
+        local_repo.clone(),
+        Arc::new(TestTransformService::new(Arc::new(Mutex::new(Vec::new())))),
+        workspace_layout.clone(),
+    ));
+
+    let data_blocks =
+        create_root_dataset_with_slices(&local_repo, &workspace_layout, &dataset_name, 2).await;
+
+    // Corrupt both data slices and remove the signature of the first block
+    for (_, slice_path) in &data_blocks {
+        corrupt_slice(slice_path);
+    }
+
+    let (first_block, _) = &data_blocks[0];
+    let (second_block, _) = &data_blocks[1];
+
+    let dataset = local_repo
+        .get_dataset(&dataset_name.as_local_ref())
+        .await
+        .unwrap();
+
+    dataset
+        .as_info_repo()
+        .delete(&format!("signatures/{}", first_block))
+        .await
+        .unwrap();
+
+    let options = VerificationOptions {
+        check_integrity: true,
+        replay_transformations: false,
+        continue_on_error: false,
+        max_concurrency: None,
+        allow_unsigned: false,
+    };
+
+    // Stops at the first problem
+    assert_matches!(
+        verification_svc
+            .verify(&dataset_name.as_local_ref(), (None, None), options.clone(), None)
+            .await,
+        Err(VerificationError::InvalidSignature(
+            BlockSignatureError::Missing { block_hash }
+        )) if block_hash == *first_block
+    );
+
+    // Collects all problems
+    let report = match verification_svc
+        .verify(
+            &dataset_name.as_local_ref(),
+            (None, None),
+            VerificationOptions {
+                continue_on_error: true,
+                ..options
+            },
+            None,
+        )
+        .await
+    {
+        Ok(VerificationResult::Invalid(report)) => report,
+        res => panic!("Unexpected result: {:?}", res),
+    };
+
+    assert_eq!(
+        report
+            .problems
+            .iter()
+            .map(|p| (p.block_hash.clone(), p.phase))
+            .collect::<Vec<_>>(),
+        vec![
+            (first_block.clone(), VerificationPhase::MetadataSignatures),
+            (second_block.clone(), VerificationPhase::DataIntegrity),
+            (first_block.clone(), VerificationPhase::DataIntegrity),
+        ]
+    );
+    assert_matches!(
+        report.problems[0].error,
+        VerificationError::InvalidSignature(BlockSignatureError::Missing { .. })
+    );
+    assert_matches!(
+        report.problems[1].error,
+        VerificationError::DataDoesNotMatchMetadata(..)
+    );
+    assert_matches!(
+        report.problems[2].error,
+        VerificationError::DataDoesNotMatchMetadata(..)
+    );
+    assert_eq!(
+        report
+            .problems_for_block(first_block, VerificationPhase::DataIntegrity)
+            .count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_verify_continue_on_missing_data() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let dataset_name = DatasetName::new_unchecked("foo");
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tempdir.path()).unwrap());
+
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+
+    let verification_svc = Arc::new(VerificationServiceImpl::new(
+        local_repo.clone(),
+        Arc::new(TestTransformService::new(Arc::new(Mutex::new(Vec::new())))),
+        workspace_layout.clone(),
+    ));
+
+    let data_blocks =
+        create_root_dataset_with_slices(&local_repo, &workspace_layout, &dataset_name, 3).await;
+
+    // Remove one data slice and corrupt another
+    std::fs::remove_file(&data_blocks[2].1).unwrap();
+    corrupt_slice(&data_blocks[0].1);
+
+    let options = VerificationOptions {
+        check_integrity: true,
+        replay_transformations: false,
+        continue_on_error: false,
+        max_concurrency: None,
+        allow_unsigned: false,
+    };
+
+    assert_matches!(
+        verification_svc
+            .verify(&dataset_name.as_local_ref(), (None, None), options.clone(), None)
+            .await,
+        Err(VerificationError::DataDoesNotMatchMetadata(
+            DataDoesNotMatchMetadata {
+                block_hash,
+                error: DataVerificationError::NotFound,
+            }
+        )) if block_hash == data_blocks[2].0
+    );
+
+    let report = match verification_svc
+        .verify(
+            &dataset_name.as_local_ref(),
+            (None, None),
+            VerificationOptions {
+                continue_on_error: true,
+                ..options
+            },
+            None,
+        )
+        .await
+    {
+        Ok(VerificationResult::Invalid(report)) => report,
+        res => panic!("Unexpected result: {:?}", res),
+    };
+
+    assert_eq!(report.problems.len(), 2);
+    assert_matches!(
+        &report.problems[0].error,
+        VerificationError::DataDoesNotMatchMetadata(DataDoesNotMatchMetadata {
+            block_hash,
+            error: DataVerificationError::NotFound,
+        }) if *block_hash == data_blocks[2].0
+    );
+    assert_matches!(
+        &report.problems[1].error,
+        VerificationError::DataDoesNotMatchMetadata(DataDoesNotMatchMetadata {
+            block_hash,
+            ..
+        }) if *block_hash == data_blocks[0].0
+    );
+}
+
+#[tokio::test]
+async fn test_verify_data_integrity_concurrently() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let dataset_name = DatasetName::new_unchecked("foo");
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tempdir.path()).unwrap());
+
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+
+    let verification_svc = Arc::new(VerificationServiceImpl::new(
+        local_repo.clone(),
+        Arc::new(TestTransformService::new(Arc::new(Mutex::new(Vec::new())))),
+        workspace_layout.clone(),
+    ));
+
+    let data_blocks =
+        create_root_dataset_with_slices(&local_repo, &workspace_layout, &dataset_name, 8).await;
+
+    for i in [1, 4, 5] {
+        corrupt_slice(&data_blocks[i].1);
+    }
+
+    let mut reports = Vec::new();
+
+    for max_concurrency in [1, 3, 8] {
+        let listener = Arc::new(RecordingVerificationListener::default());
+
+        let res = verification_svc
+            .verify(
+                &dataset_name.as_local_ref(),
+                (None, None),
+                VerificationOptions {
+                    check_integrity: true,
+                    replay_transformations: false,
+                    continue_on_error: true,
+                    max_concurrency: Some(max_concurrency),
+                    allow_unsigned: false,
+                },
+                Some(listener.clone() as Arc<dyn VerificationListener>),
+            )
+            .await;
+
+        let report = match res {
+            Ok(VerificationResult::Invalid(report)) => report,
+            res => panic!("Unexpected result: {:?}", res),
+        };
+
+        reports.push(
+            report
+                .problems
+                .iter()
+                .map(|p| (p.block_hash.clone(), p.phase, p.error.to_string()))
+                .collect::<Vec<_>>(),
+        );
+
+        // Blocks are completed in order regardless of the concurrency
+        let calls = listener.calls.lock().unwrap();
+        assert_eq!(calls.begun.len(), data_blocks.len());
+        assert_eq!(
+            calls.ended,
+            data_blocks
+                .iter()
+                .rev()
+                .map(|(h, _)| h.clone())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    assert_eq!(reports[0].len(), 3);
+    assert_eq!(
+        reports[0]
+            .iter()
+            .map(|(h, _, _)| h.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            data_blocks[5].0.clone(),
+            data_blocks[4].0.clone(),
+            data_blocks[1].0.clone()
+        ]
+    );
+    assert_eq!(reports[0], reports[1]);
+    assert_eq!(reports[0], reports[2]);
+
+    // Without continuing on errors the first problem in the block order is returned
+    for max_concurrency in [1, 8] {
+        assert_matches!(
+            verification_svc
+                .verify(
+                    &dataset_name.as_local_ref(),
+                    (None, None),
+                    VerificationOptions {
+                        check_integrity: true,
+                        replay_transformations: false,
+                        continue_on_error: false,
+                        max_concurrency: Some(max_concurrency),
+                        allow_unsigned: false,
+                    },
+                    None,
+                )
+                .await,
+            Err(VerificationError::DataDoesNotMatchMetadata(
+                DataDoesNotMatchMetadata { block_hash, .. }
+            )) if block_hash == data_blocks[5].0
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_verify_multi() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let workspace_layout = Arc::new(WorkspaceLayout::create(tempdir.path()).unwrap());
+
+    let local_repo = Arc::new(LocalDatasetRepositoryImpl::new(workspace_layout.clone()));
+
+    let verification_svc = Arc::new(VerificationServiceImpl::new(
+        local_repo.clone(),
+        Arc::new(TestTransformService::new(Arc::new(Mutex::new(Vec::new())))),
+        workspace_layout.clone(),
+    ));
+
+    let valid_name = DatasetName::new_unchecked("valid");
+    let invalid_name = DatasetName::new_unchecked("invalid");
+    let missing_name = DatasetName::new_unchecked("missing");
+
+    create_root_dataset_with_slices(&local_repo, &workspace_layout, &valid_name, 3).await;
+    let data_blocks =
+        create_root_dataset_with_slices(&local_repo, &workspace_layout, &invalid_name, 3).await;
+    corrupt_slice(&data_blocks[0].1);
+
+    let results = verification_svc
+        .verify_multi(
+            &mut [&invalid_name, &missing_name, &valid_name]
+                .into_iter()
+                .map(|name| VerificationRequest {
+                    dataset_ref: name.as_local_ref(),
+                    block_range: (None, None),
+                }),
+            VerificationOptions {
+                check_integrity: true,
+                replay_transformations: false,
+                continue_on_error: true,
+                max_concurrency: Some(2),
+                allow_unsigned: false,
+            },
+            None,
+        )
+        .await;
+
+    assert_eq!(
+        results.iter().map(|(r, _)| r.clone()).collect::<Vec<_>>(),
+        vec![
+            invalid_name.as_local_ref(),
+            missing_name.as_local_ref(),
+            valid_name.as_local_ref()
+        ]
+    );
+    assert_matches!(
+        &results[0].1,
+        Ok(VerificationResult::Invalid(report))
+            if report.problems.len() == 1 && report.problems[0].block_hash == data_blocks[0].0
+    );
+    assert_matches!(&results[1].1, Err(VerificationError::DatasetNotFound(_)));
+    assert_matches!(&results[2].1, Ok(VerificationResult::Valid));
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Creates a root dataset with the specified number of signed data slices of 5 records each,
+/// returning their block hashes and data file paths in the chain order
+async fn create_root_dataset_with_slices(
+    local_repo: &LocalDatasetRepositoryImpl,
+    workspace_layout: &WorkspaceLayout,
+    dataset_name: &DatasetName,
+    num_slices: i32,
+) -> Vec<(Multihash, std::path::PathBuf)> {
+    let dataset_layout = workspace_layout.dataset_layout(dataset_name);
+
+    let create_result = local_repo
+        .create_dataset_from_snapshot(
+            MetadataFactory::dataset_snapshot()
+                .name(dataset_name)
+                .kind(DatasetKind::Root)
+                .push_event(MetadataFactory::set_polling_source().build())
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    let dataset = local_repo
+        .get_dataset(&dataset_name.as_local_ref())
+        .await
+        .unwrap();
+
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+    let data_path = workspace_layout.run_info_dir.join("data");
+
+    let mut head = create_result.head;
+    let mut head_sequence_number = create_result.head_sequence_number;
+    let mut data_blocks = Vec::new();
+
+    for i in 0..num_slices {
+        let offset = i * 5;
+        let a: Arc<dyn Array> =
+            Arc::new(Int32Array::from((offset..offset + 5).collect::<Vec<i32>>()));
+        let record_batch = RecordBatch::try_new(schema.clone(), vec![a]).unwrap();
+        ParquetWriterHelper::from_record_batch(&data_path, &record_batch).unwrap();
+
+        let data_physical_hash = data_utils::get_file_physical_hash(&data_path).unwrap();
+
+        head = dataset
+            .as_metadata_chain()
+            .append(
+                MetadataFactory::metadata_block(AddData {
+                    input_checkpoint: None,
+                    output_data: DataSlice {
+                        logical_hash: data_utils::get_parquet_logical_hash(&data_path).unwrap(),
+                        physical_hash: data_physical_hash.clone(),
+                        interval: OffsetInterval {
+                            start: offset as i64,
+                            end: offset as i64 + 4,
+                        },
+                        size: std::fs::metadata(&data_path).unwrap().len() as i64,
+                    },
+                    output_checkpoint: None,
+                    output_watermark: None,
+                })
+                .prev(&head, head_sequence_number)
+                .build(),
+                AppendOpts::default(),
+            )
+            .await
+            .unwrap();
+        head_sequence_number += 1;
+        dataset.sign_block(&head).await.unwrap();
+
+        let slice_path = dataset_layout
+            .data_dir
+            .join(data_physical_hash.to_multibase_string());
+        std::fs::rename(&data_path, &slice_path).unwrap();
+        data_blocks.push((head.clone(), slice_path));
+    }
+
+    data_blocks
+}
+
+fn corrupt_slice(slice_path: &std::path::Path) {
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+    let a: Arc<dyn Array> = Arc::new(Int32Array::from(vec![0, 0, 0, 0, 0]));
+    let record_batch = RecordBatch::try_new(schema, vec![a]).unwrap();
+    ParquetWriterHelper::from_record_batch(slice_path, &record_batch).unwrap();
+}
+
+#[derive(Default)]
+struct RecordingVerificationListener {
+    calls: Mutex<RecordedCalls>,
+}
+
+#[derive(Default)]
+struct RecordedCalls {
+    begun: Vec<Multihash>,
+    ended: Vec<Multihash>,
+}
+
+impl VerificationListener for RecordingVerificationListener {
+    fn begin_block(
+        &self,
+        block_hash: &Multihash,
+        _block_index: usize,
+        _num_blocks: usize,
+        phase: VerificationPhase,
+    ) {
+        if phase == VerificationPhase::DataIntegrity {
+            self.calls.lock().unwrap().begun.push(block_hash.clone());
+        }
+    }
+
+    fn end_block(
+        &self,
+        block_hash: &Multihash,
+        _block_index: usize,
+        _num_blocks: usize,
+        phase: VerificationPhase,
+    ) {
+        if phase == VerificationPhase::DataIntegrity {
+            self.calls.lock().unwrap().ended.push(block_hash.clone());
+        }
+    }
+}
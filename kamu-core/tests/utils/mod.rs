@@ -20,4 +20,4 @@ mod minio_server;
 pub use minio_server::*;
 
 mod ipfs_daemon;
-pub use ipfs_daemon::*;
\ No newline at end of file
+pub use ipfs_daemon::*;
@@ -61,6 +61,19 @@ impl Default for ReadStepCsv {
     }
 }
 
+impl Default for ReadStepJsonLines {
+    fn default() -> Self {
+        Self {
+            schema: None,
+            date_format: None,
+            encoding: None,
+            multi_line: None,
+            primitives_as_string: None,
+            timestamp_format: None,
+        }
+    }
+}
+
 impl Display for ExecuteQueryResponseInvalidQuery {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", &self.message)
@@ -21,6 +21,8 @@ use thiserror::Error;
 pub struct RunArgs {
     pub args: Vec<String>,
     pub container_name: Option<String>,
+    /// Number of CPUs the container can use (e.g. `1.5`)
+    pub cpu_limit: Option<String>,
     pub detached: bool,
     pub entry_point: Option<String>,
     pub environment_vars: Vec<(String, String)>,
@@ -32,6 +34,8 @@ pub struct RunArgs {
     pub hostname: Option<String>,
     pub image: String,
     pub interactive: bool,
+    /// Maximum amount of memory the container can use (e.g. `4g`)
+    pub memory_limit: Option<String>,
     pub network: Option<String>,
     pub remove: bool,
     pub tty: bool,
@@ -52,6 +56,7 @@ impl Default for RunArgs {
         Self {
             args: Vec::new(),
             container_name: None,
+            cpu_limit: None,
             detached: false,
             entry_point: None,
             environment_vars: Vec::new(),
@@ -63,6 +68,7 @@ impl Default for RunArgs {
             hostname: None,
             image: "".to_owned(),
             interactive: false,
+            memory_limit: None,
             network: None,
             remove: true,
             tty: false,
@@ -213,6 +219,9 @@ impl ContainerRuntime {
             .map(|v| cmd.arg(format!("--name={}", v)));
         args.hostname.map(|v| cmd.arg(format!("--hostname={}", v)));
         args.network.map(|v| cmd.arg(format!("--network={}", v)));
+        args.cpu_limit.map(|v| cmd.arg(format!("--cpus={}", v)));
+        args.memory_limit
+            .map(|v| cmd.arg(format!("--memory={}", v)));
         if args.expose_all_ports {
             cmd.arg("-P");
         }